@@ -1,15 +1,362 @@
-use axum::{routing::post, Router};
-use gw_core::repo::{
-    account::AccountRepo, merchant::MerchantRepo, transaction::TransactionRepo, Pool,
+use axum::{
+    middleware,
+    routing::{delete, get, post, put},
+    Router,
 };
-use std::sync::Arc;
+use gw_core::{
+    acquirer::SimulatedAcquirer,
+    clock::{Clock, SystemClock},
+    notification::NotificationSource,
+    repo::{
+        account::AccountRepo, api_key::ApiKeyRepo, block_list::BlockListRepo,
+        customer::CustomerRepo, dispute::DisputeRepo, fraud::FraudRepo,
+        idempotency::IdempotencyRepo, limits::LimitsRepo, merchant::MerchantRepo,
+        notification::NotificationRepo, payment_route::PaymentRouteRepo, policy::PolicyRepo,
+        sca::ScaRepo, signing::SigningRepo, subscription::SubscriptionRepo,
+        three_ds::PendingAuthenticationRepo, transaction::TransactionRepo, webhook::WebhookRepo,
+        Pool,
+    },
+    secret::Secret,
+    signing::generate_secret,
+    three_ds::SimulatedDirectoryServer,
+};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 use tokio::sync::Mutex;
 
-use crate::handlers::post_transaction::handle_post_transaction;
+use crate::{
+    auth,
+    handlers::{
+        accounts::{handle_delete_account, handle_get_accounts, handle_post_account},
+        api_keys::{
+            handle_delete_api_key, handle_delete_staff_key, handle_get_api_keys,
+            handle_get_staff_keys, handle_post_api_key, handle_post_staff_key,
+            handle_rotate_api_keys,
+        },
+        block_list::{handle_delete_list_entry, handle_get_list_entries, handle_post_list_entry},
+        customers::{
+            handle_delete_customer, handle_delete_payment_method, handle_get_customer,
+            handle_get_customers, handle_post_customer, handle_post_payment_method,
+            handle_put_customer,
+        },
+        disputes::{
+            handle_get_dispute, handle_get_disputes, handle_post_dispute_file,
+            handle_post_evidence, handle_submit_dispute,
+        },
+        fraud::{handle_get_fraud_rules, handle_put_fraud_rules},
+        get_transactions::{handle_get_merchant_transactions, handle_get_transactions},
+        limits::{handle_get_limits, handle_put_limits},
+        merchants::{
+            handle_deactivate_merchant, handle_get_merchant, handle_get_merchants,
+            handle_post_merchant, handle_put_merchant,
+        },
+        notifications::{handle_get_transaction_notifications, handle_post_notification},
+        payment_routes::{
+            handle_delete_route, handle_get_routes, handle_put_route, handle_resolve_route,
+        },
+        policy::{handle_get_policies, handle_put_policies},
+        post_transaction::handle_post_transaction,
+        sca::{handle_delete_sca_thresholds, handle_get_sca_thresholds, handle_put_sca_thresholds},
+        signing::{handle_delete_signing_secret, handle_put_signing_secret},
+        subscriptions::{
+            handle_cancel_subscription, handle_get_plan, handle_get_plans, handle_get_subscription,
+            handle_get_subscriptions, handle_post_plan, handle_post_subscription,
+        },
+        three_ds::{handle_get_challenge, handle_post_challenge_result},
+        webhooks::{
+            handle_delete_webhook, handle_get_dead_events, handle_get_webhook, handle_put_webhook,
+            handle_retry_event,
+        },
+    },
+    idempotency, logging,
+    permission::{requires, Permission},
+    rate_limit::{self, RateLimiter},
+    signing, webhooks,
+};
 
+/// Every route is authenticated and declares the [`Permission`] its caller needs, a route may
+/// be added more than once to need different permissions for different methods. Merchants with a
+/// signing secret must sign every request they make. Acquirer notifications are the exception,
+/// acquirers sign them instead of using an API key.
 pub fn create_router(app_state: AppState) -> Router {
     Router::new()
-        .route("/transaction", post(handle_post_transaction))
+        .route(
+            "/transaction",
+            requires(
+                Permission::Transact,
+                post(handle_post_transaction).layer(middleware::from_fn_with_state(
+                    app_state.clone(),
+                    idempotency::idempotent,
+                )),
+            ),
+        )
+        .route(
+            "/transactions",
+            requires(Permission::ReadAllMerchants, get(handle_get_transactions)),
+        )
+        .route(
+            "/transactions/{reference}/3ds/challenge",
+            requires(Permission::Transact, get(handle_get_challenge)),
+        )
+        .route(
+            "/transactions/{reference}/3ds/result",
+            requires(Permission::Transact, post(handle_post_challenge_result)),
+        )
+        .route(
+            "/transactions/{reference}/notifications",
+            requires(
+                Permission::ReadAllMerchants,
+                get(handle_get_transaction_notifications),
+            ),
+        )
+        .route(
+            "/merchants",
+            requires(Permission::ManageMerchants, post(handle_post_merchant)),
+        )
+        .route(
+            "/merchants",
+            requires(Permission::ReadAllMerchants, get(handle_get_merchants)),
+        )
+        .route(
+            "/merchants/{merchant_id}",
+            requires(Permission::ReadMerchant, get(handle_get_merchant)),
+        )
+        .route(
+            "/merchants/{merchant_id}",
+            requires(Permission::ManageMerchants, put(handle_put_merchant)),
+        )
+        .route(
+            "/merchants/{merchant_id}/deactivate",
+            requires(
+                Permission::ManageMerchants,
+                post(handle_deactivate_merchant),
+            ),
+        )
+        .route(
+            "/merchants/{merchant_id}/transactions",
+            requires(
+                Permission::ReadMerchant,
+                get(handle_get_merchant_transactions),
+            ),
+        )
+        .route(
+            "/merchants/{merchant_id}/accounts",
+            requires(Permission::ReadMerchant, get(handle_get_accounts)),
+        )
+        .route(
+            "/merchants/{merchant_id}/accounts",
+            requires(Permission::ManageMerchants, post(handle_post_account)),
+        )
+        .route(
+            "/merchants/{merchant_id}/accounts/{acquirer}/{account_id}",
+            requires(Permission::ManageMerchants, delete(handle_delete_account)),
+        )
+        .route(
+            "/merchants/{merchant_id}/routes",
+            requires(Permission::ReadMerchant, get(handle_get_routes)),
+        )
+        .route(
+            "/merchants/{merchant_id}/routes/resolve",
+            requires(Permission::ReadMerchant, get(handle_resolve_route)),
+        )
+        .route(
+            "/merchants/{merchant_id}/routes/{scheme}/{currency}",
+            requires(
+                Permission::ManageMerchants,
+                put(handle_put_route).delete(handle_delete_route),
+            ),
+        )
+        .route(
+            "/merchants/{merchant_id}/api-keys",
+            requires(Permission::ReadMerchant, get(handle_get_api_keys)),
+        )
+        .route(
+            "/merchants/{merchant_id}/api-keys",
+            requires(Permission::ManageMerchants, post(handle_post_api_key)),
+        )
+        .route(
+            "/merchants/{merchant_id}/api-keys/rotate",
+            requires(Permission::ManageMerchants, post(handle_rotate_api_keys)),
+        )
+        .route(
+            "/merchants/{merchant_id}/api-keys/{key_id}",
+            requires(Permission::ManageMerchants, delete(handle_delete_api_key)),
+        )
+        .route(
+            "/merchants/{merchant_id}/signing-secret",
+            requires(
+                Permission::ManageMerchants,
+                put(handle_put_signing_secret).delete(handle_delete_signing_secret),
+            ),
+        )
+        .route(
+            "/merchants/{merchant_id}/limits",
+            requires(Permission::ReadMerchant, get(handle_get_limits)),
+        )
+        .route(
+            "/merchants/{merchant_id}/limits",
+            requires(Permission::ManageMerchants, put(handle_put_limits)),
+        )
+        .route(
+            "/merchants/{merchant_id}/fraud-rules",
+            requires(Permission::ReadMerchant, get(handle_get_fraud_rules)),
+        )
+        .route(
+            "/merchants/{merchant_id}/fraud-rules",
+            requires(Permission::ManageMerchants, put(handle_put_fraud_rules)),
+        )
+        .route(
+            "/merchants/{merchant_id}/policies",
+            requires(Permission::ReadMerchant, get(handle_get_policies)),
+        )
+        .route(
+            "/merchants/{merchant_id}/policies",
+            requires(Permission::ManageMerchants, put(handle_put_policies)),
+        )
+        .route(
+            "/merchants/{merchant_id}/sca-exemptions",
+            requires(Permission::ReadMerchant, get(handle_get_sca_thresholds)),
+        )
+        .route(
+            "/merchants/{merchant_id}/sca-exemptions/{currency}",
+            requires(
+                Permission::ManageMerchants,
+                put(handle_put_sca_thresholds).delete(handle_delete_sca_thresholds),
+            ),
+        )
+        .route(
+            "/merchants/{merchant_id}/customers",
+            requires(Permission::ReadMerchant, get(handle_get_customers)),
+        )
+        .route(
+            "/merchants/{merchant_id}/customers",
+            requires(Permission::Transact, post(handle_post_customer)),
+        )
+        .route(
+            "/merchants/{merchant_id}/customers/{customer_id}",
+            requires(Permission::ReadMerchant, get(handle_get_customer)),
+        )
+        .route(
+            "/merchants/{merchant_id}/customers/{customer_id}",
+            requires(
+                Permission::Transact,
+                put(handle_put_customer).delete(handle_delete_customer),
+            ),
+        )
+        .route(
+            "/merchants/{merchant_id}/customers/{customer_id}/payment-methods",
+            requires(Permission::Transact, post(handle_post_payment_method)),
+        )
+        .route(
+            "/merchants/{merchant_id}/customers/{customer_id}/payment-methods/{token}",
+            requires(Permission::Transact, delete(handle_delete_payment_method)),
+        )
+        .route(
+            "/merchants/{merchant_id}/plans",
+            requires(Permission::ReadMerchant, get(handle_get_plans)),
+        )
+        .route(
+            "/merchants/{merchant_id}/plans",
+            requires(Permission::Transact, post(handle_post_plan)),
+        )
+        .route(
+            "/merchants/{merchant_id}/plans/{plan_id}",
+            requires(Permission::ReadMerchant, get(handle_get_plan)),
+        )
+        .route(
+            "/merchants/{merchant_id}/subscriptions",
+            requires(Permission::ReadMerchant, get(handle_get_subscriptions)),
+        )
+        .route(
+            "/merchants/{merchant_id}/subscriptions",
+            requires(Permission::Transact, post(handle_post_subscription)),
+        )
+        .route(
+            "/merchants/{merchant_id}/subscriptions/{subscription_id}",
+            requires(Permission::ReadMerchant, get(handle_get_subscription)),
+        )
+        .route(
+            "/merchants/{merchant_id}/subscriptions/{subscription_id}/cancel",
+            requires(Permission::Transact, post(handle_cancel_subscription)),
+        )
+        .route(
+            "/merchants/{merchant_id}/disputes",
+            requires(Permission::ReadMerchant, get(handle_get_disputes)),
+        )
+        .route(
+            "/merchants/{merchant_id}/disputes/{dispute_id}",
+            requires(Permission::ReadMerchant, get(handle_get_dispute)),
+        )
+        .route(
+            "/merchants/{merchant_id}/disputes/{dispute_id}/evidence",
+            requires(Permission::Transact, post(handle_post_evidence)),
+        )
+        .route(
+            "/merchants/{merchant_id}/disputes/{dispute_id}/submit",
+            requires(Permission::Transact, post(handle_submit_dispute)),
+        )
+        .route(
+            "/disputes/files/{acquirer}",
+            requires(Permission::ManageMerchants, post(handle_post_dispute_file)),
+        )
+        .route(
+            "/merchants/{merchant_id}/webhook",
+            requires(Permission::ReadMerchant, get(handle_get_webhook)),
+        )
+        .route(
+            "/merchants/{merchant_id}/webhook",
+            requires(
+                Permission::ManageMerchants,
+                put(handle_put_webhook).delete(handle_delete_webhook),
+            ),
+        )
+        .route(
+            "/merchants/{merchant_id}/webhook-events/dead",
+            requires(Permission::ReadMerchant, get(handle_get_dead_events)),
+        )
+        .route(
+            "/merchants/{merchant_id}/webhook-events/{event_id}/retry",
+            requires(Permission::ManageMerchants, post(handle_retry_event)),
+        )
+        .route(
+            "/list-entries",
+            requires(Permission::ReadAllMerchants, get(handle_get_list_entries)),
+        )
+        .route(
+            "/list-entries",
+            requires(Permission::ManageMerchants, post(handle_post_list_entry)),
+        )
+        .route(
+            "/list-entries/{entry_id}",
+            requires(
+                Permission::ManageMerchants,
+                delete(handle_delete_list_entry),
+            ),
+        )
+        .route(
+            "/staff-keys",
+            requires(
+                Permission::ManageMerchants,
+                get(handle_get_staff_keys).post(handle_post_staff_key),
+            ),
+        )
+        .route(
+            "/staff-keys/{key_id}",
+            requires(Permission::ManageMerchants, delete(handle_delete_staff_key)),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            signing::verify_signature,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            rate_limit::limit_requests,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            auth::authenticate,
+        ))
+        .route("/notifications/{acquirer}", post(handle_post_notification))
+        .layer(middleware::from_fn(logging::request_id))
         .with_state(app_state)
 }
 
@@ -17,7 +364,43 @@ pub fn create_router(app_state: AppState) -> Router {
 pub struct AppStateInner {
     pub merchants: MerchantRepo,
     pub accounts: AccountRepo,
+    pub routes: PaymentRouteRepo,
     pub transactions: TransactionRepo,
+    pub idempotency: IdempotencyRepo,
+    pub api_keys: ApiKeyRepo,
+    pub signing: SigningRepo,
+    /// How far a signed request's timestamp may be from the gateway's clock
+    pub signature_max_skew_secs: i64,
+    pub limits: LimitsRepo,
+    pub rate_limiter: RateLimiter,
+    /// Used for merchants who haven't been given their own rate limit
+    pub default_requests_per_minute: u32,
+    /// Keys the card fingerprints used for velocity limits and fraud rules, so they can't be
+    /// reversed
+    pub card_fingerprint_key: Secret<String>,
+    pub fraud: FraudRepo,
+    pub block_lists: BlockListRepo,
+    pub policies: PolicyRepo,
+    pub acquirer: SimulatedAcquirer,
+    pub directory_server: SimulatedDirectoryServer,
+    /// Transactions waiting on the cardholder to complete a 3DS challenge, their cards are
+    /// sealed with the payment method key
+    pub authentications: PendingAuthenticationRepo,
+    pub sca: ScaRepo,
+    pub customers: CustomerRepo,
+    /// Encrypts the cards and accounts customers save, see [`gw_core::payment_method`]
+    pub payment_method_key: Secret<String>,
+    pub subscriptions: SubscriptionRepo,
+    /// Decides when subscriptions are due, see [`crate::scheduler`], and when webhook events
+    /// are, see [`crate::webhooks`]
+    pub clock: Arc<dyn Clock>,
+    pub webhooks: WebhookRepo,
+    /// Delivers webhook events to merchants
+    pub http_client: reqwest::Client,
+    pub notifications: NotificationRepo,
+    /// The secrets acquirers sign their notifications with
+    pub notification_secrets: HashMap<NotificationSource, Secret<String>>,
+    pub disputes: DisputeRepo,
 }
 
 impl AppStateInner {
@@ -29,9 +412,75 @@ impl AppStateInner {
             accounts: AccountRepo {
                 pool: Arc::clone(&pool),
             },
+            routes: PaymentRouteRepo {
+                pool: Arc::clone(&pool),
+            },
             transactions: TransactionRepo {
                 pool: Arc::clone(&pool),
             },
+            idempotency: IdempotencyRepo {
+                pool: Arc::clone(&pool),
+                retention_secs: idempotency::RETENTION_SECS,
+                lease_secs: idempotency::LEASE_SECS,
+            },
+            api_keys: ApiKeyRepo {
+                pool: Arc::clone(&pool),
+            },
+            signing: SigningRepo {
+                pool: Arc::clone(&pool),
+            },
+            signature_max_skew_secs: signing::DEFAULT_MAX_SKEW_SECS,
+            limits: LimitsRepo {
+                pool: Arc::clone(&pool),
+            },
+            rate_limiter: RateLimiter::default(),
+            default_requests_per_minute: rate_limit::DEFAULT_REQUESTS_PER_MINUTE,
+            // replaced from the environment by main, so fingerprints survive restarts
+            card_fingerprint_key: generate_secret(),
+            fraud: FraudRepo {
+                pool: Arc::clone(&pool),
+            },
+            block_lists: BlockListRepo {
+                pool: Arc::clone(&pool),
+            },
+            policies: PolicyRepo {
+                pool: Arc::clone(&pool),
+            },
+            acquirer: SimulatedAcquirer,
+            directory_server: SimulatedDirectoryServer,
+            authentications: PendingAuthenticationRepo {
+                pool: Arc::clone(&pool),
+            },
+            sca: ScaRepo {
+                pool: Arc::clone(&pool),
+            },
+            customers: CustomerRepo {
+                pool: Arc::clone(&pool),
+            },
+            // replaced from the environment by main, so saved payment methods survive restarts
+            payment_method_key: generate_secret(),
+            subscriptions: SubscriptionRepo {
+                pool: Arc::clone(&pool),
+            },
+            clock: Arc::new(SystemClock),
+            webhooks: WebhookRepo {
+                pool: Arc::clone(&pool),
+            },
+            http_client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(webhooks::DELIVERY_TIMEOUT_SECS))
+                .build()
+                .expect("failed to create http client"),
+            notifications: NotificationRepo {
+                pool: Arc::clone(&pool),
+            },
+            // replaced from the environment by main, until then no notification is authentic
+            notification_secrets: NotificationSource::ALL
+                .into_iter()
+                .map(|source| (source, generate_secret()))
+                .collect(),
+            disputes: DisputeRepo {
+                pool: Arc::clone(&pool),
+            },
         }
     }
 }
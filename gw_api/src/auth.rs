@@ -0,0 +1,86 @@
+use axum::{
+    extract::{Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use gw_core::api_key::{parse_key, Role};
+use tracing::{info, warn};
+
+use crate::{
+    app::AppState,
+    error::{ErrorKind, GatewayError},
+};
+
+/// Whoever's API key was used for the request, added to the request's extensions by
+/// [`authenticate`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Caller {
+    pub key_id: String,
+    pub role: Role,
+    /// Only set for merchant keys
+    pub merchant_id: Option<String>,
+}
+
+impl Caller {
+    /// Rejects a merchant acting on behalf of another merchant, staff can act for any of them
+    pub fn check(&self, merchant_id: &str) -> Result<(), GatewayError> {
+        if self.role == Role::Merchant && self.merchant_id.as_deref() != Some(merchant_id) {
+            warn!(key_id = %self.key_id, requested_merchant_id = %merchant_id, "merchant_id does not match API key");
+            return Err(GatewayError {
+                kind: ErrorKind::Forbidden,
+                message: "merchant_id does not match the API key".into(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Support staff only see masked merchant and transaction details
+    pub fn sees_masked_data(&self) -> bool {
+        self.role == Role::Support
+    }
+}
+
+fn unauthorised(message: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Unauthorised,
+        message: message.into(),
+    }
+}
+
+/// Middleware authenticating the caller from an `Authorization: Bearer <api key>` header
+pub async fn authenticate(
+    State(app): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, GatewayError> {
+    let header = request
+        .headers()
+        .get(AUTHORIZATION)
+        .ok_or_else(|| unauthorised("missing API key"))?;
+    let (key_id, secret) = header
+        .to_str()
+        .ok()
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .and_then(parse_key)
+        .ok_or_else(|| unauthorised("invalid API key"))?;
+    let key = {
+        let app_access = app.lock().await;
+        app_access.api_keys.find(key_id).await
+    };
+    let key = match key {
+        Ok(key) if key.verify(secret) && key.is_active_at(Utc::now()) => key,
+        _ => {
+            warn!(key_id, "API key rejected");
+            return Err(unauthorised("invalid API key"));
+        }
+    };
+    info!(key_id, role = %key.role, merchant_id = ?key.merchant_id, "authenticated");
+    request.extensions_mut().insert(Caller {
+        key_id: key.key_id,
+        role: key.role,
+        merchant_id: key.merchant_id,
+    });
+    Ok(next.run(request).await)
+}
@@ -7,7 +7,15 @@ pub type Result<T> = std::result::Result<T, GatewayError>;
 #[derive(Debug, Clone, Copy)]
 pub enum ErrorKind {
     Validation,
+    Unauthorised,
+    Signature,
+    Forbidden,
     Resource,
+    Conflict,
+    /// Too many requests from one merchant, see [`crate::rate_limit`]
+    RateLimited,
+    /// A card has been used too much with a merchant
+    Velocity,
     Fatal,
 }
 
@@ -21,12 +29,24 @@ impl IntoResponse for GatewayError {
     fn into_response(self) -> axum::response::Response {
         let code = match self.kind {
             ErrorKind::Validation => StatusCode::BAD_REQUEST,
+            ErrorKind::Unauthorised => StatusCode::UNAUTHORIZED,
+            ErrorKind::Signature => StatusCode::UNAUTHORIZED,
+            ErrorKind::Forbidden => StatusCode::FORBIDDEN,
             ErrorKind::Resource => StatusCode::NOT_FOUND,
+            ErrorKind::Conflict => StatusCode::CONFLICT,
+            ErrorKind::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorKind::Velocity => StatusCode::UNPROCESSABLE_ENTITY,
             ErrorKind::Fatal => StatusCode::INTERNAL_SERVER_ERROR,
         };
         let error = match self.kind {
             ErrorKind::Validation => "VALIDATION",
+            ErrorKind::Unauthorised => "UNAUTHORISED",
+            ErrorKind::Signature => "SIGNATURE",
+            ErrorKind::Forbidden => "FORBIDDEN",
             ErrorKind::Resource => "RESOURCE",
+            ErrorKind::Conflict => "CONFLICT",
+            ErrorKind::RateLimited => "RATE_LIMITED",
+            ErrorKind::Velocity => "VELOCITY_LIMIT",
             ErrorKind::Fatal => "FATAL",
         };
         let obj = json!({
@@ -39,11 +59,6 @@ impl IntoResponse for GatewayError {
 
 impl std::fmt::Display for GatewayError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        // let prefix = match self.kind {
-        //     ErrorKind::Validation => "ValidationError",
-        //     ErrorKind::Resource => "ResourceError",
-        //     ErrorKind::Fatal => "FatalError",
-        // };
         write!(f, "{}", self.message)
     }
 }
@@ -54,7 +69,7 @@ impl From<validify::ValidationErrors> for GatewayError {
     fn from(value: validify::ValidationErrors) -> Self {
         let errors = value
             .errors()
-            .into_iter()
+            .iter()
             .map(|e| {
                 format!(
                     "{} - {}",
@@ -76,11 +91,19 @@ impl From<Error> for GatewayError {
         match value.kind {
             CoreErrorKind::Database(DbErrorKind::Query) => GatewayError {
                 kind: ErrorKind::Resource,
-                message: format!("{}", value.message),
+                message: value.message,
+            },
+            CoreErrorKind::Database(DbErrorKind::Duplicate) => GatewayError {
+                kind: ErrorKind::Conflict,
+                message: value.message,
             },
             CoreErrorKind::Database(..) => GatewayError {
                 kind: ErrorKind::Fatal,
-                message: format!("{}", value.message),
+                message: value.message,
+            },
+            CoreErrorKind::Limit => GatewayError {
+                kind: ErrorKind::Velocity,
+                message: value.message,
             },
             CoreErrorKind::Type => GatewayError {
                 kind: ErrorKind::Fatal,
@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use gw_core::account::{AcquirerAccount, MerchantAccount};
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    permission::Mask,
+    requests::account::AccountRequest,
+    responses::account::AccountResponse,
+};
+
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_accounts(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let mut accounts = {
+        let app_access = app.lock().await;
+        app_access.accounts.list_for(&merchant_id).await?
+    };
+    if caller.sees_masked_data() {
+        accounts.iter_mut().for_each(Mask::mask);
+    }
+    let response = accounts
+        .iter()
+        .map(AccountResponse::from)
+        .collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+#[instrument(skip(app, payload), err(Display))]
+pub async fn handle_post_account(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    Json(payload): Json<AccountRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let account = AcquirerAccount::try_from(payload)?;
+    let id = {
+        let app_access = app.lock().await;
+        app_access
+            .accounts
+            .create_for(&merchant_id, &account)
+            .await?
+    };
+    info!(
+        acquirer = account.acquirer(),
+        account_id = id,
+        "account created"
+    );
+    let account = MerchantAccount {
+        id,
+        merchant_id,
+        account,
+    };
+    Ok((StatusCode::CREATED, Json(AccountResponse::from(&account))).into_response())
+}
+
+/// Deletes one of the merchant's accounts, as long as no routes are using it
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_account(
+    State(app): State<AppState>,
+    Path((merchant_id, acquirer, account_id)): Path<(String, String, i32)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let app_access = app.lock().await;
+    app_access
+        .accounts
+        .find_for(&merchant_id, &acquirer, account_id)
+        .await
+        .map_err(|_| GatewayError {
+            kind: ErrorKind::Resource,
+            message: format!("merchant {merchant_id} has no {acquirer} account {account_id}"),
+        })?;
+    let routes = app_access
+        .routes
+        .count_for_account(&acquirer, account_id)
+        .await?;
+    if routes > 0 {
+        return Err(GatewayError {
+            kind: ErrorKind::Conflict,
+            message: format!("{acquirer} account {account_id} is used by {routes} route(s)"),
+        });
+    }
+    app_access.accounts.delete(&acquirer, account_id).await?;
+    info!("account deleted");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
@@ -0,0 +1,162 @@
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use chrono::{TimeDelta, Utc};
+use gw_core::{
+    api_key::{ApiKey, Role},
+    repo::Repo,
+};
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    requests::api_key::{RotateRequest, StaffKeyRequest, MAX_GRACE_SECS},
+    responses::api_key::{ApiKeyResponse, NewApiKeyResponse},
+};
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_api_keys(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let keys = {
+        let app_access = app.lock().await;
+        app_access.api_keys.list_for(&merchant_id).await?
+    };
+    let response = keys.iter().map(ApiKeyResponse::from).collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+/// Creates an additional key for the merchant, its existing keys are left as they are
+#[instrument(skip(app), err(Display))]
+pub async fn handle_post_api_key(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let (key, full_key) = ApiKey::generate(&merchant_id);
+    {
+        let app_access = app.lock().await;
+        app_access.api_keys.insert_one(&key).await?;
+    }
+    info!(key_id = %key.key_id, "API key created");
+    let response = NewApiKeyResponse {
+        api_key: full_key.expose(),
+        key: ApiKeyResponse::from(&key),
+    };
+    Ok((StatusCode::CREATED, Json(response)).into_response())
+}
+
+/// Creates a new key for the merchant, their existing keys keep working for the grace period
+#[instrument(skip(app), err(Display))]
+pub async fn handle_rotate_api_keys(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    params: Result<Query<RotateRequest>, QueryRejection>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let Query(params) = params.map_err(|e| GatewayError {
+        kind: ErrorKind::Validation,
+        message: e.body_text(),
+    })?;
+    if !(0..=MAX_GRACE_SECS).contains(&params.grace_secs) {
+        return Err(GatewayError {
+            kind: ErrorKind::Validation,
+            message: format!("grace_secs must be between 0 and {MAX_GRACE_SECS}"),
+        });
+    }
+    find_merchant(&app, &merchant_id).await?;
+    let (key, full_key) = ApiKey::generate(&merchant_id);
+    let old_keys_expire_at = Utc::now() + TimeDelta::seconds(params.grace_secs);
+    {
+        let app_access = app.lock().await;
+        app_access.api_keys.rotate(&key, old_keys_expire_at).await?;
+    }
+    info!(key_id = %key.key_id, %old_keys_expire_at, "API keys rotated");
+    let response = NewApiKeyResponse {
+        api_key: full_key.expose(),
+        key: ApiKeyResponse::from(&key),
+    };
+    Ok((StatusCode::CREATED, Json(response)).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_api_key(
+    State(app): State<AppState>,
+    Path((merchant_id, key_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    {
+        let app_access = app.lock().await;
+        app_access
+            .api_keys
+            .revoke(Some(&merchant_id), &key_id)
+            .await
+            .map_err(|_| GatewayError {
+                kind: ErrorKind::Resource,
+                message: format!("merchant {merchant_id} has no active API key {key_id}"),
+            })?;
+    }
+    info!("API key revoked");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_staff_keys(
+    State(app): State<AppState>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let keys = {
+        let app_access = app.lock().await;
+        app_access.api_keys.list_staff().await?
+    };
+    let response = keys.iter().map(ApiKeyResponse::from).collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+/// Creates a key for a member of staff, which isn't tied to any merchant
+#[instrument(skip(app), err(Display))]
+pub async fn handle_post_staff_key(
+    State(app): State<AppState>,
+    Json(payload): Json<StaffKeyRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let (key, full_key) = Role::try_from(payload.role)
+        .and_then(ApiKey::generate_staff)
+        .map_err(|e| GatewayError {
+            kind: ErrorKind::Validation,
+            message: e.message,
+        })?;
+    {
+        let app_access = app.lock().await;
+        app_access.api_keys.insert_one(&key).await?;
+    }
+    info!(key_id = %key.key_id, role = %key.role, "staff API key created");
+    let response = NewApiKeyResponse {
+        api_key: full_key.expose(),
+        key: ApiKeyResponse::from(&key),
+    };
+    Ok((StatusCode::CREATED, Json(response)).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_staff_key(
+    State(app): State<AppState>,
+    Path(key_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    {
+        let app_access = app.lock().await;
+        app_access
+            .api_keys
+            .revoke(None, &key_id)
+            .await
+            .map_err(|_| GatewayError {
+                kind: ErrorKind::Resource,
+                message: format!("there is no active staff API key {key_id}"),
+            })?;
+    }
+    info!("staff API key revoked");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
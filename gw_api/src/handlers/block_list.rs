@@ -0,0 +1,122 @@
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::Utc;
+use gw_core::{
+    block_list::{EntryKind, ListEntry},
+    limits::fingerprint_pan,
+    repo::Repo,
+};
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    permission::Mask,
+    requests::block_list::{ListEntryRequest, ListEntrySearchRequest},
+    responses::block_list::ListEntryResponse,
+};
+
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_list_entries(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    search: Result<Query<ListEntrySearchRequest>, QueryRejection>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let Query(search) = search.map_err(|e| GatewayError {
+        kind: ErrorKind::Validation,
+        message: e.body_text(),
+    })?;
+    let mut entries = {
+        let app_access = app.lock().await;
+        app_access
+            .block_lists
+            .list(search.merchant_id.as_deref(), search.list)
+            .await?
+    };
+    if caller.sees_masked_data() {
+        entries.iter_mut().for_each(Mask::mask);
+    }
+    let response = entries
+        .iter()
+        .map(ListEntryResponse::from)
+        .collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_post_list_entry(
+    State(app): State<AppState>,
+    Json(payload): Json<ListEntryRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    if let Some(merchant_id) = &payload.merchant_id {
+        find_merchant(&app, merchant_id).await?;
+    }
+    let reason = payload.reason.trim();
+    if reason.is_empty() || reason.chars().count() > 255 {
+        return Err(invalid(
+            "reason must be between 1 and 255 characters".into(),
+        ));
+    }
+    if payload.expires_at.is_some_and(|at| at <= Utc::now()) {
+        return Err(invalid("expires_at must be in the future".into()));
+    }
+    let entry = {
+        let app_access = app.lock().await;
+        // pans aren't stored, only their fingerprints
+        let value = if payload.kind == EntryKind::Card {
+            let pan = payload.value.trim();
+            if !(12..=20).contains(&pan.len()) || !pan.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid("card entries must be a valid pan".into()));
+            }
+            fingerprint_pan(pan, &app_access.card_fingerprint_key)
+        } else {
+            payload.value
+        };
+        let entry = ListEntry::new(
+            payload.list,
+            payload.merchant_id,
+            payload.kind,
+            &value,
+            reason.into(),
+            payload.expires_at,
+        )
+        .map_err(|e| invalid(e.message))?;
+        app_access.block_lists.insert_one(&entry).await?;
+        entry
+    };
+    info!(entry_id = %entry.id, list = %entry.list, kind = %entry.kind, "list entry added");
+    Ok((StatusCode::CREATED, Json(ListEntryResponse::from(&entry))).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_list_entry(
+    State(app): State<AppState>,
+    Path(entry_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    {
+        let app_access = app.lock().await;
+        app_access
+            .block_lists
+            .delete_one(&entry_id, "fraud.list_entries")
+            .await
+            .map_err(|_| GatewayError {
+                kind: ErrorKind::Resource,
+                message: format!("list entry {entry_id} does not exist"),
+            })?;
+    }
+    info!("list entry removed");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn invalid(message: String) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Validation,
+        message,
+    }
+}
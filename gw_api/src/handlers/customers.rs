@@ -0,0 +1,186 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use gw_core::{customer::Customer, payment::Payment, payment_method::PaymentMethod};
+use tracing::{info, instrument};
+use validify::{Validate, Validify};
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    permission::Mask,
+    requests::{customer::CustomerRequest, transaction::payment::PaymentRequest},
+    responses::customer::{CustomerResponse, PaymentMethodResponse},
+};
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_post_customer(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    Json(payload): Json<CustomerRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let mut customer = Customer::new(merchant_id);
+    payload.apply_to(&mut customer)?;
+    customer.validify()?;
+    {
+        let app_access = app.lock().await;
+        app_access.customers.insert(&customer).await?;
+    }
+    info!(customer_id = %customer.customer_id, "customer created");
+    Ok((StatusCode::CREATED, Json(CustomerResponse::from(&customer))).into_response())
+}
+
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_customers(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let mut customers = {
+        let app_access = app.lock().await;
+        app_access.customers.list_for(&merchant_id).await?
+    };
+    if caller.sees_masked_data() {
+        customers.iter_mut().for_each(Mask::mask);
+    }
+    let response = customers
+        .iter()
+        .map(CustomerResponse::from)
+        .collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+/// Shows the customer along with their saved payment methods
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_customer(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path((merchant_id, customer_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut customer = find_customer(&app, &merchant_id, &customer_id).await?;
+    let methods = {
+        let app_access = app.lock().await;
+        app_access
+            .customers
+            .payment_methods(&merchant_id, &customer_id)
+            .await?
+    };
+    if caller.sees_masked_data() {
+        customer.mask();
+    }
+    Ok(Json(CustomerResponse::from((&customer, &methods[..]))).into_response())
+}
+
+/// Replaces the customer's details, their saved payment methods are left as they are
+#[instrument(skip(app), err(Display))]
+pub async fn handle_put_customer(
+    State(app): State<AppState>,
+    Path((merchant_id, customer_id)): Path<(String, String)>,
+    Json(payload): Json<CustomerRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut customer = find_customer(&app, &merchant_id, &customer_id).await?;
+    payload.apply_to(&mut customer)?;
+    customer.validify()?;
+    {
+        let app_access = app.lock().await;
+        app_access.customers.update(&customer).await?;
+    }
+    info!("customer updated");
+    Ok(Json(CustomerResponse::from(&customer)).into_response())
+}
+
+/// Deletes the customer and their saved payment methods, their transactions are kept
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_customer(
+    State(app): State<AppState>,
+    Path((merchant_id, customer_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    {
+        let app_access = app.lock().await;
+        app_access
+            .customers
+            .delete(&merchant_id, &customer_id)
+            .await
+            .map_err(|_| not_found(&customer_id))?;
+    }
+    info!("customer deleted");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Saves a card or account against the customer. A card's security code is checked like any
+/// other payment's, but is never saved.
+#[instrument(skip(app), err(Display))]
+pub async fn handle_post_payment_method(
+    State(app): State<AppState>,
+    Path((merchant_id, customer_id)): Path<(String, String)>,
+    Json(payload): Json<PaymentRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let payment: Payment = payload.try_into()?;
+    payment.validate()?;
+    find_customer(&app, &merchant_id, &customer_id).await?;
+    let method = {
+        let app_access = app.lock().await;
+        let method = PaymentMethod::new(
+            customer_id,
+            merchant_id,
+            &payment,
+            &app_access.payment_method_key,
+            &app_access.card_fingerprint_key,
+        )?;
+        app_access.customers.insert_payment_method(&method).await?;
+        method
+    };
+    info!(token = %method.token, "payment method saved");
+    Ok((
+        StatusCode::CREATED,
+        Json(PaymentMethodResponse::from(&method)),
+    )
+        .into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_payment_method(
+    State(app): State<AppState>,
+    Path((merchant_id, customer_id, token)): Path<(String, String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    {
+        let app_access = app.lock().await;
+        app_access
+            .customers
+            .delete_payment_method(&merchant_id, &customer_id, &token)
+            .await
+            .map_err(|_| GatewayError {
+                kind: ErrorKind::Resource,
+                message: format!("customer {customer_id} has no payment method {token}"),
+            })?;
+    }
+    info!("payment method deleted");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub(crate) async fn find_customer(
+    app: &AppState,
+    merchant_id: &str,
+    customer_id: &str,
+) -> Result<Customer, GatewayError> {
+    let app_access = app.lock().await;
+    app_access
+        .customers
+        .find(merchant_id, customer_id)
+        .await
+        .map_err(|_| not_found(customer_id))
+}
+
+fn not_found(customer_id: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Resource,
+        message: format!("customer {customer_id} does not exist"),
+    }
+}
@@ -0,0 +1,290 @@
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use gw_core::{
+    dispute::{Dispute, DisputeReport, DisputeStatus},
+    notification::{NotificationKind, NotificationSource},
+    transaction::search::{TransactionSummary, MAX_PAGE_SIZE},
+};
+use tracing::{info, instrument, warn};
+use validify::Validify;
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    permission::Mask,
+    requests::dispute::{DisputeListRequest, EvidenceRequest},
+    responses::dispute::{
+        DisputeFileError, DisputeFileResponse, DisputeResponse, EvidenceResponse,
+    },
+    webhooks,
+};
+
+/// What an acquirer's report did to the gateway's disputes
+#[derive(Debug, Default)]
+pub(crate) struct Ingested {
+    pub opened: bool,
+    pub resolved: bool,
+}
+
+/// Records what an acquirer has said about a dispute. A case it hasn't reported before opens a
+/// dispute against the transaction, charging it back, and an outcome resolves the dispute.
+/// Reporting a case again only does what hasn't already been done.
+pub(crate) async fn ingest(
+    app: &AppState,
+    report: &DisputeReport,
+) -> Result<Ingested, GatewayError> {
+    let mut ingested = Ingested::default();
+    let mut changes = vec![];
+    let mut charged_back = None;
+    // held throughout, so a case reported twice at once is only opened once
+    let app_access = app.lock().await;
+    let now = app_access.clock.now();
+    let existing = app_access
+        .disputes
+        .find_by_case(&report.acquirer, &report.case_id)
+        .await?;
+    let mut dispute = match existing {
+        Some(dispute) => dispute,
+        None => {
+            let transaction = app_access
+                .transactions
+                .find_summary(&report.transaction_reference)
+                .await?
+                .ok_or_else(|| GatewayError {
+                    kind: ErrorKind::Validation,
+                    message: format!(
+                        "transaction {} does not exist",
+                        report.transaction_reference
+                    ),
+                })?;
+            let dispute = Dispute::open(report, &transaction, now).map_err(|e| GatewayError {
+                kind: ErrorKind::Validation,
+                message: e.message,
+            })?;
+            if !app_access.disputes.insert(&dispute).await? {
+                // another gateway opened it first
+                return Ok(ingested);
+            }
+            if let Some(status) = transaction.status.after(NotificationKind::Chargeback) {
+                app_access
+                    .transactions
+                    .update_status(&transaction.reference, &status)
+                    .await?;
+                charged_back = Some(TransactionSummary {
+                    status,
+                    ..transaction
+                });
+            }
+            info!(dispute_id = %dispute.dispute_id, case_id = %dispute.case_id, "dispute opened");
+            ingested.opened = true;
+            changes.push(dispute.clone());
+            dispute
+        }
+    };
+    if let Some(outcome) = report.outcome {
+        if dispute.resolve(outcome, now) {
+            app_access.disputes.update(&dispute).await?;
+            info!(dispute_id = %dispute.dispute_id, status = %dispute.status, "dispute resolved");
+            ingested.resolved = true;
+            changes.push(dispute);
+        }
+    }
+    drop(app_access);
+    // the disputes have been recorded, so the acquirer mustn't be told it failed
+    if let Some(transaction) = charged_back {
+        if let Err(e) = webhooks::notify_change(app, &transaction).await {
+            warn!(error = %e, "webhook event not queued");
+        }
+    }
+    for dispute in changes {
+        if let Err(e) = webhooks::notify_dispute(app, &dispute).await {
+            warn!(error = %e, "webhook event not queued");
+        }
+    }
+    Ok(ingested)
+}
+
+/// Takes in a file of disputes an acquirer has sent, opening new ones and resolving those
+/// they've decided. Lines that can't be used are reported back without stopping the rest.
+#[instrument(skip(app, file), err(Display))]
+pub async fn handle_post_dispute_file(
+    State(app): State<AppState>,
+    Path(acquirer): Path<String>,
+    file: String,
+) -> Result<impl IntoResponse, GatewayError> {
+    let source = NotificationSource::try_from(acquirer).map_err(|e| GatewayError {
+        kind: ErrorKind::Resource,
+        message: e.message,
+    })?;
+    let lines =
+        DisputeReport::parse_file(&source.to_string(), &file).map_err(|e| GatewayError {
+            kind: ErrorKind::Validation,
+            message: e.message,
+        })?;
+    let mut response = DisputeFileResponse::default();
+    for (line, report) in lines {
+        let ingested = match report {
+            Ok(report) => ingest(&app, &report).await,
+            Err(e) => Err(GatewayError {
+                kind: ErrorKind::Validation,
+                message: e.message,
+            }),
+        };
+        match ingested {
+            Ok(ingested) => {
+                response.disputes += 1;
+                response.opened += usize::from(ingested.opened);
+                response.resolved += usize::from(ingested.resolved);
+            }
+            Err(e) if matches!(e.kind, ErrorKind::Validation) => {
+                response.errors.push(DisputeFileError {
+                    line,
+                    message: e.message,
+                });
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    info!(
+        disputes = response.disputes,
+        opened = response.opened,
+        resolved = response.resolved,
+        errors = response.errors.len(),
+        "dispute file received"
+    );
+    Ok(Json(response).into_response())
+}
+
+/// The merchant's disputes, newest first
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_disputes(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(merchant_id): Path<String>,
+    params: Result<Query<DisputeListRequest>, QueryRejection>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let Query(params) = params.map_err(|e| GatewayError {
+        kind: ErrorKind::Validation,
+        message: e.body_text(),
+    })?;
+    if params.limit == 0 || params.limit > MAX_PAGE_SIZE {
+        return Err(GatewayError {
+            kind: ErrorKind::Validation,
+            message: format!("limit must be between 1 and {MAX_PAGE_SIZE}"),
+        });
+    }
+    let status = params
+        .status
+        .map(DisputeStatus::try_from)
+        .transpose()
+        .map_err(|e| GatewayError {
+            kind: ErrorKind::Validation,
+            message: e.message,
+        })?;
+    find_merchant(&app, &merchant_id).await?;
+    let mut disputes = {
+        let app_access = app.lock().await;
+        app_access
+            .disputes
+            .list(&merchant_id, status, params.limit.into())
+            .await?
+    };
+    if caller.sees_masked_data() {
+        disputes.iter_mut().for_each(Mask::mask);
+    }
+    let response = disputes
+        .iter()
+        .map(DisputeResponse::from)
+        .collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_dispute(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path((merchant_id, dispute_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut dispute = {
+        let app_access = app.lock().await;
+        app_access
+            .disputes
+            .find(&merchant_id, &dispute_id)
+            .await
+            .map_err(|_| no_dispute(&dispute_id))?
+    };
+    if caller.sees_masked_data() {
+        dispute.mask();
+    }
+    Ok(Json(DisputeResponse::from(&dispute)).into_response())
+}
+
+/// Records the details of a document the merchant is relying on, until they submit their
+/// evidence or the deadline passes
+#[instrument(skip(app), err(Display))]
+pub async fn handle_post_evidence(
+    State(app): State<AppState>,
+    Path((merchant_id, dispute_id)): Path<(String, String)>,
+    Json(payload): Json<EvidenceRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let app_access = app.lock().await;
+    let mut dispute = app_access
+        .disputes
+        .find(&merchant_id, &dispute_id)
+        .await
+        .map_err(|_| no_dispute(&dispute_id))?;
+    let now = app_access.clock.now();
+    let mut evidence = payload.into_evidence(dispute_id, now)?;
+    evidence.validify()?;
+    dispute
+        .add_evidence(evidence.clone(), now)
+        .map_err(|e| GatewayError {
+            kind: ErrorKind::Conflict,
+            message: e.message,
+        })?;
+    app_access.disputes.insert_evidence(&evidence).await?;
+    info!(evidence_id = %evidence.evidence_id, "dispute evidence added");
+    Ok((StatusCode::CREATED, Json(EvidenceResponse::from(&evidence))).into_response())
+}
+
+/// Sends the merchant's evidence to the issuer, after which none can be added
+#[instrument(skip(app), err(Display))]
+pub async fn handle_submit_dispute(
+    State(app): State<AppState>,
+    Path((merchant_id, dispute_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let dispute = {
+        let app_access = app.lock().await;
+        let mut dispute = app_access
+            .disputes
+            .find(&merchant_id, &dispute_id)
+            .await
+            .map_err(|_| no_dispute(&dispute_id))?;
+        dispute
+            .submit(app_access.clock.now())
+            .map_err(|e| GatewayError {
+                kind: ErrorKind::Conflict,
+                message: e.message,
+            })?;
+        app_access.disputes.update(&dispute).await?;
+        dispute
+    };
+    info!(dispute_id = %dispute.dispute_id, "dispute evidence submitted");
+    if let Err(e) = webhooks::notify_dispute(&app, &dispute).await {
+        warn!(error = %e, "webhook event not queued");
+    }
+    Ok(Json(DisputeResponse::from(&dispute)).into_response())
+}
+
+fn no_dispute(dispute_id: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Resource,
+        message: format!("dispute {dispute_id} does not exist"),
+    }
+}
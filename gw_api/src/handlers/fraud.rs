@@ -0,0 +1,47 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use gw_core::fraud::FraudSettings;
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState, error::GatewayError, handlers::merchants::find_merchant,
+    requests::fraud::FraudSettingsRequest, responses::fraud::FraudSettingsResponse,
+};
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_fraud_rules(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let settings = {
+        let app_access = app.lock().await;
+        app_access.fraud.find(&merchant_id).await?
+    }
+    .unwrap_or_else(|| FraudSettings::new(merchant_id));
+    Ok(Json(FraudSettingsResponse::from(&settings)).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_put_fraud_rules(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    Json(payload): Json<FraudSettingsRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let settings = {
+        let app_access = app.lock().await;
+        let settings = payload.into_settings(merchant_id, &app_access.card_fingerprint_key)?;
+        app_access.fraud.upsert(&settings).await?;
+        settings
+    };
+    info!(
+        merchant_id = %settings.merchant_id,
+        rules = settings.rules.len(),
+        "fraud rules updated"
+    );
+    Ok(Json(FraudSettingsResponse::from(&settings)).into_response())
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use tracing::instrument;
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    permission::Mask,
+    requests::transaction_search::{TransactionSearch, TransactionSearchRequest},
+    responses::transaction_search::TransactionListResponse,
+};
+
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_transactions(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    params: Result<Query<TransactionSearchRequest>, QueryRejection>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let search = parse_search(params)?;
+    search_transactions(&app, &caller, search).await
+}
+
+/// Lists a single merchant's transactions, any merchant_id in the query is ignored
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_merchant_transactions(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(merchant_id): Path<String>,
+    params: Result<Query<TransactionSearchRequest>, QueryRejection>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut search = parse_search(params)?;
+    {
+        let app_access = app.lock().await;
+        app_access
+            .merchants
+            .find(&merchant_id)
+            .await
+            .map_err(|_| GatewayError {
+                kind: ErrorKind::Resource,
+                message: format!("merchant {merchant_id} does not exist"),
+            })?;
+    }
+    search.filter.merchant_id = Some(merchant_id);
+    search_transactions(&app, &caller, search).await
+}
+
+fn parse_search(
+    params: Result<Query<TransactionSearchRequest>, QueryRejection>,
+) -> Result<TransactionSearch, GatewayError> {
+    let Query(params) = params.map_err(|e| GatewayError {
+        kind: ErrorKind::Validation,
+        message: e.body_text(),
+    })?;
+    params.try_into()
+}
+
+async fn search_transactions(
+    app: &AppState,
+    caller: &Caller,
+    search: TransactionSearch,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut page = {
+        let app_access = app.lock().await;
+        app_access
+            .transactions
+            .search(&search.filter, search.cursor.as_ref(), search.limit)
+            .await?
+    };
+    if caller.sees_masked_data() {
+        page.transactions.iter_mut().for_each(Mask::mask);
+    }
+    Ok(Json(TransactionListResponse::from(&page)).into_response())
+}
@@ -0,0 +1,69 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    requests::limits::LimitsRequest,
+    responses::limits::LimitsResponse,
+};
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_limits(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let limits = {
+        let app_access = app.lock().await;
+        app_access.limits.find(&merchant_id).await?
+    };
+    Ok(Json(LimitsResponse::from(&limits)).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_put_limits(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    Json(payload): Json<LimitsRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    check_limit(
+        "requests_per_minute",
+        payload.requests_per_minute.map(u64::from),
+        i32::MAX as u64,
+    )?;
+    check_limit(
+        "max_card_transactions_per_hour",
+        payload.max_card_transactions_per_hour.map(u64::from),
+        i32::MAX as u64,
+    )?;
+    check_limit(
+        "max_card_amount_per_hour",
+        payload.max_card_amount_per_hour,
+        i64::MAX as u64,
+    )?;
+    let limits = payload.into_limits(merchant_id);
+    {
+        let app_access = app.lock().await;
+        app_access.limits.upsert(&limits).await?;
+    }
+    info!(?limits, "merchant limits updated");
+    Ok(Json(LimitsResponse::from(&limits)).into_response())
+}
+
+/// Limits have to fit in their database columns, and a limit of 0 would block the merchant
+fn check_limit(name: &str, limit: Option<u64>, max: u64) -> Result<(), GatewayError> {
+    match limit {
+        Some(limit) if limit == 0 || limit > max => Err(GatewayError {
+            kind: ErrorKind::Validation,
+            message: format!("{name} must be between 1 and {max}"),
+        }),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,146 @@
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use gw_core::{merchant::Merchant, repo::Repo, transaction::search::MAX_PAGE_SIZE};
+use tracing::{info, instrument};
+use validify::Validify;
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    permission::Mask,
+    requests::merchant::{MerchantDetailsRequest, MerchantListRequest, MerchantRequest},
+    responses::merchant::{MerchantListResponse, MerchantResponse},
+};
+
+#[instrument(skip(app, payload), fields(merchant_id = %payload.merchant_id), err(Display))]
+pub async fn handle_post_merchant(
+    State(app): State<AppState>,
+    Json(payload): Json<MerchantRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut merchant = Merchant::try_from(payload)?;
+    merchant.validify()?;
+    {
+        let app_access = app.lock().await;
+        app_access
+            .merchants
+            .insert_one(&merchant)
+            .await
+            .map_err(|e| match GatewayError::from(e) {
+                GatewayError {
+                    kind: ErrorKind::Conflict,
+                    ..
+                } => GatewayError {
+                    kind: ErrorKind::Conflict,
+                    message: format!("merchant {} already exists", merchant.merchant_id),
+                },
+                other => other,
+            })?;
+    }
+    info!("merchant created");
+    Ok((StatusCode::CREATED, Json(MerchantResponse::from(&merchant))).into_response())
+}
+
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_merchant(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut merchant = find_merchant(&app, &merchant_id).await?;
+    if caller.sees_masked_data() {
+        merchant.mask();
+    }
+    Ok(Json(MerchantResponse::from(&merchant)).into_response())
+}
+
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_merchants(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    params: Result<Query<MerchantListRequest>, QueryRejection>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let Query(params) = params.map_err(|e| GatewayError {
+        kind: ErrorKind::Validation,
+        message: e.body_text(),
+    })?;
+    if params.limit == 0 || params.limit > MAX_PAGE_SIZE {
+        return Err(GatewayError {
+            kind: ErrorKind::Validation,
+            message: format!("limit must be between 1 and {MAX_PAGE_SIZE}"),
+        });
+    }
+    let mut page = {
+        let app_access = app.lock().await;
+        app_access
+            .merchants
+            .list(params.active, params.cursor.as_deref(), params.limit)
+            .await?
+    };
+    if caller.sees_masked_data() {
+        page.merchants.iter_mut().for_each(Mask::mask);
+    }
+    Ok(Json(MerchantListResponse::from(&page)).into_response())
+}
+
+/// Replaces a merchant's details, whether it is active is left as it is
+#[instrument(skip(app, payload), err(Display))]
+pub async fn handle_put_merchant(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    Json(payload): Json<MerchantDetailsRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let existing = find_merchant(&app, &merchant_id).await?;
+    let mut merchant = payload.into_merchant(merchant_id, existing.active)?;
+    merchant.validify()?;
+    {
+        let app_access = app.lock().await;
+        app_access
+            .merchants
+            .update_one(&merchant.merchant_id, &merchant)
+            .await?;
+    }
+    info!("merchant updated");
+    Ok(Json(MerchantResponse::from(&merchant)).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_deactivate_merchant(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    {
+        let app_access = app.lock().await;
+        app_access
+            .merchants
+            .deactivate(&merchant_id)
+            .await
+            .map_err(|_| not_found(&merchant_id))?;
+    }
+    info!("merchant deactivated");
+    let merchant = find_merchant(&app, &merchant_id).await?;
+    Ok(Json(MerchantResponse::from(&merchant)).into_response())
+}
+
+pub(crate) async fn find_merchant(
+    app: &AppState,
+    merchant_id: &str,
+) -> Result<Merchant, GatewayError> {
+    let app_access = app.lock().await;
+    app_access
+        .merchants
+        .find(merchant_id)
+        .await
+        .map_err(|_| not_found(merchant_id))
+}
+
+fn not_found(merchant_id: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Resource,
+        message: format!("merchant {merchant_id} does not exist"),
+    }
+}
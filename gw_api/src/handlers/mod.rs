@@ -1 +1,18 @@
+pub mod accounts;
+pub mod api_keys;
+pub mod block_list;
+pub mod customers;
+pub mod disputes;
+pub mod fraud;
+pub mod get_transactions;
+pub mod limits;
+pub mod merchants;
+pub mod notifications;
+pub mod payment_routes;
+pub mod policy;
 pub mod post_transaction;
+pub mod sca;
+pub mod signing;
+pub mod subscriptions;
+pub mod three_ds;
+pub mod webhooks;
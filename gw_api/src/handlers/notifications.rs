@@ -0,0 +1,156 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, State},
+    http::HeaderMap,
+    response::IntoResponse,
+    Extension, Json,
+};
+use gw_core::notification::{AcquirerNotification, NotificationSource};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    handlers::disputes,
+    permission::Mask,
+    responses::notification::{NotificationAckResponse, NotificationResponse},
+    webhooks,
+};
+
+/// Takes in a change to a transaction pushed by its acquirer. The acquirer signs the body
+/// rather than using an API key. Each notification is kept, and is only applied the first time
+/// it's received, so acquirers can redeliver freely. Notifications that can't be applied are
+/// still acknowledged, so the acquirer doesn't keep sending them. Chargebacks sent with their
+/// case also open a dispute, see [`disputes::ingest`].
+#[instrument(skip(app, headers, body), err(Display))]
+pub async fn handle_post_notification(
+    State(app): State<AppState>,
+    Path(acquirer): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<impl IntoResponse, GatewayError> {
+    let source = NotificationSource::try_from(acquirer).map_err(|e| GatewayError {
+        kind: ErrorKind::Resource,
+        message: e.message,
+    })?;
+    let (secret, now) = {
+        let app_access = app.lock().await;
+        let secret = app_access.notification_secrets.get(&source).cloned();
+        (secret, app_access.clock.now())
+    };
+    let header = source.signature_header();
+    let signature = headers
+        .get(header)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| GatewayError {
+            kind: ErrorKind::Signature,
+            message: format!("{header} header is missing"),
+        })?;
+    if !secret.is_some_and(|secret| source.verify(&secret, &body, signature)) {
+        return Err(GatewayError {
+            kind: ErrorKind::Signature,
+            message: "signature does not match".into(),
+        });
+    }
+    let raw = String::from_utf8(body.to_vec()).map_err(|_| GatewayError {
+        kind: ErrorKind::Validation,
+        message: "notification is not valid: not utf-8".into(),
+    })?;
+    let mut notification = source.parse(raw, now).map_err(|e| GatewayError {
+        kind: ErrorKind::Validation,
+        message: e.message,
+    })?;
+    let dispute = source
+        .dispute_report(&notification)
+        .map_err(|e| GatewayError {
+            kind: ErrorKind::Validation,
+            message: e.message,
+        })?;
+    // held throughout, so a redelivery arriving at the same time waits and is seen as one
+    let app_access = app.lock().await;
+    if let Some(received) = app_access
+        .notifications
+        .find(&notification.acquirer, &notification.notification_id)
+        .await?
+    {
+        info!(notification_id = %received.notification_id, "notification redelivered");
+        return Ok(acknowledge(&received, true));
+    }
+    let transaction = app_access
+        .transactions
+        .find_summary(&notification.transaction_reference)
+        .await?;
+    let status = notification.apply(transaction.as_ref());
+    if !app_access.notifications.insert(&notification).await? {
+        // another gateway took it first
+        return Ok(acknowledge(&notification, true));
+    }
+    info!(
+        notification_id = %notification.notification_id,
+        reference = %notification.transaction_reference,
+        kind = %notification.kind,
+        outcome = %notification.outcome,
+        detail = notification.detail,
+        "notification received"
+    );
+    let changed = match transaction.zip(status) {
+        Some((mut transaction, status)) => {
+            app_access
+                .transactions
+                .update_status(&transaction.reference, &status)
+                .await?;
+            transaction.status = status;
+            Some(transaction)
+        }
+        None => None,
+    };
+    drop(app_access);
+    // the change has been made, so the acquirer mustn't be told it failed
+    if let Some(transaction) = changed {
+        if let Err(e) = webhooks::notify_change(&app, &transaction).await {
+            warn!(error = %e, "webhook event not queued");
+        }
+    }
+    if let Some(report) = dispute {
+        // opened even when the transaction was already charged back, a transaction can be
+        // disputed more than once
+        if let Err(e) = disputes::ingest(&app, &report).await {
+            warn!(error = %e, case_id = %report.case_id, "dispute not opened");
+        }
+    }
+    Ok(acknowledge(&notification, false))
+}
+
+/// The notifications a transaction's acquirer has sent about it, oldest first
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_transaction_notifications(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(reference): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut notifications = {
+        let app_access = app.lock().await;
+        app_access
+            .notifications
+            .list_for_transaction(&reference)
+            .await?
+    };
+    if caller.sees_masked_data() {
+        notifications.iter_mut().for_each(Mask::mask);
+    }
+    let response = notifications
+        .iter()
+        .map(NotificationResponse::from)
+        .collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+fn acknowledge(notification: &AcquirerNotification, duplicate: bool) -> axum::response::Response {
+    Json(NotificationAckResponse {
+        notification_id: &notification.notification_id,
+        outcome: notification.outcome.to_string(),
+        duplicate,
+    })
+    .into_response()
+}
@@ -0,0 +1,130 @@
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use gw_core::{
+    error::{DbErrorKind, ErrorKind as CoreErrorKind},
+    payment::Payment,
+    payment_route::PaymentRoute,
+};
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    requests::payment_route::{parse_route_key, RouteRequest, RouteResolveRequest},
+    responses::payment_route::{RouteDecisionResponse, RouteResponse},
+};
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_routes(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let routes = {
+        let app_access = app.lock().await;
+        app_access.routes.list_for(&merchant_id).await?
+    };
+    let response = routes.iter().map(RouteResponse::from).collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+/// Creates or replaces the route, the account must be one of the merchant's own
+#[instrument(skip(app), err(Display))]
+pub async fn handle_put_route(
+    State(app): State<AppState>,
+    Path((merchant_id, scheme, currency)): Path<(String, String, String)>,
+    Json(payload): Json<RouteRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let (scheme, currency) = parse_route_key(scheme, currency)?;
+    find_merchant(&app, &merchant_id).await?;
+    let route = PaymentRoute {
+        merchant_id,
+        scheme,
+        currency,
+        acquirer: payload.acquirer,
+        account_id: payload.account_id,
+    };
+    {
+        let app_access = app.lock().await;
+        app_access
+            .accounts
+            .find_for(&route.merchant_id, &route.acquirer, route.account_id)
+            .await
+            .map_err(|_| GatewayError {
+                kind: ErrorKind::Validation,
+                message: format!(
+                    "merchant {} has no {} account {}",
+                    route.merchant_id, route.acquirer, route.account_id
+                ),
+            })?;
+        app_access.routes.upsert(&route).await?;
+    }
+    info!("route updated");
+    Ok(Json(RouteResponse::from(&route)).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_route(
+    State(app): State<AppState>,
+    Path((merchant_id, scheme, currency)): Path<(String, String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let (scheme, currency) = parse_route_key(scheme, currency)?;
+    {
+        let app_access = app.lock().await;
+        app_access
+            .routes
+            .delete(&merchant_id, scheme, currency)
+            .await
+            .map_err(|e| match e.kind {
+                CoreErrorKind::Database(DbErrorKind::Query) => GatewayError {
+                    kind: ErrorKind::Resource,
+                    message: format!("merchant {merchant_id} has no {scheme} {currency} route"),
+                },
+                _ => GatewayError::from(e),
+            })?;
+    }
+    info!("route deleted");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Shows which account a payment would be sent to, without making a transaction
+#[instrument(skip(app), err(Display))]
+pub async fn handle_resolve_route(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    params: Result<Query<RouteResolveRequest>, QueryRejection>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let Query(params) = params.map_err(|e| GatewayError {
+        kind: ErrorKind::Validation,
+        message: e.body_text(),
+    })?;
+    let (scheme, currency) = parse_route_key(params.scheme, params.currency)?;
+    find_merchant(&app, &merchant_id).await?;
+    // only the scheme is used when choosing the account
+    let payment = Payment::Card {
+        scheme,
+        security_code: Default::default(),
+        expiry_date: (0, 0),
+        pan: String::new(),
+    };
+    let account = {
+        let app_access = app.lock().await;
+        app_access
+            .accounts
+            .select_for(&merchant_id, &payment, currency)
+            .await
+            .map_err(|e| match e.kind {
+                CoreErrorKind::Database(DbErrorKind::Query) => GatewayError {
+                    kind: ErrorKind::Resource,
+                    message: format!("merchant {merchant_id} has no {scheme} {currency} route"),
+                },
+                _ => GatewayError::from(e),
+            })?
+    };
+    Ok(Json(RouteDecisionResponse::new(scheme, currency, &account)).into_response())
+}
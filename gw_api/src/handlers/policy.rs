@@ -0,0 +1,40 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Json,
+};
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState, error::GatewayError, handlers::merchants::find_merchant,
+    requests::policy::PoliciesRequest, responses::policy::PoliciesResponse,
+};
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_policies(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let policies = {
+        let app_access = app.lock().await;
+        app_access.policies.find(&merchant_id).await?
+    };
+    Ok(Json(PoliciesResponse::from(&policies)).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_put_policies(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    Json(payload): Json<PoliciesRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let policies = payload.into_policies(merchant_id);
+    {
+        let app_access = app.lock().await;
+        app_access.policies.upsert(&policies).await?;
+    }
+    info!(?policies, "merchant policies updated");
+    Ok(Json(PoliciesResponse::from(&policies)).into_response())
+}
@@ -1,34 +1,155 @@
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Extension, Json};
 // use eval_macro::eval;
+use chrono::{TimeDelta, Utc};
 use gw_core::{
-    account::AcquirerAccount, billing::Billing, currency::Currency, merchant::Merchant,
-    payment::Payment, repo::Repo, transaction::transaction_builder::TransactionBuilder,
+    account::AcquirerAccount,
+    acquirer::{Acquirer, AuthorisationRequest},
+    billing::Billing,
+    block_list::{ListCheck, ListMatches},
+    currency::Currency,
+    customer::Customer,
+    fraud::{FraudCheck, FraudResult},
+    limits::{CardUsage, VelocityLimits},
+    merchant::Merchant,
+    payment::Payment,
+    payment_method::PaymentMethod,
+    repo::Repo,
+    sca::{ScaExemption, ScaIndicators},
+    three_ds::{Authentication, Challenge, DirectoryServer, PendingAuthentication},
+    transaction::{
+        transaction_builder::TransactionBuilder, Transaction, TransactionError, TransactionStatus,
+    },
 };
 use tokio::sync::Mutex;
-use tracing::instrument;
+use tracing::{info, instrument, warn};
 use validify::{Validate, Validify};
 
 use crate::{
     app::{AppState, AppStateInner},
+    auth::Caller,
     error::{ErrorKind, GatewayError},
-    requests::transaction::TransactionRequest,
+    handlers::customers::find_customer,
+    idempotency,
+    requests::transaction::{transaction_option::TransactionOptionRequest, TransactionRequest},
     responses::transaction::TransactionResponse,
+    webhooks,
 };
 
-#[instrument]
+#[instrument(skip(app, caller), fields(merchant_id = %payload.merchant_id), err(Display))]
 pub async fn handle_post_transaction(
     State(app): State<AppState>,
-    Json(mut payload): Json<TransactionRequest>,
+    Extension(caller): Extension<Caller>,
+    Json(payload): Json<TransactionRequest>,
 ) -> Result<impl IntoResponse, GatewayError> {
-    let payment = extract_payment_data(&mut payload)?;
-    payment.validate()?;
-    let billing = extract_billing_data(&mut payload)?;
-    // let customer = extract_customer_data(&mut payload)?;
+    caller.check(&payload.merchant_id)?;
+    let (transaction, options) = match prepare_transaction(&app, payload).await {
+        Ok(prepared) => prepared,
+        Err(e) => return Ok(idempotency::not_sent(e)),
+    };
+    let (transaction, challenge) = send_transaction(&app, transaction, options).await?;
+    let response = Json(TransactionResponse::from(&transaction)).into_response();
+    if let Some(challenge) = challenge {
+        // the card details are needed to authorise the transaction once the challenge is done
+        let pending = PendingAuthentication::new(transaction, challenge, Utc::now());
+        let app_access = app.lock().await;
+        app_access
+            .authentications
+            .insert(&pending, &app_access.payment_method_key)
+            .await?;
+    }
+    Ok((StatusCode::CREATED, response).into_response())
+}
+
+/// Checks, stores and authorises a transaction for the request's merchant, who the caller has
+/// already been checked against. Returns the 3DS challenge if the cardholder has one to complete.
+pub(crate) async fn process_transaction(
+    app: &AppState,
+    payload: TransactionRequest,
+) -> Result<(Transaction, Option<Challenge>), GatewayError> {
+    let (transaction, options) = prepare_transaction(app, payload).await?;
+    send_transaction(app, transaction, options).await
+}
+
+/// Checks and stores a transaction for the request's merchant. Nothing has been sent to the
+/// acquirer yet, so the request can be retried if this fails.
+async fn prepare_transaction(
+    app: &AppState,
+    mut payload: TransactionRequest,
+) -> Result<(Transaction, TransactionOptionRequest), GatewayError> {
+    let customer = match payload.customer_id.as_deref() {
+        Some(customer_id) => Some(find_customer(app, &payload.merchant_id, customer_id).await?),
+        None => None,
+    };
+    let (payment, saved) = match payload.payment_token.as_deref() {
+        Some(token) => {
+            let payment_given = payload.payment.is_some();
+            let (payment, saved) =
+                find_saved_payment(app, customer.as_ref(), token, payment_given).await?;
+            (payment, Some(saved))
+        }
+        None => {
+            let payment = extract_payment_data(&mut payload)?;
+            payment.validate()?;
+            (payment, None)
+        }
+    };
+    let billing = match &customer {
+        Some(customer) if payload.billing.is_none() => Billing::from(customer),
+        _ => extract_billing_data(&mut payload)?,
+    };
+    let ip_address = parse_ip_address(payload.ip_address.as_deref())?;
+    let options = payload.options.take().unwrap_or_default();
+    // only cards are stored credentials, the scheme transaction IDs are the card schemes'
+    let stored_credential = options
+        .stored_credential(saved.as_ref())?
+        .filter(|_| matches!(payment, Payment::Card { .. }));
     let merchant_id = payload.merchant_id;
-    let merchant = find_merchant(&app, &merchant_id).await?;
-    let account = find_account(&app, &merchant_id, &payment, payload.currency).await?;
+    let merchant = find_merchant(app, &merchant_id).await?;
+    let card_fingerprint = check_velocity(
+        app,
+        &merchant_id,
+        &payment,
+        payload.amount,
+        payload.currency,
+    )
+    .await?;
+    let lists = check_lists(
+        app,
+        &merchant_id,
+        &ListCheck {
+            card_fingerprint: card_fingerprint.as_deref(),
+            pan: match &payment {
+                Payment::Card { pan, .. } => Some(pan),
+                Payment::Account { .. } => None,
+            },
+            email: Some(&billing.email),
+            ip: ip_address,
+        },
+    )
+    .await?;
+    let blocked_by = lists.blocked_by();
+    // blocked transactions are declined anyway, and allowed ones skip the rules
+    let fraud = if blocked_by.is_some() || lists.allowed() {
+        None
+    } else {
+        screen_transaction(
+            app,
+            &merchant_id,
+            &FraudCheck {
+                amount: payload.amount,
+                currency: payload.currency,
+                payment: &payment,
+                billing: &billing,
+                bin_country: None,
+                card_fingerprint: card_fingerprint.as_deref(),
+                card_usage: CardUsage::default(),
+            },
+        )
+        .await?
+    };
+    let account = find_account(app, &merchant_id, &payment, payload.currency).await?;
     let mut transaction = {
         let tb = TransactionBuilder::new()
             .transaction_type(payload.transaction_type)
@@ -37,29 +158,100 @@ pub async fn handle_post_transaction(
             .payment(payment)
             .billing(billing)
             .merchant(merchant)
-            .account(account);
+            .account(account)
+            .merchant_reference(payload.merchant_reference)
+            .description(payload.description)
+            .metadata(payload.metadata)
+            .card_fingerprint(card_fingerprint)
+            .fraud(fraud)
+            .customer(customer)
+            .payment_token(payload.payment_token)
+            .stored_credential(stored_credential)
+            .subscription_charge(payload.subscription_charge);
         tb.build()
     };
+    if let Some(entry) = blocked_by {
+        info!(entry_id = %entry.id, reason = %entry.reason, "transaction blocked");
+        transaction.status = TransactionStatus::Failed(Some(TransactionError::Blocked));
+    }
     transaction.validify()?;
     {
         let _guard = app.lock().await;
         _guard.transactions.insert_one(&transaction).await?;
     }
-    // send the transaction off to acquirers etc.
-    {
+    Ok((transaction, options))
+}
+
+/// Authenticates the cardholder and authorises a stored transaction with the acquirer
+async fn send_transaction(
+    app: &AppState,
+    mut transaction: Transaction,
+    options: TransactionOptionRequest,
+) -> Result<(Transaction, Option<Challenge>), GatewayError> {
+    // authenticate the cardholder, unless an exemption means they don't have to be, then send the
+    // transaction off to the acquirer, unless it has already failed
+    let mut challenge = None;
+    if transaction.status == TransactionStatus::Success {
+        let is_card = matches!(transaction.payment, Payment::Card { .. });
+        if is_card {
+            transaction.sca_exemption =
+                choose_exemption(app, &transaction, options.sca_indicators()).await?;
+        }
+        if options.three_ds && is_card && transaction.sca_exemption.is_none() {
+            challenge = authenticate(app, &mut transaction).await?;
+        }
+        if transaction.status == TransactionStatus::Success {
+            authorise(app, &mut transaction).await?;
+        }
+        if transaction.needs_authentication_retry() {
+            info!(sca_exemption = ?transaction.sca_exemption, "exemption soft declined");
+            transaction.retry_with_authentication();
+            challenge = authenticate(app, &mut transaction).await?;
+            if transaction.status == TransactionStatus::Success {
+                authorise(app, &mut transaction).await?;
+            }
+        }
         let _guard = app.lock().await;
         _guard
             .transactions
             .update_one(&transaction.reference, &transaction)
             .await?;
     }
-    let response = TransactionResponse::from(&transaction);
-    Ok((StatusCode::CREATED, Json(response)).into_response())
+    info!(
+        reference = %transaction.reference,
+        status = %transaction.status,
+        fraud_decision = transaction.fraud.as_ref().map(|f| f.decision.to_string()),
+        avs_result = transaction.avs.as_ref().map(|avs| avs.code().to_string()),
+        cvv_result = transaction.cvv.map(|cvv| cvv.to_string()),
+        three_ds_status = transaction.three_ds.as_ref().map(|r| r.status.to_string()),
+        sca_exemption = transaction.sca_exemption.map(|e| e.to_string()),
+        stored_credential = transaction
+            .stored_credential
+            .as_ref()
+            .map(|c| format!("{} {}", c.usage, c.initiator)),
+        "transaction processed"
+    );
+    // the transaction may have been authorised, so the merchant mustn't be told it failed
+    if let Err(e) = webhooks::notify(app, &transaction).await {
+        warn!(error = %e, "webhook event not queued");
+    }
+    Ok((transaction, challenge))
+}
+
+fn parse_ip_address(ip_address: Option<&str>) -> Result<Option<IpAddr>, GatewayError> {
+    ip_address
+        .map(|ip| {
+            ip.trim().parse().map_err(|_| GatewayError {
+                kind: ErrorKind::Validation,
+                message: "ip_address is not a valid ip address".into(),
+            })
+        })
+        .transpose()
 }
 
 async fn find_merchant(
     app: &Arc<Mutex<AppStateInner>>,
-    id: &String,
+    id: &str,
 ) -> Result<Merchant, GatewayError> {
     let app_access = app.lock().await;
     let merchant_data = app_access
@@ -70,19 +262,225 @@ async fn find_merchant(
             kind: ErrorKind::Resource,
             message: format!("merchant {id} does not exist"),
         })?;
+    if !merchant_data.active {
+        return Err(GatewayError {
+            kind: ErrorKind::Validation,
+            message: format!("merchant {id} is not active"),
+        });
+    }
     Ok(merchant_data)
 }
 
+/// Decrypts one of the customer's saved payment methods to charge, saved cards are charged
+/// without a security code
+async fn find_saved_payment(
+    app: &Arc<Mutex<AppStateInner>>,
+    customer: Option<&Customer>,
+    token: &str,
+    payment_given: bool,
+) -> Result<(Payment, PaymentMethod), GatewayError> {
+    let Some(customer) = customer else {
+        return Err(GatewayError {
+            kind: ErrorKind::Validation,
+            message: "payment_token can only be used with a customer_id".into(),
+        });
+    };
+    if payment_given {
+        return Err(GatewayError {
+            kind: ErrorKind::Validation,
+            message: "only one of payment and payment_token can be given".into(),
+        });
+    }
+    let app_access = app.lock().await;
+    let method = app_access
+        .customers
+        .find_payment_method(&customer.merchant_id, &customer.customer_id, token)
+        .await
+        .map_err(|_| GatewayError {
+            kind: ErrorKind::Resource,
+            message: format!(
+                "customer {} has no payment method {token}",
+                customer.customer_id
+            ),
+        })?;
+    let payment = method.payment(&app_access.payment_method_key)?;
+    Ok((payment, method))
+}
+
+/// Stops a card being used too much with the merchant, before a route is found for it. Returns
+/// the card's fingerprint so it can be stored with the transaction.
+async fn check_velocity(
+    app: &Arc<Mutex<AppStateInner>>,
+    merchant_id: &str,
+    payment: &Payment,
+    amount: u64,
+    currency: Currency,
+) -> Result<Option<String>, GatewayError> {
+    let app_access = app.lock().await;
+    let Some(fingerprint) = payment.card_fingerprint(&app_access.card_fingerprint_key) else {
+        return Ok(None);
+    };
+    let limits = app_access.limits.find(merchant_id).await?.velocity;
+    if limits != VelocityLimits::default() {
+        let usage = app_access
+            .transactions
+            .card_usage(
+                merchant_id,
+                &fingerprint,
+                currency,
+                Utc::now() - TimeDelta::hours(1),
+            )
+            .await?;
+        limits.check(&usage, amount, currency)?;
+    }
+    Ok(Some(fingerprint))
+}
+
+/// Scores the transaction against the merchant's fraud rules, if they have any. The check's bin
+/// country and card usage are filled in here, and only looked up when needed.
+async fn screen_transaction(
+    app: &Arc<Mutex<AppStateInner>>,
+    merchant_id: &str,
+    check: &FraudCheck<'_>,
+) -> Result<Option<FraudResult>, GatewayError> {
+    let app_access = app.lock().await;
+    let settings = match app_access.fraud.find(merchant_id).await? {
+        Some(settings) if !settings.rules.is_empty() => settings,
+        _ => return Ok(None),
+    };
+    let bin_country = match check.payment {
+        Payment::Card { pan, .. } => app_access.fraud.bin_country(pan).await?,
+        Payment::Account { .. } => None,
+    };
+    let card_usage = match check.card_fingerprint {
+        Some(fingerprint) if settings.needs_card_usage() => {
+            app_access
+                .transactions
+                .card_usage(
+                    merchant_id,
+                    fingerprint,
+                    check.currency,
+                    Utc::now() - TimeDelta::hours(1),
+                )
+                .await?
+        }
+        _ => CardUsage::default(),
+    };
+    let result = settings.screen(&FraudCheck {
+        bin_country,
+        card_usage,
+        ..*check
+    });
+    info!(score = result.score, decision = %result.decision, rules = ?result.matched_rules, "fraud screening");
+    Ok(Some(result))
+}
+
+/// Finds the block and allow list entries the transaction matches
+async fn check_lists(
+    app: &Arc<Mutex<AppStateInner>>,
+    merchant_id: &str,
+    check: &ListCheck<'_>,
+) -> Result<ListMatches, GatewayError> {
+    let app_access = app.lock().await;
+    let matches = app_access
+        .block_lists
+        .find_matches(merchant_id, check, Utc::now())
+        .await?;
+    Ok(matches)
+}
+
+/// The SCA exemption to send to the acquirer, from the merchant's thresholds for the currency
+async fn choose_exemption(
+    app: &Arc<Mutex<AppStateInner>>,
+    transaction: &Transaction,
+    indicators: ScaIndicators,
+) -> Result<Option<ScaExemption>, GatewayError> {
+    let app_access = app.lock().await;
+    let Some(thresholds) = app_access
+        .sca
+        .find(&transaction.merchant.merchant_id, transaction.currency)
+        .await?
+    else {
+        return Ok(None);
+    };
+    Ok(thresholds.choose(
+        transaction.amount.value(),
+        indicators,
+        transaction.fraud.as_ref(),
+    ))
+}
+
+/// Authenticates the cardholder with the directory server. Returns the challenge if the issuer
+/// wants one, leaving the transaction pending until it's done.
+async fn authenticate(
+    app: &Arc<Mutex<AppStateInner>>,
+    transaction: &mut Transaction,
+) -> Result<Option<Challenge>, GatewayError> {
+    let app_access = app.lock().await;
+    match app_access
+        .directory_server
+        .authenticate(transaction)
+        .await?
+    {
+        Authentication::Frictionless(result) => {
+            info!(status = %result.status, "frictionless authentication");
+            transaction.apply_authentication(result);
+            Ok(None)
+        }
+        Authentication::Challenge(challenge) => {
+            info!(ds_transaction_id = %challenge.ds_transaction_id, "authentication challenged");
+            transaction.challenge(&challenge);
+            Ok(Some(challenge))
+        }
+    }
+}
+
+/// Has the acquirer authorise the transaction, then applies the merchant's policies to the
+/// results of the acquirer's checks, voiding the authorisation if they fail it. The saved payment
+/// method an initial stored credential transaction is paid with is chained to it.
+pub(crate) async fn authorise(
+    app: &Arc<Mutex<AppStateInner>>,
+    transaction: &mut Transaction,
+) -> Result<(), GatewayError> {
+    let app_access = app.lock().await;
+    let response = app_access
+        .acquirer
+        .authorise(&AuthorisationRequest::from(&*transaction))
+        .await?;
+    let policies = app_access
+        .policies
+        .find(&transaction.merchant.merchant_id)
+        .await?;
+    if transaction.apply_authorisation(response, &policies)? {
+        app_access.acquirer.void(transaction).await?;
+        info!(error = ?transaction.status, "authorisation voided");
+    }
+    if let (Some(token), Some(scheme_transaction_id)) = (
+        transaction.payment_token.as_deref(),
+        transaction.scheme_transaction_id_to_chain(),
+    ) {
+        app_access
+            .customers
+            .chain_payment_method(
+                &transaction.merchant.merchant_id,
+                token,
+                scheme_transaction_id,
+            )
+            .await?;
+    }
+    Ok(())
+}
+
 async fn find_account(
     app: &Arc<Mutex<AppStateInner>>,
-    merchant_id: &String,
+    merchant_id: &str,
     payment_data: &Payment,
     currency: Currency,
 ) -> Result<AcquirerAccount, GatewayError> {
     let app_access = app.lock().await;
     let account_data = app_access
         .accounts
-        .select_for(&merchant_id, &payment_data, currency)
+        .select_for(merchant_id, payment_data, currency)
         .await?;
     Ok(account_data)
 }
@@ -95,7 +493,7 @@ fn extract_billing_data(payload: &mut TransactionRequest) -> Result<Billing, Gat
     extract_trx_data(payload, TransactionRequest::take_billing_data, "billing")
 }
 
-#[instrument]
+#[instrument(skip(payload, extract_fn))]
 fn extract_trx_data<T, R>(
     payload: &mut TransactionRequest,
     extract_fn: fn(&mut TransactionRequest) -> Option<R>,
@@ -0,0 +1,98 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use gw_core::currency::Currency;
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    requests::sca::ScaThresholdsRequest,
+    responses::sca::ScaThresholdsResponse,
+};
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_sca_thresholds(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let thresholds = {
+        let app_access = app.lock().await;
+        app_access.sca.list_for(&merchant_id).await?
+    };
+    let response = thresholds
+        .iter()
+        .map(ScaThresholdsResponse::from)
+        .collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_put_sca_thresholds(
+    State(app): State<AppState>,
+    Path((merchant_id, currency)): Path<(String, String)>,
+    Json(payload): Json<ScaThresholdsRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let currency = parse_currency(currency)?;
+    find_merchant(&app, &merchant_id).await?;
+    for (name, threshold) in [
+        ("low_value", payload.low_value),
+        (
+            "transaction_risk_analysis",
+            payload.transaction_risk_analysis,
+        ),
+        ("merchant_initiated", payload.merchant_initiated),
+        ("recurring", payload.recurring),
+        ("corporate", payload.corporate),
+    ] {
+        // thresholds have to fit in their database columns
+        if threshold.is_some_and(|t| t > i64::MAX as u64) {
+            return Err(GatewayError {
+                kind: ErrorKind::Validation,
+                message: format!("{name} must be at most {}", i64::MAX),
+            });
+        }
+    }
+    let thresholds = payload.into_thresholds(merchant_id, currency);
+    {
+        let app_access = app.lock().await;
+        app_access.sca.upsert(&thresholds).await?;
+    }
+    info!(?thresholds, "sca thresholds updated");
+    Ok(Json(ScaThresholdsResponse::from(&thresholds)).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_sca_thresholds(
+    State(app): State<AppState>,
+    Path((merchant_id, currency)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let currency = parse_currency(currency)?;
+    {
+        let app_access = app.lock().await;
+        app_access
+            .sca
+            .delete(&merchant_id, currency)
+            .await
+            .map_err(|_| GatewayError {
+                kind: ErrorKind::Resource,
+                message: format!("merchant {merchant_id} has no {currency} sca thresholds"),
+            })?;
+    }
+    info!("sca thresholds deleted");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn parse_currency(currency: String) -> Result<Currency, GatewayError> {
+    currency
+        .try_into()
+        .map_err(|e: gw_core::error::Error| GatewayError {
+            kind: ErrorKind::Validation,
+            message: e.message,
+        })
+}
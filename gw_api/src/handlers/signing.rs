@@ -0,0 +1,55 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use gw_core::signing::generate_secret;
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    responses::signing::SigningSecretResponse,
+};
+
+/// Creates or replaces the merchant's signing secret, from then on they must sign their requests
+#[instrument(skip(app), err(Display))]
+pub async fn handle_put_signing_secret(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let secret = generate_secret();
+    {
+        let app_access = app.lock().await;
+        app_access.signing.set_secret(&merchant_id, &secret).await?;
+    }
+    info!("signing secret set");
+    let response = SigningSecretResponse {
+        signing_secret: secret.expose(),
+    };
+    Ok((StatusCode::CREATED, Json(response)).into_response())
+}
+
+/// Removes the merchant's signing secret, so they no longer need to sign their requests
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_signing_secret(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    {
+        let app_access = app.lock().await;
+        app_access
+            .signing
+            .remove_secret(&merchant_id)
+            .await
+            .map_err(|_| GatewayError {
+                kind: ErrorKind::Resource,
+                message: format!("merchant {merchant_id} has no signing secret"),
+            })?;
+    }
+    info!("signing secret removed");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
@@ -0,0 +1,182 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use gw_core::subscription::{Plan, Subscription, SubscriptionStatus};
+use tracing::{info, instrument};
+use validify::Validify;
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    handlers::{customers::find_customer, merchants::find_merchant},
+    permission::Mask,
+    requests::subscription::{PlanRequest, SubscriptionRequest},
+    responses::subscription::{PlanResponse, SubscriptionResponse},
+};
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_post_plan(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    Json(payload): Json<PlanRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let mut plan = payload.into_plan(merchant_id)?;
+    plan.validify()?;
+    {
+        let app_access = app.lock().await;
+        app_access.subscriptions.insert_plan(&plan).await?;
+    }
+    info!(plan_id = %plan.plan_id, "plan created");
+    Ok((StatusCode::CREATED, Json(PlanResponse::from(&plan))).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_plans(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let plans = {
+        let app_access = app.lock().await;
+        app_access.subscriptions.list_plans(&merchant_id).await?
+    };
+    let response = plans.iter().map(PlanResponse::from).collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_plan(
+    State(app): State<AppState>,
+    Path((merchant_id, plan_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let plan = find_plan(&app, &merchant_id, &plan_id).await?;
+    Ok(Json(PlanResponse::from(&plan)).into_response())
+}
+
+/// Subscribes the customer to the plan, they're first charged once any trial is over
+#[instrument(skip(app), err(Display))]
+pub async fn handle_post_subscription(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    Json(payload): Json<SubscriptionRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let plan = find_plan(&app, &merchant_id, &payload.plan_id).await?;
+    let customer = find_customer(&app, &merchant_id, &payload.customer_id).await?;
+    let subscription = {
+        let app_access = app.lock().await;
+        app_access
+            .customers
+            .find_payment_method(&merchant_id, &customer.customer_id, &payload.payment_token)
+            .await
+            .map_err(|_| GatewayError {
+                kind: ErrorKind::Resource,
+                message: format!(
+                    "customer {} has no payment method {}",
+                    customer.customer_id, payload.payment_token
+                ),
+            })?;
+        let subscription = Subscription::new(
+            &plan,
+            customer.customer_id,
+            payload.payment_token,
+            app_access.clock.now(),
+        );
+        app_access.subscriptions.insert(&subscription).await?;
+        subscription
+    };
+    info!(subscription_id = %subscription.subscription_id, "subscription created");
+    Ok((
+        StatusCode::CREATED,
+        Json(SubscriptionResponse::from(&subscription)),
+    )
+        .into_response())
+}
+
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_subscriptions(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    find_merchant(&app, &merchant_id).await?;
+    let mut subscriptions = {
+        let app_access = app.lock().await;
+        app_access.subscriptions.list_for(&merchant_id).await?
+    };
+    if caller.sees_masked_data() {
+        subscriptions.iter_mut().for_each(Mask::mask);
+    }
+    let response = subscriptions
+        .iter()
+        .map(SubscriptionResponse::from)
+        .collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_subscription(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path((merchant_id, subscription_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut subscription = find_subscription(&app, &merchant_id, &subscription_id).await?;
+    if caller.sees_masked_data() {
+        subscription.mask();
+    }
+    Ok(Json(SubscriptionResponse::from(&subscription)).into_response())
+}
+
+/// Stops the subscription being charged, it can't be started again
+#[instrument(skip(app), err(Display))]
+pub async fn handle_cancel_subscription(
+    State(app): State<AppState>,
+    Path((merchant_id, subscription_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let mut subscription = find_subscription(&app, &merchant_id, &subscription_id).await?;
+    if subscription.status == SubscriptionStatus::Cancelled {
+        return Err(GatewayError {
+            kind: ErrorKind::Conflict,
+            message: format!("subscription {subscription_id} is already cancelled"),
+        });
+    }
+    {
+        let app_access = app.lock().await;
+        subscription.cancel(app_access.clock.now());
+        app_access.subscriptions.update(&subscription).await?;
+    }
+    info!("subscription cancelled");
+    Ok(Json(SubscriptionResponse::from(&subscription)).into_response())
+}
+
+async fn find_plan(app: &AppState, merchant_id: &str, plan_id: &str) -> Result<Plan, GatewayError> {
+    let app_access = app.lock().await;
+    app_access
+        .subscriptions
+        .find_plan(merchant_id, plan_id)
+        .await
+        .map_err(|_| GatewayError {
+            kind: ErrorKind::Resource,
+            message: format!("plan {plan_id} does not exist"),
+        })
+}
+
+async fn find_subscription(
+    app: &AppState,
+    merchant_id: &str,
+    subscription_id: &str,
+) -> Result<Subscription, GatewayError> {
+    let app_access = app.lock().await;
+    app_access
+        .subscriptions
+        .find(merchant_id, subscription_id)
+        .await
+        .map_err(|_| GatewayError {
+            kind: ErrorKind::Resource,
+            message: format!("subscription {subscription_id} does not exist"),
+        })
+}
@@ -0,0 +1,104 @@
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use chrono::Utc;
+use gw_core::{repo::Repo, three_ds::DirectoryServer, transaction::TransactionStatus};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    handlers::post_transaction::authorise,
+    requests::three_ds::ChallengeResultRequest,
+    responses::{three_ds::ChallengeResponse, transaction::TransactionResponse},
+    webhooks,
+};
+
+fn no_challenge(reference: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Resource,
+        message: format!("transaction {reference} is not waiting on a challenge"),
+    }
+}
+
+/// The challenge for one of the caller's transactions, so the cardholder can be sent to the ACS
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_challenge(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(reference): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let merchant_id = caller.merchant_id.unwrap_or_default();
+    let app_access = app.lock().await;
+    let pending = app_access
+        .authentications
+        .find(
+            &reference,
+            &merchant_id,
+            Utc::now(),
+            &app_access.payment_method_key,
+        )
+        .await?
+        .ok_or_else(|| no_challenge(&reference))?;
+    Ok(Json(ChallengeResponse::from(&pending)).into_response())
+}
+
+/// Completes a challenge with the ACS's challenge response, authorising the transaction if the
+/// cardholder passed it. The security code wasn't kept during the challenge, so it's authorised
+/// without one.
+#[instrument(skip(app, caller, payload), err(Display))]
+pub async fn handle_post_challenge_result(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(reference): Path<String>,
+    Json(payload): Json<ChallengeResultRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let merchant_id = caller.merchant_id.unwrap_or_default();
+    let pending = {
+        let app_access = app.lock().await;
+        app_access
+            .authentications
+            .take(
+                &reference,
+                &merchant_id,
+                Utc::now(),
+                &app_access.payment_method_key,
+            )
+            .await?
+            .ok_or_else(|| no_challenge(&reference))?
+    };
+    let result = {
+        let app_access = app.lock().await;
+        app_access
+            .directory_server
+            .challenge_result(&pending, &payload.cres)
+            .await?
+    };
+    let mut transaction = pending.transaction;
+    // the challenge is done, so the transaction carries on from where it was left
+    transaction.status = TransactionStatus::Success;
+    transaction.apply_authentication(result);
+    if transaction.status == TransactionStatus::Success {
+        authorise(&app, &mut transaction).await?;
+    }
+    {
+        let app_access = app.lock().await;
+        app_access
+            .transactions
+            .update_one(&transaction.reference, &transaction)
+            .await?;
+    }
+    info!(
+        status = %transaction.status,
+        three_ds_status = transaction.three_ds.as_ref().map(|r| r.status.to_string()),
+        "challenge completed"
+    );
+    // the transaction may have been authorised, so the merchant mustn't be told it failed
+    if let Err(e) = webhooks::notify(&app, &transaction).await {
+        warn!(error = %e, "webhook event not queued");
+    }
+    Ok(Json(TransactionResponse::from(&transaction)).into_response())
+}
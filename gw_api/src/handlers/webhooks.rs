@@ -0,0 +1,161 @@
+use axum::{
+    extract::{rejection::QueryRejection, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use gw_core::{
+    transaction::search::MAX_PAGE_SIZE,
+    webhook::{DeliveryStatus, WebhookEndpoint},
+};
+use tracing::{info, instrument};
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+    handlers::merchants::find_merchant,
+    permission::Mask,
+    requests::webhook::{WebhookEndpointRequest, WebhookEventListRequest},
+    responses::webhook::{WebhookEndpointResponse, WebhookEventResponse},
+};
+
+#[instrument(skip(app), err(Display))]
+pub async fn handle_get_webhook(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let endpoint = find_endpoint(&app, &merchant_id).await?;
+    Ok(Json(WebhookEndpointResponse::from(&endpoint)).into_response())
+}
+
+/// Sets where the merchant's events are sent. A new endpoint gets a signing secret, which is
+/// only returned now, moving an endpoint keeps its secret.
+#[instrument(skip(app), err(Display))]
+pub async fn handle_put_webhook(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+    Json(payload): Json<WebhookEndpointRequest>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let url = payload.url()?;
+    find_merchant(&app, &merchant_id).await?;
+    let app_access = app.lock().await;
+    match app_access.webhooks.find_endpoint(&merchant_id).await? {
+        Some(mut endpoint) => {
+            app_access
+                .webhooks
+                .update_url(&merchant_id, url.as_str())
+                .await?;
+            endpoint.url = url.into();
+            info!(url = %endpoint.url, "webhook endpoint moved");
+            Ok(Json(WebhookEndpointResponse::from(&endpoint)).into_response())
+        }
+        None => {
+            let endpoint = WebhookEndpoint::new(merchant_id, url.into());
+            app_access.webhooks.insert_endpoint(&endpoint).await?;
+            info!(url = %endpoint.url, "webhook endpoint created");
+            let response = WebhookEndpointResponse {
+                signing_secret: Some(endpoint.secret.expose()),
+                ..WebhookEndpointResponse::from(&endpoint)
+            };
+            Ok((StatusCode::CREATED, Json(response)).into_response())
+        }
+    }
+}
+
+/// Stops sending the merchant events, the ones already queued wait for a new endpoint
+#[instrument(skip(app), err(Display))]
+pub async fn handle_delete_webhook(
+    State(app): State<AppState>,
+    Path(merchant_id): Path<String>,
+) -> Result<impl IntoResponse, GatewayError> {
+    {
+        let app_access = app.lock().await;
+        app_access
+            .webhooks
+            .delete_endpoint(&merchant_id)
+            .await
+            .map_err(|_| no_endpoint(&merchant_id))?;
+    }
+    info!("webhook endpoint removed");
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// The dead-letter view, the events that ran out of delivery attempts, newest first
+#[instrument(skip(app, caller), err(Display))]
+pub async fn handle_get_dead_events(
+    State(app): State<AppState>,
+    Extension(caller): Extension<Caller>,
+    Path(merchant_id): Path<String>,
+    params: Result<Query<WebhookEventListRequest>, QueryRejection>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let Query(params) = params.map_err(|e| GatewayError {
+        kind: ErrorKind::Validation,
+        message: e.body_text(),
+    })?;
+    if params.limit == 0 || params.limit > MAX_PAGE_SIZE {
+        return Err(GatewayError {
+            kind: ErrorKind::Validation,
+            message: format!("limit must be between 1 and {MAX_PAGE_SIZE}"),
+        });
+    }
+    find_merchant(&app, &merchant_id).await?;
+    let mut events = {
+        let app_access = app.lock().await;
+        app_access
+            .webhooks
+            .list_events(&merchant_id, DeliveryStatus::Dead, params.limit.into())
+            .await?
+    };
+    if caller.sees_masked_data() {
+        events.iter_mut().for_each(Mask::mask);
+    }
+    let response = events
+        .iter()
+        .map(WebhookEventResponse::from)
+        .collect::<Vec<_>>();
+    Ok(Json(response).into_response())
+}
+
+/// Sends a dead-lettered event again, with a fresh set of attempts
+#[instrument(skip(app), err(Display))]
+pub async fn handle_retry_event(
+    State(app): State<AppState>,
+    Path((merchant_id, event_id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, GatewayError> {
+    let app_access = app.lock().await;
+    let mut event = app_access
+        .webhooks
+        .find_event(&merchant_id, &event_id)
+        .await
+        .map_err(|_| GatewayError {
+            kind: ErrorKind::Resource,
+            message: format!("webhook event {event_id} does not exist"),
+        })?;
+    if event.status != DeliveryStatus::Dead {
+        return Err(GatewayError {
+            kind: ErrorKind::Conflict,
+            message: format!("webhook event {event_id} is {}, not dead", event.status),
+        });
+    }
+    event.requeue(app_access.clock.now());
+    app_access.webhooks.update_event(&event).await?;
+    info!(event_id = %event.event_id, "webhook event requeued");
+    Ok(Json(WebhookEventResponse::from(&event)).into_response())
+}
+
+async fn find_endpoint(app: &AppState, merchant_id: &str) -> Result<WebhookEndpoint, GatewayError> {
+    let app_access = app.lock().await;
+    app_access
+        .webhooks
+        .find_endpoint(merchant_id)
+        .await?
+        .ok_or_else(|| no_endpoint(merchant_id))
+}
+
+fn no_endpoint(merchant_id: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Resource,
+        message: format!("merchant {merchant_id} has no webhook endpoint"),
+    }
+}
@@ -0,0 +1,194 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::time::Duration;
+
+use gw_core::{
+    idempotency::{hash_request, Reservation, StoredResponse},
+    repo::idempotency::IdempotencyRepo,
+};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+};
+
+pub static IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+pub static IDEMPOTENT_REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+
+/// Keys are remembered for 24 hours
+pub const RETENTION_SECS: i64 = 24 * 60 * 60;
+
+/// A request still being processed holds its key for 5 minutes, and renews it every third of
+/// that while it's processed. A retry once it hasn't been renewed for the whole lease is
+/// processed again, as the gateway stopped part way through the first attempt.
+pub const LEASE_SECS: i64 = 5 * 60;
+
+/// Marks a response to a request that never reached the acquirer. Only these are released
+/// when they fail, so the merchant can retry with the same key. Any other failure is stored
+/// and replayed, as the card may have been charged.
+#[derive(Debug, Clone, Copy)]
+pub struct NotSentToAcquirer;
+
+/// The error's response, marked as [`NotSentToAcquirer`]
+pub fn not_sent(error: GatewayError) -> Response {
+    let mut response = error.into_response();
+    response.extensions_mut().insert(NotSentToAcquirer);
+    response
+}
+
+const MAX_KEY_LENGTH: usize = 255;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Middleware making requests that carry an Idempotency-Key header safe to retry. The first
+/// response for a merchant's key is stored and replayed for any repeat within the retention
+/// window, and reusing a key with a different body is rejected as a conflict.
+pub async fn idempotent(
+    State(app): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, GatewayError> {
+    let Some(key) = request.headers().get(&IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(next.run(request).await);
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key.to_string(),
+        _ => {
+            return Err(GatewayError {
+                kind: ErrorKind::Validation,
+                message: format!(
+                    "Idempotency-Key must be between 1 and {MAX_KEY_LENGTH} visible characters"
+                ),
+            })
+        }
+    };
+    let (parts, body) = request.into_parts();
+    let bytes = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|e| GatewayError {
+            kind: ErrorKind::Validation,
+            message: e.to_string(),
+        })?;
+    // bodies that aren't JSON can't be replayed, let the handler reject them
+    let body: Option<serde_json::Value> = serde_json::from_slice(&bytes).ok();
+    // keys are scoped to the authenticated merchant, so one merchant can never see another's replay
+    let merchant_id = parts
+        .extensions
+        .get::<Caller>()
+        .and_then(|caller| caller.merchant_id.clone());
+    let request = Request::from_parts(parts, Body::from(bytes));
+    let (Some(body), Some(merchant_id)) = (body, merchant_id) else {
+        return Ok(next.run(request).await);
+    };
+    let owner = Uuid::new_v4().to_string();
+    let (request_hash, reservation, repo) = {
+        let app_access = app.lock().await;
+        let request_hash = hash_request(&body, &app_access.card_fingerprint_key);
+        let reservation = app_access
+            .idempotency
+            .reserve(&merchant_id, &key, &owner, &request_hash)
+            .await?;
+        (request_hash, reservation, app_access.idempotency.clone())
+    };
+    if let Reservation::Existing(record) = reservation {
+        if !record.matches(&request_hash) {
+            return Err(GatewayError {
+                kind: ErrorKind::Conflict,
+                message: format!(
+                    "idempotency key {key} has already been used for a different request"
+                ),
+            });
+        }
+        let Some(stored) = record.response else {
+            return Err(GatewayError {
+                kind: ErrorKind::Conflict,
+                message: format!("a request with idempotency key {key} is still being processed"),
+            });
+        };
+        info!(idempotency_key = %key, "replaying stored response");
+        return Ok(replay(stored));
+    }
+
+    let lease = tokio::spawn(hold_key(
+        repo,
+        merchant_id.clone(),
+        key.clone(),
+        owner.clone(),
+    ));
+    let response = next.run(request).await;
+    lease.abort();
+    let (parts, body) = response.into_parts();
+    let bytes = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|e| GatewayError {
+            kind: ErrorKind::Fatal,
+            message: e.to_string(),
+        })?;
+    {
+        let app_access = app.lock().await;
+        if parts.status.is_server_error() && parts.extensions.get::<NotSentToAcquirer>().is_some() {
+            // nothing was committed to, so the merchant should be able to retry with the same key
+            app_access
+                .idempotency
+                .release(&merchant_id, &key, &owner)
+                .await?;
+        } else {
+            let stored = StoredResponse {
+                status_code: parts.status.as_u16(),
+                body: String::from_utf8_lossy(&bytes).into_owned(),
+            };
+            let completed = app_access
+                .idempotency
+                .complete(&merchant_id, &key, &owner, &stored)
+                .await?;
+            if !completed {
+                warn!(idempotency_key = %key, "response not stored, the key was taken over");
+            }
+        }
+    }
+    Ok(Response::from_parts(parts, Body::from(bytes)))
+}
+
+/// Renews the lease on a key while its request is processed, so a retry can't take it over
+/// and process the request a second time alongside it. Stops once the key has been lost. The
+/// request can hold the app's lock while it waits on the acquirer, so the lease is renewed
+/// through a repo of its own.
+async fn hold_key(repo: IdempotencyRepo, merchant_id: String, key: String, owner: String) {
+    let period = Duration::from_secs((repo.lease_secs / 3).max(1) as u64);
+    let mut interval = tokio::time::interval(period);
+    // the first tick is immediate, and the key was only just reserved
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        match repo.renew(&merchant_id, &key, &owner).await {
+            Ok(true) => {}
+            Ok(false) => {
+                warn!(idempotency_key = %key, "idempotency key taken over");
+                return;
+            }
+            Err(e) => warn!(error = %e, "idempotency key lease not renewed"),
+        }
+    }
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let status = StatusCode::from_u16(stored.status_code).unwrap_or(StatusCode::OK);
+    let mut response = (
+        status,
+        [(axum::http::header::CONTENT_TYPE, "application/json")],
+        stored.body,
+    )
+        .into_response();
+    response.headers_mut().insert(
+        IDEMPOTENT_REPLAYED_HEADER.clone(),
+        HeaderValue::from_static("true"),
+    );
+    response
+}
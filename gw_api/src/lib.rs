@@ -1,6 +1,14 @@
+pub mod auth;
 pub mod error;
+pub mod idempotency;
+pub mod logging;
+pub mod permission;
+pub mod rate_limit;
 pub mod requests;
 pub mod responses;
+pub mod scheduler;
+pub mod signing;
+pub mod webhooks;
 #[cfg(test)]
 pub mod test_utils;
 pub mod handlers;
@@ -0,0 +1,57 @@
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info, info_span, Instrument, Subscriber};
+use tracing_subscriber::fmt::MakeWriter;
+use uuid::Uuid;
+
+pub static REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Creates the JSON log subscriber used by the gateway, writing to the given writer.
+///
+/// Everything logged is expected to already be redacted, the request/domain types containing
+/// card or personal data mask it in their Debug impls using the rules in gw_core::utils.
+pub fn subscriber<W>(writer: W) -> impl Subscriber + Send + Sync
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt()
+        .json()
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_writer(writer)
+        .finish()
+}
+
+pub fn init() {
+    tracing::subscriber::set_global_default(subscriber(std::io::stdout))
+        .expect("failed to set log subscriber");
+}
+
+/// Middleware that wraps every request in a span carrying a request ID, which is also
+/// returned to the caller in the x-request-id header.
+pub async fn request_id(request: Request, next: Next) -> Response {
+    let request_id = Uuid::new_v4().to_string();
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+    async move {
+        info!("request received");
+        let mut response = next.run(request).await;
+        info!(status = response.status().as_u16(), "request completed");
+        if let Ok(value) = HeaderValue::from_str(&request_id) {
+            response
+                .headers_mut()
+                .insert(REQUEST_ID_HEADER.clone(), value);
+        }
+        response
+    }
+    .instrument(span)
+    .await
+}
@@ -1,16 +1,84 @@
 use dotenvy::dotenv;
-use gw_api::app::{create_appstate, create_router};
-use gw_core::repo::Pool;
+use gw_api::{
+    app::{create_appstate, create_router, AppState},
+    logging, scheduler, webhooks,
+};
+use gw_core::{
+    api_key::{ApiKey, Role},
+    notification::NotificationSource,
+    repo::{Pool, Repo},
+};
+use tracing::info;
+
+const USAGE: &str = "usage: gw_api [create-staff-key <support|admin>]";
+
+/// Creates a staff key with the role, so the first admin can be set up. The key is only
+/// written to stderr, once, it's never logged and can't be shown again.
+async fn create_staff_key(app_state: &AppState, role: &str) {
+    let (key, full_key) = Role::try_from(role.to_string())
+        .and_then(ApiKey::generate_staff)
+        .expect("role must be support or admin");
+    app_state
+        .lock()
+        .await
+        .api_keys
+        .insert_one(&key)
+        .await
+        .expect("failed to create staff key");
+    info!(key_id = %key.key_id, role = %key.role, "staff API key created");
+    eprintln!("{}", full_key.expose());
+}
 
 #[tokio::main]
 async fn main() {
     dotenv().expect("no .env file!");
-    tracing_subscriber::fmt::init();
+    logging::init();
     let db_url = std::env::var("DATABASE_URL").expect("DATABASE_URL env variable not set");
     let pool = Pool::new(&db_url)
         .await
         .expect("failed to create database pool");
     let app_state = create_appstate(pool);
+    // `gw_api create-staff-key admin` creates a staff key instead of starting the server
+    match &std::env::args().skip(1).collect::<Vec<_>>()[..] {
+        [] => {}
+        [command, role] if command == "create-staff-key" => {
+            create_staff_key(&app_state, role).await;
+            return;
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        }
+    }
+    {
+        let mut app_access = app_state.lock().await;
+        app_access.card_fingerprint_key = std::env::var("CARD_FINGERPRINT_KEY")
+            .expect("CARD_FINGERPRINT_KEY env variable not set")
+            .into();
+        app_access.payment_method_key = std::env::var("PAYMENT_METHOD_KEY")
+            .expect("PAYMENT_METHOD_KEY env variable not set")
+            .into();
+        for source in NotificationSource::ALL {
+            let var = format!("{}_NOTIFICATION_SECRET", source.to_string().to_uppercase());
+            if let Ok(secret) = std::env::var(var) {
+                app_access
+                    .notification_secrets
+                    .insert(source, secret.into());
+            }
+        }
+        if let Ok(limit) = std::env::var("RATE_LIMIT_PER_MINUTE") {
+            app_access.default_requests_per_minute = limit
+                .parse()
+                .expect("RATE_LIMIT_PER_MINUTE must be a number of requests");
+        }
+    }
+    if let Ok(skew) = std::env::var("SIGNATURE_MAX_SKEW_SECS") {
+        app_state.lock().await.signature_max_skew_secs = skew
+            .parse()
+            .expect("SIGNATURE_MAX_SKEW_SECS must be a number of seconds");
+    }
+    tokio::spawn(scheduler::run(app_state.clone()));
+    tokio::spawn(webhooks::run(app_state.clone()));
     let app = create_router(app_state);
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
     axum::serve(listener, app).await.unwrap();
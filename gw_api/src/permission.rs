@@ -0,0 +1,300 @@
+use axum::{
+    extract::{RawPathParams, Request, State},
+    middleware::{self, Next},
+    response::Response,
+    routing::MethodRouter,
+    RequestExt,
+};
+use gw_core::{
+    account::{AcquirerAccount, MerchantAccount},
+    api_key::Role,
+    block_list::{EntryKind, ListEntry},
+    customer::Customer,
+    dispute::Dispute,
+    merchant::Merchant,
+    notification::AcquirerNotification,
+    subscription::Subscription,
+    transaction::search::TransactionSummary,
+    utils,
+    webhook::WebhookEvent,
+};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+};
+
+/// What a route lets its caller do, every route in [`crate::app::create_router`] declares the
+/// one it needs with [`requires`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    /// Make payments as the caller's own merchant
+    Transact,
+    /// Read a single merchant's data, merchants can only read their own
+    ReadMerchant,
+    /// Read data across every merchant
+    ReadAllMerchants,
+    /// Set up and change merchants, their accounts, routes and credentials
+    ManageMerchants,
+}
+
+impl std::fmt::Display for Permission {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let p = match self {
+            Permission::Transact => "transact",
+            Permission::ReadMerchant => "read_merchant",
+            Permission::ReadAllMerchants => "read_all_merchants",
+            Permission::ManageMerchants => "manage_merchants",
+        };
+        write!(f, "{p}")
+    }
+}
+
+impl Permission {
+    pub fn granted_to(&self, role: Role) -> bool {
+        match self {
+            Permission::Transact => role == Role::Merchant,
+            Permission::ReadMerchant => true,
+            Permission::ReadAllMerchants => matches!(role, Role::Support | Role::Admin),
+            Permission::ManageMerchants => role == Role::Admin,
+        }
+    }
+}
+
+/// Only lets callers whose role has the permission through to the route. Merchants are also
+/// kept to routes for their own `{merchant_id}`.
+pub fn requires(
+    permission: Permission,
+    method_router: MethodRouter<AppState>,
+) -> MethodRouter<AppState> {
+    method_router.layer(middleware::from_fn_with_state(permission, authorise))
+}
+
+async fn authorise(
+    State(permission): State<Permission>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, GatewayError> {
+    let caller = request
+        .extensions()
+        .get::<Caller>()
+        .cloned()
+        .ok_or_else(|| GatewayError {
+            kind: ErrorKind::Unauthorised,
+            message: "missing API key".into(),
+        })?;
+    if !permission.granted_to(caller.role) {
+        warn!(key_id = %caller.key_id, role = %caller.role, %permission, "permission denied");
+        return Err(GatewayError {
+            kind: ErrorKind::Forbidden,
+            message: format!("API key does not have the {permission} permission"),
+        });
+    }
+    let params = request
+        .extract_parts::<RawPathParams>()
+        .await
+        .map_err(|e| GatewayError {
+            kind: ErrorKind::Validation,
+            message: e.body_text(),
+        })?;
+    if let Some((_, merchant_id)) = params.iter().find(|(name, _)| *name == "merchant_id") {
+        caller.check(merchant_id)?;
+    }
+    Ok(next.run(request).await)
+}
+
+/// Hides the details support staff don't need to help a merchant
+pub trait Mask {
+    fn mask(&mut self);
+}
+
+impl Mask for Merchant {
+    fn mask(&mut self) {
+        self.premise = utils::mask_name(&self.premise);
+        self.street = utils::mask_name(&self.street);
+        self.postcode = utils::mask_name(&self.postcode);
+    }
+}
+
+impl Mask for MerchantAccount {
+    fn mask(&mut self) {
+        match &mut self.account {
+            AcquirerAccount::BankOne(acct) => {
+                acct.merchant_identification_value =
+                    utils::mask_account_number(&acct.merchant_identification_value)
+            }
+            AcquirerAccount::BankTwo(acct) => {
+                acct.merchant_reference = utils::mask_account_number(&acct.merchant_reference)
+            }
+        }
+    }
+}
+
+impl Mask for Customer {
+    fn mask(&mut self) {
+        self.first_name = utils::mask_name(&self.first_name);
+        self.last_name = utils::mask_name(&self.last_name);
+        self.email = utils::mask_name(&self.email);
+        self.phone = utils::mask_account_number(&self.phone);
+        for address in &mut self.addresses {
+            address.premise = utils::mask_name(&address.premise);
+            address.street = utils::mask_name(&address.street);
+            address.postcode = utils::mask_name(&address.postcode);
+        }
+    }
+}
+
+impl Mask for TransactionSummary {
+    fn mask(&mut self) {
+        self.description = self.description.as_deref().map(utils::mask_name);
+        self.metadata
+            .values_mut()
+            .for_each(|value| *value = utils::mask_name(value));
+    }
+}
+
+impl Mask for WebhookEvent {
+    /// The event's data is a transaction or dispute as the API returns it, so the same details
+    /// are hidden as when they're read directly
+    fn mask(&mut self) {
+        if let Some(billing) = self.data.get_mut("billing").and_then(Value::as_object_mut) {
+            billing
+                .iter_mut()
+                .filter(|(field, _)| *field != "country")
+                .for_each(|(_, value)| mask_value(value));
+        }
+        if let Some(description) = self.data.get_mut("description") {
+            mask_value(description);
+        }
+        if let Some(metadata) = self.data.get_mut("metadata").and_then(Value::as_object_mut) {
+            metadata.values_mut().for_each(mask_value);
+        }
+        if let Some(evidence) = self.data.get_mut("evidence").and_then(Value::as_array_mut) {
+            for evidence in evidence.iter_mut().filter_map(Value::as_object_mut) {
+                evidence
+                    .iter_mut()
+                    .filter(|(field, _)| matches!(field.as_str(), "file_name" | "description"))
+                    .for_each(|(_, value)| mask_value(value));
+            }
+        }
+    }
+}
+
+impl Mask for AcquirerNotification {
+    /// Acquirers can send more than the gateway reads, so every value in the body is hidden,
+    /// the parts the gateway uses are shown alongside it
+    fn mask(&mut self) {
+        self.raw = match serde_json::from_str::<Value>(&self.raw) {
+            Ok(mut body) => {
+                mask_strings(&mut body);
+                body.to_string()
+            }
+            Err(_) => utils::mask_name(&self.raw),
+        };
+    }
+}
+
+impl Mask for Dispute {
+    fn mask(&mut self) {
+        for evidence in &mut self.evidence {
+            evidence.file_name = utils::mask_name(&evidence.file_name);
+            evidence.description = evidence.description.as_deref().map(utils::mask_name);
+        }
+    }
+}
+
+impl Mask for Subscription {
+    fn mask(&mut self) {
+        self.payment_token = utils::mask_account_number(&self.payment_token);
+    }
+}
+
+impl Mask for ListEntry {
+    /// Card fingerprints and bins don't identify anyone, emails and ips do
+    fn mask(&mut self) {
+        if matches!(self.kind, EntryKind::Email | EntryKind::Ip) {
+            self.value = utils::mask_name(&self.value);
+        }
+    }
+}
+
+fn mask_value(value: &mut Value) {
+    if let Value::String(s) = value {
+        *s = utils::mask_name(s);
+    }
+}
+
+fn mask_strings(value: &mut Value) {
+    match value {
+        Value::Array(values) => values.iter_mut().for_each(mask_strings),
+        Value::Object(values) => values.values_mut().for_each(mask_strings),
+        value => mask_value(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(Permission::Transact, [true, false, false])]
+    #[case(Permission::ReadMerchant, [true, true, true])]
+    #[case(Permission::ReadAllMerchants, [false, true, true])]
+    #[case(Permission::ManageMerchants, [false, false, true])]
+    fn permissions_by_role(#[case] permission: Permission, #[case] granted: [bool; 3]) {
+        for (role, granted) in [Role::Merchant, Role::Support, Role::Admin]
+            .into_iter()
+            .zip(granted)
+        {
+            assert_eq!(permission.granted_to(role), granted, "{role} {permission}");
+        }
+    }
+
+    #[rstest]
+    fn mask_account() {
+        let mut account = MerchantAccount {
+            id: 1,
+            merchant_id: "merchant123".into(),
+            account: AcquirerAccount::BankOne(gw_core::account::BankOneAccount {
+                merchant_identification_value: "12345678".into(),
+            }),
+        };
+        account.mask();
+        assert_eq!(
+            account.account.acquirer_data(),
+            serde_json::json!({"merchant_identification_value": "####5678"})
+        );
+    }
+
+    #[rstest]
+    fn mask_notification() {
+        let raw = serde_json::json!({
+            "notificationId": "n1",
+            "pspReference": "ref123",
+            "eventCode": "CHARGEBACK",
+            "amount": 1000,
+            "shopper": {"name": "Jane Doe"}
+        });
+        let mut notification = gw_core::notification::NotificationSource::BankOne
+            .parse(raw.to_string(), chrono::Utc::now())
+            .unwrap();
+        notification.mask();
+        assert_eq!(
+            serde_json::from_str::<Value>(&notification.raw).unwrap(),
+            serde_json::json!({
+                "notificationId": "n#",
+                "pspReference": "r#####",
+                "eventCode": "C#########",
+                "amount": 1000,
+                "shopper": {"name": "J#######"}
+            })
+        );
+        // the parts the gateway read are still shown
+        assert_eq!(notification.notification_id, "n1");
+        assert_eq!(notification.transaction_reference, "ref123");
+    }
+}
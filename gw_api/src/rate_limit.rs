@@ -0,0 +1,165 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{header::RETRY_AFTER, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tracing::warn;
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+};
+
+/// Used for merchants who don't have their own limit. Like every limit it's per gateway
+/// instance, see [`RateLimiter`].
+pub const DEFAULT_REQUESTS_PER_MINUTE: u32 = 600;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Counts each merchant's requests in fixed one minute windows. The counts are only kept in
+/// memory, so each gateway instance limits merchants separately, a merchant's limit across
+/// the gateway is their limit times the number of instances. Windows which have ended are
+/// dropped once a window, so merchants who stop making requests aren't kept.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    windows: HashMap<String, Window>,
+    pruned_at: Option<Instant>,
+}
+
+#[derive(Debug)]
+struct Window {
+    started_at: Instant,
+    requests: u32,
+}
+
+impl RateLimiter {
+    /// Counts a request from the merchant, if they're already at the limit it isn't counted and
+    /// how long until they can try again is returned
+    pub fn hit(&mut self, merchant_id: &str, limit: u32, now: Instant) -> Result<(), Duration> {
+        self.prune(now);
+        let window = self
+            .windows
+            .entry(merchant_id.to_string())
+            .or_insert(Window {
+                started_at: now,
+                requests: 0,
+            });
+        let elapsed = now.saturating_duration_since(window.started_at);
+        if elapsed >= WINDOW {
+            window.started_at = now;
+            window.requests = 0;
+        } else if window.requests >= limit {
+            return Err(WINDOW - elapsed);
+        }
+        window.requests += 1;
+        Ok(())
+    }
+
+    /// Drops the windows which have ended, at most once a window
+    fn prune(&mut self, now: Instant) {
+        if self
+            .pruned_at
+            .is_some_and(|at| now.saturating_duration_since(at) < WINDOW)
+        {
+            return;
+        }
+        self.windows
+            .retain(|_, window| now.saturating_duration_since(window.started_at) < WINDOW);
+        self.pruned_at = Some(now);
+    }
+}
+
+/// Middleware limiting how many requests each merchant can make a minute to this instance, it
+/// must run after [`crate::auth::authenticate`]. Staff requests aren't limited.
+pub async fn limit_requests(
+    State(app): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, GatewayError> {
+    let Some(merchant_id) = request
+        .extensions()
+        .get::<Caller>()
+        .and_then(|caller| caller.merchant_id.clone())
+    else {
+        return Ok(next.run(request).await);
+    };
+    let limited = {
+        let mut app_access = app.lock().await;
+        let limit = app_access
+            .limits
+            .find(&merchant_id)
+            .await?
+            .requests_per_minute
+            .unwrap_or(app_access.default_requests_per_minute);
+        app_access
+            .rate_limiter
+            .hit(&merchant_id, limit, Instant::now())
+            .err()
+            .map(|retry_after| (limit, retry_after))
+    };
+    let Some((limit, retry_after)) = limited else {
+        return Ok(next.run(request).await);
+    };
+    warn!(%merchant_id, limit, "rate limit reached");
+    // round up, so retrying straight after the wait always lands in the next window
+    let retry_after_secs = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+    let mut response = GatewayError {
+        kind: ErrorKind::RateLimited,
+        message: format!("rate limit of {limit} requests per minute reached"),
+    }
+    .into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_requests_per_window() {
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+        assert!(limiter.hit("merchant123", 2, start).is_ok());
+        assert!(limiter.hit("merchant123", 2, start).is_ok());
+        assert_eq!(
+            limiter.hit("merchant123", 2, start + Duration::from_secs(15)),
+            Err(Duration::from_secs(45))
+        );
+        // other merchants have their own windows
+        assert!(limiter.hit("merchant456", 2, start).is_ok());
+        assert!(limiter.hit("merchant123", 2, start + WINDOW).is_ok());
+        assert!(limiter.hit("merchant123", 2, start + WINDOW).is_ok());
+        assert!(limiter.hit("merchant123", 2, start + WINDOW).is_err());
+    }
+
+    #[test]
+    fn ended_windows_are_dropped() {
+        let mut limiter = RateLimiter::default();
+        let start = Instant::now();
+        assert!(limiter.hit("merchant123", 2, start).is_ok());
+        assert!(limiter
+            .hit("merchant456", 2, start + Duration::from_secs(30))
+            .is_ok());
+        assert_eq!(limiter.windows.len(), 2);
+        // merchant123's window has ended, merchant456's hasn't
+        assert!(limiter.hit("merchant789", 2, start + WINDOW).is_ok());
+        assert_eq!(limiter.windows.len(), 2);
+        assert!(!limiter.windows.contains_key("merchant123"));
+        // only pruned once a window
+        let later = start + WINDOW + Duration::from_secs(40);
+        assert!(limiter.hit("merchant789", 2, later).is_ok());
+        assert_eq!(limiter.windows.len(), 2);
+        assert!(limiter.hit("merchant789", 2, start + WINDOW * 2).is_ok());
+        assert_eq!(limiter.windows.len(), 1);
+    }
+}
@@ -0,0 +1,79 @@
+use gw_core::account::{AcquirerAccount, BankOneAccount, BankTwoAccount};
+use serde::Deserialize;
+
+use crate::error::{ErrorKind::Validation, GatewayError};
+
+/// Body of a request to create an acquirer account, the acquirer decides which fields are needed
+#[derive(Deserialize, Debug)]
+#[serde(tag = "acquirer", rename_all = "lowercase")]
+pub enum AccountRequest {
+    Bankone {
+        merchant_identification_value: String,
+    },
+    Banktwo {
+        merchant_reference: String,
+    },
+}
+
+fn check_length(field: &str, value: String) -> Result<String, GatewayError> {
+    let value = value.trim().to_string();
+    if value.is_empty() || value.len() > 255 {
+        return Err(GatewayError {
+            kind: Validation,
+            message: format!("{field} must be between 1 and 255 characters"),
+        });
+    }
+    Ok(value)
+}
+
+impl TryFrom<AccountRequest> for AcquirerAccount {
+    type Error = GatewayError;
+
+    fn try_from(value: AccountRequest) -> Result<Self, Self::Error> {
+        Ok(match value {
+            AccountRequest::Bankone {
+                merchant_identification_value,
+            } => AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: check_length(
+                    "merchant_identification_value",
+                    merchant_identification_value,
+                )?,
+            }),
+            AccountRequest::Banktwo { merchant_reference } => {
+                AcquirerAccount::BankTwo(BankTwoAccount {
+                    merchant_reference: check_length("merchant_reference", merchant_reference)?,
+                })
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn convert_to_account() {
+        let request: AccountRequest = serde_json::from_value(json!({
+            "acquirer": "banktwo",
+            "merchant_reference": " ref-1 "
+        }))
+        .unwrap();
+        assert_eq!(
+            AcquirerAccount::try_from(request).unwrap(),
+            AcquirerAccount::BankTwo(BankTwoAccount {
+                merchant_reference: "ref-1".into()
+            })
+        );
+        let request: AccountRequest = serde_json::from_value(json!({
+            "acquirer": "bankone",
+            "merchant_identification_value": ""
+        }))
+        .unwrap();
+        assert_eq!(
+            AcquirerAccount::try_from(request).unwrap_err().message,
+            "merchant_identification_value must be between 1 and 255 characters"
+        );
+    }
+}
@@ -0,0 +1,24 @@
+use serde::Deserialize;
+
+/// Old keys keep working for a day after a rotation by default
+pub const DEFAULT_GRACE_SECS: i64 = 24 * 60 * 60;
+pub const MAX_GRACE_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Query string parameters for rotating a merchant's keys
+#[derive(Deserialize, Debug)]
+pub struct RotateRequest {
+    /// How long the merchant's existing keys keep working for
+    #[serde(default = "default_grace_secs")]
+    pub grace_secs: i64,
+}
+
+fn default_grace_secs() -> i64 {
+    DEFAULT_GRACE_SECS
+}
+
+/// Body for creating a key for a member of staff
+#[derive(Deserialize, Debug)]
+pub struct StaffKeyRequest {
+    /// support or admin
+    pub role: String,
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use gw_core::block_list::{EntryKind, ListType};
+use serde::Deserialize;
+
+/// Body for adding an entry to a block or allow list, cards are given by pan
+#[derive(Deserialize)]
+pub struct ListEntryRequest {
+    #[serde(default)]
+    pub list: ListType,
+    /// Left out for entries that apply to every merchant
+    pub merchant_id: Option<String>,
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    pub value: String,
+    pub reason: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl std::fmt::Debug for ListEntryRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ListEntryRequest")
+            .field("list", &self.list)
+            .field("merchant_id", &self.merchant_id)
+            .field("kind", &self.kind)
+            .field("reason", &self.reason)
+            .field("expires_at", &self.expires_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Query string parameters for listing entries
+#[derive(Deserialize, Debug, Default)]
+pub struct ListEntrySearchRequest {
+    pub merchant_id: Option<String>,
+    pub list: Option<ListType>,
+}
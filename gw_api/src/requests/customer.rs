@@ -0,0 +1,100 @@
+use gw_core::{
+    customer::{Address, Customer},
+    utils,
+};
+use serde::Deserialize;
+
+use crate::error::{ErrorKind::Validation, GatewayError};
+
+/// Body for creating a customer or replacing their details
+#[derive(Deserialize, Default)]
+pub struct CustomerRequest {
+    #[serde(default)]
+    pub first_name: String,
+    #[serde(default)]
+    pub last_name: String,
+    #[serde(default)]
+    pub email: String,
+    #[serde(default)]
+    pub phone: String,
+    #[serde(default)]
+    pub addresses: Vec<AddressRequest>,
+}
+
+impl std::fmt::Debug for CustomerRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CustomerRequest")
+            .field("first_name", &utils::mask_name(&self.first_name))
+            .field("last_name", &utils::mask_name(&self.last_name))
+            .field("email", &utils::mask_name(&self.email))
+            .field("phone", &utils::mask_account_number(&self.phone))
+            .field("addresses", &self.addresses)
+            .finish()
+    }
+}
+
+#[derive(Deserialize, Default)]
+pub struct AddressRequest {
+    #[serde(default)]
+    pub premise: String,
+    #[serde(default)]
+    pub street: String,
+    #[serde(default)]
+    pub city: String,
+    #[serde(default)]
+    pub county: String,
+    #[serde(default)]
+    pub postcode: String,
+    pub country: String,
+}
+
+impl std::fmt::Debug for AddressRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AddressRequest")
+            .field("premise", &utils::mask_name(&self.premise))
+            .field("street", &utils::mask_name(&self.street))
+            .field("city", &self.city)
+            .field("county", &self.county)
+            .field("postcode", &utils::mask_name(&self.postcode))
+            .field("country", &self.country)
+            .finish()
+    }
+}
+
+impl TryFrom<AddressRequest> for Address {
+    type Error = GatewayError;
+
+    fn try_from(value: AddressRequest) -> Result<Self, Self::Error> {
+        let country = value
+            .country
+            .try_into()
+            .map_err(|e: gw_core::error::Error| GatewayError {
+                kind: Validation,
+                message: e.message,
+            })?;
+        Ok(Address {
+            premise: value.premise,
+            street: value.street,
+            city: value.city,
+            county: value.county,
+            postcode: value.postcode,
+            country,
+        })
+    }
+}
+
+impl CustomerRequest {
+    /// Sets the customer's details, their id, merchant and when they were created are kept
+    pub fn apply_to(self, customer: &mut Customer) -> Result<(), GatewayError> {
+        customer.addresses = self
+            .addresses
+            .into_iter()
+            .map(Address::try_from)
+            .collect::<Result<_, _>>()?;
+        customer.first_name = self.first_name;
+        customer.last_name = self.last_name;
+        customer.email = self.email;
+        customer.phone = self.phone;
+        Ok(())
+    }
+}
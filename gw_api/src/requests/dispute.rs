@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use gw_core::{
+    dispute::{Evidence, EvidenceType},
+    transaction::search::DEFAULT_PAGE_SIZE,
+};
+use serde::Deserialize;
+
+use crate::error::{ErrorKind::Validation, GatewayError};
+
+/// Body describing a document the merchant is relying on in a dispute, the file itself goes to
+/// the acquirer
+#[derive(Deserialize, Debug)]
+pub struct EvidenceRequest {
+    pub evidence_type: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: u64,
+    pub description: Option<String>,
+}
+
+impl EvidenceRequest {
+    pub fn into_evidence(
+        self,
+        dispute_id: String,
+        now: DateTime<Utc>,
+    ) -> Result<Evidence, GatewayError> {
+        let evidence_type =
+            EvidenceType::try_from(self.evidence_type).map_err(|e: gw_core::error::Error| {
+                GatewayError {
+                    kind: Validation,
+                    message: e.message,
+                }
+            })?;
+        Ok(Evidence {
+            description: self.description,
+            ..Evidence::new(
+                dispute_id,
+                evidence_type,
+                self.file_name,
+                self.content_type,
+                self.size_bytes,
+                now,
+            )
+        })
+    }
+}
+
+/// Query string parameters for listing a merchant's disputes
+#[derive(Deserialize, Debug, Default)]
+pub struct DisputeListRequest {
+    pub status: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+
+fn default_limit() -> u32 {
+    DEFAULT_PAGE_SIZE
+}
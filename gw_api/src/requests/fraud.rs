@@ -0,0 +1,159 @@
+use gw_core::{
+    currency::Currency,
+    fraud::{FraudRule, FraudSettings, RuleCondition, DEFAULT_REJECT_SCORE, DEFAULT_REVIEW_SCORE},
+    limits::fingerprint_pan,
+    secret::Secret,
+};
+use serde::Deserialize;
+
+use crate::error::{ErrorKind, GatewayError};
+
+/// Body for replacing a merchant's fraud settings, missing scores fall back to the defaults
+#[derive(Deserialize, Debug)]
+pub struct FraudSettingsRequest {
+    pub review_score: Option<u32>,
+    pub reject_score: Option<u32>,
+    #[serde(default)]
+    pub rules: Vec<FraudRuleRequest>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FraudRuleRequest {
+    pub score: u32,
+    #[serde(flatten)]
+    pub condition: RuleConditionRequest,
+}
+
+/// The same as [`RuleCondition`] apart from blocked cards being given by pan
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleConditionRequest {
+    AmountOver { currency: Currency, amount: u64 },
+    CountryMismatch,
+    BlockedBins { bins: Vec<String> },
+    BlockedCards { pans: Vec<String> },
+    BlockedEmails { emails: Vec<String> },
+    CardVelocity { max_transactions: u32 },
+}
+
+impl std::fmt::Debug for RuleConditionRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleConditionRequest::BlockedCards { pans } => f
+                .debug_struct("BlockedCards")
+                .field("pans", &pans.len())
+                .finish(),
+            RuleConditionRequest::AmountOver { currency, amount } => f
+                .debug_struct("AmountOver")
+                .field("currency", currency)
+                .field("amount", amount)
+                .finish(),
+            RuleConditionRequest::CountryMismatch => write!(f, "CountryMismatch"),
+            RuleConditionRequest::BlockedBins { bins } => {
+                f.debug_struct("BlockedBins").field("bins", bins).finish()
+            }
+            RuleConditionRequest::BlockedEmails { emails } => f
+                .debug_struct("BlockedEmails")
+                .field("emails", &emails.len())
+                .finish(),
+            RuleConditionRequest::CardVelocity { max_transactions } => f
+                .debug_struct("CardVelocity")
+                .field("max_transactions", max_transactions)
+                .finish(),
+        }
+    }
+}
+
+impl FraudSettingsRequest {
+    /// Checks the settings, fingerprinting any blocked cards so their pans aren't stored
+    pub fn into_settings(
+        self,
+        merchant_id: String,
+        fingerprint_key: &Secret<String>,
+    ) -> Result<FraudSettings, GatewayError> {
+        let review_score = self.review_score.unwrap_or(DEFAULT_REVIEW_SCORE);
+        let reject_score = self.reject_score.unwrap_or(DEFAULT_REJECT_SCORE);
+        check_score("review_score", review_score)?;
+        check_score("reject_score", reject_score)?;
+        if review_score > reject_score {
+            return Err(invalid("review_score can't be more than reject_score"));
+        }
+        let rules = self
+            .rules
+            .into_iter()
+            .map(|rule| {
+                check_score("score", rule.score)?;
+                Ok(FraudRule {
+                    score: rule.score,
+                    condition: rule.condition.into_condition(fingerprint_key)?,
+                })
+            })
+            .collect::<Result<Vec<_>, GatewayError>>()?;
+        Ok(FraudSettings {
+            merchant_id,
+            review_score,
+            reject_score,
+            rules,
+        })
+    }
+}
+
+impl RuleConditionRequest {
+    fn into_condition(
+        self,
+        fingerprint_key: &Secret<String>,
+    ) -> Result<RuleCondition, GatewayError> {
+        Ok(match self {
+            RuleConditionRequest::AmountOver { currency, amount } => {
+                RuleCondition::AmountOver { currency, amount }
+            }
+            RuleConditionRequest::CountryMismatch => RuleCondition::CountryMismatch,
+            RuleConditionRequest::BlockedBins { bins } => {
+                if let Some(bin) = bins.iter().find(|bin| !is_digits(bin, 4, 11)) {
+                    return Err(invalid(&format!("{bin} is not a valid bin")));
+                }
+                RuleCondition::BlockedBins { bins }
+            }
+            RuleConditionRequest::BlockedCards { pans } => {
+                let fingerprints = pans
+                    .into_iter()
+                    .map(|pan| {
+                        if !is_digits(&pan, 12, 20) {
+                            return Err(invalid("blocked cards must be valid pans"));
+                        }
+                        Ok(fingerprint_pan(&pan, fingerprint_key))
+                    })
+                    .collect::<Result<Vec<_>, GatewayError>>()?;
+                RuleCondition::BlockedCards { fingerprints }
+            }
+            RuleConditionRequest::BlockedEmails { emails } => RuleCondition::BlockedEmails {
+                emails: emails.iter().map(|e| e.trim().to_lowercase()).collect(),
+            },
+            RuleConditionRequest::CardVelocity { max_transactions } => {
+                RuleCondition::CardVelocity { max_transactions }
+            }
+        })
+    }
+}
+
+fn is_digits(value: &str, min: usize, max: usize) -> bool {
+    (min..=max).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Scores have to fit in their database columns
+fn check_score(name: &str, score: u32) -> Result<(), GatewayError> {
+    if score == 0 || score > i32::MAX as u32 {
+        return Err(invalid(&format!(
+            "{name} must be between 1 and {}",
+            i32::MAX
+        )));
+    }
+    Ok(())
+}
+
+fn invalid(message: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Validation,
+        message: message.into(),
+    }
+}
@@ -0,0 +1,23 @@
+use gw_core::limits::{MerchantLimits, VelocityLimits};
+use serde::Deserialize;
+
+/// Body for replacing a merchant's limits, missing limits fall back to the gateway's defaults
+#[derive(Deserialize, Debug, Default)]
+pub struct LimitsRequest {
+    pub requests_per_minute: Option<u32>,
+    pub max_card_transactions_per_hour: Option<u32>,
+    pub max_card_amount_per_hour: Option<u64>,
+}
+
+impl LimitsRequest {
+    pub fn into_limits(self, merchant_id: String) -> MerchantLimits {
+        MerchantLimits {
+            merchant_id,
+            requests_per_minute: self.requests_per_minute,
+            velocity: VelocityLimits {
+                max_card_transactions_per_hour: self.max_card_transactions_per_hour,
+                max_card_amount_per_hour: self.max_card_amount_per_hour,
+            },
+        }
+    }
+}
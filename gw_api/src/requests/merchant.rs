@@ -0,0 +1,118 @@
+use gw_core::{merchant::Merchant, transaction::search::DEFAULT_PAGE_SIZE};
+use serde::Deserialize;
+
+use crate::error::{ErrorKind::Validation, GatewayError};
+
+/// Body of a request to create a merchant
+#[derive(Deserialize, Debug)]
+pub struct MerchantRequest {
+    pub merchant_id: String,
+    #[serde(flatten)]
+    pub details: MerchantDetailsRequest,
+}
+
+/// The parts of a merchant which can be updated
+#[derive(Deserialize, Debug)]
+pub struct MerchantDetailsRequest {
+    pub name: String,
+    #[serde(default)]
+    pub premise: String,
+    #[serde(default)]
+    pub street: String,
+    #[serde(default)]
+    pub city: String,
+    #[serde(default)]
+    pub postcode: String,
+    #[serde(default)]
+    pub county: String,
+    pub country: String,
+}
+
+impl MerchantDetailsRequest {
+    pub fn into_merchant(
+        self,
+        merchant_id: String,
+        active: bool,
+    ) -> Result<Merchant, GatewayError> {
+        let country = self
+            .country
+            .try_into()
+            .map_err(|e: gw_core::error::Error| GatewayError {
+                kind: Validation,
+                message: e.message,
+            })?;
+        Ok(Merchant {
+            merchant_id,
+            name: self.name,
+            premise: self.premise,
+            street: self.street,
+            city: self.city,
+            postcode: self.postcode,
+            county: self.county,
+            country,
+            active,
+        })
+    }
+}
+
+impl TryFrom<MerchantRequest> for Merchant {
+    type Error = GatewayError;
+
+    fn try_from(value: MerchantRequest) -> Result<Self, Self::Error> {
+        value.details.into_merchant(value.merchant_id, true)
+    }
+}
+
+/// Query string parameters for listing merchants
+#[derive(Deserialize, Debug, Default)]
+pub struct MerchantListRequest {
+    pub active: Option<bool>,
+    pub cursor: Option<String>,
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+
+fn default_limit() -> u32 {
+    DEFAULT_PAGE_SIZE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gw_core::country::Country;
+    use serde_json::json;
+
+    #[test]
+    fn convert_to_merchant() {
+        let request: MerchantRequest = serde_json::from_value(json!({
+            "merchant_id": "merchant456",
+            "name": "Test",
+            "city": "Leeds",
+            "country": "GB"
+        }))
+        .unwrap();
+        let merchant = Merchant::try_from(request).unwrap();
+        assert_eq!(
+            merchant,
+            Merchant {
+                merchant_id: "merchant456".into(),
+                name: "Test".into(),
+                city: "Leeds".into(),
+                country: Country::GB,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_country() {
+        let request: MerchantRequest = serde_json::from_value(json!({
+            "merchant_id": "merchant456",
+            "name": "Test",
+            "country": "FR"
+        }))
+        .unwrap();
+        let err = Merchant::try_from(request).unwrap_err();
+        assert_eq!(err.message, "FR is not a recognised country code");
+    }
+}
@@ -0,0 +1,52 @@
+use gw_core::{card_scheme::CardScheme, currency::Currency};
+use serde::Deserialize;
+
+use crate::error::{ErrorKind::Validation, GatewayError};
+
+/// Body of a request to point a scheme and currency at one of the merchant's accounts
+#[derive(Deserialize, Debug)]
+pub struct RouteRequest {
+    pub acquirer: String,
+    pub account_id: i32,
+}
+
+/// Query string parameters for finding out which account a payment would use
+#[derive(Deserialize, Debug)]
+pub struct RouteResolveRequest {
+    pub scheme: String,
+    pub currency: String,
+}
+
+/// Parses the scheme and currency given in a route's path or query string
+pub fn parse_route_key(
+    scheme: String,
+    currency: String,
+) -> Result<(CardScheme, Currency), GatewayError> {
+    let to_validation = |e: gw_core::error::Error| GatewayError {
+        kind: Validation,
+        message: e.message,
+    };
+    Ok((
+        scheme.try_into().map_err(to_validation)?,
+        currency.try_into().map_err(to_validation)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_key() {
+        assert_eq!(
+            parse_route_key("VISA".into(), "USD".into()).unwrap(),
+            (CardScheme::Visa, Currency::USD)
+        );
+        assert_eq!(
+            parse_route_key("AMEX".into(), "USD".into())
+                .unwrap_err()
+                .message,
+            "AMEX is not a recognised card scheme"
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use gw_core::{avs::AvsPolicy, cvv::CvvPolicy, policy::MerchantPolicies};
+use serde::Deserialize;
+
+/// Body for replacing a merchant's policies, anything missing is turned off
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct PoliciesRequest {
+    pub avs: AvsPolicyRequest,
+    pub cvv: CvvPolicyRequest,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct AvsPolicyRequest {
+    pub decline_address_mismatch: bool,
+    pub decline_postcode_mismatch: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CvvPolicyRequest {
+    pub void_on_mismatch: bool,
+}
+
+impl PoliciesRequest {
+    pub fn into_policies(self, merchant_id: String) -> MerchantPolicies {
+        MerchantPolicies {
+            merchant_id,
+            avs: AvsPolicy {
+                decline_address_mismatch: self.avs.decline_address_mismatch,
+                decline_postcode_mismatch: self.avs.decline_postcode_mismatch,
+            },
+            cvv: CvvPolicy {
+                void_on_mismatch: self.cvv.void_on_mismatch,
+            },
+        }
+    }
+}
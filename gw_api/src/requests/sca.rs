@@ -0,0 +1,27 @@
+use gw_core::{currency::Currency, sca::ScaThresholds};
+use serde::Deserialize;
+
+/// Body for replacing a merchant's SCA thresholds in a currency, the highest amount in minor
+/// units each exemption is asked for. Missing exemptions are never asked for.
+#[derive(Deserialize, Debug, Default)]
+pub struct ScaThresholdsRequest {
+    pub low_value: Option<u64>,
+    pub transaction_risk_analysis: Option<u64>,
+    pub merchant_initiated: Option<u64>,
+    pub recurring: Option<u64>,
+    pub corporate: Option<u64>,
+}
+
+impl ScaThresholdsRequest {
+    pub fn into_thresholds(self, merchant_id: String, currency: Currency) -> ScaThresholds {
+        ScaThresholds {
+            merchant_id,
+            currency,
+            low_value: self.low_value,
+            transaction_risk_analysis: self.transaction_risk_analysis,
+            merchant_initiated: self.merchant_initiated,
+            recurring: self.recurring,
+            corporate: self.corporate,
+        }
+    }
+}
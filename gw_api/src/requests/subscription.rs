@@ -0,0 +1,55 @@
+use gw_core::{
+    currency::Currency,
+    subscription::{Interval, Plan},
+};
+use serde::Deserialize;
+
+use crate::error::{ErrorKind::Validation, GatewayError};
+
+/// Body for creating a plan, charged every `interval_count` intervals
+#[derive(Deserialize, Debug)]
+pub struct PlanRequest {
+    pub name: String,
+    pub amount: u64,
+    pub currency: Currency,
+    pub interval: String,
+    #[serde(default = "default_interval_count")]
+    pub interval_count: u32,
+    #[serde(default)]
+    pub trial_days: u32,
+}
+
+fn default_interval_count() -> u32 {
+    1
+}
+
+impl PlanRequest {
+    pub fn into_plan(self, merchant_id: String) -> Result<Plan, GatewayError> {
+        let interval =
+            Interval::try_from(self.interval).map_err(|e: gw_core::error::Error| GatewayError {
+                kind: Validation,
+                message: e.message,
+            })?;
+        // amounts have to fit in their database column
+        if self.amount > i64::MAX as u64 {
+            return Err(GatewayError {
+                kind: Validation,
+                message: format!("amount must be at most {}", i64::MAX),
+            });
+        }
+        Ok(Plan {
+            interval_count: self.interval_count,
+            trial_days: self.trial_days,
+            ..Plan::new(merchant_id, self.name, self.amount, self.currency, interval)
+        })
+    }
+}
+
+/// Body for subscribing one of the merchant's customers to a plan, charged to one of their
+/// saved payment methods
+#[derive(Deserialize, Debug)]
+pub struct SubscriptionRequest {
+    pub plan_id: String,
+    pub customer_id: String,
+    pub payment_token: String,
+}
@@ -0,0 +1,7 @@
+use serde::Deserialize;
+
+/// The challenge response the ACS gave the cardholder's browser
+#[derive(Deserialize, Debug)]
+pub struct ChallengeResultRequest {
+    pub cres: String,
+}
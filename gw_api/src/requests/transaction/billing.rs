@@ -1,19 +1,45 @@
 use gw_core::billing::Billing;
 use gw_core::error::Error;
+use gw_core::utils;
 use serde::Deserialize;
 
 use crate::error::ErrorKind::Validation;
 use crate::error::GatewayError;
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default)]
 pub struct BillingRequest {
     first_name: Option<String>,
     last_name: Option<String>,
     premise: Option<String>,
+    #[serde(alias = "address")]
     street: Option<String>,
     city: Option<String>,
     county: Option<String>,
+    postcode: Option<String>,
     country: Option<String>,
+    email: Option<String>,
+}
+
+impl std::fmt::Debug for BillingRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BillingRequest")
+            .field(
+                "first_name",
+                &self.first_name.as_deref().map(utils::mask_name),
+            )
+            .field(
+                "last_name",
+                &self.last_name.as_deref().map(utils::mask_name),
+            )
+            .field("premise", &self.premise)
+            .field("street", &self.street)
+            .field("city", &self.city)
+            .field("county", &self.county)
+            .field("postcode", &self.postcode)
+            .field("country", &self.country)
+            .field("email", &self.email.as_deref().map(utils::mask_name))
+            .finish()
+    }
 }
 
 impl TryFrom<BillingRequest> for Billing {
@@ -25,6 +51,7 @@ impl TryFrom<BillingRequest> for Billing {
             street: value.street.unwrap_or_default(),
             city: value.city.unwrap_or_default(),
             county: value.county.unwrap_or_default(),
+            postcode: value.postcode.unwrap_or_default(),
             country: value
                 .country
                 .unwrap_or_default()
@@ -33,6 +60,7 @@ impl TryFrom<BillingRequest> for Billing {
                     kind: Validation,
                     message: e.to_string(),
                 })?,
+            email: value.email.unwrap_or_default(),
         })
     }
 
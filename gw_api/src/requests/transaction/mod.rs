@@ -1,10 +1,10 @@
 pub mod billing;
-pub mod customer;
 pub mod payment;
 pub mod transaction_option;
 
+use std::collections::BTreeMap;
+
 use billing::BillingRequest;
-use customer::CustomerRequest;
 use gw_core::{currency::Currency, transaction::TransactionType};
 use payment::PaymentRequest;
 use serde::Deserialize;
@@ -18,8 +18,20 @@ pub struct TransactionRequest {
     pub merchant_id: String,
     pub payment: Option<PaymentRequest>,
     pub billing: Option<BillingRequest>,
-    pub customer: Option<CustomerRequest>,
+    /// One of the merchant's customers, their first address is used when billing isn't given
+    pub customer_id: Option<String>,
+    /// Charges one of the customer's saved payment methods instead of the payment given
+    pub payment_token: Option<String>,
     pub options: Option<TransactionOptionRequest>,
+    pub merchant_reference: Option<String>,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// The customer's ip address, checked against the block lists
+    pub ip_address: Option<String>,
+    /// Only set by the scheduler, see [`crate::scheduler`]
+    #[serde(skip)]
+    pub subscription_charge: Option<String>,
 }
 
 impl TransactionRequest {
@@ -29,9 +41,6 @@ impl TransactionRequest {
     pub fn take_billing_data(&mut self) -> Option<BillingRequest> {
         self.billing.take()
     }
-    // pub fn take_customer_data(&mut self) -> Option<CustomerRequest> {
-    //     self.customer.take()
-    // }
     // pub fn take_options_data(&mut self) -> Option<OptionsRequest> {
     //     self.options.take()
     // }
@@ -1,20 +1,41 @@
-use gw_core::{card_scheme::CardScheme, payment::Payment};
+use gw_core::{card_scheme::CardScheme, payment::Payment, secret::Secret, utils};
 use serde::Deserialize;
 
 use crate::error::{ErrorKind::Validation, GatewayError};
 
-#[derive(Deserialize, Debug, PartialEq, Default)]
+#[derive(Deserialize, PartialEq, Default)]
 pub struct PaymentRequest {
     payment_type: String,
     scheme: Option<CardScheme>,
     pan: Option<String>,
-    security_code: Option<String>,
+    security_code: Option<Secret<String>>,
     expiry_month: Option<u8>,
     expiry_year: Option<u32>,
     account_number: Option<String>,
     sort_code: Option<String>,
 }
 
+impl std::fmt::Debug for PaymentRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PaymentRequest")
+            .field("payment_type", &self.payment_type)
+            .field("scheme", &self.scheme)
+            .field("pan", &self.pan.as_deref().map(utils::mask_pan))
+            .field("security_code", &self.security_code)
+            .field("expiry_month", &self.expiry_month)
+            .field("expiry_year", &self.expiry_year)
+            .field(
+                "account_number",
+                &self
+                    .account_number
+                    .as_deref()
+                    .map(utils::mask_account_number),
+            )
+            .field("sort_code", &self.sort_code)
+            .finish()
+    }
+}
+
 impl PaymentRequest {
     fn get_card_missing(&self) -> Vec<&'static str> {
         let mut missing = vec![];
@@ -63,7 +84,7 @@ impl TryInto<Payment> for PaymentRequest {
         match self.payment_type.as_str() {
             "CARD" => {
                 let missing = self.get_card_missing();
-                if missing.len() > 0 {
+                if !missing.is_empty() {
                     return create_missing_error(&missing);
                 }
                 Ok(Payment::Card {
@@ -75,7 +96,7 @@ impl TryInto<Payment> for PaymentRequest {
             }
             "ACCOUNT" => {
                 let missing = self.get_account_missing();
-                if missing.len() > 0 {
+                if !missing.is_empty() {
                     return create_missing_error(&missing);
                 }
                 Ok(Payment::Account {
@@ -83,12 +104,10 @@ impl TryInto<Payment> for PaymentRequest {
                     sort_code: self.sort_code.unwrap(),
                 })
             }
-            invalid => {
-                return Err(GatewayError {
-                    kind: Validation,
-                    message: format!("{} is not a valid payment type", invalid),
-                });
-            }
+            invalid => Err(GatewayError {
+                kind: Validation,
+                message: format!("{} is not a valid payment type", invalid),
+            }),
         }
     }
 }
@@ -115,6 +134,26 @@ mod tests {
         assert_eq!(actual, expected);
     }
 
+    #[rstest]
+    fn debug_redacts_security_code() {
+        let payment_json = r#"{"payment_type": "CARD", "scheme": "VISA", "pan": "4000111122223333", "security_code": "987", "expiry_month": 1, "expiry_year": 2021}"#;
+        let request: PaymentRequest =
+            serde_json::from_str(payment_json).expect("deserialize payment request");
+        assert!(!format!("{request:?}").contains("987"));
+        assert!(!format!("{request:?}").contains("4000111122223333"));
+        let payment: Payment = request.try_into().unwrap();
+        assert!(!format!("{payment:?}").contains("987"));
+    }
+
+    #[rstest]
+    fn debug_masks_account_number() {
+        let payment_json =
+            r#"{"payment_type": "ACCOUNT", "account_number": "12345678", "sort_code": "123456"}"#;
+        let request: PaymentRequest =
+            serde_json::from_str(payment_json).expect("deserialize payment request");
+        assert!(format!("{request:?}").contains("account_number: Some(\"####5678\")"));
+    }
+
     #[rstest]
     fn deserialize_account() {
         let payment_json =
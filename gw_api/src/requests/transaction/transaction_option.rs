@@ -1,4 +1,103 @@
+use gw_core::{
+    payment_method::PaymentMethod,
+    sca::ScaIndicators,
+    stored_credential::{CredentialUsage, Initiator, StoredCredential},
+};
 use serde::Deserialize;
 
+use crate::error::{ErrorKind, GatewayError};
+
+const SCHEME_TRANSACTION_ID_MAX_LENGTH: usize = 64;
+
 #[derive(Deserialize, Default, Debug)]
-pub struct TransactionOptionRequest {}
+pub struct TransactionOptionRequest {
+    /// Authenticates the cardholder with 3-D Secure before the transaction is authorised
+    #[serde(default)]
+    pub three_ds: bool,
+    /// Started by the merchant without the cardholder there
+    #[serde(default)]
+    pub merchant_initiated: bool,
+    /// One of a series of payments for the same amount to the same merchant
+    #[serde(default)]
+    pub recurring: bool,
+    /// Made with a corporate card through a secure corporate process
+    #[serde(default)]
+    pub corporate: bool,
+    /// INITIAL when the card is being stored for later transactions, SUBSEQUENT when a stored
+    /// card is used. Payments with a saved payment method are stored credential transactions
+    /// whether or not it's given.
+    pub stored_credential: Option<String>,
+    /// The scheme transaction ID of the initial transaction, for subsequent ones. It's chained
+    /// from the saved payment method when not given.
+    pub original_scheme_transaction_id: Option<String>,
+}
+
+impl TransactionOptionRequest {
+    pub fn sca_indicators(&self) -> ScaIndicators {
+        ScaIndicators {
+            merchant_initiated: self.merchant_initiated,
+            recurring: self.recurring,
+            corporate: self.corporate,
+        }
+    }
+
+    /// The credential-on-file indicators for a card transaction, paid with the saved payment
+    /// method if there is one. A saved payment method's first transaction is the initial one,
+    /// unless it's merchant initiated.
+    pub fn stored_credential(
+        &self,
+        saved: Option<&PaymentMethod>,
+    ) -> Result<Option<StoredCredential>, GatewayError> {
+        let chained = saved.and_then(|method| method.scheme_transaction_id.clone());
+        let usage = match (&self.stored_credential, saved) {
+            (Some(usage), _) => CredentialUsage::try_from(usage.clone())
+                .map_err(|e| invalid(&format!("options.stored_credential: {}", e.message)))?,
+            (None, Some(_)) if chained.is_none() && !self.merchant_initiated => {
+                CredentialUsage::Initial
+            }
+            (None, Some(_)) => CredentialUsage::Subsequent,
+            (None, None) if self.original_scheme_transaction_id.is_none() => return Ok(None),
+            (None, None) => return Err(invalid(ONLY_SUBSEQUENT)),
+        };
+        let initiator = if self.merchant_initiated {
+            Initiator::Merchant
+        } else {
+            Initiator::Cardholder
+        };
+        let original_scheme_transaction_id = match usage {
+            CredentialUsage::Initial if initiator == Initiator::Merchant => {
+                return Err(invalid(
+                    "an INITIAL stored credential transaction can't be merchant initiated",
+                ));
+            }
+            CredentialUsage::Initial if self.original_scheme_transaction_id.is_some() => {
+                return Err(invalid(ONLY_SUBSEQUENT));
+            }
+            CredentialUsage::Initial => None,
+            CredentialUsage::Subsequent => self.original_scheme_transaction_id.clone().or(chained),
+        };
+        if original_scheme_transaction_id
+            .as_ref()
+            .is_some_and(|id| id.is_empty() || id.len() > SCHEME_TRANSACTION_ID_MAX_LENGTH)
+        {
+            return Err(invalid(
+                "options.original_scheme_transaction_id: invalid length",
+            ));
+        }
+        Ok(Some(StoredCredential {
+            usage,
+            initiator,
+            original_scheme_transaction_id,
+        }))
+    }
+}
+
+const ONLY_SUBSEQUENT: &str =
+    "options.original_scheme_transaction_id is only for SUBSEQUENT stored credential transactions";
+
+fn invalid(message: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Validation,
+        message: message.into(),
+    }
+}
@@ -0,0 +1,140 @@
+use chrono::{DateTime, Utc};
+use gw_core::{
+    currency::Currency,
+    transaction::{
+        search::{Cursor, TransactionFilter, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE},
+        TransactionType,
+    },
+};
+use serde::Deserialize;
+
+use crate::error::{ErrorKind::Validation, GatewayError};
+
+/// Query string parameters for listing transactions
+#[derive(Deserialize, Default, Debug, PartialEq)]
+pub struct TransactionSearchRequest {
+    pub merchant_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub status: Option<String>,
+    pub transaction_type: Option<TransactionType>,
+    pub currency: Option<Currency>,
+    pub min_amount: Option<u64>,
+    pub max_amount: Option<u64>,
+    pub last_four: Option<String>,
+    pub merchant_reference: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// A validated search, ready to be run against the TransactionRepo
+#[derive(Debug, PartialEq)]
+pub struct TransactionSearch {
+    pub filter: TransactionFilter,
+    pub cursor: Option<Cursor>,
+    pub limit: u32,
+}
+
+fn invalid<T>(message: String) -> Result<T, GatewayError> {
+    Err(GatewayError {
+        kind: Validation,
+        message,
+    })
+}
+
+impl TryFrom<TransactionSearchRequest> for TransactionSearch {
+    type Error = GatewayError;
+
+    fn try_from(value: TransactionSearchRequest) -> Result<Self, Self::Error> {
+        let limit = value.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+        if limit == 0 || limit > MAX_PAGE_SIZE {
+            return invalid(format!("limit must be between 1 and {MAX_PAGE_SIZE}"));
+        }
+        if let (Some(from), Some(to)) = (value.from, value.to) {
+            if from > to {
+                return invalid("from must be before to".into());
+            }
+        }
+        if let (Some(min), Some(max)) = (value.min_amount, value.max_amount) {
+            if min > max {
+                return invalid("min_amount must not be more than max_amount".into());
+            }
+        }
+        if let Some(last_four) = &value.last_four {
+            if last_four.len() != 4 || !last_four.chars().all(|c| c.is_ascii_digit()) {
+                return invalid("last_four must be 4 digits".into());
+            }
+        }
+        let status = match value.status {
+            Some(status) => {
+                Some(
+                    status
+                        .try_into()
+                        .map_err(|e: gw_core::error::Error| GatewayError {
+                            kind: Validation,
+                            message: e.message,
+                        })?,
+                )
+            }
+            None => None,
+        };
+        let cursor = match value.cursor {
+            Some(cursor) => Some(Cursor::try_from(cursor.as_str()).map_err(|e| GatewayError {
+                kind: Validation,
+                message: e.message,
+            })?),
+            None => None,
+        };
+        Ok(TransactionSearch {
+            filter: TransactionFilter {
+                merchant_id: value.merchant_id,
+                from: value.from,
+                to: value.to,
+                status,
+                r#type: value.transaction_type,
+                currency: value.currency,
+                min_amount: value.min_amount,
+                max_amount: value.max_amount,
+                last_four: value.last_four,
+                merchant_reference: value.merchant_reference,
+            },
+            cursor,
+            limit,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gw_core::transaction::TransactionStatus;
+    use rstest::*;
+
+    #[rstest]
+    fn convert_to_search() {
+        let request = TransactionSearchRequest {
+            merchant_id: Some("merchant123".into()),
+            status: Some("FAILED".into()),
+            last_four: Some("1234".into()),
+            cursor: Some("1700000000000000.abc".into()),
+            ..Default::default()
+        };
+        let search = TransactionSearch::try_from(request).unwrap();
+        assert_eq!(search.limit, DEFAULT_PAGE_SIZE);
+        assert_eq!(search.filter.status, Some(TransactionStatus::Failed(None)));
+        assert_eq!(search.filter.last_four.as_deref(), Some("1234"));
+        assert_eq!(search.cursor.unwrap().reference, "abc");
+    }
+
+    #[rstest]
+    #[case(TransactionSearchRequest { limit: Some(0), ..Default::default() }, "limit must be between 1 and 100")]
+    #[case(TransactionSearchRequest { limit: Some(101), ..Default::default() }, "limit must be between 1 and 100")]
+    #[case(TransactionSearchRequest { min_amount: Some(2), max_amount: Some(1), ..Default::default() }, "min_amount must not be more than max_amount")]
+    #[case(TransactionSearchRequest { last_four: Some("12a4".into()), ..Default::default() }, "last_four must be 4 digits")]
+    #[case(TransactionSearchRequest { status: Some("PENDING".into()), ..Default::default() }, "PENDING is not a recognised transaction status")]
+    #[case(TransactionSearchRequest { cursor: Some("nope".into()), ..Default::default() }, "nope is not a valid cursor")]
+    fn invalid_search(#[case] request: TransactionSearchRequest, #[case] message: &str) {
+        let err = TransactionSearch::try_from(request).unwrap_err();
+        assert_eq!(err.message, message);
+    }
+}
@@ -0,0 +1,47 @@
+use gw_core::transaction::search::DEFAULT_PAGE_SIZE;
+use reqwest::Url;
+use serde::Deserialize;
+
+use crate::error::{ErrorKind, GatewayError};
+
+const MAX_URL_LENGTH: usize = 2048;
+
+/// Body for setting where a merchant's events are sent
+#[derive(Deserialize, Debug)]
+pub struct WebhookEndpointRequest {
+    pub url: String,
+}
+
+impl WebhookEndpointRequest {
+    /// The endpoint's URL, which has to be an absolute http or https one
+    pub fn url(&self) -> Result<Url, GatewayError> {
+        if self.url.len() > MAX_URL_LENGTH {
+            return Err(invalid(&format!(
+                "url must be at most {MAX_URL_LENGTH} characters"
+            )));
+        }
+        let url = Url::parse(&self.url).map_err(|_| invalid("url is not a valid url"))?;
+        if !matches!(url.scheme(), "http" | "https") || url.host().is_none() {
+            return Err(invalid("url must be an http or https url"));
+        }
+        Ok(url)
+    }
+}
+
+/// Query string parameters for listing dead-lettered events
+#[derive(Deserialize, Debug, Default)]
+pub struct WebhookEventListRequest {
+    #[serde(default = "default_limit")]
+    pub limit: u32,
+}
+
+fn default_limit() -> u32 {
+    DEFAULT_PAGE_SIZE
+}
+
+fn invalid(message: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Validation,
+        message: message.into(),
+    }
+}
@@ -0,0 +1,21 @@
+use gw_core::account::MerchantAccount;
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct AccountResponse {
+    pub account_id: i32,
+    pub acquirer: &'static str,
+    /// The acquirer specific fields, e.g. merchant_identification_value for bankone
+    #[serde(flatten)]
+    pub details: serde_json::Value,
+}
+
+impl From<&MerchantAccount> for AccountResponse {
+    fn from(value: &MerchantAccount) -> Self {
+        Self {
+            account_id: value.id,
+            acquirer: value.account.acquirer(),
+            details: value.account.acquirer_data(),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use gw_core::api_key::{ApiKey, Role};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct ApiKeyResponse<'a> {
+    pub key_id: &'a str,
+    pub role: Role,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub active: bool,
+}
+
+/// A newly created key, the only time the full key is ever returned
+#[derive(Serialize, PartialEq, Debug)]
+pub struct NewApiKeyResponse<'a> {
+    pub api_key: &'a str,
+    #[serde(flatten)]
+    pub key: ApiKeyResponse<'a>,
+}
+
+impl<'a> From<&'a ApiKey> for ApiKeyResponse<'a> {
+    fn from(value: &'a ApiKey) -> Self {
+        Self {
+            key_id: &value.key_id,
+            role: value.role,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            revoked_at: value.revoked_at,
+            active: value.is_active_at(Utc::now()),
+        }
+    }
+}
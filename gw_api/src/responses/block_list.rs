@@ -0,0 +1,36 @@
+use chrono::{DateTime, Utc};
+use gw_core::block_list::{EntryKind, ListEntry, ListType};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct ListEntryResponse<'a> {
+    pub id: &'a str,
+    pub list: ListType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merchant_id: Option<&'a str>,
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    /// Cards are shown by their fingerprint
+    pub value: &'a str,
+    pub reason: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub active: bool,
+}
+
+impl<'a> From<&'a ListEntry> for ListEntryResponse<'a> {
+    fn from(value: &'a ListEntry) -> Self {
+        Self {
+            id: &value.id,
+            list: value.list,
+            merchant_id: value.merchant_id.as_deref(),
+            kind: value.kind,
+            value: &value.value,
+            reason: &value.reason,
+            expires_at: value.expires_at,
+            created_at: value.created_at,
+            active: value.expires_at.is_none_or(|at| at > Utc::now()),
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use gw_core::{
+    card_scheme::CardScheme,
+    customer::{Address, Customer},
+    payment_method::PaymentMethod,
+};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct CustomerResponse<'a> {
+    pub customer_id: &'a str,
+    pub first_name: &'a str,
+    pub last_name: &'a str,
+    pub email: &'a str,
+    pub phone: &'a str,
+    pub addresses: &'a [Address],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_methods: Option<Vec<PaymentMethodResponse<'a>>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A saved payment method, only ever shown masked
+#[derive(Serialize, PartialEq, Debug)]
+pub struct PaymentMethodResponse<'a> {
+    pub token: &'a str,
+    pub r#type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<CardScheme>,
+    pub masked_number: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry_month: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry_year: Option<u32>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl<'a> From<&'a Customer> for CustomerResponse<'a> {
+    fn from(value: &'a Customer) -> Self {
+        Self {
+            customer_id: &value.customer_id,
+            first_name: &value.first_name,
+            last_name: &value.last_name,
+            email: &value.email,
+            phone: &value.phone,
+            addresses: &value.addresses,
+            payment_methods: None,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl<'a> From<(&'a Customer, &'a [PaymentMethod])> for CustomerResponse<'a> {
+    fn from((customer, methods): (&'a Customer, &'a [PaymentMethod])) -> Self {
+        Self {
+            payment_methods: Some(methods.iter().map(PaymentMethodResponse::from).collect()),
+            ..Self::from(customer)
+        }
+    }
+}
+
+impl<'a> From<&'a PaymentMethod> for PaymentMethodResponse<'a> {
+    fn from(value: &'a PaymentMethod) -> Self {
+        Self {
+            token: &value.token,
+            r#type: if value.scheme.is_some() {
+                "CARD"
+            } else {
+                "ACCOUNT"
+            },
+            scheme: value.scheme,
+            masked_number: &value.masked_number,
+            expiry_month: value.expiry_date.map(|(_, month)| month),
+            expiry_year: value.expiry_date.map(|(year, _)| year),
+            created_at: value.created_at,
+        }
+    }
+}
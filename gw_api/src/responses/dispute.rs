@@ -0,0 +1,119 @@
+use chrono::{DateTime, Utc};
+use gw_core::{
+    card_scheme::CardScheme,
+    currency::Currency,
+    dispute::{Adjustment, AdjustmentReason, Dispute, DisputeStatus, Evidence, EvidenceType},
+};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct EvidenceResponse<'a> {
+    pub evidence_id: &'a str,
+    pub evidence_type: EvidenceType,
+    pub file_name: &'a str,
+    pub content_type: &'a str,
+    pub size_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'a str>,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct AdjustmentResponse<'a> {
+    pub adjustment_id: &'a str,
+    pub reason: AdjustmentReason,
+    /// Negative when funds were taken from the merchant
+    pub amount: i64,
+    pub currency: Currency,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct DisputeResponse<'a> {
+    pub dispute_id: &'a str,
+    pub transaction_reference: &'a str,
+    pub acquirer: &'a str,
+    pub case_id: &'a str,
+    pub scheme: CardScheme,
+    pub reason_code: &'a str,
+    pub reason: &'static str,
+    pub amount: u64,
+    pub currency: Currency,
+    pub status: DisputeStatus,
+    pub respond_by: DateTime<Utc>,
+    pub evidence: Vec<EvidenceResponse<'a>>,
+    pub adjustments: Vec<AdjustmentResponse<'a>>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submitted_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// What was done with each line of a dispute file
+#[derive(Serialize, PartialEq, Debug, Default)]
+pub struct DisputeFileResponse {
+    pub disputes: usize,
+    pub opened: usize,
+    pub resolved: usize,
+    pub errors: Vec<DisputeFileError>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct DisputeFileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl<'a> From<&'a Evidence> for EvidenceResponse<'a> {
+    fn from(value: &'a Evidence) -> Self {
+        Self {
+            evidence_id: &value.evidence_id,
+            evidence_type: value.evidence_type,
+            file_name: &value.file_name,
+            content_type: &value.content_type,
+            size_bytes: value.size_bytes,
+            description: value.description.as_deref(),
+            uploaded_at: value.uploaded_at,
+        }
+    }
+}
+
+impl<'a> From<&'a Adjustment> for AdjustmentResponse<'a> {
+    fn from(value: &'a Adjustment) -> Self {
+        Self {
+            adjustment_id: &value.adjustment_id,
+            reason: value.reason,
+            amount: value.amount,
+            currency: value.currency,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl<'a> From<&'a Dispute> for DisputeResponse<'a> {
+    fn from(value: &'a Dispute) -> Self {
+        Self {
+            dispute_id: &value.dispute_id,
+            transaction_reference: &value.transaction_reference,
+            acquirer: &value.acquirer,
+            case_id: &value.case_id,
+            scheme: value.scheme,
+            reason_code: &value.reason_code,
+            reason: value.reason(),
+            amount: value.amount,
+            currency: value.currency,
+            status: value.status,
+            respond_by: value.respond_by,
+            evidence: value.evidence.iter().map(EvidenceResponse::from).collect(),
+            adjustments: value
+                .adjustments
+                .iter()
+                .map(AdjustmentResponse::from)
+                .collect(),
+            created_at: value.created_at,
+            submitted_at: value.submitted_at,
+            resolved_at: value.resolved_at,
+        }
+    }
+}
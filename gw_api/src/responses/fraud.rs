@@ -0,0 +1,22 @@
+use gw_core::fraud::{FraudRule, FraudSettings};
+use serde::Serialize;
+
+/// Blocked cards are shown by their fingerprints, the pans aren't kept
+#[derive(Serialize, PartialEq, Debug)]
+pub struct FraudSettingsResponse<'a> {
+    pub merchant_id: &'a str,
+    pub review_score: u32,
+    pub reject_score: u32,
+    pub rules: &'a [FraudRule],
+}
+
+impl<'a> From<&'a FraudSettings> for FraudSettingsResponse<'a> {
+    fn from(value: &'a FraudSettings) -> Self {
+        Self {
+            merchant_id: &value.merchant_id,
+            review_score: value.review_score,
+            reject_score: value.reject_score,
+            rules: &value.rules,
+        }
+    }
+}
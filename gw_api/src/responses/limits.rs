@@ -0,0 +1,21 @@
+use gw_core::limits::MerchantLimits;
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct LimitsResponse<'a> {
+    pub merchant_id: &'a str,
+    pub requests_per_minute: Option<u32>,
+    pub max_card_transactions_per_hour: Option<u32>,
+    pub max_card_amount_per_hour: Option<u64>,
+}
+
+impl<'a> From<&'a MerchantLimits> for LimitsResponse<'a> {
+    fn from(value: &'a MerchantLimits) -> Self {
+        Self {
+            merchant_id: &value.merchant_id,
+            requests_per_minute: value.requests_per_minute,
+            max_card_transactions_per_hour: value.velocity.max_card_transactions_per_hour,
+            max_card_amount_per_hour: value.velocity.max_card_amount_per_hour,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use gw_core::{
+    country::Country,
+    merchant::{Merchant, MerchantPage},
+};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct MerchantResponse<'a> {
+    pub merchant_id: &'a str,
+    pub name: &'a str,
+    pub premise: &'a str,
+    pub street: &'a str,
+    pub city: &'a str,
+    pub postcode: &'a str,
+    pub county: &'a str,
+    pub country: Country,
+    pub active: bool,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct MerchantListResponse<'a> {
+    pub merchants: Vec<MerchantResponse<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<&'a str>,
+}
+
+impl<'a> From<&'a Merchant> for MerchantResponse<'a> {
+    fn from(value: &'a Merchant) -> Self {
+        Self {
+            merchant_id: &value.merchant_id,
+            name: &value.name,
+            premise: &value.premise,
+            street: &value.street,
+            city: &value.city,
+            postcode: &value.postcode,
+            county: &value.county,
+            country: value.country,
+            active: value.active,
+        }
+    }
+}
+
+impl<'a> From<&'a MerchantPage> for MerchantListResponse<'a> {
+    fn from(value: &'a MerchantPage) -> Self {
+        Self {
+            merchants: value.merchants.iter().map(Into::into).collect(),
+            next_cursor: value.next_cursor.as_deref(),
+        }
+    }
+}
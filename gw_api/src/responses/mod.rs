@@ -1 +1,18 @@
+pub mod account;
+pub mod api_key;
+pub mod block_list;
+pub mod customer;
+pub mod dispute;
+pub mod fraud;
+pub mod limits;
+pub mod merchant;
+pub mod notification;
+pub mod payment_route;
+pub mod policy;
+pub mod sca;
+pub mod signing;
+pub mod subscription;
+pub mod three_ds;
 pub mod transaction;
+pub mod transaction_search;
+pub mod webhook;
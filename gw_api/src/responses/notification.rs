@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use gw_core::notification::AcquirerNotification;
+use serde::Serialize;
+
+/// The gateway's answer to an acquirer, sent back the same for redeliveries
+#[derive(Serialize, PartialEq, Debug)]
+pub struct NotificationAckResponse<'a> {
+    pub notification_id: &'a str,
+    pub outcome: String,
+    /// Whether the notification had already been received
+    pub duplicate: bool,
+}
+
+/// A notification as it was received, for audit
+#[derive(Serialize, PartialEq, Debug)]
+pub struct NotificationResponse<'a> {
+    pub acquirer: &'a str,
+    pub notification_id: &'a str,
+    pub transaction_reference: &'a str,
+    pub kind: String,
+    pub outcome: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<&'a str>,
+    pub received_at: DateTime<Utc>,
+    pub raw: &'a str,
+}
+
+impl<'a> From<&'a AcquirerNotification> for NotificationResponse<'a> {
+    fn from(value: &'a AcquirerNotification) -> Self {
+        Self {
+            acquirer: &value.acquirer,
+            notification_id: &value.notification_id,
+            transaction_reference: &value.transaction_reference,
+            kind: value.kind.to_string(),
+            outcome: value.outcome.to_string(),
+            detail: value.detail.as_deref(),
+            received_at: value.received_at,
+            raw: &value.raw,
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use gw_core::{
+    account::AcquirerAccount, card_scheme::CardScheme, currency::Currency,
+    payment_route::PaymentRoute,
+};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct RouteResponse<'a> {
+    pub scheme: CardScheme,
+    pub currency: Currency,
+    pub acquirer: &'a str,
+    pub account_id: i32,
+}
+
+impl<'a> From<&'a PaymentRoute> for RouteResponse<'a> {
+    fn from(value: &'a PaymentRoute) -> Self {
+        Self {
+            scheme: value.scheme,
+            currency: value.currency,
+            acquirer: &value.acquirer,
+            account_id: value.account_id,
+        }
+    }
+}
+
+/// The account a payment would be sent to
+#[derive(Serialize, PartialEq, Debug)]
+pub struct RouteDecisionResponse {
+    pub scheme: CardScheme,
+    pub currency: Currency,
+    pub acquirer: &'static str,
+    pub account: serde_json::Value,
+}
+
+impl RouteDecisionResponse {
+    pub fn new(scheme: CardScheme, currency: Currency, account: &AcquirerAccount) -> Self {
+        Self {
+            scheme,
+            currency,
+            acquirer: account.acquirer(),
+            account: account.acquirer_data(),
+        }
+    }
+}
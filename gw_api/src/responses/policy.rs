@@ -0,0 +1,47 @@
+use gw_core::{avs::AvsPolicy, cvv::CvvPolicy, policy::MerchantPolicies};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct PoliciesResponse<'a> {
+    pub merchant_id: &'a str,
+    pub avs: AvsPolicyResponse,
+    pub cvv: CvvPolicyResponse,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct AvsPolicyResponse {
+    pub decline_address_mismatch: bool,
+    pub decline_postcode_mismatch: bool,
+}
+
+impl From<&AvsPolicy> for AvsPolicyResponse {
+    fn from(value: &AvsPolicy) -> Self {
+        Self {
+            decline_address_mismatch: value.decline_address_mismatch,
+            decline_postcode_mismatch: value.decline_postcode_mismatch,
+        }
+    }
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct CvvPolicyResponse {
+    pub void_on_mismatch: bool,
+}
+
+impl From<&CvvPolicy> for CvvPolicyResponse {
+    fn from(value: &CvvPolicy) -> Self {
+        Self {
+            void_on_mismatch: value.void_on_mismatch,
+        }
+    }
+}
+
+impl<'a> From<&'a MerchantPolicies> for PoliciesResponse<'a> {
+    fn from(value: &'a MerchantPolicies) -> Self {
+        Self {
+            merchant_id: &value.merchant_id,
+            avs: (&value.avs).into(),
+            cvv: (&value.cvv).into(),
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use gw_core::{currency::Currency, sca::ScaThresholds};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct ScaThresholdsResponse {
+    pub currency: Currency,
+    pub low_value: Option<u64>,
+    pub transaction_risk_analysis: Option<u64>,
+    pub merchant_initiated: Option<u64>,
+    pub recurring: Option<u64>,
+    pub corporate: Option<u64>,
+}
+
+impl From<&ScaThresholds> for ScaThresholdsResponse {
+    fn from(value: &ScaThresholds) -> Self {
+        Self {
+            currency: value.currency,
+            low_value: value.low_value,
+            transaction_risk_analysis: value.transaction_risk_analysis,
+            merchant_initiated: value.merchant_initiated,
+            recurring: value.recurring,
+            corporate: value.corporate,
+        }
+    }
+}
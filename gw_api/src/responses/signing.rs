@@ -0,0 +1,7 @@
+use serde::Serialize;
+
+/// A newly created signing secret, the only time it is ever returned
+#[derive(Serialize, PartialEq, Debug)]
+pub struct SigningSecretResponse<'a> {
+    pub signing_secret: &'a str,
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use gw_core::{
+    currency::Currency,
+    subscription::{Interval, Plan, Subscription, SubscriptionStatus},
+};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct PlanResponse<'a> {
+    pub plan_id: &'a str,
+    pub name: &'a str,
+    pub amount: u64,
+    pub currency: Currency,
+    pub interval: Interval,
+    pub interval_count: u32,
+    pub trial_days: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct SubscriptionResponse<'a> {
+    pub subscription_id: &'a str,
+    pub plan_id: &'a str,
+    pub customer_id: &'a str,
+    pub payment_token: &'a str,
+    pub status: SubscriptionStatus,
+    pub next_charge_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_at: Option<DateTime<Utc>>,
+    pub failed_attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_transaction: Option<&'a str>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+impl<'a> From<&'a Plan> for PlanResponse<'a> {
+    fn from(value: &'a Plan) -> Self {
+        Self {
+            plan_id: &value.plan_id,
+            name: &value.name,
+            amount: value.amount,
+            currency: value.currency,
+            interval: value.interval,
+            interval_count: value.interval_count,
+            trial_days: value.trial_days,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl<'a> From<&'a Subscription> for SubscriptionResponse<'a> {
+    fn from(value: &'a Subscription) -> Self {
+        Self {
+            subscription_id: &value.subscription_id,
+            plan_id: &value.plan_id,
+            customer_id: &value.customer_id,
+            payment_token: &value.payment_token,
+            status: value.status,
+            next_charge_at: value.next_charge_at,
+            retry_at: value.retry_at,
+            failed_attempts: value.failed_attempts,
+            last_transaction: value.last_transaction.as_deref(),
+            created_at: value.created_at,
+            cancelled_at: value.cancelled_at,
+        }
+    }
+}
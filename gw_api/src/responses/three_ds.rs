@@ -0,0 +1,25 @@
+use chrono::{DateTime, Utc};
+use gw_core::three_ds::PendingAuthentication;
+use serde::Serialize;
+
+/// What the merchant's page needs to send the cardholder to the ACS
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ChallengeResponse<'a> {
+    pub reference: &'a str,
+    pub ds_transaction_id: &'a str,
+    pub acs_url: &'a str,
+    pub creq: &'a str,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl<'a> From<&'a PendingAuthentication> for ChallengeResponse<'a> {
+    fn from(value: &'a PendingAuthentication) -> Self {
+        Self {
+            reference: &value.transaction.reference,
+            ds_transaction_id: &value.challenge.ds_transaction_id,
+            acs_url: &value.challenge.acs_url,
+            creq: &value.challenge.creq,
+            expires_at: value.expires_at,
+        }
+    }
+}
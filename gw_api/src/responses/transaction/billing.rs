@@ -15,6 +15,8 @@ pub struct BillingResponse<'a> {
     city: &'a str,
     #[serde(skip_serializing_if = "str::is_empty")]
     county: &'a str,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    postcode: &'a str,
     // #[serde(skip_serializing_if = "Option::is_none")]
     country: Country,
 }
@@ -28,6 +30,7 @@ impl<'a> From<&'a Billing> for BillingResponse<'a> {
             street: &value.street,
             city: &value.city,
             county: &value.county,
+            postcode: &value.postcode,
             country: value.country,
         }
     }
@@ -1,9 +1,17 @@
 mod billing;
 mod payment;
 
+use std::collections::BTreeMap;
+
 use billing::BillingResponse;
 use gw_core::{
+    avs::AvsResult,
     currency::Currency,
+    cvv::CvvResult,
+    fraud::FraudResult,
+    sca::ScaExemption,
+    stored_credential::StoredCredential,
+    three_ds::ThreeDsResult,
     transaction::{Transaction, TransactionError, TransactionStatus},
 };
 use payment::PaymentResponse;
@@ -19,6 +27,30 @@ pub struct TransactionResponse<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error: Option<TransactionError>,
     pub reference: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merchant_reference: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<&'a BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fraud: Option<&'a FraudResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub avs: Option<&'a AvsResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cvv: Option<CvvResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub three_ds: Option<&'a ThreeDsResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sca_exemption: Option<ScaExemption>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_id: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payment_token: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stored_credential: Option<&'a StoredCredential>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme_transaction_id: Option<&'a str>,
 }
 
 impl<'a> From<&'a Transaction> for TransactionResponse<'a> {
@@ -37,6 +69,18 @@ impl<'a> From<&'a Transaction> for TransactionResponse<'a> {
             status: value.status.to_string(),
             error: if let Some(e) = error { e.clone() } else { None },
             reference: value.reference.clone(),
+            merchant_reference: value.merchant_reference.as_deref(),
+            description: value.description.as_deref(),
+            metadata: Some(&value.metadata).filter(|m| !m.is_empty()),
+            fraud: value.fraud.as_ref(),
+            avs: value.avs.as_ref(),
+            cvv: value.cvv,
+            three_ds: value.three_ds.as_ref(),
+            sca_exemption: value.sca_exemption,
+            customer_id: value.customer.as_ref().map(|c| c.customer_id.as_str()),
+            payment_token: value.payment_token.as_deref(),
+            stored_credential: value.stored_credential.as_ref(),
+            scheme_transaction_id: value.scheme_transaction_id.as_deref(),
         }
     }
 }
@@ -94,6 +138,7 @@ mod tests {
             status: "SUCCESS".into(),
             error: None,
             reference: trx.reference.clone(),
+            ..Default::default()
         };
         let exp_json = r#"\{
   "amount": 12345,
@@ -113,4 +158,37 @@ mod tests {
 \}"#;
         check_serialize_to_response(&trx, &exp, exp_json);
     }
+
+    #[rstest]
+    fn can_serialise_order_details_to_response() {
+        let metadata = BTreeMap::from([("basket_id".to_string(), "987".to_string())]);
+        let trx = TransactionBuilder::new()
+            .transaction_type(TransactionType::Auth)
+            .payment(Payment::from((
+                CardScheme::Visa,
+                (2023, 1),
+                "123",
+                "4000111122223333",
+            )))
+            .amount(12345)
+            .currency(Currency::GBP)
+            .account(AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: "12345678".into(),
+            }))
+            .merchant(Merchant::default())
+            .billing(Billing::default())
+            .merchant_reference(Some("order-123".into()))
+            .description(Some("2 x widgets".into()))
+            .metadata(metadata.clone())
+            .build();
+        let act = TransactionResponse::from(&trx);
+        assert_eq!(act.merchant_reference, Some("order-123"));
+        assert_eq!(act.description, Some("2 x widgets"));
+        assert_eq!(act.metadata, Some(&metadata));
+        let act_json = serde_json::to_value(&act).unwrap();
+        assert_eq!(
+            act_json["metadata"],
+            serde_json::json!({"basket_id": "987"})
+        );
+    }
 }
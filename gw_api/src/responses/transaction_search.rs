@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use gw_core::{
+    card_scheme::CardScheme,
+    currency::Currency,
+    transaction::{
+        search::{TransactionPage, TransactionSummary},
+        TransactionError,
+    },
+};
+use serde::Serialize;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct TransactionSummaryResponse<'a> {
+    pub reference: &'a str,
+    pub merchant_id: &'a str,
+    pub transaction_type: String,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'a TransactionError>,
+    pub amount: u64,
+    pub currency: Currency,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheme: Option<CardScheme>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pan: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merchant_reference: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<&'a str>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: &'a BTreeMap<String, String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct TransactionListResponse<'a> {
+    pub transactions: Vec<TransactionSummaryResponse<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+impl<'a> From<&'a TransactionSummary> for TransactionSummaryResponse<'a> {
+    fn from(value: &'a TransactionSummary) -> Self {
+        Self {
+            reference: &value.reference,
+            merchant_id: &value.merchant_id,
+            transaction_type: value.r#type.to_string(),
+            status: value.status.to_string(),
+            error: value.status.reason(),
+            amount: value.amount,
+            currency: value.currency,
+            scheme: value.card_scheme,
+            pan: value.masked_pan.as_deref(),
+            merchant_reference: value.merchant_reference.as_deref(),
+            description: value.description.as_deref(),
+            metadata: &value.metadata,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl<'a> From<&'a TransactionPage> for TransactionListResponse<'a> {
+    fn from(value: &'a TransactionPage) -> Self {
+        Self {
+            transactions: value.transactions.iter().map(Into::into).collect(),
+            next_cursor: value.next_cursor.as_ref().map(|c| c.to_string()),
+        }
+    }
+}
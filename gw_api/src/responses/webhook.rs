@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use gw_core::webhook::{DeliveryStatus, EventType, WebhookEndpoint, WebhookEvent};
+use serde::Serialize;
+use serde_json::Value;
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct WebhookEndpointResponse<'a> {
+    pub url: &'a str,
+    /// Only returned when the endpoint is created, after that it's kept the same
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signing_secret: Option<&'a str>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl<'a> From<&'a WebhookEndpoint> for WebhookEndpointResponse<'a> {
+    fn from(value: &'a WebhookEndpoint) -> Self {
+        Self {
+            url: &value.url,
+            signing_secret: None,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Serialize, PartialEq, Debug)]
+pub struct WebhookEventResponse<'a> {
+    pub event_id: &'a str,
+    pub event_type: EventType,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<&'a str>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub data: &'a Value,
+}
+
+impl<'a> From<&'a WebhookEvent> for WebhookEventResponse<'a> {
+    fn from(value: &'a WebhookEvent) -> Self {
+        Self {
+            event_id: &value.event_id,
+            event_type: value.event_type,
+            status: value.status,
+            attempts: value.attempts,
+            last_error: value.last_error.as_deref(),
+            next_attempt_at: value.next_attempt_at,
+            created_at: value.created_at,
+            delivered_at: value.delivered_at,
+            data: &value.data,
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use gw_core::{
+    stored_credential::CredentialUsage,
+    subscription::Subscription,
+    transaction::{TransactionStatus, TransactionType},
+};
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    app::AppState,
+    error::{ErrorKind, GatewayError},
+    handlers::post_transaction::process_transaction,
+    requests::transaction::{transaction_option::TransactionOptionRequest, TransactionRequest},
+    webhooks,
+};
+
+/// How often main looks for subscriptions to charge and 3DS challenges to expire
+pub const RUN_EVERY_SECS: u64 = 60;
+
+/// The most subscriptions charged in one run, the rest wait for the next one
+const BATCH_SIZE: i64 = 100;
+
+/// How long a run has to charge the subscriptions it claims, before another run can claim them
+const LEASE_SECS: i64 = 5 * 60;
+
+/// Charges subscriptions as they fall due, and fails transactions whose 3DS challenges weren't
+/// completed in time, forever. Main spawns it alongside the server.
+pub async fn run(app: AppState) {
+    let mut interval = tokio::time::interval(Duration::from_secs(RUN_EVERY_SECS));
+    loop {
+        interval.tick().await;
+        let _ = charge_due_subscriptions(&app).await;
+        let _ = expire_challenges(&app).await;
+    }
+}
+
+/// Fails the transactions whose 3DS challenges expired by the app's clock, letting their
+/// merchants know, returning how many were failed
+#[instrument(skip(app), err(Display))]
+pub async fn expire_challenges(app: &AppState) -> Result<usize, GatewayError> {
+    let expired = {
+        let app_access = app.lock().await;
+        let now = app_access.clock.now();
+        app_access.authentications.expire(now).await?
+    };
+    for reference in &expired {
+        info!(reference = %reference, "3DS challenge expired");
+        let summary = {
+            let app_access = app.lock().await;
+            app_access.transactions.find_summary(reference).await?
+        };
+        if let Some(summary) = summary {
+            if let Err(e) = webhooks::notify_change(app, &summary).await {
+                warn!(error = %e, "webhook event not queued");
+            }
+        }
+    }
+    Ok(expired.len())
+}
+
+/// Charges the subscriptions due by the app's clock, returning how many were charged
+#[instrument(skip(app), err(Display))]
+pub async fn charge_due_subscriptions(app: &AppState) -> Result<usize, GatewayError> {
+    let (now, due) = {
+        let app_access = app.lock().await;
+        let now = app_access.clock.now();
+        let lease_until = now + TimeDelta::seconds(LEASE_SECS);
+        let due = app_access
+            .subscriptions
+            .claim_due(now, lease_until, BATCH_SIZE)
+            .await?;
+        (now, due)
+    };
+    let count = due.len();
+    for subscription in due {
+        // one subscription going wrong shouldn't hold up the others
+        if let Err(e) = charge(app, subscription, now).await {
+            error!(error = %e, "subscription could not be charged");
+        }
+    }
+    Ok(count)
+}
+
+/// The key of the charge for the subscription's current period or retry, the same however many
+/// times the charge is attempted
+fn charge_key(subscription: &Subscription) -> String {
+    let due_at = subscription.retry_at.unwrap_or(subscription.next_charge_at);
+    format!("{}-{}", subscription.subscription_id, due_at.timestamp())
+}
+
+/// Makes a merchant initiated, recurring transaction with the subscription's saved payment
+/// method, as a subsequent stored credential transaction, then moves the subscription on or
+/// schedules a retry. A charge that was made before the scheduler was stopped, or before the
+/// subscription could be saved, is recorded rather than made again. One that was stopped before
+/// the acquirer answered may have been authorised, so it's left for someone to check rather than
+/// recorded or retried, and the subscription is claimed again once its lease runs out.
+#[instrument(skip_all, fields(subscription_id = %subscription.subscription_id))]
+async fn charge(
+    app: &AppState,
+    mut subscription: Subscription,
+    now: DateTime<Utc>,
+) -> Result<(), GatewayError> {
+    let key = charge_key(&subscription);
+    let (plan, made) = {
+        let app_access = app.lock().await;
+        let plan = app_access
+            .subscriptions
+            .find_plan(&subscription.merchant_id, &subscription.plan_id)
+            .await?;
+        let made = app_access
+            .transactions
+            .find_subscription_charge(&subscription.merchant_id, &key)
+            .await?;
+        (plan, made)
+    };
+    if let Some(made) = made {
+        match made.status {
+            // failed transactions were never authorised, or had their authorisations voided
+            TransactionStatus::Failed(_) => subscription.record_failure(now),
+            TransactionStatus::Success
+            | TransactionStatus::Settled
+            | TransactionStatus::ChargedBack
+                if made.processed_at.is_some() =>
+            {
+                subscription.record_success(&plan, now)
+            }
+            _ => {
+                return Err(GatewayError {
+                    kind: ErrorKind::Conflict,
+                    message: format!(
+                        "charge {} was interrupted before the acquirer answered, check it with \
+                         them before charging again",
+                        made.reference
+                    ),
+                })
+            }
+        }
+        warn!(reference = %made.reference, status = %made.status, "subscription already charged");
+        subscription.last_transaction = Some(made.reference);
+        let app_access = app.lock().await;
+        app_access.subscriptions.update(&subscription).await?;
+        return Ok(());
+    }
+    let request = TransactionRequest {
+        amount: plan.amount,
+        currency: plan.currency,
+        transaction_type: TransactionType::Auth,
+        merchant_id: subscription.merchant_id.clone(),
+        payment: None,
+        billing: None,
+        customer_id: Some(subscription.customer_id.clone()),
+        payment_token: Some(subscription.payment_token.clone()),
+        options: Some(TransactionOptionRequest {
+            merchant_initiated: true,
+            recurring: true,
+            // chained to the initial transaction by the payment method
+            stored_credential: Some(CredentialUsage::Subsequent.to_string()),
+            ..Default::default()
+        }),
+        merchant_reference: None,
+        description: Some(plan.name.clone()),
+        metadata: BTreeMap::from([(
+            "subscription_id".to_string(),
+            subscription.subscription_id.clone(),
+        )]),
+        ip_address: None,
+        subscription_charge: Some(key),
+    };
+    match process_transaction(app, request).await {
+        Ok((transaction, _)) => {
+            subscription.last_transaction = Some(transaction.reference.clone());
+            if transaction.status == TransactionStatus::Success {
+                subscription.record_success(&plan, now);
+            } else {
+                subscription.record_failure(now);
+            }
+        }
+        // the charge couldn't be made, like when the saved payment method has been deleted or
+        // the card is over its velocity limits, so it's retried like a decline
+        Err(e) if !matches!(e.kind, ErrorKind::Fatal) => {
+            warn!(error = %e, "subscription charge not made");
+            subscription.record_failure(now);
+        }
+        Err(e) => return Err(e),
+    }
+    info!(
+        status = %subscription.status,
+        failed_attempts = subscription.failed_attempts,
+        "subscription charged"
+    );
+    let app_access = app.lock().await;
+    app_access.subscriptions.update(&subscription).await?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::HeaderName,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use gw_core::signing::SignedRequest;
+use tracing::warn;
+
+use crate::{
+    app::AppState,
+    auth::Caller,
+    error::{ErrorKind, GatewayError},
+};
+
+pub static SIGNATURE_HEADER: HeaderName = HeaderName::from_static("x-signature");
+pub static SIGNATURE_TIMESTAMP_HEADER: HeaderName =
+    HeaderName::from_static("x-signature-timestamp");
+pub static SIGNATURE_NONCE_HEADER: HeaderName = HeaderName::from_static("x-signature-nonce");
+
+/// Signed requests are accepted up to 5 minutes either side of the gateway's clock
+pub const DEFAULT_MAX_SKEW_SECS: i64 = 5 * 60;
+
+const MAX_NONCE_LENGTH: usize = 128;
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+fn signature_error(message: &str) -> GatewayError {
+    GatewayError {
+        kind: ErrorKind::Signature,
+        message: message.into(),
+    }
+}
+
+fn header<'r>(request: &'r Request, name: &HeaderName) -> Result<&'r str, GatewayError> {
+    request
+        .headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| signature_error(&format!("missing {name} header")))
+}
+
+/// Middleware checking the HMAC signature of requests from merchants who have a signing secret,
+/// it must run after [`crate::auth::authenticate`]. Each nonce can only be used once within the
+/// clock skew window, so a captured request can't be replayed.
+pub async fn verify_signature(
+    State(app): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, GatewayError> {
+    let Some(merchant_id) = request
+        .extensions()
+        .get::<Caller>()
+        .and_then(|caller| caller.merchant_id.clone())
+    else {
+        return Ok(next.run(request).await);
+    };
+    let (secret, max_skew_secs) = {
+        let app_access = app.lock().await;
+        (
+            app_access.signing.find_secret(&merchant_id).await?,
+            app_access.signature_max_skew_secs,
+        )
+    };
+    let Some(secret) = secret else {
+        return Ok(next.run(request).await);
+    };
+    let signature = header(&request, &SIGNATURE_HEADER)?.to_string();
+    let nonce = header(&request, &SIGNATURE_NONCE_HEADER)?.to_string();
+    if nonce.is_empty() || nonce.len() > MAX_NONCE_LENGTH {
+        return Err(signature_error(&format!(
+            "{SIGNATURE_NONCE_HEADER} must be between 1 and {MAX_NONCE_LENGTH} characters"
+        )));
+    }
+    let timestamp = header(&request, &SIGNATURE_TIMESTAMP_HEADER)?
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+        .ok_or_else(|| {
+            signature_error(&format!(
+                "{SIGNATURE_TIMESTAMP_HEADER} must be seconds since the unix epoch"
+            ))
+        })?;
+    let max_skew = TimeDelta::seconds(max_skew_secs);
+    if (Utc::now() - timestamp).abs() > max_skew {
+        return Err(signature_error(
+            "signature timestamp is outside the allowed window",
+        ));
+    }
+
+    let (parts, body) = request.into_parts();
+    let bytes = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|e| GatewayError {
+            kind: ErrorKind::Validation,
+            message: e.to_string(),
+        })?;
+    let path = parts
+        .uri
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+    let signed = SignedRequest {
+        method: parts.method.as_str(),
+        path,
+        timestamp: timestamp.timestamp(),
+        nonce: &nonce,
+        body: &bytes,
+    };
+    if !signed.verify(&secret, &signature) {
+        warn!(%merchant_id, "invalid request signature");
+        return Err(signature_error("invalid signature"));
+    }
+    let fresh = {
+        let app_access = app.lock().await;
+        app_access
+            .signing
+            .use_nonce(&merchant_id, &nonce, timestamp + max_skew)
+            .await?
+    };
+    if !fresh {
+        warn!(%merchant_id, nonce, "signature nonce reused");
+        return Err(signature_error("signature nonce has already been used"));
+    }
+    Ok(next
+        .run(Request::from_parts(parts, Body::from(bytes)))
+        .await)
+}
@@ -0,0 +1,199 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use gw_core::{
+    dispute::Dispute,
+    transaction::{search::TransactionSummary, Transaction},
+    webhook::{EventType, WebhookEndpoint, WebhookEvent},
+};
+use reqwest::{header::CONTENT_TYPE, Url};
+use serde_json::Value;
+use tracing::{error, info, instrument, warn};
+
+use crate::{
+    app::AppState,
+    error::GatewayError,
+    responses::{
+        dispute::DisputeResponse, transaction::TransactionResponse,
+        transaction_search::TransactionSummaryResponse,
+    },
+    signing::{SIGNATURE_HEADER, SIGNATURE_NONCE_HEADER, SIGNATURE_TIMESTAMP_HEADER},
+};
+
+/// How often main looks for events to deliver
+pub const RUN_EVERY_SECS: u64 = 5;
+
+/// How long a merchant's endpoint has to answer before the attempt counts as failed
+pub const DELIVERY_TIMEOUT_SECS: u64 = 10;
+
+/// The most events delivered in one run, the rest wait for the next one
+const BATCH_SIZE: i64 = 100;
+
+/// How long claimed events are left alone, so they're retried if delivery is cut short
+const LEASE_SECS: i64 = 5 * 60;
+
+/// Adds an event for the transaction's status to the outbox, if its merchant has an endpoint
+/// to send it to. Called whenever a transaction's status is settled, so merchants hear about
+/// each change without polling.
+pub(crate) async fn notify(app: &AppState, transaction: &Transaction) -> Result<(), GatewayError> {
+    let data = serde_json::to_value(TransactionResponse::from(transaction))
+        .expect("transaction responses are json");
+    queue(
+        app,
+        &transaction.merchant.merchant_id,
+        EventType::from(&transaction.status),
+        data,
+    )
+    .await
+}
+
+/// Like [`notify`], for a stored transaction whose status has moved on since it was processed
+pub(crate) async fn notify_change(
+    app: &AppState,
+    transaction: &TransactionSummary,
+) -> Result<(), GatewayError> {
+    let data = serde_json::to_value(TransactionSummaryResponse::from(transaction))
+        .expect("transaction responses are json");
+    queue(
+        app,
+        &transaction.merchant_id,
+        EventType::from(&transaction.status),
+        data,
+    )
+    .await
+}
+
+/// Adds an event for the dispute's status to the outbox, if its merchant has an endpoint
+pub(crate) async fn notify_dispute(app: &AppState, dispute: &Dispute) -> Result<(), GatewayError> {
+    let data =
+        serde_json::to_value(DisputeResponse::from(dispute)).expect("dispute responses are json");
+    queue(
+        app,
+        &dispute.merchant_id,
+        EventType::from(dispute.status),
+        data,
+    )
+    .await
+}
+
+async fn queue(
+    app: &AppState,
+    merchant_id: &str,
+    event_type: EventType,
+    data: Value,
+) -> Result<(), GatewayError> {
+    let app_access = app.lock().await;
+    if app_access
+        .webhooks
+        .find_endpoint(merchant_id)
+        .await?
+        .is_none()
+    {
+        return Ok(());
+    }
+    let event = WebhookEvent::new(merchant_id.into(), event_type, data, app_access.clock.now());
+    app_access.webhooks.insert_event(&event).await?;
+    info!(event_id = %event.event_id, event_type = %event.event_type, "webhook event queued");
+    Ok(())
+}
+
+/// Delivers events as they fall due, forever. Main spawns it alongside the server.
+pub async fn run(app: AppState) {
+    let mut interval = tokio::time::interval(Duration::from_secs(RUN_EVERY_SECS));
+    loop {
+        interval.tick().await;
+        let _ = deliver_due_events(&app).await;
+    }
+}
+
+/// Delivers the events due by the app's clock, returning how many were attempted
+#[instrument(skip(app), err(Display))]
+pub async fn deliver_due_events(app: &AppState) -> Result<usize, GatewayError> {
+    let (now, due) = {
+        let app_access = app.lock().await;
+        let now = app_access.clock.now();
+        let lease_until = now + TimeDelta::seconds(LEASE_SECS);
+        let due = app_access
+            .webhooks
+            .claim_due(now, lease_until, BATCH_SIZE)
+            .await?;
+        (now, due)
+    };
+    let count = due.len();
+    for event in due {
+        // one event going wrong shouldn't hold up the others
+        if let Err(e) = deliver(app, event, now).await {
+            error!(error = %e, "webhook event could not be delivered");
+        }
+    }
+    Ok(count)
+}
+
+/// Posts the signed event to the merchant's endpoint, then records the delivery or schedules
+/// a retry
+#[instrument(skip_all, fields(event_id = %event.event_id, merchant_id = %event.merchant_id))]
+async fn deliver(
+    app: &AppState,
+    mut event: WebhookEvent,
+    now: DateTime<Utc>,
+) -> Result<(), GatewayError> {
+    let (endpoint, client) = {
+        let app_access = app.lock().await;
+        let endpoint = app_access
+            .webhooks
+            .find_endpoint(&event.merchant_id)
+            .await?;
+        (endpoint, app_access.http_client.clone())
+    };
+    // the merchant may have removed their endpoint since the event was queued, the event waits
+    // for a new one like it would for one that's down
+    let result = match endpoint {
+        Some(endpoint) => send(&client, &endpoint, &event, now).await,
+        None => Err("no webhook endpoint".to_string()),
+    };
+    match result {
+        Ok(()) => event.record_delivery(now),
+        Err(e) => {
+            warn!(error = %e, attempts = event.attempts + 1, "webhook delivery failed");
+            event.record_failure(e, now);
+        }
+    }
+    info!(status = %event.status, attempts = event.attempts, "webhook delivery attempted");
+    let app_access = app.lock().await;
+    app_access.webhooks.update_event(&event).await?;
+    Ok(())
+}
+
+/// Anything but a 2xx response is a failure, described for the dead-letter view
+async fn send(
+    client: &reqwest::Client,
+    endpoint: &WebhookEndpoint,
+    event: &WebhookEvent,
+    now: DateTime<Utc>,
+) -> Result<(), String> {
+    let url = Url::parse(&endpoint.url).map_err(|e| e.to_string())?;
+    // signed over the path and query, the same as the requests merchants sign
+    let path = match url.query() {
+        Some(query) => format!("{}?{query}", url.path()),
+        None => url.path().to_string(),
+    };
+    let body = event.body();
+    let timestamp = now.timestamp();
+    let signature = event.sign(&endpoint.secret, &path, timestamp, &body);
+    let response = client
+        .post(url)
+        .header(CONTENT_TYPE, "application/json")
+        .header(&SIGNATURE_HEADER, signature)
+        .header(&SIGNATURE_TIMESTAMP_HEADER, timestamp.to_string())
+        .header(&SIGNATURE_NONCE_HEADER, &event.event_id)
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| e.without_url().to_string())?;
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {}", status.as_u16()))
+    }
+}
@@ -0,0 +1,147 @@
+mod common;
+use axum_test::TestServer;
+use common::{
+    create_admin_server, create_api_key, create_request, create_unauthenticated_server,
+    CreateRequestAction,
+};
+use serde_json::{json, Value};
+
+async fn post_transaction(server: &TestServer, api_key: &str) -> axum_test::TestResponse {
+    server
+        .post("/transaction")
+        .authorization_bearer(api_key)
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn missing_or_invalid_key(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    let response = server
+        .post("/transaction")
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await;
+    assert_eq!(response.status_code(), 401);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "UNAUTHORISED", "message": "missing API key"})
+    );
+    let api_key = create_api_key(pool, "merchant123").await;
+    let (key_id, _) = api_key.split_once('.').unwrap();
+    for bad_key in [
+        "nonsense".to_string(),
+        format!("{key_id}.wrongsecret"),
+        format!("gw_missing.{}", "a".repeat(64)),
+    ] {
+        let response = post_transaction(&server, &bad_key).await;
+        assert_eq!(response.status_code(), 401);
+        assert_eq!(
+            response.json::<Value>(),
+            json!({"error": "UNAUTHORISED", "message": "invalid API key"})
+        );
+    }
+    assert_eq!(post_transaction(&server, &api_key).await.status_code(), 201);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn merchant_must_match_key(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    create_admin_server(pool.clone())
+        .await
+        .post("/merchants")
+        .json(&json!({"merchant_id": "merchant456", "name": "Other", "country": "GB"}))
+        .await;
+    let other_key = create_api_key(pool, "merchant456").await;
+    let response = post_transaction(&server, &other_key).await;
+    assert_eq!(response.status_code(), 403);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "FORBIDDEN", "message": "merchant_id does not match the API key"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn create_and_list_keys(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    let admin = create_admin_server(pool).await;
+    let response = admin.post("/merchants/merchant123/api-keys").await;
+    assert_eq!(response.status_code(), 201);
+    let created = response.json::<Value>();
+    let api_key = created["api_key"].as_str().unwrap();
+    assert!(api_key.starts_with("gw_"));
+    assert_eq!(created["active"], true);
+    assert_eq!(post_transaction(&server, api_key).await.status_code(), 201);
+    let listed = admin
+        .get("/merchants/merchant123/api-keys")
+        .await
+        .json::<Value>();
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["key_id"], created["key_id"]);
+    // the full key is only ever returned when it's created
+    assert!(!listed.to_string().contains(api_key));
+    let response = admin.post("/merchants/nobody/api-keys").await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn rotate_keys(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    let admin = create_admin_server(pool.clone()).await;
+    let old_key = create_api_key(pool, "merchant123").await;
+    let response = admin.post("/merchants/merchant123/api-keys/rotate").await;
+    assert_eq!(response.status_code(), 201);
+    let new_key = response.json::<Value>()["api_key"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    // both keys work during the grace period
+    assert_eq!(post_transaction(&server, &old_key).await.status_code(), 201);
+    assert_eq!(post_transaction(&server, &new_key).await.status_code(), 201);
+    let listed = admin
+        .get("/merchants/merchant123/api-keys")
+        .await
+        .json::<Value>();
+    assert!(listed[0].get("expires_at").is_none());
+    assert!(listed[1].get("expires_at").is_some());
+    // rotating without a grace period stops the previous keys straight away
+    let response = admin
+        .post("/merchants/merchant123/api-keys/rotate")
+        .add_query_param("grace_secs", 0)
+        .await;
+    let newest_key = response.json::<Value>()["api_key"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    assert_eq!(post_transaction(&server, &old_key).await.status_code(), 401);
+    assert_eq!(post_transaction(&server, &new_key).await.status_code(), 401);
+    assert_eq!(
+        post_transaction(&server, &newest_key).await.status_code(),
+        201
+    );
+    let response = admin
+        .post("/merchants/merchant123/api-keys/rotate")
+        .add_query_param("grace_secs", -1)
+        .await;
+    assert_eq!(response.status_code(), 400);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn revoke_key(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    let admin = create_admin_server(pool).await;
+    let created = admin
+        .post("/merchants/merchant123/api-keys")
+        .await
+        .json::<Value>();
+    let key_id = created["key_id"].as_str().unwrap();
+    let api_key = created["api_key"].as_str().unwrap();
+    let response = admin
+        .delete(&format!("/merchants/merchant123/api-keys/{key_id}"))
+        .await;
+    assert_eq!(response.status_code(), 204);
+    assert_eq!(post_transaction(&server, api_key).await.status_code(), 401);
+    let response = admin
+        .delete(&format!("/merchants/merchant123/api-keys/{key_id}"))
+        .await;
+    assert_eq!(response.status_code(), 404);
+}
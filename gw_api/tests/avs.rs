@@ -0,0 +1,104 @@
+mod common;
+use axum_test::TestServer;
+use common::{create_admin_server, create_request, create_server, CreateRequestAction};
+use serde_json::{json, Value};
+
+async fn post_transaction(server: &TestServer, address: &str, postcode: &str) -> Value {
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![
+            ("billing.address", address).into(),
+            ("billing.postcode", postcode).into(),
+        ]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn get_and_set_policies(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let response = server.get("/merchants/merchant123/policies").await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "merchant_id": "merchant123",
+            "avs": {"decline_address_mismatch": false, "decline_postcode_mismatch": false},
+            "cvv": {"void_on_mismatch": false}
+        })
+    );
+    let admin = create_admin_server(pool).await;
+    let response = admin
+        .put("/merchants/merchant123/policies")
+        .json(&json!({"avs": {"decline_postcode_mismatch": true}}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let policies = json!({
+        "merchant_id": "merchant123",
+        "avs": {"decline_address_mismatch": false, "decline_postcode_mismatch": true},
+        "cvv": {"void_on_mismatch": false}
+    });
+    assert_eq!(response.json::<Value>(), policies);
+    let response = server.get("/merchants/merchant123/policies").await;
+    assert_eq!(response.json::<Value>(), policies);
+    let response = admin
+        .put("/merchants/nobody/policies")
+        .json(&json!({}))
+        .await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn avs_result_is_shown_on_the_transaction(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let transaction = post_transaction(&server, "10 Downing Street", "SW1A 2AA").await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(transaction["billing"]["street"], "10 Downing Street");
+    assert_eq!(transaction["billing"]["postcode"], "SW1A 2AA");
+    assert_eq!(
+        transaction["avs"],
+        json!({"code": "Y", "address": "MATCH", "postcode": "MATCH"})
+    );
+    let transaction = post_transaction(&server, "10 Downing Street", "LS99 1AB").await;
+    assert_eq!(
+        transaction["avs"],
+        json!({"code": "A", "address": "MATCH", "postcode": "NO_MATCH"})
+    );
+    let response = server
+        .post("/transaction")
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await;
+    assert_eq!(response.json::<Value>()["avs"]["code"], "U");
+    let avs_results: Vec<(String, Option<String>)> = sqlx::query_as(
+        "SELECT billing_postcode, avs_result FROM transaction.transactions ORDER BY created_at",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        avs_results,
+        vec![
+            ("SW1A 2AA".into(), Some("Y".into())),
+            ("LS99 1AB".into(), Some("A".into())),
+            ("".into(), Some("U".into())),
+        ]
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn mismatches_are_declined_by_policy(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let admin = create_admin_server(pool).await;
+    let response = admin
+        .put("/merchants/merchant123/policies")
+        .json(&json!({"avs": {"decline_address_mismatch": true}}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let transaction = post_transaction(&server, "10 Downing Street", "LS99 1AB").await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    let transaction = post_transaction(&server, "99 Acacia Avenue", "SW1A 2AA").await;
+    assert_eq!(transaction["status"], "FAILED");
+    assert_eq!(transaction["error"], "AVS_MISMATCH");
+    assert_eq!(transaction["avs"]["code"], "Z");
+}
@@ -0,0 +1,269 @@
+mod common;
+use axum_test::{TestResponse, TestServer};
+use chrono::{TimeDelta, Utc};
+use common::{
+    create_api_key, create_request, create_staff_key, create_unauthenticated_server,
+    CreateRequestAction,
+};
+use gw_core::api_key::Role;
+use serde_json::{json, Value};
+
+/// A server shared by an admin and merchant123, so both use the same card fingerprint key
+struct Keys {
+    server: TestServer,
+    admin: String,
+    merchant: String,
+}
+
+impl Keys {
+    async fn new(pool: &sqlx::PgPool) -> Self {
+        Self {
+            server: create_unauthenticated_server(pool.clone()),
+            admin: create_staff_key(pool.clone(), Role::Admin).await,
+            merchant: create_api_key(pool.clone(), "merchant123").await,
+        }
+    }
+
+    async fn add_entry(&self, entry: Value) -> TestResponse {
+        self.server
+            .post("/list-entries")
+            .authorization_bearer(&self.admin)
+            .json(&entry)
+            .await
+    }
+
+    /// The ids of the entries the query lists
+    async fn entry_ids(&self, query: &str) -> Vec<String> {
+        let response = self
+            .server
+            .get(&format!("/list-entries{query}"))
+            .authorization_bearer(&self.admin)
+            .await;
+        assert_eq!(response.status_code(), 200);
+        response
+            .json::<Vec<Value>>()
+            .iter()
+            .map(|entry| entry["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    async fn post_transaction(&self, overrides: Vec<CreateRequestAction>) -> TestResponse {
+        self.server
+            .post("/transaction")
+            .authorization_bearer(&self.merchant)
+            .json(&create_request(overrides))
+            .await
+    }
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn manage_list_entries(pool: sqlx::PgPool) {
+    let keys = Keys::new(&pool).await;
+    let response = keys
+        .add_entry(json!({
+            "type": "card",
+            "value": "4000111122223333",
+            "reason": "reported stolen"
+        }))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let card = response.json::<Value>();
+    assert_eq!(card["list"], "block");
+    assert_eq!(card["type"], "card");
+    assert_eq!(card["value"].as_str().unwrap().len(), 64);
+    assert_eq!(card["active"], true);
+    assert!(card.get("merchant_id").is_none());
+    assert!(!card.to_string().contains("4000111122223333"));
+    let expires_at = Utc::now() + TimeDelta::days(1);
+    let response = keys
+        .add_entry(json!({
+            "list": "allow",
+            "merchant_id": "merchant123",
+            "type": "email",
+            "value": " VIP@Example.com",
+            "reason": "known customer",
+            "expires_at": expires_at
+        }))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let email = response.json::<Value>();
+    assert_eq!(email["value"], "vip@example.com");
+    assert_eq!(email["merchant_id"], "merchant123");
+    let email_id = email["id"].as_str().unwrap();
+    assert_eq!(keys.entry_ids("").await.len(), 2);
+    assert_eq!(keys.entry_ids("?list=allow").await, vec![email_id]);
+    assert_eq!(
+        keys.entry_ids("?merchant_id=merchant123").await,
+        vec![email_id]
+    );
+    let response = keys
+        .server
+        .get("/list-entries?list=other")
+        .authorization_bearer(&keys.admin)
+        .await;
+    assert_eq!(response.status_code(), 400);
+    for (entry, message) in [
+        (
+            json!({"type": "ip", "value": "300.0.0.1", "reason": "bot"}),
+            "300.0.0.1 is not a valid ip",
+        ),
+        (
+            json!({"type": "bin", "value": "40", "reason": "bot"}),
+            "40 is not a valid bin",
+        ),
+        (
+            json!({"type": "card", "value": "4000", "reason": "bot"}),
+            "card entries must be a valid pan",
+        ),
+        (
+            json!({"type": "email", "value": "a@b.com", "reason": " "}),
+            "reason must be between 1 and 255 characters",
+        ),
+        (
+            json!({"type": "email", "value": "a@b.com", "reason": "bot", "expires_at": "2020-01-01T00:00:00Z"}),
+            "expires_at must be in the future",
+        ),
+    ] {
+        let response = keys.add_entry(entry.clone()).await;
+        assert_eq!(response.status_code(), 400, "{entry}");
+        assert_eq!(
+            response.json::<Value>(),
+            json!({"error": "VALIDATION", "message": message})
+        );
+    }
+    let response = keys
+        .add_entry(
+            json!({"merchant_id": "nobody", "type": "ip", "value": "10.0.0.1", "reason": "bot"}),
+        )
+        .await;
+    assert_eq!(response.status_code(), 404);
+    let path = format!("/list-entries/{}", card["id"].as_str().unwrap());
+    for status in [204, 404] {
+        let response = keys
+            .server
+            .delete(&path)
+            .authorization_bearer(&keys.admin)
+            .await;
+        assert_eq!(response.status_code(), status);
+    }
+    assert_eq!(keys.entry_ids("").await, vec![email_id]);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn support_sees_masked_list_entries(pool: sqlx::PgPool) {
+    let keys = Keys::new(&pool).await;
+    let support = create_staff_key(pool.clone(), Role::Support).await;
+    for (kind, value) in [("email", "fraud@example.com"), ("bin", "400099")] {
+        let response = keys
+            .add_entry(json!({"type": kind, "value": value, "reason": "chargebacks"}))
+            .await;
+        assert_eq!(response.status_code(), 201);
+    }
+    let response = keys
+        .server
+        .get("/list-entries")
+        .authorization_bearer(&support)
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let mut values = response
+        .json::<Vec<Value>>()
+        .iter()
+        .map(|entry| entry["value"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    values.sort();
+    assert_eq!(values, vec!["400099", "f################"]);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn blocked_transactions_are_declined(pool: sqlx::PgPool) {
+    sqlx::query("INSERT INTO account.merchant (id, name) VALUES ('merchant456', 'Other')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let keys = Keys::new(&pool).await;
+    for entry in [
+        json!({"type": "card", "value": "4000111122223333", "reason": "stolen"}),
+        json!({"type": "ip", "value": "10.0.0.1", "reason": "bot"}),
+        json!({"merchant_id": "merchant123", "type": "email", "value": "fraud@example.com", "reason": "chargebacks"}),
+        json!({"merchant_id": "merchant456", "type": "bin", "value": "400022", "reason": "not for merchant123"}),
+    ] {
+        assert_eq!(keys.add_entry(entry).await.status_code(), 201);
+    }
+    // expired entries are ignored, so this one is expired by hand
+    let response = keys
+        .add_entry(json!({"type": "bin", "value": "400033", "reason": "old"}))
+        .await;
+    sqlx::query(
+        "UPDATE fraud.list_entries SET expires_at = now() - interval '1 minute' WHERE id = $1",
+    )
+    .bind(response.json::<Value>()["id"].as_str().unwrap())
+    .execute(&pool)
+    .await
+    .unwrap();
+    let other_card = ("payment.pan", "4000991122223333");
+    for overrides in [
+        vec![],
+        vec![other_card.into(), ("ip_address", "10.0.0.1").into()],
+        vec![
+            other_card.into(),
+            ("billing.email", "Fraud@Example.com").into(),
+        ],
+    ] {
+        let response = keys.post_transaction(overrides).await;
+        assert_eq!(response.status_code(), 201);
+        let transaction = response.json::<Value>();
+        assert_eq!(transaction["status"], "FAILED");
+        assert_eq!(transaction["error"], "BLOCKED");
+    }
+    for overrides in [
+        vec![other_card.into(), ("ip_address", "10.0.0.2").into()],
+        vec![("payment.pan", "4000221122223333").into()],
+        vec![("payment.pan", "4000331122223333").into()],
+    ] {
+        let response = keys.post_transaction(overrides).await;
+        assert_eq!(response.json::<Value>()["status"], "SUCCESS");
+    }
+    let response = keys
+        .post_transaction(vec![("ip_address", "not an ip").into()])
+        .await;
+    assert_eq!(response.status_code(), 400);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "VALIDATION", "message": "ip_address is not a valid ip address"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn allowed_transactions_skip_fraud_rules(pool: sqlx::PgPool) {
+    let keys = Keys::new(&pool).await;
+    let response = keys
+        .server
+        .put("/merchants/merchant123/fraud-rules")
+        .authorization_bearer(&keys.admin)
+        .json(&json!({
+            "rules": [{"type": "amount_over", "currency": "GBP", "amount": 100, "score": 100}]
+        }))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    keys.add_entry(
+        json!({"list": "allow", "type": "email", "value": "vip@example.com", "reason": "known"}),
+    )
+    .await;
+    keys.add_entry(json!({"type": "ip", "value": "10.0.0.1", "reason": "bot"}))
+        .await;
+    let vip = ("billing.email", "vip@example.com");
+    let transaction = keys
+        .post_transaction(vec![vip.into()])
+        .await
+        .json::<Value>();
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert!(transaction.get("fraud").is_none());
+    let transaction = keys.post_transaction(vec![]).await.json::<Value>();
+    assert_eq!(transaction["error"], "FRAUD_REJECTED");
+    // blocks win over allows
+    let transaction = keys
+        .post_transaction(vec![vip.into(), ("ip_address", "10.0.0.1").into()])
+        .await
+        .json::<Value>();
+    assert_eq!(transaction["error"], "BLOCKED");
+}
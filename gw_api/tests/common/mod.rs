@@ -1,16 +1,61 @@
-use std::collections::{BTreeMap, VecDeque};
-
+#![allow(dead_code)] // each test crate only uses some of these helpers
+use axum::http::header::AUTHORIZATION;
 use axum_test::TestServer;
 use gw_api::app::{create_appstate, create_router};
-use gw_core::repo::Pool;
+use gw_core::{
+    api_key::{ApiKey, Role},
+    repo::{api_key::ApiKeyRepo, Pool, Repo},
+};
 use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// Creates a server whose requests are all authenticated with an API key for merchant123
+pub async fn create_server(pool: sqlx::PgPool) -> TestServer {
+    let api_key = create_api_key(pool.clone(), "merchant123").await;
+    create_server_with_key(pool, &api_key)
+}
 
-pub fn create_server(pool: sqlx::PgPool) -> TestServer {
+/// Creates a server whose requests are all authenticated with a staff key with the admin role
+pub async fn create_admin_server(pool: sqlx::PgPool) -> TestServer {
+    let api_key = create_staff_key(pool.clone(), Role::Admin).await;
+    create_server_with_key(pool, &api_key)
+}
+
+pub fn create_server_with_key(pool: sqlx::PgPool, api_key: &str) -> TestServer {
+    let mut server = create_unauthenticated_server(pool);
+    server.add_header(AUTHORIZATION, format!("Bearer {api_key}"));
+    server
+}
+
+pub fn create_unauthenticated_server(pool: sqlx::PgPool) -> TestServer {
     let pool = Pool::from(pool);
     let app_state = create_appstate(pool);
     let router = create_router(app_state);
-    let server = TestServer::new(router).expect("creating server failed");
-    server
+    TestServer::new(router).expect("creating server failed")
+}
+
+/// Creates an API key for the merchant, returning the full key
+pub async fn create_api_key(pool: sqlx::PgPool, merchant_id: &str) -> String {
+    let repo = ApiKeyRepo {
+        pool: Arc::new(Pool::from(pool)),
+    };
+    let (key, full_key) = ApiKey::generate(merchant_id);
+    repo.insert_one(&key)
+        .await
+        .expect("creating API key failed");
+    full_key.expose().clone()
+}
+
+/// Creates a staff key with the role, returning the full key
+pub async fn create_staff_key(pool: sqlx::PgPool, role: Role) -> String {
+    let repo = ApiKeyRepo {
+        pool: Arc::new(Pool::from(pool)),
+    };
+    let (key, full_key) = ApiKey::generate_staff(role).expect("not a staff role");
+    repo.insert_one(&key)
+        .await
+        .expect("creating staff key failed");
+    full_key.expose().clone()
 }
 
 #[derive(Clone)]
@@ -41,7 +86,7 @@ impl From<&str> for CreateRequestAction {
 
 /// Creates a VISA CARD AUTH request, whose values can be overriden by passing a slice of overrides of this type: (&str, serde_json::Value).
 /// The &str key can be made into a path by separating nodes using '.'. Each section of the path corresponds to a key/value pair in a JSON object.
-pub fn create_request<'a>(overrides: Vec<CreateRequestAction>) -> serde_json::Value {
+pub fn create_request(overrides: Vec<CreateRequestAction>) -> serde_json::Value {
     let default_payment = serde_json::Map::from_iter([
         ("scheme".into(), serde_json::Value::String("VISA".into())),
         (
@@ -84,8 +129,7 @@ pub fn create_request<'a>(overrides: Vec<CreateRequestAction>) -> serde_json::Va
                 perform_action(
                     &mut default,
                     |map| {
-                        map.entry(path.last().unwrap())
-                            .and_modify(|x| *x = value.clone());
+                        map.insert(path.last().unwrap().clone(), value.clone());
                     },
                     &path,
                 );
@@ -101,7 +145,7 @@ pub fn create_request<'a>(overrides: Vec<CreateRequestAction>) -> serde_json::Va
             }
         };
     }
-    default.into()
+    default
 }
 
 fn perform_action<F: FnMut(&mut Map<String, Value>)>(
@@ -110,7 +154,7 @@ fn perform_action<F: FnMut(&mut Map<String, Value>)>(
     path: &[String],
 ) {
     for node in path[..path.len() - 1].iter() {
-        obj = obj.get_mut(&node).unwrap();
+        obj = obj.get_mut(node).unwrap();
     }
     match obj {
         Value::Object(map) => {
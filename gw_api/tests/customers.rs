@@ -0,0 +1,230 @@
+mod common;
+use axum_test::TestServer;
+use common::{create_request, create_server, create_server_with_key, create_staff_key};
+use gw_core::api_key::Role;
+use serde_json::{json, Value};
+
+async fn create_customer(server: &TestServer) -> Value {
+    let response = server
+        .post("/merchants/merchant123/customers")
+        .json(&json!({
+            "first_name": "Jo",
+            "last_name": "Bloggs",
+            "email": "jo@example.com",
+            "phone": "+44 113 496 0000",
+            "addresses": [
+                {"premise": "10", "street": "Downing Street", "postcode": "SW1A 2AA", "country": "GB"}
+            ]
+        }))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()
+}
+
+async fn save_card(server: &TestServer, customer_id: &str) -> Value {
+    let response = server
+        .post(&format!(
+            "/merchants/merchant123/customers/{customer_id}/payment-methods"
+        ))
+        .json(&create_request(vec![])["payment"])
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn create_update_and_delete_customers(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let customer = create_customer(&server).await;
+    let customer_id = customer["customer_id"].as_str().unwrap();
+    assert_eq!(customer["email"], "jo@example.com");
+    assert_eq!(customer["addresses"][0]["street"], "Downing Street");
+    let path = format!("/merchants/merchant123/customers/{customer_id}");
+    let response = server.get("/merchants/merchant123/customers").await;
+    assert_eq!(response.json::<Value>(), json!([customer]));
+    let response = server.get(&path).await;
+    assert_eq!(response.json::<Value>()["payment_methods"], json!([]));
+
+    let response = server
+        .put(&path)
+        .json(&json!({"first_name": "Jo", "email": "not an email"}))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    let response = server
+        .put(&path)
+        .json(&json!({"addresses": [{"country": "XX"}]}))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    let response = server
+        .put(&path)
+        .json(&json!({"first_name": "Joanne", "addresses": []}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let response = server.get(&path).await;
+    let updated = response.json::<Value>();
+    assert_eq!(updated["first_name"], "Joanne");
+    assert_eq!(updated["email"], "");
+    assert_eq!(updated["addresses"], json!([]));
+    assert_eq!(updated["created_at"], customer["created_at"]);
+
+    let response = server.delete(&path).await;
+    assert_eq!(response.status_code(), 204);
+    let response = server.get(&path).await;
+    assert_eq!(response.status_code(), 404);
+    assert_eq!(
+        response.json::<Value>()["message"],
+        format!("customer {customer_id} does not exist")
+    );
+    let response = server.delete(&path).await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn payment_methods_are_saved_masked(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let customer = create_customer(&server).await;
+    let customer_id = customer["customer_id"].as_str().unwrap();
+    let method = save_card(&server, customer_id).await;
+    assert_eq!(method["type"], "CARD");
+    assert_eq!(method["scheme"], "VISA");
+    assert_eq!(method["masked_number"], "400011######3333");
+    assert_eq!(method["expiry_month"], 12);
+    assert!(!method.to_string().contains("4000111122223333"));
+    let response = server
+        .get(&format!("/merchants/merchant123/customers/{customer_id}"))
+        .await;
+    assert_eq!(response.json::<Value>()["payment_methods"], json!([method]));
+
+    let response = server
+        .post(&format!(
+            "/merchants/merchant123/customers/{customer_id}/payment-methods"
+        ))
+        .json(&json!({"payment_type": "CARD", "scheme": "VISA"}))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    let response = server
+        .post("/merchants/merchant123/customers/nobody/payment-methods")
+        .json(&create_request(vec![])["payment"])
+        .await;
+    assert_eq!(response.status_code(), 404);
+
+    let path = format!(
+        "/merchants/merchant123/customers/{customer_id}/payment-methods/{}",
+        method["token"].as_str().unwrap()
+    );
+    let response = server.delete(&path).await;
+    assert_eq!(response.status_code(), 204);
+    let response = server.delete(&path).await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn transactions_charge_saved_payment_methods(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let customer = create_customer(&server).await;
+    let customer_id = customer["customer_id"].as_str().unwrap();
+    let method = save_card(&server, customer_id).await;
+    let token = method["token"].as_str().unwrap();
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![
+            "!payment".into(),
+            "!billing".into(),
+            ("customer_id", customer_id).into(),
+            ("payment_token", token).into(),
+        ]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let transaction = response.json::<Value>();
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(transaction["customer_id"], customer_id);
+    assert_eq!(transaction["payment_token"], token);
+    assert_eq!(transaction["payment"]["pan"], "400011######3333");
+    // the customer's address is used for billing, and there's no security code to check
+    assert_eq!(transaction["billing"]["postcode"], "SW1A 2AA");
+    assert_eq!(transaction["billing"]["last_name"], "Bloggs");
+    assert_eq!(transaction.get("cvv"), None);
+
+    // billing given with the transaction is used over the customer's
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![
+            ("customer_id", customer_id).into(),
+            ("billing.postcode", "LS1 4AB").into(),
+        ]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let transaction = response.json::<Value>();
+    assert_eq!(transaction["customer_id"], customer_id);
+    assert_eq!(transaction["billing"]["postcode"], "LS1 4AB");
+    assert_eq!(transaction.get("payment_token"), None);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn saved_payment_methods_need_their_customer(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let customer = create_customer(&server).await;
+    let customer_id = customer["customer_id"].as_str().unwrap();
+    let method = save_card(&server, customer_id).await;
+    let token = method["token"].as_str().unwrap();
+    for (overrides, status, message) in [
+        (
+            vec!["!payment".into(), ("payment_token", token).into()],
+            400,
+            "payment_token can only be used with a customer_id".to_string(),
+        ),
+        (
+            vec![
+                ("customer_id", customer_id).into(),
+                ("payment_token", token).into(),
+            ],
+            400,
+            "only one of payment and payment_token can be given".to_string(),
+        ),
+        (
+            vec![
+                "!payment".into(),
+                ("customer_id", customer_id).into(),
+                ("payment_token", "missing").into(),
+            ],
+            404,
+            format!("customer {customer_id} has no payment method missing"),
+        ),
+        (
+            vec![
+                "!payment".into(),
+                ("customer_id", "nobody").into(),
+                ("payment_token", token).into(),
+            ],
+            404,
+            "customer nobody does not exist".to_string(),
+        ),
+    ] {
+        let response = server
+            .post("/transaction")
+            .json(&create_request(overrides))
+            .await;
+        assert_eq!(response.status_code(), status, "{message}");
+        assert_eq!(response.json::<Value>()["message"], message);
+    }
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn support_sees_masked_customers(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let customer = create_customer(&server).await;
+    let customer_id = customer["customer_id"].as_str().unwrap();
+    let support_key = create_staff_key(pool.clone(), Role::Support).await;
+    let support = create_server_with_key(pool, &support_key);
+    let response = support
+        .get(&format!("/merchants/merchant123/customers/{customer_id}"))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let masked = response.json::<Value>();
+    assert_eq!(masked["first_name"], "J#");
+    assert_eq!(masked["email"], "j#############");
+    assert_eq!(masked["addresses"][0]["street"], "D#############");
+    assert_eq!(masked["addresses"][0]["country"], "GB");
+    let response = support.get("/merchants/merchant123/customers").await;
+    assert_eq!(response.json::<Value>()[0]["phone"], "############0000");
+}
@@ -0,0 +1,59 @@
+mod common;
+use axum_test::TestServer;
+use common::{create_admin_server, create_request, create_server};
+use serde_json::{json, Value};
+
+async fn post_transaction(server: &TestServer, security_code: &str) -> Value {
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![(
+            "payment.security_code",
+            security_code,
+        )
+            .into()]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn cvv_result_is_shown_on_the_transaction(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let transaction = post_transaction(&server, "123").await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(transaction["cvv"], "MATCH");
+    let transaction = post_transaction(&server, "999").await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(transaction["cvv"], "NO_MATCH");
+    let cvv_results: Vec<Option<String>> =
+        sqlx::query_scalar("SELECT cvv_result FROM transaction.transactions ORDER BY created_at")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+    assert_eq!(cvv_results, vec![Some("M".into()), Some("N".into())]);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn mismatches_are_voided_by_policy(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let admin = create_admin_server(pool.clone()).await;
+    let response = admin
+        .put("/merchants/merchant123/policies")
+        .json(&json!({"cvv": {"void_on_mismatch": true}}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.json::<Value>()["cvv"]["void_on_mismatch"], true);
+    let transaction = post_transaction(&server, "123").await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    let transaction = post_transaction(&server, "999").await;
+    assert_eq!(transaction["status"], "FAILED");
+    assert_eq!(transaction["error"], "CVV_MISMATCH");
+    assert_eq!(transaction["cvv"], "NO_MATCH");
+    let status: String =
+        sqlx::query_scalar("SELECT status FROM transaction.transactions WHERE reference = $1")
+            .bind(transaction["reference"].as_str().unwrap())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "FAILED");
+}
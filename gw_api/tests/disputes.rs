@@ -0,0 +1,348 @@
+mod common;
+use std::sync::Arc;
+
+use axum::http::header::AUTHORIZATION;
+use axum_test::{TestResponse, TestServer};
+use chrono::{TimeDelta, TimeZone, Utc};
+use common::{create_api_key, create_request, create_staff_key};
+use gw_api::app::{create_appstate, create_router};
+use gw_core::{api_key::Role, clock::MockClock, notification::NotificationSource, repo::Pool};
+use serde_json::{json, Value};
+
+const HEADER: &str = "case_id,transaction_reference,reason_code,amount,respond_by,status";
+
+struct Servers {
+    merchant: TestServer,
+    admin: TestServer,
+    acquirer: TestServer,
+    clock: Arc<MockClock>,
+}
+
+async fn create_servers(pool: sqlx::PgPool) -> Servers {
+    let api_key = create_api_key(pool.clone(), "merchant123").await;
+    let staff_key = create_staff_key(pool.clone(), Role::Admin).await;
+    let app_state = create_appstate(Pool::from(pool));
+    let clock = Arc::new(MockClock::new(
+        Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap(),
+    ));
+    {
+        let mut app_access = app_state.lock().await;
+        app_access.clock = clock.clone();
+        app_access
+            .notification_secrets
+            .insert(NotificationSource::BankOne, "bankone-secret".into());
+    }
+    let server = || TestServer::new(create_router(app_state.clone())).unwrap();
+    let mut merchant = server();
+    merchant.add_header(AUTHORIZATION, format!("Bearer {api_key}"));
+    let mut admin = server();
+    admin.add_header(AUTHORIZATION, format!("Bearer {staff_key}"));
+    Servers {
+        merchant,
+        admin,
+        acquirer: server(),
+        clock,
+    }
+}
+
+async fn create_transaction(servers: &Servers) -> String {
+    let response = servers
+        .merchant
+        .post("/transaction")
+        .json(&create_request(vec![]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()["reference"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn upload(servers: &Servers, lines: &[String]) -> TestResponse {
+    servers
+        .admin
+        .post("/disputes/files/bankone")
+        .text(format!("{HEADER}\n{}\n", lines.join("\n")))
+        .await
+}
+
+async fn disputes(servers: &Servers) -> Value {
+    let response = servers
+        .merchant
+        .get("/merchants/merchant123/disputes")
+        .await;
+    assert_eq!(response.status_code(), 200);
+    response.json::<Value>()
+}
+
+fn evidence() -> Value {
+    json!({
+        "evidence_type": "PROOF_OF_DELIVERY",
+        "file_name": "tracking.pdf",
+        "content_type": "application/pdf",
+        "size_bytes": 48213,
+        "description": "Signed for by the cardholder"
+    })
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn dispute_files_open_and_resolve_disputes(pool: sqlx::PgPool) {
+    let servers = create_servers(pool.clone()).await;
+    servers
+        .admin
+        .put("/merchants/merchant123/webhook")
+        .json(&json!({"url": "https://example.com/hooks"}))
+        .await;
+    let reference = create_transaction(&servers).await;
+
+    let response = upload(
+        &servers,
+        &[
+            format!("case1,{reference},13.1,,2026-01-20,OPEN"),
+            "case2,missing,13.1,,,OPEN".into(),
+            format!("case3,{reference},4837,,,OPEN"),
+            format!("case4,{reference},13.1,,,PENDING"),
+        ],
+    )
+    .await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "disputes": 1,
+            "opened": 1,
+            "resolved": 0,
+            "errors": [
+                {"line": 3, "message": "transaction missing does not exist"},
+                {"line": 4, "message": "4837 is not a recognised VISA reason code"},
+                {"line": 5, "message": "PENDING is not a recognised dispute file status"}
+            ]
+        })
+    );
+    let listed = disputes(&servers).await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    let dispute = &listed[0];
+    assert_eq!(dispute["transaction_reference"], reference);
+    assert_eq!(dispute["case_id"], "case1");
+    assert_eq!(dispute["scheme"], "VISA");
+    assert_eq!(dispute["reason"], "Merchandise or services not received");
+    assert_eq!(dispute["status"], "RECEIVED");
+    assert_eq!(dispute["amount"], 12345);
+    assert_eq!(dispute["respond_by"], "2026-01-20T23:59:59Z");
+    assert_eq!(dispute["adjustments"][0]["reason"], "CHARGEBACK");
+    assert_eq!(dispute["adjustments"][0]["amount"], -12345);
+    let response = servers
+        .merchant
+        .get("/merchants/merchant123/transactions")
+        .await;
+    assert_eq!(
+        response.json::<Value>()["transactions"][0]["status"],
+        "CHARGED_BACK"
+    );
+
+    // acquirers send the same cases again, they're only acted on once
+    let response = upload(
+        &servers,
+        &[
+            format!("case1,{reference},13.1,,2026-01-20,OPEN"),
+            format!("case1,{reference},13.1,,2026-01-20,WON"),
+            format!("case1,{reference},13.1,,2026-01-20,LOST"),
+        ],
+    )
+    .await;
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"disputes": 3, "opened": 0, "resolved": 1, "errors": []})
+    );
+    let dispute = servers
+        .merchant
+        .get(&format!(
+            "/merchants/merchant123/disputes/{}",
+            dispute["dispute_id"].as_str().unwrap()
+        ))
+        .await
+        .json::<Value>();
+    assert_eq!(dispute["status"], "WON");
+    assert_eq!(dispute["resolved_at"], "2026-01-01T09:00:00Z");
+    assert_eq!(dispute["adjustments"][1]["reason"], "CHARGEBACK_REVERSAL");
+    assert_eq!(dispute["adjustments"][1]["amount"], 12345);
+
+    let events: Vec<String> = sqlx::query_scalar(
+        "SELECT event_type FROM transaction.webhook_events ORDER BY created_at, event_type",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            "DISPUTE_RECEIVED",
+            "DISPUTE_WON",
+            "TRANSACTION_CHARGED_BACK",
+            "TRANSACTION_SUCCEEDED",
+        ]
+    );
+
+    let response = servers
+        .admin
+        .post("/disputes/files/bankone")
+        .text("case1,ref,13.1,,,OPEN")
+        .await;
+    assert_eq!(response.status_code(), 400);
+    let response = servers
+        .admin
+        .post("/disputes/files/bankthree")
+        .text(HEADER)
+        .await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn merchants_submit_evidence_before_the_deadline(pool: sqlx::PgPool) {
+    let servers = create_servers(pool).await;
+    let reference = create_transaction(&servers).await;
+    upload(
+        &servers,
+        &[
+            format!("case1,{reference},13.1,5000,2026-01-10,OPEN"),
+            format!("case2,{reference},10.4,,2026-01-10,OPEN"),
+        ],
+    )
+    .await;
+    let listed = disputes(&servers).await;
+    let id = |case_id: &str| {
+        listed
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|d| d["case_id"] == case_id)
+            .unwrap()["dispute_id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    let dispute = format!("/merchants/merchant123/disputes/{}", id("case1"));
+
+    // nothing to submit yet
+    let response = servers.merchant.post(&format!("{dispute}/submit")).await;
+    assert_eq!(response.status_code(), 409);
+    let response = servers
+        .merchant
+        .post(&format!("{dispute}/evidence"))
+        .json(&evidence())
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let added = response.json::<Value>();
+    assert_eq!(added["evidence_type"], "PROOF_OF_DELIVERY");
+    assert_eq!(added["description"], "Signed for by the cardholder");
+    for (field, value) in [
+        ("evidence_type", json!("VIDEO")),
+        ("size_bytes", json!(0)),
+        ("size_bytes", json!(20_000_000)),
+        ("file_name", json!(" ")),
+    ] {
+        let mut invalid = evidence();
+        invalid[field] = value;
+        let response = servers
+            .merchant
+            .post(&format!("{dispute}/evidence"))
+            .json(&invalid)
+            .await;
+        assert_eq!(response.status_code(), 400, "{field}");
+    }
+
+    let response = servers.merchant.post(&format!("{dispute}/submit")).await;
+    assert_eq!(response.status_code(), 200);
+    let submitted = response.json::<Value>();
+    assert_eq!(submitted["status"], "EVIDENCE_SUBMITTED");
+    assert_eq!(submitted["amount"], 5000);
+    assert_eq!(submitted["evidence"], json!([added]));
+    let response = servers
+        .merchant
+        .post(&format!("{dispute}/evidence"))
+        .json(&evidence())
+        .await;
+    assert_eq!(response.status_code(), 409);
+    let response = servers.merchant.post(&format!("{dispute}/submit")).await;
+    assert_eq!(response.status_code(), 409);
+
+    // once the deadline has passed the merchant can't respond
+    servers.clock.advance(TimeDelta::days(10));
+    let response = servers
+        .merchant
+        .post(&format!(
+            "/merchants/merchant123/disputes/{}/evidence",
+            id("case2")
+        ))
+        .json(&evidence())
+        .await;
+    assert_eq!(response.status_code(), 409);
+
+    let response = servers
+        .merchant
+        .get("/merchants/merchant123/disputes?status=EVIDENCE_SUBMITTED")
+        .await;
+    assert_eq!(response.json::<Value>().as_array().unwrap().len(), 1);
+    for query in ["status=OPEN", "limit=0"] {
+        let response = servers
+            .merchant
+            .get(&format!("/merchants/merchant123/disputes?{query}"))
+            .await;
+        assert_eq!(response.status_code(), 400, "{query}");
+    }
+    let response = servers
+        .merchant
+        .get("/merchants/merchant123/disputes/missing")
+        .await;
+    assert_eq!(response.status_code(), 404);
+    let response = servers
+        .merchant
+        .post("/merchants/merchant123/disputes/missing/evidence")
+        .json(&evidence())
+        .await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn chargeback_notifications_open_disputes(pool: sqlx::PgPool) {
+    let servers = create_servers(pool).await;
+    let reference = create_transaction(&servers).await;
+    let body = json!({
+        "notificationId": "n1",
+        "pspReference": reference,
+        "eventCode": "CHARGEBACK",
+        "disputeId": "case1",
+        "reasonCode": "10.4",
+        "amount": 2000
+    })
+    .to_string();
+    let signature = NotificationSource::BankOne.sign(&"bankone-secret".into(), body.as_bytes());
+    let response = servers
+        .acquirer
+        .post("/notifications/bankone")
+        .add_header("x-bankone-signature", signature)
+        .text(body)
+        .await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.json::<Value>()["outcome"], "APPLIED");
+
+    let listed = disputes(&servers).await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["case_id"], "case1");
+    assert_eq!(listed[0]["reason_code"], "10.4");
+    assert_eq!(listed[0]["amount"], 2000);
+    // Visa gives 30 days when the acquirer doesn't say
+    assert_eq!(listed[0]["respond_by"], "2026-01-31T09:00:00Z");
+    assert_eq!(listed[0]["adjustments"][0]["amount"], -2000);
+
+    // the acquirer's file reporting the same case doesn't open it again
+    let response = upload(&servers, &[format!("case1,{reference},10.4,2000,,LOST")]).await;
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"disputes": 1, "opened": 0, "resolved": 1, "errors": []})
+    );
+    let listed = disputes(&servers).await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["status"], "LOST");
+    assert_eq!(listed[0]["adjustments"].as_array().unwrap().len(), 1);
+}
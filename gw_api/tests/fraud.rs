@@ -0,0 +1,262 @@
+mod common;
+use axum_test::{TestResponse, TestServer};
+use common::{
+    create_admin_server, create_api_key, create_request, create_server, create_staff_key,
+    create_unauthenticated_server, CreateRequestAction,
+};
+use gw_core::api_key::Role;
+use serde_json::{json, Value};
+
+/// A server shared by an admin and merchant123, so both use the same card fingerprint key
+struct Keys {
+    server: TestServer,
+    admin: String,
+    merchant: String,
+}
+
+impl Keys {
+    async fn new(pool: &sqlx::PgPool) -> Self {
+        Self {
+            server: create_unauthenticated_server(pool.clone()),
+            admin: create_staff_key(pool.clone(), Role::Admin).await,
+            merchant: create_api_key(pool.clone(), "merchant123").await,
+        }
+    }
+
+    async fn set_rules(&self, settings: Value) -> TestResponse {
+        self.server
+            .put("/merchants/merchant123/fraud-rules")
+            .authorization_bearer(&self.admin)
+            .json(&settings)
+            .await
+    }
+
+    async fn post_transaction(&self, overrides: Vec<CreateRequestAction>) -> Value {
+        let response = self
+            .server
+            .post("/transaction")
+            .authorization_bearer(&self.merchant)
+            .json(&create_request(overrides))
+            .await;
+        assert_eq!(response.status_code(), 201);
+        response.json::<Value>()
+    }
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn get_and_set_fraud_rules(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let response = server.get("/merchants/merchant123/fraud-rules").await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "merchant_id": "merchant123",
+            "review_score": 50,
+            "reject_score": 100,
+            "rules": []
+        })
+    );
+    let admin = create_admin_server(pool).await;
+    let response = admin
+        .put("/merchants/merchant123/fraud-rules")
+        .json(&json!({
+            "reject_score": 80,
+            "rules": [
+                {"type": "amount_over", "currency": "GBP", "amount": 10000, "score": 40},
+                {"type": "blocked_emails", "emails": [" Fraud@Example.com "], "score": 80},
+                {"type": "blocked_cards", "pans": ["4000111122223333"], "score": 80}
+            ]
+        }))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let settings = response.json::<Value>();
+    assert_eq!(settings["review_score"], 50);
+    assert_eq!(settings["reject_score"], 80);
+    assert_eq!(settings["rules"][1]["emails"], json!(["fraud@example.com"]));
+    let fingerprint = settings["rules"][2]["fingerprints"][0].as_str().unwrap();
+    assert_eq!(fingerprint.len(), 64);
+    assert!(!settings.to_string().contains("4000111122223333"));
+    let response = server.get("/merchants/merchant123/fraud-rules").await;
+    assert_eq!(response.json::<Value>(), settings);
+    for (body, message) in [
+        (
+            json!({"review_score": 90, "reject_score": 80}),
+            "review_score can't be more than reject_score",
+        ),
+        (
+            json!({"rules": [{"type": "country_mismatch", "score": 0}]}),
+            "score must be between 1 and 2147483647",
+        ),
+        (
+            json!({"rules": [{"type": "blocked_bins", "bins": ["4000ab"], "score": 10}]}),
+            "4000ab is not a valid bin",
+        ),
+        (
+            json!({"rules": [{"type": "blocked_cards", "pans": ["1234"], "score": 10}]}),
+            "blocked cards must be valid pans",
+        ),
+    ] {
+        let response = admin
+            .put("/merchants/merchant123/fraud-rules")
+            .json(&body)
+            .await;
+        assert_eq!(response.status_code(), 400, "{body}");
+        assert_eq!(
+            response.json::<Value>(),
+            json!({"error": "VALIDATION", "message": message})
+        );
+    }
+    let response = admin
+        .put("/merchants/merchant123/fraud-rules")
+        .json(&json!({"rules": [{"type": "unknown", "score": 10}]}))
+        .await;
+    assert_eq!(response.status_code(), 422);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn merchants_without_rules_are_not_screened(pool: sqlx::PgPool) {
+    let keys = Keys::new(&pool).await;
+    let transaction = keys.post_transaction(vec![]).await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert!(transaction.get("fraud").is_none());
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn high_scores_are_reviewed_or_rejected(pool: sqlx::PgPool) {
+    sqlx::query("INSERT INTO fraud.card_bins VALUES ('400011', 'US')")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let keys = Keys::new(&pool).await;
+    let response = keys
+        .set_rules(json!({
+            "rules": [
+                {"type": "amount_over", "currency": "GBP", "amount": 10000, "score": 60},
+                {"type": "country_mismatch", "score": 40}
+            ]
+        }))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    // a GB billing address with a US card
+    let transaction = keys.post_transaction(vec![("amount", 5000).into()]).await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(
+        transaction["fraud"],
+        json!({"score": 40, "decision": "ACCEPT", "matched_rules": ["country_mismatch"]})
+    );
+    let transaction = keys
+        .post_transaction(vec![("payment.pan", "4000221122223333").into()])
+        .await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(transaction["fraud"]["decision"], "REVIEW");
+    let transaction = keys.post_transaction(vec![]).await;
+    assert_eq!(transaction["status"], "FAILED");
+    assert_eq!(transaction["error"], "FRAUD_REJECTED");
+    assert_eq!(
+        transaction["fraud"],
+        json!({
+            "score": 100,
+            "decision": "REJECT",
+            "matched_rules": ["amount_over", "country_mismatch"]
+        })
+    );
+    let row: (
+        String,
+        Option<String>,
+        Option<i32>,
+        Option<String>,
+        Option<Value>,
+    ) = sqlx::query_as(
+        "SELECT status, failure_reason, fraud_score, fraud_decision, fraud_rules \
+             FROM transaction.transactions WHERE reference = $1",
+    )
+    .bind(transaction["reference"].as_str().unwrap())
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        row,
+        (
+            "FAILED".into(),
+            Some("FRAUD_REJECTED".into()),
+            Some(100),
+            Some("REJECT".into()),
+            Some(json!(["amount_over", "country_mismatch"]))
+        )
+    );
+    // listed transactions still show why they were declined
+    let response = keys
+        .server
+        .get("/transactions")
+        .authorization_bearer(&keys.admin)
+        .add_query_param("status", "FAILED")
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let listed = response.json::<Value>();
+    assert_eq!(
+        listed["transactions"][0]["reference"],
+        transaction["reference"]
+    );
+    assert_eq!(listed["transactions"][0]["error"], "FRAUD_REJECTED");
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn blocked_cards_bins_and_emails(pool: sqlx::PgPool) {
+    let keys = Keys::new(&pool).await;
+    let response = keys
+        .set_rules(json!({
+            "rules": [
+                {"type": "blocked_cards", "pans": ["4000111122223333"], "score": 100},
+                {"type": "blocked_bins", "bins": ["4000999"], "score": 100},
+                {"type": "blocked_emails", "emails": ["fraud@example.com"], "score": 100}
+            ]
+        }))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    for (overrides, rule) in [
+        (vec![], "blocked_cards"),
+        (
+            vec![("payment.pan", "4000999911112222").into()],
+            "blocked_bins",
+        ),
+        (
+            vec![
+                ("payment.pan", "4000111122224444").into(),
+                ("billing.email", "FRAUD@example.com").into(),
+            ],
+            "blocked_emails",
+        ),
+    ] {
+        let transaction = keys.post_transaction(overrides).await;
+        assert_eq!(transaction["error"], "FRAUD_REJECTED", "{rule}");
+        assert_eq!(transaction["fraud"]["matched_rules"], json!([rule]));
+    }
+    let transaction = keys
+        .post_transaction(vec![("payment.pan", "4000111122224444").into()])
+        .await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(transaction["fraud"]["decision"], "ACCEPT");
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn card_velocity_rule(pool: sqlx::PgPool) {
+    let keys = Keys::new(&pool).await;
+    keys.set_rules(json!({
+        "rules": [{"type": "card_velocity", "max_transactions": 2, "score": 100}]
+    }))
+    .await;
+    for _ in 0..2 {
+        let transaction = keys.post_transaction(vec![]).await;
+        assert_eq!(transaction["status"], "SUCCESS");
+    }
+    let transaction = keys.post_transaction(vec![]).await;
+    assert_eq!(transaction["error"], "FRAUD_REJECTED");
+    assert_eq!(
+        transaction["fraud"]["matched_rules"],
+        json!(["card_velocity"])
+    );
+    // rejected transactions don't count towards the card's usage
+    let transaction = keys.post_transaction(vec![]).await;
+    assert_eq!(transaction["fraud"]["score"], 100);
+}
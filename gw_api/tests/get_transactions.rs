@@ -0,0 +1,137 @@
+mod common;
+use axum_test::TestServer;
+use common::{create_admin_server, create_request, create_server};
+use serde_json::{json, Value};
+
+async fn post_transactions(server: &TestServer, amounts: &[u64]) {
+    for (i, amount) in amounts.iter().enumerate() {
+        let response = server
+            .post("/transaction")
+            .json(&create_request(vec![
+                ("amount", *amount).into(),
+                ("merchant_reference", format!("order-{i}")).into(),
+            ]))
+            .await;
+        assert_eq!(response.status_code(), 201);
+    }
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn list_transactions(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let admin = create_admin_server(pool).await;
+    post_transactions(&server, &[100, 200]).await;
+    let response = admin.get("/transactions").await;
+    assert_eq!(response.status_code(), 200);
+    let body = response.json::<Value>();
+    let transactions = body["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 2);
+    // newest first
+    assert_eq!(transactions[0]["merchant_reference"], "order-1");
+    assert_eq!(transactions[0]["amount"], 200);
+    assert_eq!(transactions[0]["merchant_id"], "merchant123");
+    assert_eq!(transactions[0]["status"], "SUCCESS");
+    assert_eq!(transactions[0]["transaction_type"], "AUTH");
+    assert_eq!(transactions[0]["pan"], "400011######3333");
+    assert!(body.get("next_cursor").is_none());
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn filter_transactions(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let admin = create_admin_server(pool).await;
+    post_transactions(&server, &[100, 200, 300]).await;
+    let response = admin
+        .get("/transactions")
+        .add_query_param("min_amount", 150)
+        .add_query_param("max_amount", 250)
+        .add_query_param("last_four", "3333")
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let body = response.json::<Value>();
+    let transactions = body["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0]["merchant_reference"], "order-1");
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn page_through_transactions(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let admin = create_admin_server(pool).await;
+    post_transactions(&server, &[100, 200, 300]).await;
+    let first = admin
+        .get("/transactions")
+        .add_query_param("limit", 2)
+        .await
+        .json::<Value>();
+    assert_eq!(first["transactions"].as_array().unwrap().len(), 2);
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let second = admin
+        .get("/transactions")
+        .add_query_param("limit", 2)
+        .add_query_param("cursor", cursor)
+        .await
+        .json::<Value>();
+    let transactions = second["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0]["merchant_reference"], "order-0");
+    assert!(second.get("next_cursor").is_none());
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn list_merchant_transactions(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    post_transactions(&server, &[100]).await;
+    let response = server.get("/merchants/merchant123/transactions").await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(
+        response.json::<Value>()["transactions"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+    // the path always wins over the merchant_id query parameter
+    let response = server
+        .get("/merchants/merchant123/transactions")
+        .add_query_param("merchant_id", "someone-else")
+        .await;
+    assert_eq!(
+        response.json::<Value>()["transactions"]
+            .as_array()
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn unknown_merchant(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let response = server.get("/merchants/nobody/transactions").await;
+    assert_eq!(response.status_code(), 404);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "RESOURCE", "message": "merchant nobody does not exist"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn invalid_query(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let response = server
+        .get("/transactions")
+        .add_query_param("limit", 500)
+        .await;
+    assert_eq!(response.status_code(), 400);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "VALIDATION", "message": "limit must be between 1 and 100"})
+    );
+    let response = server
+        .get("/transactions")
+        .add_query_param("min_amount", "lots")
+        .await;
+    assert_eq!(response.status_code(), 400);
+    assert_eq!(response.json::<Value>()["error"], "VALIDATION");
+}
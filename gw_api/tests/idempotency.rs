@@ -0,0 +1,307 @@
+mod common;
+use axum::http::header::AUTHORIZATION;
+use axum_test::TestServer;
+use common::{
+    create_admin_server, create_api_key, create_request, create_server,
+    create_unauthenticated_server, CreateRequestAction,
+};
+use gw_api::app::{create_appstate, create_router};
+use gw_core::{idempotency::hash_request, repo::Pool};
+use serde_json::json;
+
+async fn count_transactions(pool: &sqlx::PgPool) -> i64 {
+    sqlx::query_scalar("SELECT count(*) FROM transaction.transactions")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn repeat_returns_original_response(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let request = create_request(Vec::<CreateRequestAction>::new());
+    let first = server
+        .post("/transaction")
+        .add_header("Idempotency-Key", "order-1")
+        .json(&request)
+        .await;
+    let second = server
+        .post("/transaction")
+        .add_header("Idempotency-Key", "order-1")
+        .json(&request)
+        .await;
+    assert_eq!(first.status_code(), 201);
+    assert_eq!(second.status_code(), 201);
+    assert_eq!(
+        first.json::<serde_json::Value>(),
+        second.json::<serde_json::Value>()
+    );
+    assert!(first.maybe_header("idempotent-replayed").is_none());
+    assert_eq!(second.header("idempotent-replayed"), "true");
+    assert_eq!(count_transactions(&pool).await, 1);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn repeat_of_failed_request_returns_same_error(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let request = create_request(vec!["!payment.pan".into()]);
+    for _ in 0..2 {
+        let response = server
+            .post("/transaction")
+            .add_header("Idempotency-Key", "order-1")
+            .json(&request)
+            .await;
+        assert_eq!(response.status_code(), 400);
+        assert_eq!(
+            response.json::<serde_json::Value>(),
+            json!({"error": "VALIDATION", "message": "missing fields: pan"})
+        );
+    }
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn same_key_different_body_is_a_conflict(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let first = server
+        .post("/transaction")
+        .add_header("Idempotency-Key", "order-1")
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await;
+    assert_eq!(first.status_code(), 201);
+    let second = server
+        .post("/transaction")
+        .add_header("Idempotency-Key", "order-1")
+        .json(&create_request(vec![("amount", 999).into()]))
+        .await;
+    assert_eq!(second.status_code(), 409);
+    assert_eq!(
+        second.json::<serde_json::Value>(),
+        json!({
+            "error": "CONFLICT",
+            "message": "idempotency key order-1 has already been used for a different request"
+        })
+    );
+    assert_eq!(count_transactions(&pool).await, 1);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn keys_are_scoped_to_merchant(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    create_admin_server(pool.clone())
+        .await
+        .post("/merchants")
+        .json(&json!({"merchant_id": "merchant456", "name": "Other", "country": "GB"}))
+        .await;
+    let other_key = create_api_key(pool.clone(), "merchant456").await;
+    let first = server
+        .post("/transaction")
+        .authorization_bearer(&other_key)
+        .add_header("Idempotency-Key", "order-1")
+        .json(&create_request(vec![("merchant_id", "merchant456").into()]))
+        .await;
+    // merchant456 has no payment routes
+    assert_eq!(first.status_code(), 404);
+    let own_key = create_api_key(pool, "merchant123").await;
+    let second = server
+        .post("/transaction")
+        .authorization_bearer(&own_key)
+        .add_header("Idempotency-Key", "order-1")
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await;
+    assert_eq!(second.status_code(), 201);
+    assert!(second.maybe_header("idempotent-replayed").is_none());
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn no_key_creates_new_transactions(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let request = create_request(Vec::<CreateRequestAction>::new());
+    let first = server.post("/transaction").json(&request).await;
+    let second = server.post("/transaction").json(&request).await;
+    assert_ne!(
+        first.json::<serde_json::Value>()["reference"],
+        second.json::<serde_json::Value>()["reference"]
+    );
+    assert_eq!(count_transactions(&pool).await, 2);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn empty_key_is_rejected(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let response = server
+        .post("/transaction")
+        .add_header("Idempotency-Key", "")
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await;
+    assert_eq!(response.status_code(), 400);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn abandoned_requests_can_be_retried_once_the_lease_is_over(pool: sqlx::PgPool) {
+    let app_state = create_appstate(Pool::from(pool.clone()));
+    app_state.lock().await.card_fingerprint_key = "fingerprint-key".into();
+    let api_key = create_api_key(pool.clone(), "merchant123").await;
+    let mut server = TestServer::new(create_router(app_state)).unwrap();
+    server.add_header(AUTHORIZATION, format!("Bearer {api_key}"));
+    let request = create_request(Vec::<CreateRequestAction>::new());
+    // as if the gateway stopped while processing the first attempt
+    let hash = hash_request(
+        &serde_json::to_value(&request).unwrap(),
+        &"fingerprint-key".into(),
+    );
+    sqlx::query(
+        "INSERT INTO transaction.idempotency (merchant_id, idempotency_key, request_hash) \
+         VALUES ('merchant123', 'order-1', $1)",
+    )
+    .bind(hash)
+    .execute(&pool)
+    .await
+    .unwrap();
+    let retry = || {
+        server
+            .post("/transaction")
+            .add_header("Idempotency-Key", "order-1")
+            .json(&request)
+    };
+    let response = retry().await;
+    assert_eq!(response.status_code(), 409);
+    assert_eq!(
+        response.json::<serde_json::Value>()["message"],
+        "a request with idempotency key order-1 is still being processed"
+    );
+
+    sqlx::query("UPDATE transaction.idempotency SET renewed_at = now() - interval '6 minutes'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = retry().await;
+    assert_eq!(response.status_code(), 201);
+    assert_eq!(count_transactions(&pool).await, 1);
+    let response = retry().await;
+    assert_eq!(response.header("idempotent-replayed"), "true");
+}
+
+/// Makes every statement of the kind on transaction.transactions fail, until it's dropped
+async fn fail_transaction_statements(pool: &sqlx::PgPool, statement: &str) {
+    sqlx::query(
+        "CREATE FUNCTION fail_statement() RETURNS trigger AS $$ \
+         BEGIN RAISE EXCEPTION 'statement failed'; END $$ LANGUAGE plpgsql",
+    )
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query(&format!(
+        "CREATE TRIGGER fail_statement BEFORE {statement} ON transaction.transactions \
+         FOR EACH ROW EXECUTE FUNCTION fail_statement()"
+    ))
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn stop_failing_transaction_statements(pool: &sqlx::PgPool) {
+    sqlx::query("DROP TRIGGER fail_statement ON transaction.transactions")
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn failures_after_authorisation_are_replayed(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let request = create_request(Vec::<CreateRequestAction>::new());
+    // the transaction is authorised, then can't be updated with the acquirer's answer
+    fail_transaction_statements(&pool, "UPDATE").await;
+    let retry = || {
+        server
+            .post("/transaction")
+            .add_header("Idempotency-Key", "order-1")
+            .json(&request)
+    };
+    let first = retry().await;
+    assert_eq!(first.status_code(), 500);
+    stop_failing_transaction_statements(&pool).await;
+    // the card may have been charged, so it isn't authorised again
+    let second = retry().await;
+    assert_eq!(second.status_code(), 500);
+    assert_eq!(second.header("idempotent-replayed"), "true");
+    assert_eq!(
+        first.json::<serde_json::Value>(),
+        second.json::<serde_json::Value>()
+    );
+    assert_eq!(count_transactions(&pool).await, 1);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn failures_before_the_acquirer_can_be_retried(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let request = create_request(Vec::<CreateRequestAction>::new());
+    fail_transaction_statements(&pool, "INSERT").await;
+    let retry = || {
+        server
+            .post("/transaction")
+            .add_header("Idempotency-Key", "order-1")
+            .json(&request)
+    };
+    let first = retry().await;
+    assert_eq!(first.status_code(), 500);
+    assert_eq!(count_transactions(&pool).await, 0);
+    stop_failing_transaction_statements(&pool).await;
+    let second = retry().await;
+    assert_eq!(second.status_code(), 201);
+    assert!(second.maybe_header("idempotent-replayed").is_none());
+    assert_eq!(count_transactions(&pool).await, 1);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn slow_requests_keep_their_keys(pool: sqlx::PgPool) {
+    let api_key = create_api_key(pool.clone(), "merchant123").await;
+    // two gateways with short leases
+    let mut instances = vec![];
+    for _ in 0..2 {
+        let app_state = create_appstate(Pool::from(pool.clone()));
+        {
+            let mut app_access = app_state.lock().await;
+            app_access.card_fingerprint_key = "fingerprint-key".into();
+            app_access.idempotency.lease_secs = 2;
+        }
+        let mut server = TestServer::new(create_router(app_state)).unwrap();
+        server.add_header(AUTHORIZATION, format!("Bearer {api_key}"));
+        instances.push(server);
+    }
+    sqlx::query(
+        "CREATE FUNCTION slow_statement() RETURNS trigger AS $$ \
+         BEGIN PERFORM pg_sleep(5); RETURN NEW; END $$ LANGUAGE plpgsql",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        "CREATE TRIGGER slow_statement BEFORE INSERT ON transaction.transactions \
+         FOR EACH ROW EXECUTE FUNCTION slow_statement()",
+    )
+    .execute(&pool)
+    .await
+    .unwrap();
+    let request = create_request(Vec::<CreateRequestAction>::new());
+    let send = |server: &TestServer| {
+        server
+            .post("/transaction")
+            .add_header("Idempotency-Key", "order-1")
+            .json(&request)
+    };
+    let first = async { send(&instances[0]).await };
+    // retried on the other gateway once the first attempt has outlived its lease
+    let retry = async {
+        tokio::time::sleep(std::time::Duration::from_secs(3)).await;
+        send(&instances[1]).await
+    };
+    let (first, retry) = tokio::join!(first, retry);
+    assert_eq!(first.status_code(), 201);
+    assert_eq!(retry.status_code(), 409);
+    assert_eq!(
+        retry.json::<serde_json::Value>()["message"],
+        "a request with idempotency key order-1 is still being processed"
+    );
+    assert_eq!(count_transactions(&pool).await, 1);
+}
@@ -0,0 +1,139 @@
+mod common;
+use axum_test::TestServer;
+use common::{create_admin_server, create_request, create_server, CreateRequestAction};
+use serde_json::{json, Value};
+
+async fn set_limits(pool: &sqlx::PgPool, limits: Value) -> Value {
+    let admin = create_admin_server(pool.clone()).await;
+    let response = admin
+        .put("/merchants/merchant123/limits")
+        .json(&limits)
+        .await;
+    assert_eq!(response.status_code(), 200);
+    response.json::<Value>()
+}
+
+async fn post_transaction(server: &TestServer, pan: &str) -> axum_test::TestResponse {
+    server
+        .post("/transaction")
+        .json(&create_request(vec![("payment.pan", pan).into()]))
+        .await
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn get_and_set_limits(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let response = server.get("/merchants/merchant123/limits").await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "merchant_id": "merchant123",
+            "requests_per_minute": null,
+            "max_card_transactions_per_hour": null,
+            "max_card_amount_per_hour": null
+        })
+    );
+    let limits = json!({
+        "merchant_id": "merchant123",
+        "requests_per_minute": 100,
+        "max_card_transactions_per_hour": 5,
+        "max_card_amount_per_hour": null
+    });
+    assert_eq!(set_limits(&pool, limits.clone()).await, limits);
+    let response = server.get("/merchants/merchant123/limits").await;
+    assert_eq!(response.json::<Value>(), limits);
+    let admin = create_admin_server(pool).await;
+    let response = admin
+        .put("/merchants/merchant123/limits")
+        .json(&json!({"requests_per_minute": 0}))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "error": "VALIDATION",
+            "message": "requests_per_minute must be between 1 and 2147483647"
+        })
+    );
+    let response = admin.put("/merchants/nobody/limits").json(&json!({})).await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn requests_over_the_rate_limit_are_rejected(pool: sqlx::PgPool) {
+    set_limits(&pool, json!({"requests_per_minute": 2})).await;
+    let server = create_server(pool.clone()).await;
+    for _ in 0..2 {
+        let response = server.get("/merchants/merchant123").await;
+        assert_eq!(response.status_code(), 200);
+    }
+    let response = server.get("/merchants/merchant123").await;
+    assert_eq!(response.status_code(), 429);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "error": "RATE_LIMITED",
+            "message": "rate limit of 2 requests per minute reached"
+        })
+    );
+    let retry_after: u64 = response
+        .header("retry-after")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=60).contains(&retry_after));
+    // staff aren't limited
+    let admin = create_admin_server(pool).await;
+    for _ in 0..3 {
+        let response = admin.get("/merchants/merchant123").await;
+        assert_eq!(response.status_code(), 200);
+    }
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn card_transaction_velocity(pool: sqlx::PgPool) {
+    set_limits(&pool, json!({"max_card_transactions_per_hour": 1})).await;
+    let server = create_server(pool).await;
+    let response = post_transaction(&server, "4000111122223333").await;
+    assert_eq!(response.status_code(), 201);
+    let response = post_transaction(&server, "4000111122223333").await;
+    assert_eq!(response.status_code(), 422);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "error": "VELOCITY_LIMIT",
+            "message": "card has reached the limit of 1 transactions per hour"
+        })
+    );
+    let response = post_transaction(&server, "4000111122224444").await;
+    assert_eq!(response.status_code(), 201);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn card_amount_velocity(pool: sqlx::PgPool) {
+    set_limits(&pool, json!({"max_card_amount_per_hour": 20000})).await;
+    let server = create_server(pool).await;
+    let response = post_transaction(&server, "4000111122223333").await;
+    assert_eq!(response.status_code(), 201);
+    let response = post_transaction(&server, "4000111122223333").await;
+    assert_eq!(response.status_code(), 422);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "error": "VELOCITY_LIMIT",
+            "message": "card would go over the limit of 20000 GBP per hour"
+        })
+    );
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![("amount", 7000).into()]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let response = server
+        .post("/transaction")
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await;
+    assert_eq!(response.status_code(), 422);
+}
@@ -0,0 +1,112 @@
+mod common;
+use std::sync::{Arc, Mutex};
+
+use common::{create_request, create_server, CreateRequestAction};
+use gw_api::logging;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Collects everything written by the log subscriber so it can be checked for card data.
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl CapturedLogs {
+    fn output(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).expect("logs are utf8")
+    }
+}
+
+impl std::io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'w> MakeWriter<'w> for CapturedLogs {
+    type Writer = CapturedLogs;
+
+    fn make_writer(&'w self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+async fn post_and_capture(pool: sqlx::PgPool, overrides: Vec<CreateRequestAction>) -> String {
+    let logs = CapturedLogs::default();
+    let _guard = tracing::subscriber::set_default(logging::subscriber(logs.clone()));
+    let server = create_server(pool).await;
+    let response = server
+        .post("/transaction")
+        .json(&create_request(overrides))
+        .await;
+    assert!(response.maybe_header("x-request-id").is_some());
+    logs.output()
+}
+
+fn assert_no_sensitive_data(logs: &str) {
+    for sensitive in [
+        "4000111122223333",
+        r#"\"9731\""#,
+        "Bartholomew",
+        "Fitzgerald",
+    ] {
+        assert!(
+            !logs.contains(sensitive),
+            "{sensitive} found in logs:\n{logs}"
+        );
+    }
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn successful_transaction_logs_are_redacted(pool: sqlx::PgPool) {
+    let logs = post_and_capture(
+        pool,
+        vec![
+            ("payment.security_code", "9731").into(),
+            ("billing", serde_json::json!({"first_name": "Bartholomew", "last_name": "Fitzgerald", "country": "GB"})).into(),
+        ],
+    )
+    .await;
+    assert_no_sensitive_data(&logs);
+    assert!(logs.contains("400011######3333"));
+    assert!(logs.contains("Secret([REDACTED])"));
+    assert!(logs.contains("B##########"));
+    assert!(logs.contains("transaction processed"));
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn failed_transaction_logs_are_redacted(pool: sqlx::PgPool) {
+    let logs = post_and_capture(
+        pool,
+        vec![
+            ("payment.security_code", "9731").into(),
+            ("merchant_id", "invalid123").into(),
+        ],
+    )
+    .await;
+    assert_no_sensitive_data(&logs);
+    assert!(logs.contains("merchant_id does not match the API key"));
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn logs_are_json_with_request_and_merchant_ids(pool: sqlx::PgPool) {
+    let logs = post_and_capture(pool, vec![]).await;
+    let lines = logs
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).expect("log line is json"))
+        .collect::<Vec<_>>();
+    assert!(!lines.is_empty());
+    for line in lines.iter() {
+        assert!(
+            line["span"]["request_id"].is_string() || line["spans"][0]["request_id"].is_string()
+        );
+    }
+    let processed = lines
+        .iter()
+        .find(|line| line["fields"]["message"] == "transaction processed")
+        .expect("transaction processed log");
+    assert_eq!(processed["span"]["merchant_id"], "merchant123");
+}
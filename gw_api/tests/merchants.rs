@@ -0,0 +1,158 @@
+mod common;
+use common::{create_admin_server, create_request, create_server, CreateRequestAction};
+use serde_json::{json, Value};
+
+fn merchant_request(merchant_id: &str) -> Value {
+    json!({
+        "merchant_id": merchant_id,
+        "name": "  New Merchant  ",
+        "premise": "1",
+        "street": "High Street",
+        "city": "Leeds",
+        "postcode": "LS1 1AA",
+        "county": "West Yorkshire",
+        "country": "GB"
+    })
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn create_and_get_merchant(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let expected = json!({
+        "merchant_id": "merchant456",
+        "name": "New Merchant",
+        "premise": "1",
+        "street": "High Street",
+        "city": "Leeds",
+        "postcode": "LS1 1AA",
+        "county": "West Yorkshire",
+        "country": "GB",
+        "active": true
+    });
+    let response = server
+        .post("/merchants")
+        .json(&merchant_request("merchant456"))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    assert_eq!(response.json::<Value>(), expected);
+    let response = server.get("/merchants/merchant456").await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.json::<Value>(), expected);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn create_duplicate_merchant(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let response = server
+        .post("/merchants")
+        .json(&merchant_request("merchant123"))
+        .await;
+    assert_eq!(response.status_code(), 409);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "CONFLICT", "message": "merchant merchant123 already exists"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn create_invalid_merchant(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let response = server
+        .post("/merchants")
+        .json(&merchant_request("bad id"))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "VALIDATION", "message": "merchant_id - invalid characters"})
+    );
+    let mut request = merchant_request("merchant456");
+    request["country"] = "XX".into();
+    let response = server.post("/merchants").json(&request).await;
+    assert_eq!(response.status_code(), 400);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "VALIDATION", "message": "XX is not a recognised country code"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn update_merchant(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let mut request = merchant_request("ignored");
+    request["name"] = "Renamed".into();
+    request["country"] = "US".into();
+    let response = server.put("/merchants/merchant123").json(&request).await;
+    assert_eq!(response.status_code(), 200);
+    let body = response.json::<Value>();
+    assert_eq!(body["merchant_id"], "merchant123");
+    assert_eq!(body["name"], "Renamed");
+    assert_eq!(body["country"], "US");
+    let response = server.get("/merchants/merchant123").await;
+    assert_eq!(response.json::<Value>(), body);
+    let response = server.put("/merchants/nobody").json(&request).await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn deactivate_merchant(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool.clone()).await;
+    let merchant_server = create_server(pool).await;
+    let response = server.post("/merchants/merchant123/deactivate").await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.json::<Value>()["active"], false);
+    let response = merchant_server
+        .post("/transaction")
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "VALIDATION", "message": "merchant merchant123 is not active"})
+    );
+    let response = server.post("/merchants/nobody/deactivate").await;
+    assert_eq!(response.status_code(), 404);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "RESOURCE", "message": "merchant nobody does not exist"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn list_merchants(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    for id in ["merchant456", "merchant789"] {
+        server.post("/merchants").json(&merchant_request(id)).await;
+    }
+    server.post("/merchants/merchant456/deactivate").await;
+    let ids = |body: &Value| {
+        body["merchants"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["merchant_id"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    let first = server
+        .get("/merchants")
+        .add_query_param("limit", 2)
+        .await
+        .json::<Value>();
+    assert_eq!(ids(&first), vec!["merchant123", "merchant456"]);
+    let second = server
+        .get("/merchants")
+        .add_query_param("limit", 2)
+        .add_query_param("cursor", first["next_cursor"].as_str().unwrap())
+        .await
+        .json::<Value>();
+    assert_eq!(ids(&second), vec!["merchant789"]);
+    assert!(second.get("next_cursor").is_none());
+    let active = server
+        .get("/merchants")
+        .add_query_param("active", true)
+        .await
+        .json::<Value>();
+    assert_eq!(ids(&active), vec!["merchant123", "merchant789"]);
+    let response = server.get("/merchants").add_query_param("limit", 0).await;
+    assert_eq!(response.status_code(), 400);
+}
@@ -0,0 +1,248 @@
+mod common;
+use axum::http::header::AUTHORIZATION;
+use axum_test::{TestResponse, TestServer};
+use common::{create_api_key, create_request, create_staff_key};
+use gw_api::app::{create_appstate, create_router};
+use gw_core::{api_key::Role, notification::NotificationSource, repo::Pool};
+use serde_json::{json, Value};
+
+const SECRET: &str = "bankone-secret";
+
+struct Servers {
+    merchant: TestServer,
+    admin: TestServer,
+    /// Acquirers don't have API keys
+    acquirer: TestServer,
+}
+
+async fn create_servers(pool: sqlx::PgPool) -> Servers {
+    let api_key = create_api_key(pool.clone(), "merchant123").await;
+    let staff_key = create_staff_key(pool.clone(), Role::Admin).await;
+    let app_state = create_appstate(Pool::from(pool));
+    app_state
+        .lock()
+        .await
+        .notification_secrets
+        .insert(NotificationSource::BankOne, SECRET.into());
+    let server = || TestServer::new(create_router(app_state.clone())).unwrap();
+    let mut merchant = server();
+    merchant.add_header(AUTHORIZATION, format!("Bearer {api_key}"));
+    let mut admin = server();
+    admin.add_header(AUTHORIZATION, format!("Bearer {staff_key}"));
+    Servers {
+        merchant,
+        admin,
+        acquirer: server(),
+    }
+}
+
+async fn create_transaction(servers: &Servers) -> String {
+    let response = servers
+        .merchant
+        .post("/transaction")
+        .json(&create_request(vec![]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()["reference"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+fn bank_one_notification(id: &str, reference: &str, event_code: &str) -> String {
+    json!({"notificationId": id, "pspReference": reference, "eventCode": event_code}).to_string()
+}
+
+async fn notify(servers: &Servers, body: String) -> TestResponse {
+    let signature = NotificationSource::BankOne.sign(&SECRET.into(), body.as_bytes());
+    servers
+        .acquirer
+        .post("/notifications/bankone")
+        .add_header("x-bankone-signature", signature)
+        .text(body)
+        .await
+}
+
+async fn status(servers: &Servers, reference: &str) -> Value {
+    let response = servers
+        .merchant
+        .get("/merchants/merchant123/transactions")
+        .await;
+    response.json::<Value>()["transactions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["reference"] == reference)
+        .unwrap()["status"]
+        .clone()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn notifications_move_transactions_through_the_state_machine(pool: sqlx::PgPool) {
+    let servers = create_servers(pool.clone()).await;
+    servers
+        .admin
+        .put("/merchants/merchant123/webhook")
+        .json(&json!({"url": "https://example.com/hooks"}))
+        .await;
+    let reference = create_transaction(&servers).await;
+
+    let response = notify(
+        &servers,
+        bank_one_notification("n1", &reference, "SETTLEMENT"),
+    )
+    .await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"notification_id": "n1", "outcome": "APPLIED", "duplicate": false})
+    );
+    assert_eq!(status(&servers, &reference).await, "SETTLED");
+    let response = notify(
+        &servers,
+        bank_one_notification("n2", &reference, "CHARGEBACK"),
+    )
+    .await;
+    assert_eq!(response.json::<Value>()["outcome"], "APPLIED");
+    assert_eq!(status(&servers, &reference).await, "CHARGED_BACK");
+    // a charged back transaction can't be declined, but the notification is still kept
+    let response = notify(&servers, bank_one_notification("n3", &reference, "DECLINE")).await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.json::<Value>()["outcome"], "IGNORED");
+    assert_eq!(status(&servers, &reference).await, "CHARGED_BACK");
+
+    let response = servers
+        .admin
+        .get(&format!("/transactions/{reference}/notifications"))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let notifications = response.json::<Value>();
+    assert_eq!(notifications.as_array().unwrap().len(), 3);
+    assert_eq!(notifications[0]["acquirer"], "bankone");
+    assert_eq!(notifications[0]["kind"], "SETTLEMENT");
+    assert_eq!(notifications[0]["detail"], "SUCCESS to SETTLED");
+    assert_eq!(
+        notifications[0]["raw"],
+        bank_one_notification("n1", &reference, "SETTLEMENT")
+    );
+    assert_eq!(
+        notifications[2]["detail"],
+        "a CHARGED_BACK transaction can't have a DECLINE"
+    );
+
+    // the merchant hears about each change
+    let events: Vec<String> = sqlx::query_scalar(
+        "SELECT event_type FROM transaction.webhook_events ORDER BY created_at, event_type",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            "TRANSACTION_SUCCEEDED",
+            "TRANSACTION_SETTLED",
+            "TRANSACTION_CHARGED_BACK"
+        ]
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn late_declines_fail_transactions(pool: sqlx::PgPool) {
+    let servers = create_servers(pool).await;
+    let reference = create_transaction(&servers).await;
+    let response = notify(&servers, bank_one_notification("n1", &reference, "DECLINE")).await;
+    assert_eq!(response.json::<Value>()["outcome"], "APPLIED");
+    assert_eq!(status(&servers, &reference).await, "FAILED");
+    let response = notify(
+        &servers,
+        bank_one_notification("n2", &reference, "SETTLEMENT"),
+    )
+    .await;
+    assert_eq!(response.json::<Value>()["outcome"], "IGNORED");
+
+    let response = notify(
+        &servers,
+        bank_one_notification("n3", "missing", "SETTLEMENT"),
+    )
+    .await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.json::<Value>()["outcome"], "IGNORED");
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn redelivered_notifications_are_only_applied_once(pool: sqlx::PgPool) {
+    let servers = create_servers(pool).await;
+    let reference = create_transaction(&servers).await;
+    let body = bank_one_notification("n1", &reference, "SETTLEMENT");
+    notify(&servers, body.clone()).await;
+    let response = notify(&servers, body).await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"notification_id": "n1", "outcome": "APPLIED", "duplicate": true})
+    );
+    // a redelivery with the same id is the same notification, whatever it says
+    let response = notify(
+        &servers,
+        bank_one_notification("n1", &reference, "CHARGEBACK"),
+    )
+    .await;
+    assert_eq!(response.json::<Value>()["duplicate"], true);
+    assert_eq!(status(&servers, &reference).await, "SETTLED");
+    let response = servers
+        .admin
+        .get(&format!("/transactions/{reference}/notifications"))
+        .await;
+    assert_eq!(response.json::<Value>().as_array().unwrap().len(), 1);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn unauthentic_notifications_are_rejected(pool: sqlx::PgPool) {
+    let servers = create_servers(pool).await;
+    let reference = create_transaction(&servers).await;
+    let body = bank_one_notification("n1", &reference, "SETTLEMENT");
+
+    let response = servers
+        .acquirer
+        .post("/notifications/bankone")
+        .text(body.clone())
+        .await;
+    assert_eq!(response.status_code(), 401);
+    let signature = NotificationSource::BankOne.sign(&"wrong".into(), body.as_bytes());
+    let response = servers
+        .acquirer
+        .post("/notifications/bankone")
+        .add_header("x-bankone-signature", signature)
+        .text(body.clone())
+        .await;
+    assert_eq!(response.status_code(), 401);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "SIGNATURE", "message": "signature does not match"})
+    );
+    // the other acquirer's secret hasn't been set, so nothing it sends is authentic
+    let signature = NotificationSource::BankTwo.sign(&SECRET.into(), body.as_bytes());
+    let response = servers
+        .acquirer
+        .post("/notifications/banktwo")
+        .add_header("x-banktwo-signature", signature)
+        .text(body.clone())
+        .await;
+    assert_eq!(response.status_code(), 401);
+    let response = servers
+        .acquirer
+        .post("/notifications/bankthree")
+        .text(body)
+        .await;
+    assert_eq!(response.status_code(), 404);
+    assert_eq!(status(&servers, &reference).await, "SUCCESS");
+
+    let response = notify(&servers, json!({"notificationId": "n1"}).to_string()).await;
+    assert_eq!(response.status_code(), 400);
+    let response = servers
+        .admin
+        .get(&format!("/transactions/{reference}/notifications"))
+        .await;
+    assert_eq!(response.json::<Value>(), json!([]));
+}
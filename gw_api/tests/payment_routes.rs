@@ -0,0 +1,162 @@
+mod common;
+use common::create_admin_server;
+use serde_json::{json, Value};
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn manage_accounts(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let response = server
+        .post("/merchants/merchant123/accounts")
+        .json(&json!({"acquirer": "banktwo", "merchant_reference": "ref-1"}))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let created = response.json::<Value>();
+    assert_eq!(created["acquirer"], "banktwo");
+    assert_eq!(created["merchant_reference"], "ref-1");
+    let id = created["account_id"].as_i64().unwrap();
+    let response = server.get("/merchants/merchant123/accounts").await;
+    assert_eq!(
+        response.json::<Value>(),
+        json!([
+            {"account_id": 0, "acquirer": "bankone", "merchant_identification_value": "merchant123"},
+            created
+        ])
+    );
+    let response = server
+        .delete(&format!("/merchants/merchant123/accounts/banktwo/{id}"))
+        .await;
+    assert_eq!(response.status_code(), 204);
+    let response = server
+        .delete(&format!("/merchants/merchant123/accounts/banktwo/{id}"))
+        .await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn invalid_accounts(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let response = server
+        .post("/merchants/nobody/accounts")
+        .json(&json!({"acquirer": "bankone", "merchant_identification_value": "mid"}))
+        .await;
+    assert_eq!(response.status_code(), 404);
+    let response = server
+        .post("/merchants/merchant123/accounts")
+        .json(&json!({"acquirer": "bankone", "merchant_identification_value": " "}))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    // the seeded account is used by the seeded routes
+    let response = server
+        .delete("/merchants/merchant123/accounts/bankone/0")
+        .await;
+    assert_eq!(response.status_code(), 409);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "CONFLICT", "message": "bankone account 0 is used by 2 route(s)"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn manage_routes(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let account = server
+        .post("/merchants/merchant123/accounts")
+        .json(&json!({"acquirer": "banktwo", "merchant_reference": "ref-1"}))
+        .await
+        .json::<Value>();
+    let id = account["account_id"].as_i64().unwrap();
+    let response = server
+        .put("/merchants/merchant123/routes/MASTERCARD/USD")
+        .json(&json!({"acquirer": "banktwo", "account_id": id}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let route =
+        json!({"scheme": "MASTERCARD", "currency": "USD", "acquirer": "banktwo", "account_id": id});
+    assert_eq!(response.json::<Value>(), route);
+    let response = server.get("/merchants/merchant123/routes").await;
+    assert_eq!(
+        response.json::<Value>(),
+        json!([
+            route,
+            {"scheme": "VISA", "currency": "GBP", "acquirer": "bankone", "account_id": 0},
+            {"scheme": "VISA", "currency": "USD", "acquirer": "bankone", "account_id": 0}
+        ])
+    );
+    let response = server
+        .get("/merchants/merchant123/routes/resolve")
+        .add_query_param("scheme", "MASTERCARD")
+        .add_query_param("currency", "USD")
+        .await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "scheme": "MASTERCARD",
+            "currency": "USD",
+            "acquirer": "banktwo",
+            "account": {"merchant_reference": "ref-1"}
+        })
+    );
+    let response = server
+        .delete("/merchants/merchant123/routes/MASTERCARD/USD")
+        .await;
+    assert_eq!(response.status_code(), 204);
+    let response = server
+        .get("/merchants/merchant123/routes/resolve")
+        .add_query_param("scheme", "MASTERCARD")
+        .add_query_param("currency", "USD")
+        .await;
+    assert_eq!(response.status_code(), 404);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "RESOURCE", "message": "merchant merchant123 has no MASTERCARD USD route"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn invalid_routes(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let response = server
+        .put("/merchants/merchant123/routes/VISA/GBP")
+        .json(&json!({"acquirer": "banktwo", "account_id": 99}))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "VALIDATION", "message": "merchant merchant123 has no banktwo account 99"})
+    );
+    let response = server
+        .put("/merchants/merchant123/routes/VISA/GBP")
+        .json(&json!({"acquirer": "bankthree", "account_id": 0}))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    let response = server
+        .put("/merchants/merchant123/routes/AMEX/GBP")
+        .json(&json!({"acquirer": "bankone", "account_id": 0}))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "VALIDATION", "message": "AMEX is not a recognised card scheme"})
+    );
+    let response = server
+        .delete("/merchants/merchant123/routes/MASTERCARD/GBP")
+        .await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn broken_routes_are_not_missing_routes(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool.clone()).await;
+    sqlx::query("UPDATE account.paymentroute SET acquirer = 'bankthree' WHERE currency = 'USD'")
+        .execute(&pool)
+        .await
+        .unwrap();
+    let response = server
+        .get("/merchants/merchant123/routes/resolve")
+        .add_query_param("scheme", "VISA")
+        .add_query_param("currency", "USD")
+        .await;
+    assert_eq!(response.status_code(), 500);
+    assert_eq!(response.json::<Value>()["error"], "FATAL");
+}
@@ -0,0 +1,526 @@
+mod common;
+use axum::http::Method;
+use axum_test::TestServer;
+use common::{
+    create_api_key, create_request, create_staff_key, create_unauthenticated_server,
+    CreateRequestAction,
+};
+use gw_core::api_key::Role;
+use serde_json::{json, Value};
+
+const M: Role = Role::Merchant;
+const S: Role = Role::Support;
+const A: Role = Role::Admin;
+
+/// Every route along with the roles allowed to use it
+fn routes() -> Vec<(Method, &'static str, &'static str, Vec<Role>)> {
+    vec![
+        (Method::POST, "/transaction", "transact", vec![M]),
+        (
+            Method::GET,
+            "/transactions",
+            "read_all_merchants",
+            vec![S, A],
+        ),
+        (
+            Method::GET,
+            "/transactions/missing/notifications",
+            "read_all_merchants",
+            vec![S, A],
+        ),
+        (
+            Method::GET,
+            "/transactions/missing/3ds/challenge",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::POST,
+            "/transactions/missing/3ds/result",
+            "transact",
+            vec![M],
+        ),
+        (Method::POST, "/merchants", "manage_merchants", vec![A]),
+        (Method::GET, "/merchants", "read_all_merchants", vec![S, A]),
+        (
+            Method::GET,
+            "/merchants/merchant123",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::PUT,
+            "/merchants/merchant123",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/transactions",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/accounts",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/accounts",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::DELETE,
+            "/merchants/merchant123/accounts/bankone/99",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/routes",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/routes/resolve",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::PUT,
+            "/merchants/merchant123/routes/MASTERCARD/EUR",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::DELETE,
+            "/merchants/merchant123/routes/MASTERCARD/EUR",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/api-keys",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/api-keys",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/api-keys/rotate",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::DELETE,
+            "/merchants/merchant123/api-keys/missing",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::PUT,
+            "/merchants/merchant123/signing-secret",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::DELETE,
+            "/merchants/merchant123/signing-secret",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/deactivate",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/limits",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::PUT,
+            "/merchants/merchant123/limits",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/fraud-rules",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::PUT,
+            "/merchants/merchant123/fraud-rules",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/policies",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::PUT,
+            "/merchants/merchant123/policies",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/sca-exemptions",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::PUT,
+            "/merchants/merchant123/sca-exemptions/EUR",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::DELETE,
+            "/merchants/merchant123/sca-exemptions/EUR",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/customers",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/customers",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/customers/missing",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::PUT,
+            "/merchants/merchant123/customers/missing",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::DELETE,
+            "/merchants/merchant123/customers/missing",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/customers/missing/payment-methods",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::DELETE,
+            "/merchants/merchant123/customers/missing/payment-methods/missing",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/plans",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/plans",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/plans/missing",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/subscriptions",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/subscriptions",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/subscriptions/missing",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/subscriptions/missing/cancel",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/disputes",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/disputes/missing",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/disputes/missing/evidence",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/disputes/missing/submit",
+            "transact",
+            vec![M],
+        ),
+        (
+            Method::POST,
+            "/disputes/files/bankone",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/webhook",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::PUT,
+            "/merchants/merchant123/webhook",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::DELETE,
+            "/merchants/merchant123/webhook",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/merchants/merchant123/webhook-events/dead",
+            "read_merchant",
+            vec![M, S, A],
+        ),
+        (
+            Method::POST,
+            "/merchants/merchant123/webhook-events/missing/retry",
+            "manage_merchants",
+            vec![A],
+        ),
+        (
+            Method::GET,
+            "/list-entries",
+            "read_all_merchants",
+            vec![S, A],
+        ),
+        (Method::POST, "/list-entries", "manage_merchants", vec![A]),
+        (
+            Method::DELETE,
+            "/list-entries/missing",
+            "manage_merchants",
+            vec![A],
+        ),
+        (Method::GET, "/staff-keys", "manage_merchants", vec![A]),
+        (Method::POST, "/staff-keys", "manage_merchants", vec![A]),
+        (
+            Method::DELETE,
+            "/staff-keys/missing",
+            "manage_merchants",
+            vec![A],
+        ),
+    ]
+}
+
+async fn keys(pool: &sqlx::PgPool) -> Vec<(Role, String)> {
+    vec![
+        (M, create_api_key(pool.clone(), "merchant123").await),
+        (S, create_staff_key(pool.clone(), S).await),
+        (A, create_staff_key(pool.clone(), A).await),
+    ]
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn every_route_and_role(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    let keys = keys(&pool).await;
+    for (method, path, permission, allowed) in routes() {
+        let response = server.method(method.clone(), path).await;
+        assert_eq!(response.status_code(), 401, "{method} {path} without a key");
+        for (role, key) in keys.iter() {
+            let response = server
+                .method(method.clone(), path)
+                .authorization_bearer(key)
+                .json(&create_request(Vec::<CreateRequestAction>::new()))
+                .await;
+            if allowed.contains(role) {
+                assert!(
+                    ![401, 403].contains(&response.status_code().as_u16()),
+                    "{role} should be allowed to {method} {path}"
+                );
+            } else {
+                assert_eq!(
+                    response.status_code(),
+                    403,
+                    "{role} shouldn't be allowed to {method} {path}"
+                );
+                assert_eq!(
+                    response.json::<Value>(),
+                    json!({
+                        "error": "FORBIDDEN",
+                        "message": format!("API key does not have the {permission} permission")
+                    })
+                );
+            }
+        }
+        // once the signing secret route has turned signing on merchant123's requests need
+        // signing, which tests/signing.rs covers
+        sqlx::query("DELETE FROM account.signing_secret")
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn merchants_only_read_their_own_data(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    let keys = keys(&pool).await;
+    for (method, path, _, allowed) in routes() {
+        if !path.contains("merchant123") || !allowed.contains(&M) {
+            continue;
+        }
+        let other_path = path.replace("merchant123", "merchant456");
+        for (role, key) in keys.iter() {
+            let response = server
+                .method(method.clone(), &other_path)
+                .authorization_bearer(key)
+                .await;
+            if *role == M {
+                assert_eq!(response.status_code(), 403, "{method} {other_path}");
+                assert_eq!(
+                    response.json::<Value>(),
+                    json!({"error": "FORBIDDEN", "message": "merchant_id does not match the API key"})
+                );
+            } else if allowed.contains(role) {
+                assert_ne!(response.status_code(), 403, "{role} {method} {other_path}");
+            }
+        }
+    }
+}
+
+async fn get(server: &TestServer, path: &str, key: &str) -> Value {
+    let response = server.get(path).authorization_bearer(key).await;
+    assert_eq!(response.status_code(), 200);
+    response.json::<Value>()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn support_sees_masked_data(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    let keys = keys(&pool).await;
+    let (_, merchant_key) = &keys[0];
+    let response = server
+        .post("/transaction")
+        .authorization_bearer(merchant_key)
+        .json(&create_request(vec![
+            ("description", "2 x widgets").into(),
+            ("metadata", json!({"customer": "Jones"})).into(),
+        ]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    for (role, key) in keys.iter() {
+        let merchant = get(&server, "/merchants/merchant123", key).await;
+        let accounts = get(&server, "/merchants/merchant123/accounts", key).await;
+        let transactions = get(&server, "/merchants/merchant123/transactions", key).await;
+        let transaction = &transactions["transactions"][0];
+        if *role == S {
+            assert_eq!(merchant["street"], "S#####");
+            assert_eq!(merchant["postcode"], "P#######");
+            assert_eq!(merchant["name"], "Test Merchant");
+            assert_eq!(accounts[0]["merchant_identification_value"], "#######t123");
+            assert_eq!(transaction["description"], "2##########");
+            assert_eq!(transaction["metadata"]["customer"], "J####");
+            let merchants = get(&server, "/merchants", key).await;
+            assert_eq!(merchants["merchants"][0]["street"], "S#####");
+        } else {
+            assert_eq!(merchant["street"], "Street");
+            assert_eq!(merchant["postcode"], "Postcode");
+            assert_eq!(accounts[0]["merchant_identification_value"], "merchant123");
+            assert_eq!(transaction["description"], "2 x widgets");
+            assert_eq!(transaction["metadata"]["customer"], "Jones");
+        }
+        assert_eq!(transaction["pan"], "400011######3333");
+    }
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn manage_staff_keys(pool: sqlx::PgPool) {
+    let server = create_unauthenticated_server(pool.clone());
+    let admin_key = create_staff_key(pool, A).await;
+    let response = server
+        .post("/staff-keys")
+        .authorization_bearer(&admin_key)
+        .json(&json!({"role": "support"}))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let created = response.json::<Value>();
+    assert_eq!(created["role"], "support");
+    let support_key = created["api_key"].as_str().unwrap();
+    let key_id = created["key_id"].as_str().unwrap();
+    get(&server, "/merchants", support_key).await;
+    let listed = get(&server, "/staff-keys", &admin_key).await;
+    assert_eq!(listed.as_array().unwrap().len(), 2);
+    for role in ["merchant", "root"] {
+        let response = server
+            .post("/staff-keys")
+            .authorization_bearer(&admin_key)
+            .json(&json!({"role": role}))
+            .await;
+        assert_eq!(response.status_code(), 400);
+    }
+    let response = server
+        .delete(&format!("/staff-keys/{key_id}"))
+        .authorization_bearer(&admin_key)
+        .await;
+    assert_eq!(response.status_code(), 204);
+    let response = server
+        .get("/merchants")
+        .authorization_bearer(support_key)
+        .await;
+    assert_eq!(response.status_code(), 401);
+}
@@ -1,13 +1,12 @@
 mod common;
 use common::{create_request, create_server, CreateRequestAction};
 use serde_json::json;
-use sqlx;
 
 macro_rules! test_case {
     ($name:ident, $endpoint:expr, $status_code:expr, $body:expr, $overrides:expr) => {
         #[sqlx::test(migrations = "../gw_core/migrations")]
         async fn $name(_pool: sqlx::PgPool) {
-            let server = create_server(_pool.clone());
+            let server = create_server(_pool.clone()).await;
             let response = server
                 .post($endpoint)
                 .json(&create_request($overrides))
@@ -28,7 +27,7 @@ macro_rules! test_case {
     ($name:ident, $endpoint:expr, $status_code:expr, $body:expr) => {
         #[sqlx::test(migrations = "../gw_core/migrations")]
         async fn $name(_pool: sqlx::PgPool) {
-            let server = create_server(_pool.clone());
+            let server = create_server(_pool.clone()).await;
             let response = server
                 .post($endpoint)
                 .json(&create_request(Vec::<CreateRequestAction>::new()))
@@ -61,12 +60,19 @@ test_case! {simple_transaction, "/transaction", 201, json!({
         "country": "GB"
     },
     "status": "SUCCESS",
-    "reference": "[a-z0-9-]+"
+    "reference": "[a-z0-9-]+",
+    "avs": {
+        "code": "U",
+        "address": "NOT_CHECKED",
+        "postcode": "NOT_CHECKED"
+    },
+    "cvv": "MATCH",
+    "scheme_transaction_id": "[0-9]+"
 })}
 
-test_case! {merchant_doesnt_exist, "/transaction", 404, json!({
-    "error": "RESOURCE",
-    "message": "merchant invalid123 does not exist"
+test_case! {merchant_doesnt_match_api_key, "/transaction", 403, json!({
+    "error": "FORBIDDEN",
+    "message": "merchant_id does not match the API key"
 }), vec![("merchant_id", "invalid123").into()]}
 
 test_case! {missing_payment_details, "/transaction", 400, json!({
@@ -98,3 +104,47 @@ test_case! {no_account_or_country_but_country_errors, "/transaction", 400, json!
     "error": "VALIDATION",
     "message": "TypeError:  is not a recognised country code"
 }), vec![("currency", "JPY").into(), "!billing.country".into()]}
+
+test_case! {with_merchant_reference_and_metadata, "/transaction", 201, json!({
+    "amount": 12345,
+    "currency": "GBP",
+    "payment": {
+        "scheme": "VISA",
+        "pan": "400011######3333",
+        "expiry_year": 2026,
+        "expiry_month": 12,
+        "type": "CARD"
+    },
+    "billing": {
+        "country": "GB"
+    },
+    "status": "SUCCESS",
+    "reference": "[a-z0-9-]+",
+    "merchant_reference": "order-123",
+    "description": "2 x widgets",
+    "metadata": {
+        "basket_id": "987",
+        "channel": "web"
+    },
+    "avs": {
+        "code": "U",
+        "address": "NOT_CHECKED",
+        "postcode": "NOT_CHECKED"
+    },
+    "cvv": "MATCH",
+    "scheme_transaction_id": "[0-9]+"
+}), vec![
+    ("merchant_reference", "order-123").into(),
+    ("description", " 2 x widgets ").into(),
+    ("metadata", json!({"channel": "web", "basket_id": "987"})).into(),
+]}
+
+test_case! {bad_merchant_reference, "/transaction", 400, json!({
+    "error": "VALIDATION",
+    "message": "merchant_reference - invalid characters"
+}), vec![("merchant_reference", "order<123>").into()]}
+
+test_case! {bad_metadata_key, "/transaction", 400, json!({
+    "error": "VALIDATION",
+    "message": "metadata - invalid key bad key"
+}), vec![("metadata", json!({"bad key": "1"})).into()]}
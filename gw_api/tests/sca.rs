@@ -0,0 +1,158 @@
+mod common;
+use axum_test::TestServer;
+use common::{create_admin_server, create_request, create_server};
+use serde_json::{json, Value};
+
+async fn set_thresholds(pool: &sqlx::PgPool, thresholds: Value) {
+    let admin = create_admin_server(pool.clone()).await;
+    let response = admin
+        .put("/merchants/merchant123/sca-exemptions/GBP")
+        .json(&thresholds)
+        .await;
+    assert_eq!(response.status_code(), 200);
+}
+
+async fn post_transaction(server: &TestServer, pan: &str, options: Value) -> Value {
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![
+            ("payment.pan", pan).into(),
+            ("options", options).into(),
+        ]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn get_set_and_delete_thresholds(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let response = server.get("/merchants/merchant123/sca-exemptions").await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.json::<Value>(), json!([]));
+    let admin = create_admin_server(pool).await;
+    let response = admin
+        .put("/merchants/merchant123/sca-exemptions/EUR")
+        .json(&json!({"low_value": 3000, "transaction_risk_analysis": 50000}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let thresholds = json!({
+        "currency": "EUR",
+        "low_value": 3000,
+        "transaction_risk_analysis": 50000,
+        "merchant_initiated": null,
+        "recurring": null,
+        "corporate": null
+    });
+    assert_eq!(response.json::<Value>(), thresholds);
+    let response = server.get("/merchants/merchant123/sca-exemptions").await;
+    assert_eq!(response.json::<Value>(), json!([thresholds]));
+
+    let response = admin
+        .put("/merchants/merchant123/sca-exemptions/XYZ")
+        .json(&json!({}))
+        .await;
+    assert_eq!(response.status_code(), 400);
+    let response = admin
+        .put("/merchants/nobody/sca-exemptions/EUR")
+        .json(&json!({}))
+        .await;
+    assert_eq!(response.status_code(), 404);
+
+    let response = admin
+        .delete("/merchants/merchant123/sca-exemptions/EUR")
+        .await;
+    assert_eq!(response.status_code(), 204);
+    let response = admin
+        .delete("/merchants/merchant123/sca-exemptions/EUR")
+        .await;
+    assert_eq!(response.status_code(), 404);
+    let response = server.get("/merchants/merchant123/sca-exemptions").await;
+    assert_eq!(response.json::<Value>(), json!([]));
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn exemptions_are_chosen_by_threshold(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let transaction = post_transaction(&server, "4000111122223333", json!({})).await;
+    assert!(transaction.get("sca_exemption").is_none());
+
+    set_thresholds(&pool, json!({"low_value": 20000, "recurring": 20000})).await;
+    // an exemption means the cardholder isn't authenticated, even when 3DS was asked for
+    let transaction =
+        post_transaction(&server, "4000111122223333", json!({"three_ds": true})).await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(transaction["sca_exemption"], "LOW_VALUE");
+    assert!(transaction.get("three_ds").is_none());
+    let transaction =
+        post_transaction(&server, "4000111122223333", json!({"recurring": true})).await;
+    assert_eq!(transaction["sca_exemption"], "RECURRING");
+
+    set_thresholds(&pool, json!({"low_value": 10000})).await;
+    let transaction =
+        post_transaction(&server, "4000111122223333", json!({"three_ds": true})).await;
+    assert!(transaction.get("sca_exemption").is_none());
+    assert_eq!(transaction["three_ds"]["status"], "AUTHENTICATED");
+
+    let exemptions: Vec<Option<String>> = sqlx::query_scalar(
+        "SELECT sca_exemption FROM transaction.transactions ORDER BY created_at",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        exemptions,
+        vec![
+            None,
+            Some("LOW_VALUE".into()),
+            Some("RECURRING".into()),
+            None
+        ]
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn risk_analysis_needs_accepted_screening(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    set_thresholds(&pool, json!({"transaction_risk_analysis": 50000})).await;
+    let transaction = post_transaction(&server, "4000111122223333", json!({})).await;
+    assert!(transaction.get("sca_exemption").is_none());
+    let admin = create_admin_server(pool).await;
+    let response = admin
+        .put("/merchants/merchant123/fraud-rules")
+        .json(&json!({
+            "rules": [{"type": "amount_over", "currency": "GBP", "amount": 100000, "score": 80}]
+        }))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let transaction = post_transaction(&server, "4000111122223333", json!({})).await;
+    assert_eq!(transaction["fraud"]["decision"], "ACCEPT");
+    assert_eq!(transaction["sca_exemption"], "TRA");
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn soft_declines_are_retried_with_authentication(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    set_thresholds(
+        &pool,
+        json!({"low_value": 20000, "merchant_initiated": 20000}),
+    )
+    .await;
+    let transaction = post_transaction(&server, "4000111122220005", json!({})).await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert!(transaction.get("sca_exemption").is_none());
+    assert_eq!(transaction["three_ds"]["status"], "AUTHENTICATED");
+    assert_eq!(transaction["cvv"], "MATCH");
+
+    // the cardholder isn't there to authenticate a merchant initiated transaction
+    let transaction = post_transaction(
+        &server,
+        "4000111122220005",
+        json!({"merchant_initiated": true}),
+    )
+    .await;
+    assert_eq!(transaction["status"], "FAILED");
+    assert_eq!(transaction["error"], "SCA_REQUIRED");
+    assert_eq!(transaction["sca_exemption"], "MIT");
+    assert!(transaction.get("three_ds").is_none());
+}
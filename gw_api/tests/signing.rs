@@ -0,0 +1,146 @@
+mod common;
+use axum_test::{TestResponse, TestServer};
+use chrono::Utc;
+use common::{create_admin_server, create_request, create_server, CreateRequestAction};
+use gw_core::{secret::Secret, signing::SignedRequest};
+use serde_json::{json, Value};
+
+async fn enable_signing(pool: &sqlx::PgPool) -> Secret<String> {
+    let admin = create_admin_server(pool.clone()).await;
+    let response = admin.put("/merchants/merchant123/signing-secret").await;
+    assert_eq!(response.status_code(), 201);
+    let secret = response.json::<Value>()["signing_secret"]
+        .as_str()
+        .expect("signing secret returned")
+        .to_string();
+    Secret::new(secret)
+}
+
+async fn post_signed(
+    server: &TestServer,
+    secret: &Secret<String>,
+    timestamp: i64,
+    nonce: &str,
+    signed_body: &[u8],
+    sent_body: &[u8],
+) -> TestResponse {
+    let signature = SignedRequest {
+        method: "POST",
+        path: "/transaction",
+        timestamp,
+        nonce,
+        body: signed_body,
+    }
+    .sign(secret);
+    server
+        .post("/transaction")
+        .content_type("application/json")
+        .add_header("x-signature", signature)
+        .add_header("x-signature-timestamp", timestamp.to_string())
+        .add_header("x-signature-nonce", nonce.to_string())
+        .bytes(sent_body.to_vec().into())
+        .await
+}
+
+fn body() -> Vec<u8> {
+    serde_json::to_vec(&create_request(Vec::<CreateRequestAction>::new())).unwrap()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn unsigned_requests_are_rejected_once_enabled(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    enable_signing(&pool).await;
+    let response = server
+        .post("/transaction")
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await;
+    assert_eq!(response.status_code(), 401);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "SIGNATURE", "message": "missing x-signature header"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn every_merchant_route_needs_a_signature(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    enable_signing(&pool).await;
+    let response = server
+        .post("/merchants/merchant123/customers/customer123/payment-methods")
+        .json(&json!({"payment": {"type": "CARD", "pan": "4000111122223333"}}))
+        .await;
+    assert_eq!(response.status_code(), 401);
+    assert_eq!(response.json::<Value>()["error"], "SIGNATURE");
+    let response = server.get("/merchants/merchant123/transactions").await;
+    assert_eq!(response.status_code(), 401);
+
+    // staff aren't merchants, they don't sign their requests
+    let admin = create_admin_server(pool).await;
+    let response = admin.get("/merchants/merchant123/transactions").await;
+    assert_eq!(response.status_code(), 200);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn signed_request_succeeds_once(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let secret = enable_signing(&pool).await;
+    let now = Utc::now().timestamp();
+    let body = body();
+    let response = post_signed(&server, &secret, now, "nonce-1", &body, &body).await;
+    assert_eq!(response.status_code(), 201);
+    let replay = post_signed(&server, &secret, now, "nonce-1", &body, &body).await;
+    assert_eq!(replay.status_code(), 401);
+    assert_eq!(
+        replay.json::<Value>(),
+        json!({"error": "SIGNATURE", "message": "signature nonce has already been used"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn stale_timestamp_is_rejected(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let secret = enable_signing(&pool).await;
+    let stale = Utc::now().timestamp() - 10 * 60;
+    let body = body();
+    let response = post_signed(&server, &secret, stale, "nonce-1", &body, &body).await;
+    assert_eq!(response.status_code(), 401);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "SIGNATURE", "message": "signature timestamp is outside the allowed window"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn tampered_body_is_rejected(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let secret = enable_signing(&pool).await;
+    let now = Utc::now().timestamp();
+    let tampered = serde_json::to_vec(&create_request(vec![("amount", 99999).into()])).unwrap();
+    let response = post_signed(&server, &secret, now, "nonce-1", &body(), &tampered).await;
+    assert_eq!(response.status_code(), 401);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "SIGNATURE", "message": "invalid signature"})
+    );
+    let wrong_secret = Secret::new("wrong".to_string());
+    let response = post_signed(&server, &wrong_secret, now, "nonce-2", &body(), &body()).await;
+    assert_eq!(response.status_code(), 401);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn disabling_signing_allows_unsigned_requests(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    enable_signing(&pool).await;
+    let admin = create_admin_server(pool).await;
+    let response = admin.delete("/merchants/merchant123/signing-secret").await;
+    assert_eq!(response.status_code(), 204);
+    let response = server
+        .post("/transaction")
+        .json(&create_request(Vec::<CreateRequestAction>::new()))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let response = admin.delete("/merchants/merchant123/signing-secret").await;
+    assert_eq!(response.status_code(), 404);
+    let response = admin.put("/merchants/missing/signing-secret").await;
+    assert_eq!(response.status_code(), 404);
+}
@@ -0,0 +1,184 @@
+mod common;
+use axum_test::TestServer;
+use common::{create_request, create_server};
+use serde_json::{json, Value};
+
+/// Creates a customer with a saved card, returning the customer id and payment token
+async fn save_card(server: &TestServer) -> (String, String) {
+    let response = server
+        .post("/merchants/merchant123/customers")
+        .json(&json!({"first_name": "Jo"}))
+        .await;
+    let customer_id = response.json::<Value>()["customer_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let response = server
+        .post(&format!(
+            "/merchants/merchant123/customers/{customer_id}/payment-methods"
+        ))
+        .json(&create_request(vec![])["payment"])
+        .await;
+    let token = response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    (customer_id, token)
+}
+
+async fn charge_saved_card(
+    server: &TestServer,
+    customer_id: &str,
+    token: &str,
+    options: Value,
+) -> Value {
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![
+            "!payment".into(),
+            ("customer_id", customer_id).into(),
+            ("payment_token", token).into(),
+            ("options", options).into(),
+        ]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn saved_cards_are_chained_to_their_initial_transaction(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let (customer_id, token) = save_card(&server).await;
+
+    let initial = charge_saved_card(&server, &customer_id, &token, json!({})).await;
+    assert_eq!(initial["status"], "SUCCESS");
+    assert_eq!(
+        initial["stored_credential"],
+        json!({"usage": "INITIAL", "initiator": "CIT"})
+    );
+    let scheme_transaction_id = initial["scheme_transaction_id"].as_str().unwrap();
+
+    for options in [json!({"merchant_initiated": true}), json!({})] {
+        let subsequent = charge_saved_card(&server, &customer_id, &token, options).await;
+        assert_eq!(subsequent["status"], "SUCCESS");
+        assert_eq!(subsequent["stored_credential"]["usage"], "SUBSEQUENT");
+        assert_eq!(
+            subsequent["stored_credential"]["original_scheme_transaction_id"],
+            scheme_transaction_id
+        );
+        assert_ne!(subsequent["scheme_transaction_id"], scheme_transaction_id);
+    }
+    let subsequent = charge_saved_card(
+        &server,
+        &customer_id,
+        &token,
+        json!({"merchant_initiated": true, "original_scheme_transaction_id": "987654321098765"}),
+    )
+    .await;
+    assert_eq!(
+        subsequent["stored_credential"],
+        json!({
+            "usage": "SUBSEQUENT",
+            "initiator": "MIT",
+            "original_scheme_transaction_id": "987654321098765"
+        })
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn merchant_initiated_transactions_before_an_initial_one_are_not_chained(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let (customer_id, token) = save_card(&server).await;
+    let subsequent = charge_saved_card(
+        &server,
+        &customer_id,
+        &token,
+        json!({"merchant_initiated": true}),
+    )
+    .await;
+    assert_eq!(
+        subsequent["stored_credential"],
+        json!({"usage": "SUBSEQUENT", "initiator": "MIT"})
+    );
+    // only initial transactions are chained to
+    let cit = charge_saved_card(&server, &customer_id, &token, json!({})).await;
+    assert_eq!(cit["stored_credential"]["usage"], "INITIAL");
+    let mit = charge_saved_card(
+        &server,
+        &customer_id,
+        &token,
+        json!({"merchant_initiated": true}),
+    )
+    .await;
+    assert_eq!(
+        mit["stored_credential"]["original_scheme_transaction_id"],
+        cit["scheme_transaction_id"]
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn cards_given_in_full_are_stored_credentials_when_asked(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![]))
+        .await;
+    let transaction = response.json::<Value>();
+    assert_eq!(transaction.get("stored_credential"), None);
+    assert!(transaction["scheme_transaction_id"].is_string());
+
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![(
+            "options",
+            json!({"stored_credential": "INITIAL"}),
+        )
+            .into()]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let initial = response.json::<Value>();
+    assert_eq!(
+        initial["stored_credential"],
+        json!({"usage": "INITIAL", "initiator": "CIT"})
+    );
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![(
+            "options",
+            json!({
+                "stored_credential": "SUBSEQUENT",
+                "merchant_initiated": true,
+                "original_scheme_transaction_id": initial["scheme_transaction_id"]
+            }),
+        )
+            .into()]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    assert_eq!(
+        response.json::<Value>()["stored_credential"],
+        json!({
+            "usage": "SUBSEQUENT",
+            "initiator": "MIT",
+            "original_scheme_transaction_id": initial["scheme_transaction_id"]
+        })
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn invalid_stored_credential_options_are_rejected(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    for options in [
+        json!({"stored_credential": "FIRST"}),
+        json!({"stored_credential": "INITIAL", "merchant_initiated": true}),
+        json!({"stored_credential": "INITIAL", "original_scheme_transaction_id": "123"}),
+        json!({"original_scheme_transaction_id": "123"}),
+        json!({"stored_credential": "SUBSEQUENT", "original_scheme_transaction_id": ""}),
+        json!({"stored_credential": "SUBSEQUENT", "original_scheme_transaction_id": "1".repeat(65)}),
+    ] {
+        let response = server
+            .post("/transaction")
+            .json(&create_request(vec![("options", options.clone()).into()]))
+            .await;
+        assert_eq!(response.status_code(), 400, "{options}");
+    }
+}
@@ -0,0 +1,323 @@
+mod common;
+use std::sync::Arc;
+
+use axum::http::header::AUTHORIZATION;
+use axum_test::TestServer;
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+use common::{create_admin_server, create_api_key, create_request};
+use gw_api::{
+    app::{create_appstate, create_router, AppState},
+    scheduler::charge_due_subscriptions,
+};
+use gw_core::{clock::MockClock, repo::Pool};
+use serde_json::{json, Value};
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap()
+}
+
+/// A merchant123 server whose subscriptions are charged by the clock returned with it
+async fn create_scheduled_server(pool: sqlx::PgPool) -> (TestServer, AppState, Arc<MockClock>) {
+    let api_key = create_api_key(pool.clone(), "merchant123").await;
+    let app_state = create_appstate(Pool::from(pool));
+    let clock = Arc::new(MockClock::new(start()));
+    app_state.lock().await.clock = clock.clone();
+    let mut server = TestServer::new(create_router(app_state.clone())).unwrap();
+    server.add_header(AUTHORIZATION, format!("Bearer {api_key}"));
+    (server, app_state, clock)
+}
+
+async fn create_plan(server: &TestServer, trial_days: u32) -> String {
+    let response = server
+        .post("/merchants/merchant123/plans")
+        .json(&json!({
+            "name": "Monthly",
+            "amount": 999,
+            "currency": "GBP",
+            "interval": "MONTH",
+            "trial_days": trial_days
+        }))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()["plan_id"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+/// Creates a customer with a saved card, returning the customer id and payment token
+async fn create_customer(server: &TestServer, pan: &str) -> (String, String) {
+    let response = server
+        .post("/merchants/merchant123/customers")
+        .json(&json!({"first_name": "Jo"}))
+        .await;
+    let customer_id = response.json::<Value>()["customer_id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let response = server
+        .post(&format!(
+            "/merchants/merchant123/customers/{customer_id}/payment-methods"
+        ))
+        .json(&create_request(vec![("payment.pan", pan).into()])["payment"])
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let token = response.json::<Value>()["token"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    (customer_id, token)
+}
+
+async fn subscribe(server: &TestServer, plan_id: &str, customer_id: &str, token: &str) -> Value {
+    let response = server
+        .post("/merchants/merchant123/subscriptions")
+        .json(&json!({"plan_id": plan_id, "customer_id": customer_id, "payment_token": token}))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()
+}
+
+async fn get_subscription(server: &TestServer, subscription_id: &str) -> Value {
+    let response = server
+        .get(&format!(
+            "/merchants/merchant123/subscriptions/{subscription_id}"
+        ))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    response.json::<Value>()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn create_plans_and_subscriptions(pool: sqlx::PgPool) {
+    let (server, _, _) = create_scheduled_server(pool).await;
+    let plan_id = create_plan(&server, 0).await;
+    let response = server.get("/merchants/merchant123/plans").await;
+    let plans = response.json::<Value>();
+    assert_eq!(plans[0]["plan_id"], plan_id);
+    assert_eq!(plans[0]["interval"], "MONTH");
+    assert_eq!(plans[0]["interval_count"], 1);
+    let response = server
+        .get(&format!("/merchants/merchant123/plans/{plan_id}"))
+        .await;
+    assert_eq!(response.json::<Value>(), plans[0]);
+    for plan in [
+        json!({"name": "Fortnightly", "amount": 999, "currency": "GBP", "interval": "FORTNIGHT"}),
+        json!({"name": "Free", "amount": 0, "currency": "GBP", "interval": "MONTH"}),
+        json!({"name": "", "amount": 999, "currency": "GBP", "interval": "MONTH"}),
+    ] {
+        let response = server
+            .post("/merchants/merchant123/plans")
+            .json(&plan)
+            .await;
+        assert_eq!(response.status_code(), 400, "{plan}");
+    }
+
+    let (customer_id, token) = create_customer(&server, "4000111122223333").await;
+    for (plan, customer, payment_token) in [
+        ("missing", customer_id.as_str(), token.as_str()),
+        (&plan_id, "missing", &token),
+        (&plan_id, &customer_id, "missing"),
+    ] {
+        let response = server
+            .post("/merchants/merchant123/subscriptions")
+            .json(
+                &json!({"plan_id": plan, "customer_id": customer, "payment_token": payment_token}),
+            )
+            .await;
+        assert_eq!(response.status_code(), 404);
+    }
+    let subscription = subscribe(&server, &plan_id, &customer_id, &token).await;
+    assert_eq!(subscription["status"], "ACTIVE");
+    assert_eq!(subscription["next_charge_at"], "2026-01-01T09:00:00Z");
+    let subscription_id = subscription["subscription_id"].as_str().unwrap();
+    let response = server.get("/merchants/merchant123/subscriptions").await;
+    assert_eq!(response.json::<Value>(), json!([subscription]));
+
+    let path = format!("/merchants/merchant123/subscriptions/{subscription_id}/cancel");
+    let response = server.post(&path).await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.json::<Value>()["status"], "CANCELLED");
+    let response = server.post(&path).await;
+    assert_eq!(response.status_code(), 409);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn subscriptions_are_charged_when_due(pool: sqlx::PgPool) {
+    let (server, app_state, clock) = create_scheduled_server(pool.clone()).await;
+    let plan_id = create_plan(&server, 14).await;
+    let (customer_id, token) = create_customer(&server, "4000111122223333").await;
+    let subscription = subscribe(&server, &plan_id, &customer_id, &token).await;
+    let subscription_id = subscription["subscription_id"].as_str().unwrap();
+    assert_eq!(subscription["status"], "TRIALING");
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 0);
+
+    clock.advance(TimeDelta::days(14));
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    let subscription = get_subscription(&server, subscription_id).await;
+    assert_eq!(subscription["status"], "ACTIVE");
+    assert_eq!(subscription["next_charge_at"], "2026-02-15T09:00:00Z");
+    assert!(subscription["last_transaction"].is_string());
+    let response = server.get("/merchants/merchant123/transactions").await;
+    let transactions = response.json::<Value>()["transactions"].clone();
+    assert_eq!(transactions.as_array().unwrap().len(), 1);
+    assert_eq!(
+        transactions[0]["reference"],
+        subscription["last_transaction"]
+    );
+    assert_eq!(transactions[0]["amount"], 999);
+    assert_eq!(
+        transactions[0]["metadata"]["subscription_id"],
+        subscription_id
+    );
+    let (usage, initiator): (String, String) = sqlx::query_as(
+        "SELECT stored_credential_usage, stored_credential_initiator FROM transaction.transactions",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!((usage.as_str(), initiator.as_str()), ("SUBSEQUENT", "MIT"));
+    // nothing more is due until the next period
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 0);
+    clock.advance(TimeDelta::days(31));
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    let subscription = get_subscription(&server, subscription_id).await;
+    assert_eq!(subscription["next_charge_at"], "2026-03-15T09:00:00Z");
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn declined_charges_are_retried(pool: sqlx::PgPool) {
+    let (server, app_state, clock) = create_scheduled_server(pool.clone()).await;
+    // the simulated acquirer soft declines exemptions for this card, and merchant initiated
+    // transactions can't be authenticated instead
+    let admin = create_admin_server(pool).await;
+    let thresholds = "/merchants/merchant123/sca-exemptions/GBP";
+    let response = admin
+        .put(thresholds)
+        .json(&json!({"merchant_initiated": 100000}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let plan_id = create_plan(&server, 0).await;
+    let (customer_id, token) = create_customer(&server, "4000111122220005").await;
+    let subscription = subscribe(&server, &plan_id, &customer_id, &token).await;
+    let subscription_id = subscription["subscription_id"].as_str().unwrap();
+
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    let subscription = get_subscription(&server, subscription_id).await;
+    assert_eq!(subscription["status"], "PAST_DUE");
+    assert_eq!(subscription["failed_attempts"], 1);
+    assert_eq!(subscription["retry_at"], "2026-01-02T09:00:00Z");
+    assert!(subscription["last_transaction"].is_string());
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 0);
+
+    let response = admin.delete(thresholds).await;
+    assert_eq!(response.status_code(), 204);
+    clock.advance(TimeDelta::days(1));
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    let subscription = get_subscription(&server, subscription_id).await;
+    assert_eq!(subscription["status"], "ACTIVE");
+    assert_eq!(subscription["failed_attempts"], 0);
+    assert_eq!(subscription.get("retry_at"), None);
+    // the period paid for is the one that was declined
+    assert_eq!(subscription["next_charge_at"], "2026-02-01T09:00:00Z");
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn subscriptions_are_left_unpaid_once_retries_run_out(pool: sqlx::PgPool) {
+    let (server, app_state, clock) = create_scheduled_server(pool).await;
+    let plan_id = create_plan(&server, 0).await;
+    let (customer_id, token) = create_customer(&server, "4000111122223333").await;
+    let subscription = subscribe(&server, &plan_id, &customer_id, &token).await;
+    let subscription_id = subscription["subscription_id"].as_str().unwrap();
+    let response = server
+        .delete(&format!(
+            "/merchants/merchant123/customers/{customer_id}/payment-methods/{token}"
+        ))
+        .await;
+    assert_eq!(response.status_code(), 204);
+
+    for (failed_attempts, retry_in_days) in [(1, 1), (2, 3), (3, 7)] {
+        assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+        let subscription = get_subscription(&server, subscription_id).await;
+        assert_eq!(subscription["status"], "PAST_DUE");
+        assert_eq!(subscription["failed_attempts"], failed_attempts);
+        clock.advance(TimeDelta::days(retry_in_days));
+    }
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    let subscription = get_subscription(&server, subscription_id).await;
+    assert_eq!(subscription["status"], "UNPAID");
+    assert_eq!(subscription.get("last_transaction"), None);
+    clock.advance(TimeDelta::days(365));
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 0);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn interrupted_charges_are_not_made_again(pool: sqlx::PgPool) {
+    let (server, app_state, clock) = create_scheduled_server(pool.clone()).await;
+    let plan_id = create_plan(&server, 0).await;
+    let (customer_id, token) = create_customer(&server, "4000111122223333").await;
+    let subscription = subscribe(&server, &plan_id, &customer_id, &token).await;
+    let subscription_id = subscription["subscription_id"].as_str().unwrap();
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    let charged = get_subscription(&server, subscription_id).await;
+
+    // as if the scheduler stopped after charging, before the subscription was saved
+    sqlx::query(
+        "UPDATE account.subscriptions SET next_charge_at = $1, last_transaction = NULL, \
+         claimed_until = $2",
+    )
+    .bind(start())
+    .bind(start() + TimeDelta::minutes(5))
+    .execute(&pool)
+    .await
+    .unwrap();
+    // it's claimed until the lease runs out
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 0);
+    clock.advance(TimeDelta::minutes(5));
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    let subscription = get_subscription(&server, subscription_id).await;
+    assert_eq!(subscription, charged);
+    let response = server.get("/merchants/merchant123/transactions").await;
+    let transactions = response.json::<Value>()["transactions"].clone();
+    assert_eq!(transactions.as_array().unwrap().len(), 1);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn charges_interrupted_before_the_acquirer_answered_are_not_recorded(pool: sqlx::PgPool) {
+    let (server, app_state, clock) = create_scheduled_server(pool.clone()).await;
+    let plan_id = create_plan(&server, 0).await;
+    let (customer_id, token) = create_customer(&server, "4000111122223333").await;
+    let subscription = subscribe(&server, &plan_id, &customer_id, &token).await;
+    let subscription_id = subscription["subscription_id"].as_str().unwrap();
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    let response = server.get("/merchants/merchant123/transactions").await;
+    let transaction = response.json::<Value>()["transactions"][0].clone();
+    // the scheduler's key doesn't take the merchant's reference
+    assert!(transaction.get("merchant_reference").is_none());
+
+    // as if the scheduler stopped after storing the charge, before the acquirer answered
+    sqlx::query("UPDATE transaction.transactions SET processed_at = NULL")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "UPDATE account.subscriptions SET next_charge_at = $1, last_transaction = NULL, \
+         claimed_until = NULL",
+    )
+    .bind(start())
+    .execute(&pool)
+    .await
+    .unwrap();
+    let before = get_subscription(&server, subscription_id).await;
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    // neither recorded as paid nor charged again
+    assert_eq!(get_subscription(&server, subscription_id).await, before);
+    let response = server.get("/merchants/merchant123/transactions").await;
+    let transactions = response.json::<Value>()["transactions"].clone();
+    assert_eq!(transactions.as_array().unwrap().len(), 1);
+    // and left claimed until the lease runs out, when it's flagged again
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 0);
+    clock.advance(TimeDelta::minutes(5));
+    assert_eq!(charge_due_subscriptions(&app_state).await.unwrap(), 1);
+    assert_eq!(get_subscription(&server, subscription_id).await, before);
+}
@@ -0,0 +1,230 @@
+mod common;
+use std::sync::Arc;
+
+use axum::http::header::AUTHORIZATION;
+use axum_test::TestServer;
+use chrono::{TimeDelta, Utc};
+use common::{create_api_key, create_request, create_server};
+use gw_api::{
+    app::{create_appstate, create_router, AppState},
+    scheduler::expire_challenges,
+};
+use gw_core::{
+    clock::MockClock,
+    repo::Pool,
+    three_ds::{CHALLENGE_TIMEOUT_SECS, SIMULATED_ACS_URL},
+};
+use serde_json::{json, Value};
+
+/// A merchant123 server, sharing its payment method key with every other one made by this
+async fn create_instance(pool: sqlx::PgPool, api_key: &str) -> (TestServer, AppState) {
+    let app_state = create_appstate(Pool::from(pool));
+    app_state.lock().await.payment_method_key = "payment-method-key".into();
+    let mut server = TestServer::new(create_router(app_state.clone())).unwrap();
+    server.add_header(AUTHORIZATION, format!("Bearer {api_key}"));
+    (server, app_state)
+}
+
+async fn post_transaction(server: &TestServer, pan: &str) -> Value {
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![
+            ("payment.pan", pan).into(),
+            ("options", json!({"three_ds": true})).into(),
+        ]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()
+}
+
+async fn stored_three_ds(pool: &sqlx::PgPool, reference: &str) -> (String, Option<String>) {
+    sqlx::query_as(
+        "SELECT status, three_ds_status FROM transaction.transactions WHERE reference = $1",
+    )
+    .bind(reference)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn frictionless_authentication(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let transaction = post_transaction(&server, "4000111122223333").await;
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(transaction["three_ds"]["status"], "AUTHENTICATED");
+    assert_eq!(transaction["three_ds"]["eci"], "05");
+    assert!(transaction["three_ds"].get("cavv").is_none());
+    let reference = transaction["reference"].as_str().unwrap();
+    assert_eq!(
+        stored_three_ds(&pool, reference).await,
+        ("SUCCESS".into(), Some("Y".into()))
+    );
+
+    let transaction = post_transaction(&server, "4000111122220003").await;
+    assert_eq!(transaction["status"], "FAILED");
+    assert_eq!(transaction["error"], "AUTHENTICATION_FAILED");
+    assert_eq!(transaction["three_ds"]["status"], "NOT_AUTHENTICATED");
+    assert_eq!(transaction["three_ds"]["eci"], "07");
+    // failed authentications never reach the acquirer
+    assert!(transaction.get("cvv").is_none());
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn three_ds_is_opt_in(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![
+            ("payment.pan", "4000111122220003").into()
+        ]))
+        .await;
+    let transaction = response.json::<Value>();
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert!(transaction.get("three_ds").is_none());
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn challenge_flow(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let transaction = post_transaction(&server, "4000111100000002").await;
+    assert_eq!(transaction["status"], "PENDING_3DS");
+    assert_eq!(transaction["three_ds"]["status"], "CHALLENGE_REQUIRED");
+    assert!(transaction.get("cvv").is_none());
+    let reference = transaction["reference"].as_str().unwrap();
+    assert_eq!(
+        stored_three_ds(&pool, reference).await,
+        ("PENDING_3DS".into(), Some("C".into()))
+    );
+
+    let response = server
+        .get(&format!("/transactions/{reference}/3ds/challenge"))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let challenge = response.json::<Value>();
+    assert_eq!(challenge["reference"], reference);
+    assert_eq!(challenge["acs_url"], SIMULATED_ACS_URL);
+    assert_eq!(
+        challenge["ds_transaction_id"],
+        transaction["three_ds"]["ds_transaction_id"]
+    );
+    assert!(!challenge["creq"].as_str().unwrap().is_empty());
+
+    let response = server
+        .post(&format!("/transactions/{reference}/3ds/result"))
+        .json(&json!({"cres": "Y"}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let transaction = response.json::<Value>();
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert_eq!(transaction["three_ds"]["status"], "AUTHENTICATED");
+    assert_eq!(transaction["three_ds"]["eci"], "05");
+    // the security code isn't kept while the cardholder is challenged, so it isn't checked
+    assert!(transaction.get("cvv").is_none());
+    assert_eq!(
+        stored_three_ds(&pool, reference).await,
+        ("SUCCESS".into(), Some("Y".into()))
+    );
+
+    // a challenge can only be completed once
+    let response = server
+        .post(&format!("/transactions/{reference}/3ds/result"))
+        .json(&json!({"cres": "Y"}))
+        .await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn failed_challenge(pool: sqlx::PgPool) {
+    let server = create_server(pool.clone()).await;
+    let transaction = post_transaction(&server, "4000111100000002").await;
+    let reference = transaction["reference"].as_str().unwrap();
+    let response = server
+        .post(&format!("/transactions/{reference}/3ds/result"))
+        .json(&json!({"cres": "N"}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let transaction = response.json::<Value>();
+    assert_eq!(transaction["status"], "FAILED");
+    assert_eq!(transaction["error"], "AUTHENTICATION_FAILED");
+    assert_eq!(
+        stored_three_ds(&pool, reference).await,
+        ("FAILED".into(), Some("N".into()))
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn unknown_challenges_are_not_found(pool: sqlx::PgPool) {
+    let server = create_server(pool).await;
+    let response = server.get("/transactions/missing/3ds/challenge").await;
+    assert_eq!(response.status_code(), 404);
+    let response = server
+        .post("/transactions/missing/3ds/result")
+        .json(&json!({"cres": "Y"}))
+        .await;
+    assert_eq!(response.status_code(), 404);
+    assert_eq!(
+        response.json::<Value>(),
+        json!({"error": "RESOURCE", "message": "transaction missing is not waiting on a challenge"})
+    );
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn challenges_can_be_completed_on_another_instance(pool: sqlx::PgPool) {
+    let api_key = create_api_key(pool.clone(), "merchant123").await;
+    let (server, _) = create_instance(pool.clone(), &api_key).await;
+    let transaction = post_transaction(&server, "4000111100000002").await;
+    let reference = transaction["reference"].as_str().unwrap();
+    let sealed: String = sqlx::query_scalar(
+        "SELECT sealed_card FROM transaction.pending_authentications WHERE reference = $1",
+    )
+    .bind(reference)
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert!(!sealed.contains("4000111100000002"));
+
+    let (other, _) = create_instance(pool.clone(), &api_key).await;
+    let response = other
+        .get(&format!("/transactions/{reference}/3ds/challenge"))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let response = other
+        .post(&format!("/transactions/{reference}/3ds/result"))
+        .json(&json!({"cres": "Y"}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let transaction = response.json::<Value>();
+    assert_eq!(transaction["status"], "SUCCESS");
+    assert!(transaction.get("cvv").is_none());
+    let pending: i64 =
+        sqlx::query_scalar("SELECT count(*) FROM transaction.pending_authentications")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!(pending, 0);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn expired_challenges_fail_their_transactions(pool: sqlx::PgPool) {
+    let api_key = create_api_key(pool.clone(), "merchant123").await;
+    let (server, app_state) = create_instance(pool.clone(), &api_key).await;
+    let clock = Arc::new(MockClock::new(Utc::now()));
+    app_state.lock().await.clock = clock.clone();
+    let transaction = post_transaction(&server, "4000111100000002").await;
+    let reference = transaction["reference"].as_str().unwrap();
+    assert_eq!(expire_challenges(&app_state).await.unwrap(), 0);
+
+    clock.advance(TimeDelta::seconds(CHALLENGE_TIMEOUT_SECS + 1));
+    assert_eq!(expire_challenges(&app_state).await.unwrap(), 1);
+    assert_eq!(
+        stored_three_ds(&pool, reference).await,
+        ("FAILED".into(), Some("C".into()))
+    );
+    let response = server
+        .post(&format!("/transactions/{reference}/3ds/result"))
+        .json(&json!({"cres": "Y"}))
+        .await;
+    assert_eq!(response.status_code(), 404);
+    assert_eq!(expire_challenges(&app_state).await.unwrap(), 0);
+}
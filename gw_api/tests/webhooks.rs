@@ -0,0 +1,286 @@
+mod common;
+use std::sync::Arc;
+
+use axum::http::header::AUTHORIZATION;
+use axum_test::TestServer;
+use chrono::{TimeDelta, TimeZone, Utc};
+use common::{create_admin_server, create_api_key, create_request, create_staff_key};
+use gw_api::{
+    app::{create_appstate, create_router, AppState},
+    webhooks::deliver_due_events,
+};
+use gw_core::{
+    api_key::Role, clock::MockClock, repo::Pool, secret::Secret, signing::SignedRequest,
+    webhook::MAX_ATTEMPTS,
+};
+use serde_json::{json, Value};
+use wiremock::{
+    matchers::{method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// A merchant123 server and an admin server sharing an app, whose events are delivered by the
+/// clock returned with them
+async fn create_webhook_servers(
+    pool: sqlx::PgPool,
+) -> (TestServer, TestServer, AppState, Arc<MockClock>) {
+    let api_key = create_api_key(pool.clone(), "merchant123").await;
+    let staff_key = create_staff_key(pool.clone(), Role::Admin).await;
+    let app_state = create_appstate(Pool::from(pool));
+    let clock = Arc::new(MockClock::new(
+        Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap(),
+    ));
+    app_state.lock().await.clock = clock.clone();
+    let mut server = TestServer::new(create_router(app_state.clone())).unwrap();
+    server.add_header(AUTHORIZATION, format!("Bearer {api_key}"));
+    let mut admin = TestServer::new(create_router(app_state.clone())).unwrap();
+    admin.add_header(AUTHORIZATION, format!("Bearer {staff_key}"));
+    (server, admin, app_state, clock)
+}
+
+/// Points merchant123's webhook at the receiver, returning the signing secret
+async fn set_endpoint(admin: &TestServer, receiver: &MockServer) -> String {
+    let response = admin
+        .put("/merchants/merchant123/webhook")
+        .json(&json!({"url": format!("{}/hooks", receiver.uri())}))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    response.json::<Value>()["signing_secret"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+async fn respond_with(receiver: &MockServer, status: u16) {
+    receiver.reset().await;
+    Mock::given(method("POST"))
+        .and(path("/hooks"))
+        .respond_with(ResponseTemplate::new(status))
+        .mount(receiver)
+        .await;
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn webhook_endpoints_are_configured(pool: sqlx::PgPool) {
+    let server = create_admin_server(pool).await;
+    let response = server.get("/merchants/merchant123/webhook").await;
+    assert_eq!(response.status_code(), 404);
+
+    let response = server
+        .put("/merchants/merchant123/webhook")
+        .json(&json!({"url": "https://example.com/hooks"}))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    let created = response.json::<Value>();
+    assert_eq!(created["url"], "https://example.com/hooks");
+    assert_eq!(created["signing_secret"].as_str().unwrap().len(), 64);
+
+    // moving the endpoint keeps the secret, so it isn't returned again
+    let response = server
+        .put("/merchants/merchant123/webhook")
+        .json(&json!({"url": "https://example.com/events?source=gateway"}))
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let moved = response.json::<Value>();
+    assert_eq!(moved.get("signing_secret"), None);
+    assert_eq!(moved["created_at"], created["created_at"]);
+    let response = server.get("/merchants/merchant123/webhook").await;
+    assert_eq!(
+        response.json::<Value>(),
+        json!({
+            "url": "https://example.com/events?source=gateway",
+            "created_at": created["created_at"]
+        })
+    );
+
+    for url in ["example.com/hooks", "ftp://example.com/hooks", "https://"] {
+        let response = server
+            .put("/merchants/merchant123/webhook")
+            .json(&json!({"url": url}))
+            .await;
+        assert_eq!(response.status_code(), 400, "{url}");
+    }
+    let response = server
+        .put("/merchants/missing/webhook")
+        .json(&json!({"url": "https://example.com/hooks"}))
+        .await;
+    assert_eq!(response.status_code(), 404);
+
+    let response = server.delete("/merchants/merchant123/webhook").await;
+    assert_eq!(response.status_code(), 204);
+    let response = server.delete("/merchants/merchant123/webhook").await;
+    assert_eq!(response.status_code(), 404);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn transaction_events_are_signed_and_delivered(pool: sqlx::PgPool) {
+    let (server, admin, app_state, _) = create_webhook_servers(pool).await;
+    let receiver = MockServer::start().await;
+    respond_with(&receiver, 200).await;
+
+    // merchants without an endpoint aren't sent events
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    assert_eq!(deliver_due_events(&app_state).await.unwrap(), 0);
+
+    let secret = set_endpoint(&admin, &receiver).await;
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![]))
+        .await;
+    let transaction = response.json::<Value>();
+    assert_eq!(deliver_due_events(&app_state).await.unwrap(), 1);
+    assert_eq!(deliver_due_events(&app_state).await.unwrap(), 0);
+
+    let requests = receiver.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    let request = &requests[0];
+    let event = request.body_json::<Value>().unwrap();
+    assert_eq!(event["event_type"], "TRANSACTION_SUCCEEDED");
+    assert_eq!(event["data"], transaction);
+    let header = |name: &str| request.headers[name].to_str().unwrap().to_string();
+    assert_eq!(header("x-signature-nonce"), event["event_id"]);
+    assert_eq!(header("content-type"), "application/json");
+    let signed = SignedRequest {
+        method: "POST",
+        path: "/hooks",
+        timestamp: header("x-signature-timestamp").parse().unwrap(),
+        nonce: &header("x-signature-nonce"),
+        body: &request.body,
+    };
+    assert!(signed.verify(&Secret::new(secret), &header("x-signature")));
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn failed_deliveries_are_retried_then_dead_lettered(pool: sqlx::PgPool) {
+    let (server, admin, app_state, clock) = create_webhook_servers(pool).await;
+    let receiver = MockServer::start().await;
+    respond_with(&receiver, 500).await;
+    set_endpoint(&admin, &receiver).await;
+    server
+        .post("/transaction")
+        .json(&create_request(vec![]))
+        .await;
+
+    assert_eq!(deliver_due_events(&app_state).await.unwrap(), 1);
+    // the first retry is 30 seconds later, then each waits twice as long as the last
+    for wait in [30, 60, 120] {
+        clock.advance(TimeDelta::seconds(wait - 1));
+        assert_eq!(deliver_due_events(&app_state).await.unwrap(), 0);
+        clock.advance(TimeDelta::seconds(1));
+        assert_eq!(deliver_due_events(&app_state).await.unwrap(), 1);
+    }
+    let response = server
+        .get("/merchants/merchant123/webhook-events/dead")
+        .await;
+    assert_eq!(response.json::<Value>(), json!([]));
+    for _ in 4..MAX_ATTEMPTS {
+        clock.advance(TimeDelta::hours(6));
+        assert_eq!(deliver_due_events(&app_state).await.unwrap(), 1);
+    }
+    clock.advance(TimeDelta::days(1));
+    assert_eq!(deliver_due_events(&app_state).await.unwrap(), 0);
+    let requests = receiver.received_requests().await.unwrap();
+    assert_eq!(requests.len(), MAX_ATTEMPTS as usize);
+    // every attempt is the same event
+    assert!(requests.iter().all(|r| r.body == requests[0].body));
+
+    let response = server
+        .get("/merchants/merchant123/webhook-events/dead")
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let dead = response.json::<Value>();
+    assert_eq!(dead.as_array().unwrap().len(), 1);
+    assert_eq!(dead[0]["status"], "DEAD");
+    assert_eq!(dead[0]["attempts"], MAX_ATTEMPTS);
+    assert_eq!(dead[0]["last_error"], "HTTP 500");
+    assert_eq!(dead[0]["event_type"], "TRANSACTION_SUCCEEDED");
+    let event_id = dead[0]["event_id"].as_str().unwrap();
+
+    // once the receiver is fixed, the event can be sent again
+    respond_with(&receiver, 200).await;
+    let retry = format!("/merchants/merchant123/webhook-events/{event_id}/retry");
+    let response = admin.post(&retry).await;
+    assert_eq!(response.status_code(), 200);
+    assert_eq!(response.json::<Value>()["status"], "PENDING");
+    assert_eq!(response.json::<Value>()["attempts"], 0);
+    let response = admin.post(&retry).await;
+    assert_eq!(response.status_code(), 409);
+    assert_eq!(deliver_due_events(&app_state).await.unwrap(), 1);
+    assert_eq!(receiver.received_requests().await.unwrap().len(), 1);
+    let response = server
+        .get("/merchants/merchant123/webhook-events/dead")
+        .await;
+    assert_eq!(response.json::<Value>(), json!([]));
+
+    let response = admin
+        .post("/merchants/merchant123/webhook-events/missing/retry")
+        .await;
+    assert_eq!(response.status_code(), 404);
+    let response = server
+        .get("/merchants/merchant123/webhook-events/dead?limit=0")
+        .await;
+    assert_eq!(response.status_code(), 400);
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn support_sees_masked_dead_events(pool: sqlx::PgPool) {
+    let support_key = create_staff_key(pool.clone(), Role::Support).await;
+    let (server, admin, app_state, clock) = create_webhook_servers(pool).await;
+    let mut support = TestServer::new(create_router(app_state.clone())).unwrap();
+    support.add_header(AUTHORIZATION, format!("Bearer {support_key}"));
+    let receiver = MockServer::start().await;
+    respond_with(&receiver, 500).await;
+    set_endpoint(&admin, &receiver).await;
+    server
+        .post("/transaction")
+        .json(&create_request(vec![
+            ("billing.first_name", "Jane").into(),
+            ("billing.street", "Downing Street").into(),
+            ("description", "Flowers for Jane").into(),
+        ]))
+        .await;
+    for _ in 0..MAX_ATTEMPTS {
+        assert_eq!(deliver_due_events(&app_state).await.unwrap(), 1);
+        clock.advance(TimeDelta::days(1));
+    }
+    let response = support
+        .get("/merchants/merchant123/webhook-events/dead")
+        .await;
+    assert_eq!(response.status_code(), 200);
+    let data = &response.json::<Value>()[0]["data"];
+    assert_eq!(data["billing"]["first_name"], "J###");
+    assert_eq!(data["billing"]["street"], "D#############");
+    assert_eq!(data["billing"]["country"], "GB");
+    assert_eq!(data["description"], "F###############");
+    assert_eq!(data["payment"]["pan"], "400011######3333");
+    // admins see the event as it was queued
+    let response = admin
+        .get("/merchants/merchant123/webhook-events/dead")
+        .await;
+    let data = &response.json::<Value>()[0]["data"];
+    assert_eq!(data["billing"]["first_name"], "Jane");
+    assert_eq!(data["description"], "Flowers for Jane");
+}
+
+#[sqlx::test(migrations = "../gw_core/migrations")]
+async fn authorised_transactions_succeed_when_events_cant_be_queued(pool: sqlx::PgPool) {
+    let (server, admin, _, _) = create_webhook_servers(pool.clone()).await;
+    let receiver = MockServer::start().await;
+    set_endpoint(&admin, &receiver).await;
+    sqlx::query("ALTER TABLE transaction.webhook_events RENAME TO webhook_events_gone")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    // the acquirer has the payment, so a retry would charge the cardholder again
+    let response = server
+        .post("/transaction")
+        .json(&create_request(vec![]))
+        .await;
+    assert_eq!(response.status_code(), 201);
+    assert_eq!(response.json::<Value>()["status"], "SUCCESS");
+}
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS transaction.idempotency (
+    merchant_id varchar(255) NOT NULL,
+    idempotency_key varchar(255) NOT NULL,
+    request_hash TEXT NOT NULL,
+    status_code INTEGER,
+    response_body TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (merchant_id, idempotency_key)
+);
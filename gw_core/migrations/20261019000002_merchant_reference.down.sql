@@ -0,0 +1,14 @@
+DROP INDEX transaction.bankone_merchant_reference_idx;
+DROP INDEX transaction.banktwo_merchant_reference_idx;
+DROP INDEX transaction.bankone_metadata_idx;
+DROP INDEX transaction.banktwo_metadata_idx;
+
+ALTER TABLE transaction.bankone
+    DROP COLUMN merchant_reference,
+    DROP COLUMN description,
+    DROP COLUMN metadata;
+
+ALTER TABLE transaction.banktwo
+    DROP COLUMN merchant_reference,
+    DROP COLUMN description,
+    DROP COLUMN metadata;
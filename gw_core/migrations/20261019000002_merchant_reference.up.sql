@@ -0,0 +1,14 @@
+ALTER TABLE transaction.bankone
+    ADD COLUMN merchant_reference TEXT,
+    ADD COLUMN description TEXT,
+    ADD COLUMN metadata JSONB NOT NULL DEFAULT '{}';
+
+ALTER TABLE transaction.banktwo
+    ADD COLUMN merchant_reference TEXT,
+    ADD COLUMN description TEXT,
+    ADD COLUMN metadata JSONB NOT NULL DEFAULT '{}';
+
+CREATE INDEX bankone_merchant_reference_idx ON transaction.bankone (merchant_id, merchant_reference);
+CREATE INDEX banktwo_merchant_reference_idx ON transaction.banktwo (merchant_id, merchant_reference);
+CREATE INDEX bankone_metadata_idx ON transaction.bankone USING GIN (metadata);
+CREATE INDEX banktwo_metadata_idx ON transaction.banktwo USING GIN (metadata);
@@ -0,0 +1,12 @@
+DROP INDEX transaction.bankone_merchant_created_idx;
+DROP INDEX transaction.banktwo_merchant_created_idx;
+DROP INDEX transaction.bankone_created_idx;
+DROP INDEX transaction.banktwo_created_idx;
+
+ALTER TABLE transaction.bankone
+    DROP COLUMN status,
+    DROP COLUMN created_at;
+
+ALTER TABLE transaction.banktwo
+    DROP COLUMN status,
+    DROP COLUMN created_at;
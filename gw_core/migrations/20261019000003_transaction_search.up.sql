@@ -0,0 +1,12 @@
+ALTER TABLE transaction.bankone
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'SUCCESS',
+    ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+ALTER TABLE transaction.banktwo
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'SUCCESS',
+    ADD COLUMN created_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+CREATE INDEX bankone_merchant_created_idx ON transaction.bankone (merchant_id, created_at DESC, reference DESC);
+CREATE INDEX banktwo_merchant_created_idx ON transaction.banktwo (merchant_id, created_at DESC, reference DESC);
+CREATE INDEX bankone_created_idx ON transaction.bankone (created_at DESC, reference DESC);
+CREATE INDEX banktwo_created_idx ON transaction.banktwo (created_at DESC, reference DESC);
@@ -0,0 +1,90 @@
+CREATE TABLE IF NOT EXISTS transaction.bankone (
+    reference TEXT PRIMARY KEY,
+    transaction_type TEXT NOT NULL,
+    merchant_id varchar(255) REFERENCES account.merchant NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    card_scheme TEXT default '',
+    encrypted_pan TEXT,
+    masked_pan TEXT,
+    expiry_date TEXT DEFAULT '',
+    billing_name TEXT DEFAULT '',
+    billing_premise TEXT DEFAULT '',
+    billing_street TEXT DEFAULT '',
+    billing_city TEXT DEFAULT '',
+    billing_country TEXT DEFAULT '',
+    billing_county TEXT DEFAULT 'GB',
+    customer_name TEXT DEFAULT '',
+    customer_premise TEXT DEFAULT '',
+    customer_street TEXT DEFAULT '',
+    customer_city TEXT DEFAULT '',
+    customer_country TEXT DEFAULT '',
+    customer_county TEXT DEFAULT 'GB',
+
+    merchant_identification_value TEXT NOT NULL,
+    merchant_reference TEXT,
+    description TEXT,
+    metadata JSONB NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'SUCCESS',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE TABLE IF NOT EXISTS transaction.banktwo (
+    reference TEXT PRIMARY KEY,
+    transaction_type TEXT NOT NULL,
+    merchant_id varchar(255) REFERENCES account.merchant NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    card_scheme TEXT default '',
+    encrypted_pan TEXT,
+    masked_pan TEXT,
+    expiry_date TEXT DEFAULT '',
+    billing_name TEXT DEFAULT '',
+    billing_premise TEXT DEFAULT '',
+    billing_street TEXT DEFAULT '',
+    billing_city TEXT DEFAULT '',
+    billing_country TEXT DEFAULT '',
+    billing_county TEXT DEFAULT 'GB',
+    customer_name TEXT DEFAULT '',
+    customer_premise TEXT DEFAULT '',
+    customer_street TEXT DEFAULT '',
+    customer_city TEXT DEFAULT '',
+    customer_country TEXT DEFAULT '',
+    customer_county TEXT DEFAULT 'GB',
+
+    banktwo_merchant_id TEXT NOT NULL,
+    merchant_reference TEXT,
+    description TEXT,
+    metadata JSONB NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'SUCCESS',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+INSERT INTO transaction.bankone
+SELECT reference, transaction_type, merchant_id, amount, currency, card_scheme, encrypted_pan,
+    masked_pan, expiry_date, billing_name, billing_premise, billing_street, billing_city,
+    billing_country, billing_county, customer_name, customer_premise, customer_street,
+    customer_city, customer_country, customer_county,
+    acquirer_data->>'merchant_identification_value',
+    merchant_reference, description, metadata, status, created_at
+FROM transaction.transactions WHERE acquirer = 'bankone';
+
+INSERT INTO transaction.banktwo
+SELECT reference, transaction_type, merchant_id, amount, currency, card_scheme, encrypted_pan,
+    masked_pan, expiry_date, billing_name, billing_premise, billing_street, billing_city,
+    billing_country, billing_county, customer_name, customer_premise, customer_street,
+    customer_city, customer_country, customer_county,
+    acquirer_data->>'merchant_reference',
+    merchant_reference, description, metadata, status, created_at
+FROM transaction.transactions WHERE acquirer = 'banktwo';
+
+DROP TABLE transaction.transactions;
+
+CREATE INDEX bankone_merchant_reference_idx ON transaction.bankone (merchant_id, merchant_reference);
+CREATE INDEX banktwo_merchant_reference_idx ON transaction.banktwo (merchant_id, merchant_reference);
+CREATE INDEX bankone_metadata_idx ON transaction.bankone USING GIN (metadata);
+CREATE INDEX banktwo_metadata_idx ON transaction.banktwo USING GIN (metadata);
+CREATE INDEX bankone_merchant_created_idx ON transaction.bankone (merchant_id, created_at DESC, reference DESC);
+CREATE INDEX banktwo_merchant_created_idx ON transaction.banktwo (merchant_id, created_at DESC, reference DESC);
+CREATE INDEX bankone_created_idx ON transaction.bankone (created_at DESC, reference DESC);
+CREATE INDEX banktwo_created_idx ON transaction.banktwo (created_at DESC, reference DESC);
@@ -0,0 +1,58 @@
+-- One table for every acquirer's transactions, anything acquirer specific lives in acquirer_data
+CREATE TABLE IF NOT EXISTS transaction.transactions (
+    reference TEXT PRIMARY KEY,
+    transaction_type TEXT NOT NULL,
+    merchant_id varchar(255) REFERENCES account.merchant NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    card_scheme TEXT default '',
+    encrypted_pan TEXT,
+    masked_pan TEXT,
+    expiry_date TEXT DEFAULT '',
+    billing_name TEXT DEFAULT '',
+    billing_premise TEXT DEFAULT '',
+    billing_street TEXT DEFAULT '',
+    billing_city TEXT DEFAULT '',
+    billing_country TEXT DEFAULT '',
+    billing_county TEXT DEFAULT 'GB',
+    customer_name TEXT DEFAULT '',
+    customer_premise TEXT DEFAULT '',
+    customer_street TEXT DEFAULT '',
+    customer_city TEXT DEFAULT '',
+    customer_country TEXT DEFAULT '',
+    customer_county TEXT DEFAULT 'GB',
+    acquirer TEXT NOT NULL,
+    acquirer_data JSONB NOT NULL DEFAULT '{}',
+    merchant_reference TEXT,
+    description TEXT,
+    metadata JSONB NOT NULL DEFAULT '{}',
+    status TEXT NOT NULL DEFAULT 'SUCCESS',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+INSERT INTO transaction.transactions
+SELECT reference, transaction_type, merchant_id, amount, currency, card_scheme, encrypted_pan,
+    masked_pan, expiry_date, billing_name, billing_premise, billing_street, billing_city,
+    billing_country, billing_county, customer_name, customer_premise, customer_street,
+    customer_city, customer_country, customer_county, 'bankone',
+    jsonb_build_object('merchant_identification_value', merchant_identification_value),
+    merchant_reference, description, metadata, status, created_at
+FROM transaction.bankone;
+
+INSERT INTO transaction.transactions
+SELECT reference, transaction_type, merchant_id, amount, currency, card_scheme, encrypted_pan,
+    masked_pan, expiry_date, billing_name, billing_premise, billing_street, billing_city,
+    billing_country, billing_county, customer_name, customer_premise, customer_street,
+    customer_city, customer_country, customer_county, 'banktwo',
+    jsonb_build_object('merchant_reference', banktwo_merchant_id),
+    merchant_reference, description, metadata, status, created_at
+FROM transaction.banktwo;
+
+DROP TABLE transaction.bankone;
+DROP TABLE transaction.banktwo;
+
+CREATE INDEX transactions_merchant_reference_idx ON transaction.transactions (merchant_id, merchant_reference);
+CREATE INDEX transactions_metadata_idx ON transaction.transactions USING GIN (metadata);
+CREATE INDEX transactions_merchant_created_idx ON transaction.transactions (merchant_id, created_at DESC, reference DESC);
+CREATE INDEX transactions_created_idx ON transaction.transactions (created_at DESC, reference DESC);
+CREATE INDEX transactions_acquirer_idx ON transaction.transactions (acquirer);
@@ -0,0 +1,7 @@
+ALTER TABLE account.bankone
+    ALTER COLUMN id DROP IDENTITY,
+    ALTER COLUMN merchant_identification_value DROP NOT NULL;
+
+ALTER TABLE account.banktwo
+    ALTER COLUMN id DROP IDENTITY,
+    ALTER COLUMN banktwo_merchant_id DROP NOT NULL;
@@ -0,0 +1,11 @@
+-- account ids were never generated, so accounts could only be created by hand
+ALTER TABLE account.bankone
+    ALTER COLUMN id ADD GENERATED BY DEFAULT AS IDENTITY,
+    ALTER COLUMN merchant_identification_value SET NOT NULL;
+
+ALTER TABLE account.banktwo
+    ALTER COLUMN id ADD GENERATED BY DEFAULT AS IDENTITY,
+    ALTER COLUMN banktwo_merchant_id SET NOT NULL;
+
+SELECT setval(pg_get_serial_sequence('account.bankone', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM account.bankone;
+SELECT setval(pg_get_serial_sequence('account.banktwo', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM account.banktwo;
@@ -0,0 +1 @@
+ALTER TABLE account.merchant DROP COLUMN active;
@@ -0,0 +1,2 @@
+-- merchants are deactivated rather than deleted so their transactions are kept
+ALTER TABLE account.merchant ADD COLUMN active BOOLEAN NOT NULL DEFAULT TRUE;
@@ -0,0 +1,2 @@
+ALTER TABLE account.bankone DROP COLUMN merchant_id;
+ALTER TABLE account.banktwo DROP COLUMN merchant_id;
@@ -0,0 +1,11 @@
+-- accounts belong to a merchant, so routes can only use the merchant's own accounts
+ALTER TABLE account.bankone ADD COLUMN merchant_id varchar(255) REFERENCES account.merchant;
+ALTER TABLE account.banktwo ADD COLUMN merchant_id varchar(255) REFERENCES account.merchant;
+
+UPDATE account.bankone a SET merchant_id = r.merchant_id
+FROM account.paymentroute r WHERE r.acquirer = 'bankone' AND r.account_id = a.id;
+UPDATE account.banktwo a SET merchant_id = r.merchant_id
+FROM account.paymentroute r WHERE r.acquirer = 'banktwo' AND r.account_id = a.id;
+
+CREATE INDEX bankone_merchant_idx ON account.bankone (merchant_id);
+CREATE INDEX banktwo_merchant_idx ON account.banktwo (merchant_id);
@@ -0,0 +1,11 @@
+-- only a hash of each key's secret is kept, the key itself is shown once when it is created
+CREATE TABLE IF NOT EXISTS account.api_key (
+    key_id TEXT PRIMARY KEY,
+    merchant_id varchar(255) REFERENCES account.merchant NOT NULL,
+    secret_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    expires_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+CREATE INDEX api_key_merchant_idx ON account.api_key (merchant_id);
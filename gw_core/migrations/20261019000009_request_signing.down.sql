@@ -0,0 +1,2 @@
+DROP TABLE account.signature_nonce;
+DROP TABLE account.signing_secret;
@@ -0,0 +1,17 @@
+-- merchants with a signing secret must sign every request, the secret is needed to check
+-- signatures so it can't be hashed like API keys are
+CREATE TABLE IF NOT EXISTS account.signing_secret (
+    merchant_id varchar(255) PRIMARY KEY REFERENCES account.merchant,
+    secret TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- nonces are only kept for as long as their timestamp would be accepted
+CREATE TABLE IF NOT EXISTS account.signature_nonce (
+    merchant_id varchar(255) NOT NULL,
+    nonce TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (merchant_id, nonce)
+);
+
+CREATE INDEX signature_nonce_expires_idx ON account.signature_nonce (expires_at);
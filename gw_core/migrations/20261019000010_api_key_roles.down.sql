@@ -0,0 +1,6 @@
+DELETE FROM account.api_key WHERE role <> 'merchant';
+
+ALTER TABLE account.api_key
+    DROP CONSTRAINT api_key_merchant_role,
+    ALTER COLUMN merchant_id SET NOT NULL,
+    DROP COLUMN role;
@@ -0,0 +1,5 @@
+-- staff keys aren't tied to a merchant, merchant keys always are
+ALTER TABLE account.api_key
+    ADD COLUMN role TEXT NOT NULL DEFAULT 'merchant' CHECK (role IN ('merchant', 'support', 'admin')),
+    ALTER COLUMN merchant_id DROP NOT NULL,
+    ADD CONSTRAINT api_key_merchant_role CHECK ((role = 'merchant') = (merchant_id IS NOT NULL));
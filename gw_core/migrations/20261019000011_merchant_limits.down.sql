@@ -0,0 +1,5 @@
+DROP INDEX IF EXISTS transaction.transactions_card_fingerprint_idx;
+
+ALTER TABLE transaction.transactions DROP COLUMN card_fingerprint;
+
+DROP TABLE IF EXISTS account.merchant_limits;
@@ -0,0 +1,13 @@
+-- a missing row, or a NULL limit, means the gateway's default is used
+CREATE TABLE IF NOT EXISTS account.merchant_limits (
+    merchant_id varchar(255) PRIMARY KEY REFERENCES account.merchant,
+    requests_per_minute INTEGER CHECK (requests_per_minute > 0),
+    max_card_transactions_per_hour INTEGER CHECK (max_card_transactions_per_hour > 0),
+    max_card_amount_per_hour BIGINT CHECK (max_card_amount_per_hour > 0)
+);
+
+-- a keyed hash of the pan, so a card's recent transactions can be found without storing it
+ALTER TABLE transaction.transactions ADD COLUMN card_fingerprint TEXT;
+
+CREATE INDEX transactions_card_fingerprint_idx
+    ON transaction.transactions (merchant_id, card_fingerprint, created_at);
@@ -0,0 +1,8 @@
+ALTER TABLE transaction.transactions DROP COLUMN fraud_rules;
+ALTER TABLE transaction.transactions DROP COLUMN fraud_decision;
+ALTER TABLE transaction.transactions DROP COLUMN fraud_score;
+
+DROP TABLE IF EXISTS fraud.card_bins;
+DROP TABLE IF EXISTS fraud.merchant_rules;
+
+DROP SCHEMA IF EXISTS fraud;
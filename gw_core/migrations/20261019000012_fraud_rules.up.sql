@@ -0,0 +1,19 @@
+CREATE SCHEMA IF NOT EXISTS fraud;
+
+-- a merchant without a row isn't screened
+CREATE TABLE IF NOT EXISTS fraud.merchant_rules (
+    merchant_id varchar(255) PRIMARY KEY REFERENCES account.merchant,
+    review_score INTEGER NOT NULL CHECK (review_score > 0),
+    reject_score INTEGER NOT NULL CHECK (reject_score > 0),
+    rules JSONB NOT NULL DEFAULT '[]'
+);
+
+-- loaded from the card schemes' bin tables, the longest matching prefix of a pan wins
+CREATE TABLE IF NOT EXISTS fraud.card_bins (
+    bin TEXT PRIMARY KEY CHECK (bin ~ '^[0-9]{4,11}$'),
+    country char(2) NOT NULL
+);
+
+ALTER TABLE transaction.transactions ADD COLUMN fraud_score INTEGER;
+ALTER TABLE transaction.transactions ADD COLUMN fraud_decision TEXT;
+ALTER TABLE transaction.transactions ADD COLUMN fraud_rules JSONB;
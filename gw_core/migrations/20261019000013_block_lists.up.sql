@@ -0,0 +1,14 @@
+-- entries without a merchant apply to every merchant, a NULL expires_at never expires
+CREATE TABLE IF NOT EXISTS fraud.list_entries (
+    id TEXT PRIMARY KEY,
+    list TEXT NOT NULL CHECK (list IN ('block', 'allow')),
+    merchant_id varchar(255) REFERENCES account.merchant,
+    kind TEXT NOT NULL CHECK (kind IN ('card', 'bin', 'email', 'ip')),
+    value TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX list_entries_value_idx ON fraud.list_entries (kind, value);
+CREATE INDEX list_entries_merchant_idx ON fraud.list_entries (merchant_id);
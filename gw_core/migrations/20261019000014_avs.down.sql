@@ -0,0 +1,4 @@
+ALTER TABLE transaction.transactions DROP COLUMN avs_result;
+ALTER TABLE transaction.transactions DROP COLUMN billing_postcode;
+
+DROP TABLE IF EXISTS account.merchant_policies;
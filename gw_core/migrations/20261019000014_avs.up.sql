@@ -0,0 +1,10 @@
+-- a missing row means every policy is off
+CREATE TABLE IF NOT EXISTS account.merchant_policies (
+    merchant_id varchar(255) PRIMARY KEY REFERENCES account.merchant,
+    decline_avs_address_mismatch BOOLEAN NOT NULL DEFAULT FALSE,
+    decline_avs_postcode_mismatch BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+ALTER TABLE transaction.transactions ADD COLUMN billing_postcode TEXT;
+-- the acquirer's single character AVS result code
+ALTER TABLE transaction.transactions ADD COLUMN avs_result char(1);
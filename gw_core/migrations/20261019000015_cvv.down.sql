@@ -0,0 +1,3 @@
+ALTER TABLE transaction.transactions DROP COLUMN cvv_result;
+
+ALTER TABLE account.merchant_policies DROP COLUMN void_on_cvv_mismatch;
@@ -0,0 +1,5 @@
+ALTER TABLE account.merchant_policies
+    ADD COLUMN void_on_cvv_mismatch BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- M, N or P for match, no match and not processed
+ALTER TABLE transaction.transactions ADD COLUMN cvv_result char(1);
@@ -0,0 +1,3 @@
+ALTER TABLE transaction.transactions DROP COLUMN three_ds_ds_transaction_id;
+ALTER TABLE transaction.transactions DROP COLUMN three_ds_eci;
+ALTER TABLE transaction.transactions DROP COLUMN three_ds_status;
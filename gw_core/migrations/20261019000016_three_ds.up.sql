@@ -0,0 +1,5 @@
+-- the 3DS transStatus, with the ECI and directory server transaction ID sent to the acquirer.
+-- The CAVV is only ever passed on to the acquirer.
+ALTER TABLE transaction.transactions ADD COLUMN three_ds_status char(1);
+ALTER TABLE transaction.transactions ADD COLUMN three_ds_eci char(2);
+ALTER TABLE transaction.transactions ADD COLUMN three_ds_ds_transaction_id TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE transaction.transactions DROP COLUMN sca_exemption;
+DROP TABLE IF EXISTS account.sca_thresholds;
@@ -0,0 +1,13 @@
+-- the highest amount each SCA exemption is asked for, a NULL means it's never asked for
+CREATE TABLE IF NOT EXISTS account.sca_thresholds (
+    merchant_id varchar(255) NOT NULL REFERENCES account.merchant,
+    currency TEXT NOT NULL,
+    low_value BIGINT,
+    transaction_risk_analysis BIGINT,
+    merchant_initiated BIGINT,
+    recurring BIGINT,
+    corporate BIGINT,
+    PRIMARY KEY (merchant_id, currency)
+);
+
+ALTER TABLE transaction.transactions ADD COLUMN sca_exemption TEXT;
@@ -0,0 +1,4 @@
+ALTER TABLE transaction.transactions DROP COLUMN payment_token;
+ALTER TABLE transaction.transactions DROP COLUMN customer_id;
+DROP TABLE IF EXISTS account.payment_methods;
+DROP TABLE IF EXISTS account.customers;
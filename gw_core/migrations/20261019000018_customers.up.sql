@@ -0,0 +1,31 @@
+CREATE TABLE IF NOT EXISTS account.customers (
+    id TEXT PRIMARY KEY,
+    merchant_id varchar(255) NOT NULL REFERENCES account.merchant,
+    first_name TEXT NOT NULL DEFAULT '',
+    last_name TEXT NOT NULL DEFAULT '',
+    email TEXT NOT NULL DEFAULT '',
+    phone TEXT NOT NULL DEFAULT '',
+    addresses JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX customers_merchant_idx ON account.customers (merchant_id, created_at);
+
+-- the card or account details are only stored encrypted, security codes are never stored
+CREATE TABLE IF NOT EXISTS account.payment_methods (
+    token TEXT PRIMARY KEY,
+    customer_id TEXT NOT NULL REFERENCES account.customers ON DELETE CASCADE,
+    merchant_id varchar(255) NOT NULL REFERENCES account.merchant,
+    card_scheme TEXT,
+    masked_number TEXT NOT NULL,
+    expiry_year INTEGER,
+    expiry_month SMALLINT,
+    card_fingerprint TEXT,
+    sealed TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX payment_methods_customer_idx ON account.payment_methods (customer_id);
+
+ALTER TABLE transaction.transactions ADD COLUMN customer_id TEXT;
+ALTER TABLE transaction.transactions ADD COLUMN payment_token TEXT;
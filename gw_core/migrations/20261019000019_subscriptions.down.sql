@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS account.subscriptions;
+DROP TABLE IF EXISTS account.plans;
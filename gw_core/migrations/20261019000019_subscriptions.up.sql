@@ -0,0 +1,33 @@
+CREATE TABLE IF NOT EXISTS account.plans (
+    id TEXT PRIMARY KEY,
+    merchant_id varchar(255) NOT NULL REFERENCES account.merchant,
+    name TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    currency TEXT NOT NULL,
+    billing_interval TEXT NOT NULL,
+    interval_count INTEGER NOT NULL,
+    trial_days INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX plans_merchant_idx ON account.plans (merchant_id, created_at);
+
+-- retry_at is only set while a declined charge is waiting to be retried
+CREATE TABLE IF NOT EXISTS account.subscriptions (
+    id TEXT PRIMARY KEY,
+    merchant_id varchar(255) NOT NULL REFERENCES account.merchant,
+    plan_id TEXT NOT NULL REFERENCES account.plans,
+    customer_id TEXT NOT NULL REFERENCES account.customers ON DELETE CASCADE,
+    payment_token TEXT NOT NULL,
+    status TEXT NOT NULL,
+    next_charge_at TIMESTAMPTZ NOT NULL,
+    retry_at TIMESTAMPTZ,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    last_transaction TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    cancelled_at TIMESTAMPTZ
+);
+
+CREATE INDEX subscriptions_merchant_idx ON account.subscriptions (merchant_id, created_at);
+CREATE INDEX subscriptions_due_idx ON account.subscriptions (COALESCE(retry_at, next_charge_at))
+    WHERE status IN ('TRIALING', 'ACTIVE', 'PAST_DUE');
@@ -0,0 +1,6 @@
+ALTER TABLE account.payment_methods DROP COLUMN scheme_transaction_id;
+
+ALTER TABLE transaction.transactions DROP COLUMN scheme_transaction_id;
+ALTER TABLE transaction.transactions DROP COLUMN original_scheme_transaction_id;
+ALTER TABLE transaction.transactions DROP COLUMN stored_credential_initiator;
+ALTER TABLE transaction.transactions DROP COLUMN stored_credential_usage;
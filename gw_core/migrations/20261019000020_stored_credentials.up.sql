@@ -0,0 +1,8 @@
+ALTER TABLE transaction.transactions ADD COLUMN stored_credential_usage TEXT;
+ALTER TABLE transaction.transactions ADD COLUMN stored_credential_initiator TEXT;
+ALTER TABLE transaction.transactions ADD COLUMN original_scheme_transaction_id TEXT;
+ALTER TABLE transaction.transactions ADD COLUMN scheme_transaction_id TEXT;
+
+-- the scheme transaction ID of the initial transaction made with the payment method, later
+-- merchant initiated transactions are chained to it
+ALTER TABLE account.payment_methods ADD COLUMN scheme_transaction_id TEXT;
@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS transaction.webhook_events;
+DROP TABLE IF EXISTS account.webhook_endpoints;
@@ -0,0 +1,26 @@
+-- the secret signs the events sent to the endpoint, so it can't be hashed like API keys are
+CREATE TABLE IF NOT EXISTS account.webhook_endpoints (
+    merchant_id varchar(255) PRIMARY KEY REFERENCES account.merchant,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- the outbox, events stay here once they've been delivered or dead-lettered
+CREATE TABLE IF NOT EXISTS transaction.webhook_events (
+    id TEXT PRIMARY KEY,
+    merchant_id varchar(255) NOT NULL REFERENCES account.merchant,
+    event_type TEXT NOT NULL,
+    data JSONB NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    next_attempt_at TIMESTAMPTZ NOT NULL,
+    last_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    delivered_at TIMESTAMPTZ
+);
+
+CREATE INDEX webhook_events_due_idx ON transaction.webhook_events (next_attempt_at)
+    WHERE status = 'PENDING';
+CREATE INDEX webhook_events_merchant_idx
+    ON transaction.webhook_events (merchant_id, status, created_at);
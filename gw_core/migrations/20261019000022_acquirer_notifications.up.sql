@@ -0,0 +1,16 @@
+-- every notification an acquirer pushes is kept with what was done about it, the acquirer's id
+-- for it makes redeliveries no-ops
+CREATE TABLE IF NOT EXISTS transaction.acquirer_notifications (
+    acquirer TEXT NOT NULL,
+    notification_id TEXT NOT NULL,
+    transaction_reference TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    raw TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    detail TEXT,
+    received_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (acquirer, notification_id)
+);
+
+CREATE INDEX acquirer_notifications_transaction_idx
+    ON transaction.acquirer_notifications (transaction_reference, received_at);
@@ -0,0 +1,3 @@
+DROP TABLE IF EXISTS transaction.adjustments;
+DROP TABLE IF EXISTS transaction.dispute_evidence;
+DROP TABLE IF EXISTS transaction.disputes;
@@ -0,0 +1,49 @@
+-- the acquirer's case id makes disputes it reports more than once the same dispute
+CREATE TABLE IF NOT EXISTS transaction.disputes (
+    id TEXT PRIMARY KEY,
+    merchant_id varchar(255) NOT NULL REFERENCES account.merchant,
+    transaction_reference TEXT NOT NULL,
+    acquirer TEXT NOT NULL,
+    case_id TEXT NOT NULL,
+    scheme TEXT NOT NULL,
+    reason_code TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    currency TEXT NOT NULL,
+    status TEXT NOT NULL,
+    respond_by TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    submitted_at TIMESTAMPTZ,
+    resolved_at TIMESTAMPTZ,
+    UNIQUE (acquirer, case_id)
+);
+
+CREATE INDEX disputes_merchant_idx ON transaction.disputes (merchant_id, created_at);
+
+-- only what the merchant says about each file, the files go to the acquirer
+CREATE TABLE IF NOT EXISTS transaction.dispute_evidence (
+    id TEXT PRIMARY KEY,
+    dispute_id TEXT NOT NULL REFERENCES transaction.disputes ON DELETE CASCADE,
+    evidence_type TEXT NOT NULL,
+    file_name TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    description TEXT,
+    uploaded_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX dispute_evidence_dispute_idx ON transaction.dispute_evidence (dispute_id, uploaded_at);
+
+-- a transaction's net amount is its amount plus the sum of its adjustments, which are negative
+-- when funds are taken from the merchant
+CREATE TABLE IF NOT EXISTS transaction.adjustments (
+    id TEXT PRIMARY KEY,
+    transaction_reference TEXT NOT NULL,
+    merchant_id varchar(255) NOT NULL REFERENCES account.merchant,
+    dispute_id TEXT REFERENCES transaction.disputes ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX adjustments_transaction_idx ON transaction.adjustments (transaction_reference, created_at);
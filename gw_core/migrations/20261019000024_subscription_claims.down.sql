@@ -0,0 +1 @@
+ALTER TABLE account.subscriptions DROP COLUMN claimed_until;
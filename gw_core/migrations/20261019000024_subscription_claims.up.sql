@@ -0,0 +1,2 @@
+-- set while a scheduler is charging the subscription, so no other one charges it too
+ALTER TABLE account.subscriptions ADD COLUMN claimed_until TIMESTAMPTZ;
@@ -0,0 +1,16 @@
+-- transactions waiting on the cardholder to complete a 3DS challenge. The card is needed to
+-- authorise the transaction once it's done, so it's sealed with the payment method key. Its
+-- security code is never stored. The row is deleted when the challenge is completed or expires.
+CREATE TABLE IF NOT EXISTS transaction.pending_authentications (
+    reference TEXT PRIMARY KEY REFERENCES transaction.transactions ON DELETE CASCADE,
+    merchant_id varchar(255) NOT NULL,
+    sealed_card TEXT NOT NULL,
+    ds_transaction_id TEXT NOT NULL,
+    acs_transaction_id TEXT NOT NULL,
+    acs_url TEXT NOT NULL,
+    creq TEXT NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX pending_authentications_expires_at_idx
+    ON transaction.pending_authentications (expires_at);
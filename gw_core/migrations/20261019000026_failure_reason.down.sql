@@ -0,0 +1 @@
+ALTER TABLE transaction.transactions DROP COLUMN failure_reason;
@@ -0,0 +1,2 @@
+-- why a failed transaction failed, e.g. FRAUD_REJECTED, null for every other status
+ALTER TABLE transaction.transactions ADD COLUMN failure_reason TEXT;
@@ -0,0 +1,2 @@
+ALTER TABLE transaction.idempotency DROP COLUMN renewed_at;
+ALTER TABLE transaction.idempotency DROP COLUMN owner;
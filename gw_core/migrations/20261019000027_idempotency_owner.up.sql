@@ -0,0 +1,4 @@
+-- the request holding a key renews it while it's being processed, so a retry only takes over
+-- a key whose request has stopped renewing it, and only the owner can store its response
+ALTER TABLE transaction.idempotency ADD COLUMN owner TEXT;
+ALTER TABLE transaction.idempotency ADD COLUMN renewed_at TIMESTAMPTZ NOT NULL DEFAULT now();
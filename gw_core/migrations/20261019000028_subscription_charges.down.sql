@@ -0,0 +1,3 @@
+ALTER TABLE transaction.transactions DROP COLUMN processed_at;
+DROP INDEX transaction.transactions_subscription_charge_idx;
+ALTER TABLE transaction.transactions DROP COLUMN subscription_charge;
@@ -0,0 +1,9 @@
+-- the scheduler's key for the subscription charge a transaction was made for, kept apart from
+-- the merchant's reference, so a charge can only be made once
+ALTER TABLE transaction.transactions ADD COLUMN subscription_charge TEXT;
+CREATE UNIQUE INDEX transactions_subscription_charge_idx
+    ON transaction.transactions (merchant_id, subscription_charge);
+-- set once a transaction's outcome is known, a transaction stored as a success without it was
+-- interrupted before the acquirer answered, and may or may not have been authorised
+ALTER TABLE transaction.transactions ADD COLUMN processed_at TIMESTAMPTZ;
+UPDATE transaction.transactions SET processed_at = created_at;
@@ -1,11 +1,12 @@
-use sqlx::{postgres::PgArguments, query::Query, Postgres};
+use serde::Serialize;
+use serde_json::json;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct BankOneAccount {
     pub merchant_identification_value: String,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct BankTwoAccount {
     pub merchant_reference: String,
 }
@@ -16,15 +17,33 @@ pub enum AcquirerAccount {
     BankTwo(BankTwoAccount),
 }
 
+/// An acquirer account along with its id and the merchant who owns it
+#[derive(Debug, PartialEq)]
+pub struct MerchantAccount {
+    pub id: i32,
+    pub merchant_id: String,
+    pub account: AcquirerAccount,
+}
+
 impl AcquirerAccount {
-    pub fn get_db_values_str(&self) -> String {
-        todo!()
+    /// The acquirer's name, which transactions are stored against
+    pub fn acquirer(&self) -> &'static str {
+        match self {
+            AcquirerAccount::BankOne(..) => "bankone",
+            AcquirerAccount::BankTwo(..) => "banktwo",
+        }
     }
-    pub fn bind_to(&self, stmt: Query<Postgres, PgArguments>) -> Query<Postgres, PgArguments> {
-        todo!()
+
+    /// The acquirer specific details which are stored alongside each transaction
+    pub fn acquirer_data(&self) -> serde_json::Value {
+        match self {
+            AcquirerAccount::BankOne(acct) => json!(acct),
+            AcquirerAccount::BankTwo(acct) => json!(acct),
+        }
     }
 }
 
+#[allow(dead_code)] // no acquirer connectors use these yet
 trait Iso8853<'a> {
     fn merchant_id(&'a self) -> &'a str;
 }
@@ -35,6 +54,7 @@ impl<'a> Iso8853<'a> for BankOneAccount {
     }
 }
 
+#[allow(dead_code)]
 trait Apacs30<'a> {
     fn merchant_id(&'a self) -> &'a str;
 }
@@ -59,4 +79,16 @@ mod tests {
             assert_eq!(<BankOneAccount as Apacs30>::merchant_id(&acct), "1");
         }
     }
+
+    #[test]
+    fn acquirer_data() {
+        let acq_acct = AcquirerAccount::BankTwo(BankTwoAccount {
+            merchant_reference: "456".into(),
+        });
+        assert_eq!(acq_acct.acquirer(), "banktwo");
+        assert_eq!(
+            acq_acct.acquirer_data(),
+            json!({"merchant_reference": "456"})
+        );
+    }
 }
@@ -0,0 +1,365 @@
+use crate::{
+    avs::{AvsData, AvsResult},
+    cvv::CvvResult,
+    error::Error,
+    payment::Payment,
+    policy::MerchantPolicies,
+    sca::ScaExemption,
+    stored_credential::StoredCredential,
+    three_ds::ThreeDsResult,
+    transaction::{Transaction, TransactionError, TransactionStatus},
+};
+use uuid::Uuid;
+
+/// What is sent to the acquirer to authorise a transaction
+#[derive(Debug)]
+pub struct AuthorisationRequest<'a> {
+    pub transaction: &'a Transaction,
+    pub avs: AvsData,
+    /// The ECI, CAVV and directory server transaction ID from 3DS authentication
+    pub three_ds: Option<&'a ThreeDsResult>,
+    pub sca_exemption: Option<ScaExemption>,
+    /// The credential-on-file indicators, with the scheme transaction ID it's chained to
+    pub stored_credential: Option<&'a StoredCredential>,
+}
+
+impl<'a> From<&'a Transaction> for AuthorisationRequest<'a> {
+    fn from(value: &'a Transaction) -> Self {
+        Self {
+            transaction: value,
+            avs: AvsData::from(&value.billing),
+            three_ds: value.three_ds.as_ref(),
+            sca_exemption: value.sca_exemption,
+            stored_credential: value.stored_credential.as_ref(),
+        }
+    }
+}
+
+/// The acquirer's answer, with their result codes left as they sent them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AuthorisationResponse {
+    pub approved: bool,
+    pub avs_code: Option<String>,
+    pub cvv_code: Option<String>,
+    /// The issuer declined because the cardholder has to be authenticated, a soft decline
+    pub sca_required: bool,
+    /// The scheme's ID for the transaction, which later stored credential transactions are
+    /// chained to
+    pub scheme_transaction_id: Option<String>,
+}
+
+pub trait Acquirer {
+    #[allow(async_fn_in_trait)] // only using in own code
+    async fn authorise(
+        &self,
+        request: &AuthorisationRequest,
+    ) -> Result<AuthorisationResponse, Error>;
+
+    /// Cancels an approved authorisation so the funds aren't held on the card
+    #[allow(async_fn_in_trait)]
+    async fn void(&self, transaction: &Transaction) -> Result<(), Error>;
+}
+
+/// Stands in for the acquirers until there are real connections to them. Everything is
+/// approved, the address or postcode fail AVS when their numerics start with 99, and a security
+/// code of 999 doesn't match. Saved cards come without a security code, so it isn't checked.
+/// Exemptions for card numbers ending 0005 are soft declined. Approved cards are given a random
+/// 15 digit scheme transaction ID.
+#[derive(Debug, Default)]
+pub struct SimulatedAcquirer;
+
+impl Acquirer for SimulatedAcquirer {
+    async fn authorise(
+        &self,
+        request: &AuthorisationRequest<'_>,
+    ) -> Result<AuthorisationResponse, Error> {
+        let avs = &request.avs;
+        let avs_code = if avs.is_empty() {
+            "U"
+        } else {
+            match (
+                avs.address_numerics.starts_with("99"),
+                avs.postcode_numerics.starts_with("99"),
+            ) {
+                (false, false) => "Y",
+                (false, true) => "A",
+                (true, false) => "Z",
+                (true, true) => "N",
+            }
+        };
+        let cvv_code = match &request.transaction.payment {
+            Payment::Card { security_code, .. } if security_code.expose().is_empty() => None,
+            Payment::Card { security_code, .. } if security_code.expose() == "999" => Some("N"),
+            Payment::Card { .. } => Some("M"),
+            Payment::Account { .. } => None,
+        };
+        if let Payment::Card { pan, .. } = &request.transaction.payment {
+            if request.sca_exemption.is_some() && pan.ends_with("0005") {
+                return Ok(AuthorisationResponse {
+                    sca_required: true,
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(AuthorisationResponse {
+            approved: true,
+            avs_code: Some(avs_code.into()),
+            cvv_code: cvv_code.map(String::from),
+            sca_required: false,
+            scheme_transaction_id: matches!(request.transaction.payment, Payment::Card { .. })
+                .then(|| format!("{:015}", Uuid::new_v4().as_u128() % 1_000_000_000_000_000)),
+        })
+    }
+
+    async fn void(&self, _transaction: &Transaction) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl Transaction {
+    /// Records the acquirer's response on the transaction, failing it if the acquirer declined
+    /// or the merchant's policies decline the results of the acquirer's checks. Returns whether
+    /// the acquirer approved an authorisation that has been failed, so needs voiding.
+    pub fn apply_authorisation(
+        &mut self,
+        response: AuthorisationResponse,
+        policies: &MerchantPolicies,
+    ) -> Result<bool, Error> {
+        self.avs = response.avs_code.map(AvsResult::try_from).transpose()?;
+        self.cvv = response.cvv_code.map(CvvResult::try_from).transpose()?;
+        self.scheme_transaction_id = response.scheme_transaction_id;
+        if !response.approved {
+            let error = if response.sca_required {
+                TransactionError::ScaRequired
+            } else {
+                TransactionError::Declined
+            };
+            self.status = TransactionStatus::Failed(Some(error));
+            return Ok(false);
+        }
+        let error = if self
+            .avs
+            .as_ref()
+            .is_some_and(|avs| policies.avs.declines(avs))
+        {
+            TransactionError::AvsMismatch
+        } else if self.cvv.is_some_and(|cvv| policies.cvv.voids(cvv)) {
+            TransactionError::CvvMismatch
+        } else {
+            return Ok(false);
+        };
+        self.status = TransactionStatus::Failed(Some(error));
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::{AcquirerAccount, BankOneAccount},
+        avs::AvsPolicy,
+        billing::Billing,
+        card_scheme::CardScheme,
+        currency::Currency,
+        cvv::CvvPolicy,
+        merchant::Merchant,
+        payment::Payment,
+        transaction::{transaction_builder::TransactionBuilder, TransactionType},
+    };
+    use rstest::*;
+
+    fn transaction(premise: &str, postcode: &str) -> Transaction {
+        TransactionBuilder::new()
+            .transaction_type(TransactionType::Auth)
+            .amount(12345)
+            .currency(Currency::GBP)
+            .payment(Payment::from((
+                CardScheme::Visa,
+                (2030, 1),
+                "123",
+                "4000111122223333",
+            )))
+            .billing(Billing {
+                premise: premise.into(),
+                postcode: postcode.into(),
+                ..Default::default()
+            })
+            .merchant(Merchant::default())
+            .account(AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: "12345678".into(),
+            }))
+            .build()
+    }
+
+    #[rstest]
+    #[case("10", "LS1 4AB", "Y")]
+    #[case("10", "LS99 4AB", "A")]
+    #[case("99", "LS1 4AB", "Z")]
+    #[case("99", "99", "N")]
+    #[case("", "", "U")]
+    #[tokio::test]
+    async fn simulated_avs_codes(
+        #[case] premise: &str,
+        #[case] postcode: &str,
+        #[case] code: &str,
+    ) {
+        let trx = transaction(premise, postcode);
+        let response = SimulatedAcquirer
+            .authorise(&AuthorisationRequest::from(&trx))
+            .await
+            .unwrap();
+        assert!(response.approved);
+        assert_eq!(response.avs_code.as_deref(), Some(code));
+    }
+
+    #[rstest]
+    #[case(Some("M"), false, TransactionStatus::Success)]
+    #[case(Some("N"), false, TransactionStatus::Success)]
+    #[case(Some("M"), true, TransactionStatus::Success)]
+    #[case(Some("P"), true, TransactionStatus::Success)]
+    #[case(None, true, TransactionStatus::Success)]
+    #[case(
+        Some("N"),
+        true,
+        TransactionStatus::Failed(Some(TransactionError::CvvMismatch))
+    )]
+    fn cvv_mismatches_are_voided(
+        #[case] cvv_code: Option<&str>,
+        #[case] void_on_mismatch: bool,
+        #[case] status: TransactionStatus,
+    ) {
+        let mut trx = transaction("10", "LS1 4AB");
+        let policies = MerchantPolicies {
+            cvv: CvvPolicy { void_on_mismatch },
+            ..MerchantPolicies::new("merchant123".into())
+        };
+        let response = AuthorisationResponse {
+            approved: true,
+            avs_code: None,
+            cvv_code: cvv_code.map(String::from),
+            ..Default::default()
+        };
+        let void = trx.apply_authorisation(response, &policies).unwrap();
+        assert_eq!(void, status != TransactionStatus::Success);
+        assert_eq!(trx.status, status);
+        assert_eq!(trx.cvv.map(|cvv| cvv.to_string()).as_deref(), cvv_code);
+    }
+
+    #[rstest]
+    #[case("123", Some("M"))]
+    #[case("999", Some("N"))]
+    #[case("", None)]
+    #[tokio::test]
+    async fn simulated_cvv_codes(#[case] security_code: &str, #[case] code: Option<&str>) {
+        let mut trx = transaction("10", "LS1 4AB");
+        trx.payment = Payment::from((
+            CardScheme::Visa,
+            (2030, 1),
+            security_code,
+            "4000111122223333",
+        ));
+        let response = SimulatedAcquirer
+            .authorise(&AuthorisationRequest::from(&trx))
+            .await
+            .unwrap();
+        assert_eq!(response.cvv_code.as_deref(), code);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn simulated_scheme_transaction_ids() {
+        let mut trx = transaction("10", "LS1 4AB");
+        let response = SimulatedAcquirer
+            .authorise(&AuthorisationRequest::from(&trx))
+            .await
+            .unwrap();
+        let id = response.scheme_transaction_id.clone().unwrap();
+        assert!(id.len() == 15 && id.chars().all(|c| c.is_ascii_digit()));
+        let policies = MerchantPolicies::new("merchant123".into());
+        trx.apply_authorisation(response, &policies).unwrap();
+        assert_eq!(trx.scheme_transaction_id, Some(id));
+        trx.payment = Payment::Account {
+            account_number: "12345678".into(),
+            sort_code: "010203".into(),
+        };
+        let response = SimulatedAcquirer
+            .authorise(&AuthorisationRequest::from(&trx))
+            .await
+            .unwrap();
+        assert_eq!(response.scheme_transaction_id, None);
+    }
+
+    #[rstest]
+    #[case(true, Some("Y"), AvsPolicy { decline_address_mismatch: true, decline_postcode_mismatch: true }, TransactionStatus::Success)]
+    #[case(true, Some("A"), AvsPolicy::default(), TransactionStatus::Success)]
+    #[case(true, Some("A"), AvsPolicy { decline_address_mismatch: false, decline_postcode_mismatch: true }, TransactionStatus::Failed(Some(TransactionError::AvsMismatch)))]
+    #[case(true, None, AvsPolicy { decline_address_mismatch: true, decline_postcode_mismatch: true }, TransactionStatus::Success)]
+    #[case(false, Some("N"), AvsPolicy { decline_address_mismatch: true, decline_postcode_mismatch: true }, TransactionStatus::Failed(Some(TransactionError::Declined)))]
+    fn apply_authorisation(
+        #[case] approved: bool,
+        #[case] avs_code: Option<&str>,
+        #[case] avs: AvsPolicy,
+        #[case] status: TransactionStatus,
+    ) {
+        let mut trx = transaction("10", "LS1 4AB");
+        let policies = MerchantPolicies {
+            avs,
+            ..MerchantPolicies::new("merchant123".into())
+        };
+        let response = AuthorisationResponse {
+            approved,
+            avs_code: avs_code.map(String::from),
+            ..Default::default()
+        };
+        let void = trx.apply_authorisation(response, &policies).unwrap();
+        assert_eq!(void, approved && status != TransactionStatus::Success);
+        assert_eq!(trx.status, status);
+        assert_eq!(
+            trx.avs.map(|avs| avs.code().to_string()).as_deref(),
+            avs_code
+        );
+    }
+
+    #[rstest]
+    fn unknown_avs_codes_are_errors() {
+        let mut trx = transaction("10", "LS1 4AB");
+        let response = AuthorisationResponse {
+            approved: true,
+            avs_code: Some("?".into()),
+            ..Default::default()
+        };
+        let policies = MerchantPolicies::new("merchant123".into());
+        assert!(trx.apply_authorisation(response, &policies).is_err());
+        assert_eq!(trx.avs, None);
+    }
+
+    #[rstest]
+    #[case("4000111122220005", None, false)]
+    #[case("4000111122220005", Some(ScaExemption::LowValue), true)]
+    #[case("4000111122223333", Some(ScaExemption::LowValue), false)]
+    #[tokio::test]
+    async fn simulated_soft_declines(
+        #[case] pan: &str,
+        #[case] sca_exemption: Option<ScaExemption>,
+        #[case] soft_declined: bool,
+    ) {
+        let mut trx = transaction("10", "LS1 4AB");
+        trx.payment = Payment::from((CardScheme::Visa, (2030, 1), "123", pan));
+        trx.sca_exemption = sca_exemption;
+        let response = SimulatedAcquirer
+            .authorise(&AuthorisationRequest::from(&trx))
+            .await
+            .unwrap();
+        assert_eq!(response.approved, !soft_declined);
+        assert_eq!(response.sca_required, soft_declined);
+        let policies = MerchantPolicies::new("merchant123".into());
+        assert!(!trx.apply_authorisation(response, &policies).unwrap());
+        assert_eq!(trx.needs_authentication_retry(), soft_declined);
+        if soft_declined {
+            trx.retry_with_authentication();
+            assert_eq!(trx.status, TransactionStatus::Success);
+            assert_eq!(trx.sca_exemption, None);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    error::{Error, ErrorKind},
+    secret::Secret,
+};
+
+/// Every API key starts with this, so leaked keys are easy to search for
+pub const KEY_PREFIX: &str = "gw_";
+
+/// Who a key belongs to, which decides what it is allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Belongs to a single merchant
+    Merchant,
+    /// Gateway staff helping merchants, who can look at every merchant's data
+    Support,
+    /// Gateway staff who set merchants up
+    Admin,
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let r = match self {
+            Role::Merchant => "merchant",
+            Role::Support => "support",
+            Role::Admin => "admin",
+        };
+        write!(f, "{r}")
+    }
+}
+
+impl TryFrom<String> for Role {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Role, Self::Error> {
+        match value.as_str() {
+            "merchant" => Ok(Self::Merchant),
+            "support" => Ok(Self::Support),
+            "admin" => Ok(Self::Admin),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised role"),
+            }),
+        }
+    }
+}
+
+/// An API credential. Keys look like `gw_{key_id}.{secret}`, the key id is used to look the key
+/// up and only a hash of the secret is stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKey {
+    pub key_id: String,
+    /// Only merchant keys have a merchant, staff keys can act across all of them
+    pub merchant_id: Option<String>,
+    pub role: Role,
+    pub secret_hash: String,
+    pub created_at: DateTime<Utc>,
+    /// Set when the key is rotated, so the old key keeps working while callers switch over
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    /// Creates a new key for the merchant, returning it along with the full key to hand to them
+    pub fn generate(merchant_id: &str) -> (ApiKey, Secret<String>) {
+        Self::new(Some(merchant_id.into()), Role::Merchant)
+    }
+
+    /// Creates a new key for a member of staff, returning it along with the full key
+    pub fn generate_staff(role: Role) -> Result<(ApiKey, Secret<String>), Error> {
+        if role == Role::Merchant {
+            return Err(Error {
+                kind: ErrorKind::Type,
+                message: "staff keys can't have the merchant role".into(),
+            });
+        }
+        Ok(Self::new(None, role))
+    }
+
+    fn new(merchant_id: Option<String>, role: Role) -> (ApiKey, Secret<String>) {
+        let key_id = Uuid::new_v4().simple().to_string();
+        let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let key = ApiKey {
+            key_id: key_id.clone(),
+            merchant_id,
+            role,
+            secret_hash: hash_secret(&secret),
+            created_at: Utc::now(),
+            expires_at: None,
+            revoked_at: None,
+        };
+        (key, Secret::new(format!("{KEY_PREFIX}{key_id}.{secret}")))
+    }
+
+    /// Whether the key can be used at the given time
+    pub fn is_active_at(&self, at: DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| at < expires_at)
+    }
+
+    /// Checks the secret against the stored hash in constant time
+    pub fn verify(&self, secret: &str) -> bool {
+        let hash = hash_secret(secret);
+        hash.len() == self.secret_hash.len()
+            && hash
+                .bytes()
+                .zip(self.secret_hash.bytes())
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+}
+
+/// Splits a full key into its key id and secret
+pub fn parse_key(key: &str) -> Option<(&str, &str)> {
+    key.strip_prefix(KEY_PREFIX)?
+        .split_once('.')
+        .filter(|(key_id, secret)| !key_id.is_empty() && !secret.is_empty())
+}
+
+pub fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn generate_and_verify() {
+        let (key, full_key) = ApiKey::generate("merchant123");
+        let (key_id, secret) = parse_key(full_key.expose()).unwrap();
+        assert_eq!(key_id, key.key_id);
+        assert!(key.verify(secret));
+        assert!(!key.verify("not the secret"));
+        assert!(!key.secret_hash.contains(secret));
+    }
+
+    #[test]
+    fn staff_keys_have_no_merchant() {
+        let (key, _) = ApiKey::generate_staff(Role::Support).unwrap();
+        assert_eq!(key.merchant_id, None);
+        assert_eq!(key.role, Role::Support);
+        let (key, _) = ApiKey::generate("merchant123");
+        assert_eq!(key.role, Role::Merchant);
+        let err = ApiKey::generate_staff(Role::Merchant).unwrap_err();
+        assert_eq!(err.message, "staff keys can't have the merchant role");
+        let err = Role::try_from("root".to_string()).unwrap_err();
+        assert_eq!(err.message, "root is not a recognised role");
+    }
+
+    #[test]
+    fn parse_invalid_keys() {
+        assert_eq!(parse_key("gw_abc.def"), Some(("abc", "def")));
+        assert_eq!(parse_key("abc.def"), None);
+        assert_eq!(parse_key("gw_abc"), None);
+        assert_eq!(parse_key("gw_.def"), None);
+        assert_eq!(parse_key("gw_abc."), None);
+    }
+
+    #[test]
+    fn active_until_expired_or_revoked() {
+        let now = Utc::now();
+        let (mut key, _) = ApiKey::generate("merchant123");
+        assert!(key.is_active_at(now));
+        key.expires_at = Some(now + TimeDelta::hours(1));
+        assert!(key.is_active_at(now));
+        assert!(!key.is_active_at(now + TimeDelta::hours(1)));
+        key.expires_at = None;
+        key.revoked_at = Some(now);
+        assert!(!key.is_active_at(now));
+    }
+}
@@ -0,0 +1,169 @@
+use serde::{Serialize, Serializer};
+
+use crate::{
+    billing::Billing,
+    error::{Error, ErrorKind},
+};
+
+/// Acquirers only compare the digits of the address and postcode, and only this many of each
+pub const MAX_AVS_DIGITS: usize = 5;
+
+/// The numeric parts of the billing address sent to the acquirer for address verification
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AvsData {
+    pub address_numerics: String,
+    pub postcode_numerics: String,
+}
+
+impl From<&Billing> for AvsData {
+    fn from(value: &Billing) -> Self {
+        let numerics = |s: &str| {
+            s.chars()
+                .filter(char::is_ascii_digit)
+                .take(MAX_AVS_DIGITS)
+                .collect::<String>()
+        };
+        Self {
+            address_numerics: numerics(&format!("{} {}", value.premise, value.street)),
+            postcode_numerics: numerics(&value.postcode),
+        }
+    }
+}
+
+impl AvsData {
+    pub fn is_empty(&self) -> bool {
+        self.address_numerics.is_empty() && self.postcode_numerics.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AvsCheck {
+    Match,
+    NoMatch,
+    NotChecked,
+}
+
+/// The acquirer's address verification result, kept as the code they sent back
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvsResult {
+    code: char,
+    pub address: AvsCheck,
+    pub postcode: AvsCheck,
+}
+
+impl AvsResult {
+    pub fn code(&self) -> char {
+        self.code
+    }
+}
+
+impl TryFrom<String> for AvsResult {
+    type Error = Error;
+
+    /// Parses the standard single character AVS result codes
+    fn try_from(value: String) -> Result<AvsResult, Self::Error> {
+        use AvsCheck::*;
+        let (address, postcode) = match value.as_str() {
+            "Y" | "M" | "D" | "F" | "X" => (Match, Match),
+            "A" | "B" => (Match, NoMatch),
+            "Z" | "P" | "W" => (NoMatch, Match),
+            "N" | "C" => (NoMatch, NoMatch),
+            "U" | "R" | "S" | "G" | "I" | "E" => (NotChecked, NotChecked),
+            invalid => {
+                return Err(Error {
+                    kind: ErrorKind::Type,
+                    message: format!("{invalid} is not a recognised AVS result code"),
+                })
+            }
+        };
+        Ok(AvsResult {
+            code: value.chars().next().expect("codes are one character"),
+            address,
+            postcode,
+        })
+    }
+}
+
+impl Serialize for AvsResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+        let mut s = serializer.serialize_struct("AvsResult", 3)?;
+        s.serialize_field("code", &self.code)?;
+        s.serialize_field("address", &self.address)?;
+        s.serialize_field("postcode", &self.postcode)?;
+        s.end()
+    }
+}
+
+/// Which AVS results a merchant wants declined, even when the acquirer approves the payment
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AvsPolicy {
+    pub decline_address_mismatch: bool,
+    pub decline_postcode_mismatch: bool,
+}
+
+impl AvsPolicy {
+    pub fn declines(&self, result: &AvsResult) -> bool {
+        (self.decline_address_mismatch && result.address == AvsCheck::NoMatch)
+            || (self.decline_postcode_mismatch && result.postcode == AvsCheck::NoMatch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("10", "Downing Street", "SW1A 2AA", "10", "12")]
+    #[case("Flat 3, 123", "456 Long Road", "AB12 3CD", "31234", "123")]
+    #[case("", "", "", "", "")]
+    fn numerics_from_billing(
+        #[case] premise: &str,
+        #[case] street: &str,
+        #[case] postcode: &str,
+        #[case] address_numerics: &str,
+        #[case] postcode_numerics: &str,
+    ) {
+        let billing = Billing {
+            premise: premise.into(),
+            street: street.into(),
+            postcode: postcode.into(),
+            ..Default::default()
+        };
+        let avs = AvsData::from(&billing);
+        assert_eq!(avs.address_numerics, address_numerics);
+        assert_eq!(avs.postcode_numerics, postcode_numerics);
+    }
+
+    #[rstest]
+    #[case("Y", AvsCheck::Match, AvsCheck::Match)]
+    #[case("A", AvsCheck::Match, AvsCheck::NoMatch)]
+    #[case("Z", AvsCheck::NoMatch, AvsCheck::Match)]
+    #[case("N", AvsCheck::NoMatch, AvsCheck::NoMatch)]
+    #[case("U", AvsCheck::NotChecked, AvsCheck::NotChecked)]
+    fn parse_codes(#[case] code: &str, #[case] address: AvsCheck, #[case] postcode: AvsCheck) {
+        let result = AvsResult::try_from(code.to_string()).unwrap();
+        assert_eq!(result.code().to_string(), code);
+        assert_eq!((result.address, result.postcode), (address, postcode));
+    }
+
+    #[rstest]
+    fn unknown_codes_are_errors() {
+        for code in ["", "YY", "Q"] {
+            assert!(AvsResult::try_from(code.to_string()).is_err());
+        }
+    }
+
+    #[rstest]
+    #[case(AvsPolicy::default(), "N", false)]
+    #[case(AvsPolicy { decline_address_mismatch: true, decline_postcode_mismatch: false }, "A", false)]
+    #[case(AvsPolicy { decline_address_mismatch: true, decline_postcode_mismatch: false }, "Z", true)]
+    #[case(AvsPolicy { decline_address_mismatch: false, decline_postcode_mismatch: true }, "A", true)]
+    #[case(AvsPolicy { decline_address_mismatch: true, decline_postcode_mismatch: true }, "U", false)]
+    fn policy_declines(#[case] policy: AvsPolicy, #[case] code: &str, #[case] declined: bool) {
+        let result = AvsResult::try_from(code.to_string()).unwrap();
+        assert_eq!(policy.declines(&result), declined);
+    }
+}
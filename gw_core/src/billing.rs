@@ -1,7 +1,7 @@
-use crate::country::Country;
+use crate::{country::Country, utils};
 use validify::Validify;
 
-#[derive(Default, PartialEq, Debug, Clone, Validify)]
+#[derive(Default, PartialEq, Clone, Validify)]
 pub struct Billing {
     #[modify(trim)]
     pub first_name: String,
@@ -15,5 +15,25 @@ pub struct Billing {
     pub city: String,
     #[modify(trim)]
     pub county: String,
+    #[modify(trim)]
+    pub postcode: String,
     pub country: Country,
+    #[modify(trim)]
+    pub email: String,
+}
+
+impl std::fmt::Debug for Billing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Billing")
+            .field("first_name", &utils::mask_name(&self.first_name))
+            .field("last_name", &utils::mask_name(&self.last_name))
+            .field("premise", &self.premise)
+            .field("street", &self.street)
+            .field("city", &self.city)
+            .field("county", &self.county)
+            .field("postcode", &self.postcode)
+            .field("country", &self.country)
+            .field("email", &utils::mask_name(&self.email))
+            .finish()
+    }
 }
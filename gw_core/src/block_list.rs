@@ -0,0 +1,231 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{Error, ErrorKind};
+
+/// Blocked entries decline any transaction they match, allowed entries let a transaction skip
+/// the merchant's fraud rules. A block always wins over an allow.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListType {
+    #[default]
+    Block,
+    Allow,
+}
+
+impl std::fmt::Display for ListType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let l = match self {
+            ListType::Block => "block",
+            ListType::Allow => "allow",
+        };
+        write!(f, "{l}")
+    }
+}
+
+impl TryFrom<String> for ListType {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<ListType, Self::Error> {
+        match value.as_str() {
+            "block" => Ok(Self::Block),
+            "allow" => Ok(Self::Allow),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised list"),
+            }),
+        }
+    }
+}
+
+/// What an entry's value is matched against
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    /// A card fingerprint, see [`crate::payment::Payment::card_fingerprint`]
+    Card,
+    /// The start of a pan
+    Bin,
+    Email,
+    Ip,
+}
+
+impl std::fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let k = match self {
+            EntryKind::Card => "card",
+            EntryKind::Bin => "bin",
+            EntryKind::Email => "email",
+            EntryKind::Ip => "ip",
+        };
+        write!(f, "{k}")
+    }
+}
+
+impl TryFrom<String> for EntryKind {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<EntryKind, Self::Error> {
+        match value.as_str() {
+            "card" => Ok(Self::Card),
+            "bin" => Ok(Self::Bin),
+            "email" => Ok(Self::Email),
+            "ip" => Ok(Self::Ip),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised list entry type"),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListEntry {
+    pub id: String,
+    pub list: ListType,
+    /// Entries without a merchant apply to every merchant
+    pub merchant_id: Option<String>,
+    pub kind: EntryKind,
+    /// Emails are lower case and ips are in their standard form, so they can be compared
+    pub value: String,
+    /// Why the entry was added, for ops rather than merchants
+    pub reason: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ListEntry {
+    pub fn new(
+        list: ListType,
+        merchant_id: Option<String>,
+        kind: EntryKind,
+        value: &str,
+        reason: String,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<ListEntry, Error> {
+        Ok(ListEntry {
+            id: Uuid::new_v4().simple().to_string(),
+            list,
+            merchant_id,
+            kind,
+            value: normalise(kind, value)?,
+            reason,
+            expires_at,
+            created_at: Utc::now(),
+        })
+    }
+}
+
+fn normalise(kind: EntryKind, value: &str) -> Result<String, Error> {
+    let value = value.trim();
+    let valid = match kind {
+        EntryKind::Card => value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit()),
+        EntryKind::Bin => {
+            (4..=11).contains(&value.len()) && value.chars().all(|c| c.is_ascii_digit())
+        }
+        EntryKind::Email => {
+            value.len() <= 255
+                && value
+                    .split_once('@')
+                    .is_some_and(|(user, domain)| !user.is_empty() && !domain.is_empty())
+        }
+        EntryKind::Ip => {
+            return value
+                .parse::<IpAddr>()
+                .map(|ip| ip.to_string())
+                .map_err(|_| Error {
+                    kind: ErrorKind::Type,
+                    message: format!("{value} is not a valid ip"),
+                })
+        }
+    };
+    if !valid {
+        return Err(Error {
+            kind: ErrorKind::Type,
+            message: format!("{value} is not a valid {kind}"),
+        });
+    }
+    Ok(value.to_lowercase())
+}
+
+/// The details of a transaction that are looked up in the lists
+#[derive(Debug, Default)]
+pub struct ListCheck<'a> {
+    pub card_fingerprint: Option<&'a str>,
+    pub pan: Option<&'a str>,
+    pub email: Option<&'a str>,
+    pub ip: Option<IpAddr>,
+}
+
+/// The entries a transaction matched
+#[derive(Debug, Default, PartialEq)]
+pub struct ListMatches {
+    pub entries: Vec<ListEntry>,
+}
+
+impl ListMatches {
+    pub fn blocked_by(&self) -> Option<&ListEntry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.list == ListType::Block)
+    }
+
+    pub fn allowed(&self) -> bool {
+        self.blocked_by().is_none()
+            && self
+                .entries
+                .iter()
+                .any(|entry| entry.list == ListType::Allow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case(EntryKind::Bin, "400011", Some("400011"))]
+    #[case(EntryKind::Bin, "400", None)]
+    #[case(EntryKind::Bin, "4000ab", None)]
+    #[case(EntryKind::Email, " Fraud@Example.com ", Some("fraud@example.com"))]
+    #[case(EntryKind::Email, "example.com", None)]
+    #[case(EntryKind::Email, "@example.com", None)]
+    #[case(EntryKind::Ip, "192.168.0.1", Some("192.168.0.1"))]
+    #[case(EntryKind::Ip, "2001:DB8:0:0::1", Some("2001:db8::1"))]
+    #[case(EntryKind::Ip, "300.1.1.1", None)]
+    #[case(
+        EntryKind::Card,
+        "0123456789ABCDEF0123456789abcdef0123456789ABCDEF0123456789abcdef",
+        Some("0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef")
+    )]
+    #[case(EntryKind::Card, "4000111122223333", None)]
+    fn values_are_normalised(
+        #[case] kind: EntryKind,
+        #[case] value: &str,
+        #[case] exp: Option<&str>,
+    ) {
+        let entry = ListEntry::new(ListType::Block, None, kind, value, "test".into(), None);
+        assert_eq!(entry.ok().map(|e| e.value), exp.map(String::from));
+    }
+
+    #[rstest]
+    fn blocks_win_over_allows() {
+        let entry =
+            |list| ListEntry::new(list, None, EntryKind::Bin, "4000", "test".into(), None).unwrap();
+        let matches = ListMatches {
+            entries: vec![entry(ListType::Allow)],
+        };
+        assert!(matches.allowed());
+        assert_eq!(matches.blocked_by(), None);
+        let matches = ListMatches {
+            entries: vec![entry(ListType::Allow), entry(ListType::Block)],
+        };
+        assert!(!matches.allowed());
+        assert_eq!(matches.blocked_by().unwrap().list, ListType::Block);
+        assert!(!ListMatches::default().allowed());
+    }
+}
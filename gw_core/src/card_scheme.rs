@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Error, ErrorKind};
+
 #[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
 pub enum CardScheme {
     #[serde(rename = "VISA")]
@@ -8,7 +10,6 @@ pub enum CardScheme {
     Mastercard,
 }
 
-
 impl std::fmt::Display for CardScheme {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -17,4 +18,19 @@ impl std::fmt::Display for CardScheme {
         };
         write!(f, "{s}")
     }
-}
\ No newline at end of file
+}
+
+impl TryFrom<String> for CardScheme {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<CardScheme, Self::Error> {
+        match value.as_str() {
+            "VISA" => Ok(Self::Visa),
+            "MASTERCARD" => Ok(Self::Mastercard),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised card scheme"),
+            }),
+        }
+    }
+}
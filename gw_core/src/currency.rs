@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Error, ErrorKind};
+
 #[derive(Clone, Copy, Debug, PartialEq, Default, Serialize, Deserialize)]
 pub enum Currency {
     #[default]
@@ -32,3 +34,20 @@ impl std::fmt::Display for Currency {
         )
     }
 }
+
+impl TryFrom<String> for Currency {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Currency, Self::Error> {
+        match value.as_str() {
+            "GBP" => Ok(Self::GBP),
+            "EUR" => Ok(Self::EUR),
+            "USD" => Ok(Self::USD),
+            "JPY" => Ok(Self::JPY),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised currency"),
+            }),
+        }
+    }
+}
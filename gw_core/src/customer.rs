@@ -1,2 +1,218 @@
-#[derive(Default, Debug, Clone, PartialEq)]
-pub struct Customer {}
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validify::{ValidationError, Validify};
+
+use crate::{billing::Billing, country::Country, utils};
+
+pub const MAX_ADDRESSES: u64 = 10;
+
+#[derive(Default, Clone, PartialEq, Serialize, Deserialize, Validify)]
+pub struct Address {
+    #[modify(trim)]
+    #[validate(length(max = 255))]
+    pub premise: String,
+    #[modify(trim)]
+    #[validate(length(max = 255))]
+    pub street: String,
+    #[modify(trim)]
+    #[validate(length(max = 255))]
+    pub city: String,
+    #[modify(trim)]
+    #[validate(length(max = 255))]
+    pub county: String,
+    #[modify(trim)]
+    #[validate(length(max = 255))]
+    pub postcode: String,
+    pub country: Country,
+}
+
+impl std::fmt::Debug for Address {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Address")
+            .field("premise", &utils::mask_name(&self.premise))
+            .field("street", &utils::mask_name(&self.street))
+            .field("city", &self.city)
+            .field("county", &self.county)
+            .field("postcode", &utils::mask_name(&self.postcode))
+            .field("country", &self.country)
+            .finish()
+    }
+}
+
+/// One of a merchant's customers, so their details and payment methods can be reused
+#[derive(Default, Clone, PartialEq, Validify)]
+pub struct Customer {
+    pub customer_id: String,
+    pub merchant_id: String,
+    #[modify(trim)]
+    #[validate(length(max = 255))]
+    pub first_name: String,
+    #[modify(trim)]
+    #[validate(length(max = 255))]
+    pub last_name: String,
+    #[modify(trim)]
+    #[validate(length(max = 255), custom(validate_email))]
+    pub email: String,
+    #[modify(trim)]
+    #[validate(length(max = 32), custom(validate_phone))]
+    pub phone: String,
+    /// The first address is used for billing when a transaction doesn't give one
+    #[validify]
+    #[validate(length(max = MAX_ADDRESSES, message = "too many addresses"))]
+    pub addresses: Vec<Address>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl std::fmt::Debug for Customer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Customer")
+            .field("customer_id", &self.customer_id)
+            .field("merchant_id", &self.merchant_id)
+            .field("first_name", &utils::mask_name(&self.first_name))
+            .field("last_name", &utils::mask_name(&self.last_name))
+            .field("email", &utils::mask_name(&self.email))
+            .field("phone", &utils::mask_account_number(&self.phone))
+            .field("addresses", &self.addresses)
+            .field("created_at", &self.created_at)
+            .finish()
+    }
+}
+
+impl Customer {
+    pub fn new(merchant_id: String) -> Self {
+        Self {
+            customer_id: Uuid::new_v4().simple().to_string(),
+            merchant_id,
+            // to the microsecond, as postgres keeps it
+            created_at: Utc::now().trunc_subsecs(6),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&Customer> for Billing {
+    fn from(value: &Customer) -> Self {
+        let address = value.addresses.first().cloned().unwrap_or_default();
+        Billing {
+            first_name: value.first_name.clone(),
+            last_name: value.last_name.clone(),
+            premise: address.premise,
+            street: address.street,
+            city: address.city,
+            county: address.county,
+            postcode: address.postcode,
+            country: address.country,
+            email: value.email.clone(),
+        }
+    }
+}
+
+fn validate_email(email: &str) -> Result<(), ValidationError> {
+    if email.is_empty()
+        || email
+            .split_once('@')
+            .is_some_and(|(user, domain)| !user.is_empty() && !domain.is_empty())
+    {
+        Ok(())
+    } else {
+        Err(ValidationError::new_field("email").with_message("invalid email address".into()))
+    }
+}
+
+/// Phone numbers are kept as given, as long as they look like one
+fn validate_phone(phone: &str) -> Result<(), ValidationError> {
+    let looks_like_number = phone
+        .chars()
+        .all(|c| c.is_ascii_digit() || " +-()".contains(c))
+        && phone.chars().any(|c| c.is_ascii_digit());
+    if phone.is_empty() || looks_like_number {
+        Ok(())
+    } else {
+        Err(ValidationError::new_field("phone").with_message("invalid phone number".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("", "", true)]
+    #[case("jo@example.com", "+44 (0)113 496 0000", true)]
+    #[case("example.com", "", false)]
+    #[case("jo@", "", false)]
+    #[case("", "0113-496-0000", true)]
+    #[case("", "phone me", false)]
+    #[case("", "+", false)]
+    fn test_validate_contact_details(
+        #[case] email: &str,
+        #[case] phone: &str,
+        #[case] valid: bool,
+    ) {
+        let mut customer = Customer {
+            email: email.into(),
+            phone: phone.into(),
+            ..Customer::new("merchant123".into())
+        };
+        assert_eq!(customer.validify().is_ok(), valid);
+    }
+
+    #[test]
+    fn test_too_many_addresses() {
+        let mut customer = Customer {
+            addresses: vec![Address::default(); MAX_ADDRESSES as usize + 1],
+            ..Customer::new("merchant123".into())
+        };
+        assert!(customer.validify().is_err());
+        customer.addresses.pop();
+        assert!(customer.validify().is_ok());
+    }
+
+    #[test]
+    fn test_billing_from_customer() {
+        let customer = Customer {
+            first_name: " Jo ".into(),
+            last_name: "Bloggs".into(),
+            email: "jo@example.com".into(),
+            addresses: vec![
+                Address {
+                    premise: "10".into(),
+                    street: "Downing Street".into(),
+                    postcode: "SW1A 2AA".into(),
+                    ..Default::default()
+                },
+                Address {
+                    premise: "1".into(),
+                    ..Default::default()
+                },
+            ],
+            ..Customer::new("merchant123".into())
+        };
+        let billing = Billing::from(&customer);
+        assert_eq!(billing.first_name, " Jo ");
+        assert_eq!(billing.premise, "10");
+        assert_eq!(billing.postcode, "SW1A 2AA");
+        assert_eq!(billing.email, "jo@example.com");
+        assert_eq!(
+            Billing::from(&Customer::new("merchant123".into())),
+            Billing::default()
+        );
+    }
+
+    #[test]
+    fn test_debug_is_masked() {
+        let customer = Customer {
+            customer_id: "cus123".into(),
+            first_name: "Jo".into(),
+            phone: "01134960000".into(),
+            created_at: DateTime::default(),
+            ..Default::default()
+        };
+        assert_eq!(
+            format!("{customer:?}"),
+            "Customer { customer_id: \"cus123\", merchant_id: \"\", first_name: \"J#\", last_name: \"\", email: \"\", phone: \"#######0000\", addresses: [], created_at: 1970-01-01T00:00:00Z }"
+        );
+    }
+}
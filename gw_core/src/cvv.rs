@@ -0,0 +1,80 @@
+use serde::Serialize;
+
+use crate::error::{Error, ErrorKind};
+
+/// Whether the card's security code matched, from the acquirer's CVV2 result code
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CvvResult {
+    Match,
+    NoMatch,
+    NotProcessed,
+}
+
+impl std::fmt::Display for CvvResult {
+    /// The result code it's stored as
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let c = match self {
+            CvvResult::Match => "M",
+            CvvResult::NoMatch => "N",
+            CvvResult::NotProcessed => "P",
+        };
+        write!(f, "{c}")
+    }
+}
+
+impl TryFrom<String> for CvvResult {
+    type Error = Error;
+
+    /// Codes S and U, where the code should have been sent or the issuer doesn't check it, are
+    /// treated as not processed
+    fn try_from(value: String) -> Result<CvvResult, Self::Error> {
+        match value.as_str() {
+            "M" => Ok(Self::Match),
+            "N" => Ok(Self::NoMatch),
+            "P" | "S" | "U" => Ok(Self::NotProcessed),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised CVV2 result code"),
+            }),
+        }
+    }
+}
+
+/// What a merchant wants done with an approved authorisation whose CVV2 result is a mismatch
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CvvPolicy {
+    pub void_on_mismatch: bool,
+}
+
+impl CvvPolicy {
+    pub fn voids(&self, result: CvvResult) -> bool {
+        self.void_on_mismatch && result == CvvResult::NoMatch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    #[rstest]
+    #[case("M", Some(CvvResult::Match))]
+    #[case("N", Some(CvvResult::NoMatch))]
+    #[case("P", Some(CvvResult::NotProcessed))]
+    #[case("U", Some(CvvResult::NotProcessed))]
+    #[case("X", None)]
+    fn parse_codes(#[case] code: &str, #[case] exp: Option<CvvResult>) {
+        assert_eq!(CvvResult::try_from(code.to_string()).ok(), exp);
+    }
+
+    #[rstest]
+    fn only_mismatches_are_voided() {
+        let policy = CvvPolicy {
+            void_on_mismatch: true,
+        };
+        assert!(policy.voids(CvvResult::NoMatch));
+        assert!(!policy.voids(CvvResult::NotProcessed));
+        assert!(!CvvPolicy::default().voids(CvvResult::NoMatch));
+    }
+}
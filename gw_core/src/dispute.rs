@@ -0,0 +1,732 @@
+use chrono::{DateTime, NaiveDate, SubsecRound, TimeDelta, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+use validify::Validify;
+
+use crate::{
+    card_scheme::CardScheme,
+    currency::Currency,
+    error::{Error, ErrorKind},
+    transaction::search::TransactionSummary,
+};
+
+/// The columns a dispute file starts with, one dispute per line after it
+pub const DISPUTE_FILE_HEADER: &str =
+    "case_id,transaction_reference,reason_code,amount,respond_by,status";
+
+const VISA_REASONS: [(&str, &str); 25] = [
+    ("10.1", "EMV liability shift counterfeit fraud"),
+    ("10.2", "EMV liability shift non-counterfeit fraud"),
+    ("10.3", "Other fraud, card-present environment"),
+    ("10.4", "Other fraud, card-absent environment"),
+    ("10.5", "Visa fraud monitoring program"),
+    ("11.1", "Card recovery bulletin"),
+    ("11.2", "Declined authorization"),
+    ("11.3", "No authorization"),
+    ("12.1", "Late presentment"),
+    ("12.2", "Incorrect transaction code"),
+    ("12.3", "Incorrect currency"),
+    ("12.4", "Incorrect account number"),
+    ("12.5", "Incorrect amount"),
+    ("12.6.1", "Duplicate processing"),
+    ("12.6.2", "Paid by other means"),
+    ("12.7", "Invalid data"),
+    ("13.1", "Merchandise or services not received"),
+    ("13.2", "Cancelled recurring transaction"),
+    (
+        "13.3",
+        "Not as described or defective merchandise or services",
+    ),
+    ("13.4", "Counterfeit merchandise"),
+    ("13.5", "Misrepresentation"),
+    ("13.6", "Credit not processed"),
+    ("13.7", "Cancelled merchandise or services"),
+    ("13.8", "Original credit transaction not accepted"),
+    ("13.9", "Non-receipt of cash or load transaction value"),
+];
+
+const MASTERCARD_REASONS: [(&str, &str); 13] = [
+    ("4808", "Authorization-related chargeback"),
+    ("4831", "Transaction amount differs"),
+    ("4834", "Point-of-interaction error"),
+    ("4837", "No cardholder authorization"),
+    ("4841", "Cancelled recurring or digital goods transaction"),
+    ("4849", "Questionable merchant activity"),
+    ("4853", "Cardholder dispute"),
+    ("4855", "Goods or services not provided"),
+    ("4859", "Addendum, no-show or ATM dispute"),
+    ("4860", "Credit not processed"),
+    ("4863", "Cardholder does not recognise, potential fraud"),
+    ("4870", "Chip liability shift"),
+    ("4871", "Chip and PIN liability shift"),
+];
+
+/// What the scheme's reason code means, if the scheme has one by that code
+pub fn reason(scheme: CardScheme, code: &str) -> Option<&'static str> {
+    let reasons: &[(&str, &str)] = match scheme {
+        CardScheme::Visa => &VISA_REASONS,
+        CardScheme::Mastercard => &MASTERCARD_REASONS,
+    };
+    reasons
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, description)| *description)
+}
+
+/// How long merchants have to respond to a dispute when the acquirer doesn't say
+fn response_window(scheme: CardScheme) -> TimeDelta {
+    match scheme {
+        CardScheme::Visa => TimeDelta::days(30),
+        CardScheme::Mastercard => TimeDelta::days(45),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DisputeStatus {
+    /// Waiting on the merchant's evidence
+    Received,
+    /// The merchant has made their case, waiting on the issuer
+    EvidenceSubmitted,
+    /// The issuer found for the merchant, so the funds are returned
+    Won,
+    Lost,
+}
+
+impl std::fmt::Display for DisputeStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            DisputeStatus::Received => "RECEIVED",
+            DisputeStatus::EvidenceSubmitted => "EVIDENCE_SUBMITTED",
+            DisputeStatus::Won => "WON",
+            DisputeStatus::Lost => "LOST",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl TryFrom<String> for DisputeStatus {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "RECEIVED" => Ok(Self::Received),
+            "EVIDENCE_SUBMITTED" => Ok(Self::EvidenceSubmitted),
+            "WON" => Ok(Self::Won),
+            "LOST" => Ok(Self::Lost),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised dispute status"),
+            }),
+        }
+    }
+}
+
+impl DisputeStatus {
+    pub fn is_resolved(&self) -> bool {
+        matches!(self, DisputeStatus::Won | DisputeStatus::Lost)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EvidenceType {
+    Receipt,
+    ProofOfDelivery,
+    CustomerCommunication,
+    RefundPolicy,
+    CancellationPolicy,
+    Other,
+}
+
+impl std::fmt::Display for EvidenceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let e = match self {
+            EvidenceType::Receipt => "RECEIPT",
+            EvidenceType::ProofOfDelivery => "PROOF_OF_DELIVERY",
+            EvidenceType::CustomerCommunication => "CUSTOMER_COMMUNICATION",
+            EvidenceType::RefundPolicy => "REFUND_POLICY",
+            EvidenceType::CancellationPolicy => "CANCELLATION_POLICY",
+            EvidenceType::Other => "OTHER",
+        };
+        write!(f, "{e}")
+    }
+}
+
+impl TryFrom<String> for EvidenceType {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "RECEIPT" => Ok(Self::Receipt),
+            "PROOF_OF_DELIVERY" => Ok(Self::ProofOfDelivery),
+            "CUSTOMER_COMMUNICATION" => Ok(Self::CustomerCommunication),
+            "REFUND_POLICY" => Ok(Self::RefundPolicy),
+            "CANCELLATION_POLICY" => Ok(Self::CancellationPolicy),
+            "OTHER" => Ok(Self::Other),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised evidence type"),
+            }),
+        }
+    }
+}
+
+/// A document the merchant is relying on. Only its details are kept, the file itself is sent
+/// to the acquirer by the merchant.
+#[derive(Debug, Clone, PartialEq, Validify)]
+pub struct Evidence {
+    pub evidence_id: String,
+    pub dispute_id: String,
+    pub evidence_type: EvidenceType,
+    #[modify(trim)]
+    #[validate(length(min = 1, max = 255))]
+    pub file_name: String,
+    #[modify(trim)]
+    #[validate(length(min = 1, max = 255))]
+    pub content_type: String,
+    /// Acquirers won't take files over 10MiB
+    #[validate(range(min = 1.0, max = 10485760.0))]
+    pub size_bytes: u64,
+    #[validate(length(max = 1000))]
+    pub description: Option<String>,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+impl Evidence {
+    pub fn new(
+        dispute_id: String,
+        evidence_type: EvidenceType,
+        file_name: String,
+        content_type: String,
+        size_bytes: u64,
+        now: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            evidence_id: Uuid::new_v4().simple().to_string(),
+            dispute_id,
+            evidence_type,
+            file_name,
+            content_type,
+            size_bytes,
+            description: None,
+            uploaded_at: now.trunc_subsecs(6),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AdjustmentReason {
+    /// The disputed amount taken back when a dispute is received
+    Chargeback,
+    /// The disputed amount returned when a dispute is won
+    ChargebackReversal,
+}
+
+impl std::fmt::Display for AdjustmentReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let r = match self {
+            AdjustmentReason::Chargeback => "CHARGEBACK",
+            AdjustmentReason::ChargebackReversal => "CHARGEBACK_REVERSAL",
+        };
+        write!(f, "{r}")
+    }
+}
+
+impl TryFrom<String> for AdjustmentReason {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "CHARGEBACK" => Ok(Self::Chargeback),
+            "CHARGEBACK_REVERSAL" => Ok(Self::ChargebackReversal),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised adjustment reason"),
+            }),
+        }
+    }
+}
+
+/// A change to what the merchant nets from a transaction after it was authorised. The net
+/// amount is the transaction's amount plus all of its adjustments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adjustment {
+    pub adjustment_id: String,
+    pub transaction_reference: String,
+    pub merchant_id: String,
+    pub dispute_id: Option<String>,
+    pub reason: AdjustmentReason,
+    /// In minor units of the currency, negative when funds are taken from the merchant
+    pub amount: i64,
+    pub currency: Currency,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A line of a dispute file, by its number, and what it said or why it couldn't be read
+pub type DisputeFileLine = (usize, Result<DisputeReport, Error>);
+
+/// What an acquirer has said about one of its disputes, in a dispute file or a chargeback
+/// notification
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisputeReport {
+    pub acquirer: String,
+    /// The acquirer's id for the dispute, unique to the acquirer
+    pub case_id: String,
+    pub transaction_reference: String,
+    pub reason_code: String,
+    /// The whole transaction is disputed when this isn't given
+    pub amount: Option<u64>,
+    pub respond_by: Option<DateTime<Utc>>,
+    /// Set once the issuer has decided, to won or lost
+    pub outcome: Option<DisputeStatus>,
+}
+
+impl DisputeReport {
+    /// Reads the lines of a dispute file after its header. Fields can't be quoted, so can't
+    /// contain commas.
+    pub fn parse_file(acquirer: &str, file: &str) -> Result<Vec<DisputeFileLine>, Error> {
+        let mut lines = file.lines();
+        if lines.next().map(str::trim) != Some(DISPUTE_FILE_HEADER) {
+            return Err(invalid(format!(
+                "dispute files must start with {DISPUTE_FILE_HEADER}"
+            )));
+        }
+        let reports = lines
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| (i + 2, Self::parse_line(acquirer, line)))
+            .collect();
+        Ok(reports)
+    }
+
+    fn parse_line(acquirer: &str, line: &str) -> Result<Self, Error> {
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [case_id, reference, reason_code, amount, respond_by, status] = fields[..] else {
+            return Err(invalid(format!(
+                "expected 6 fields but there are {}",
+                fields.len()
+            )));
+        };
+        if case_id.is_empty() || case_id.len() > 255 {
+            return Err(invalid(
+                "case_id must be between 1 and 255 characters".into(),
+            ));
+        }
+        let amount = match amount {
+            "" => None,
+            amount => Some(
+                amount
+                    .parse()
+                    .map_err(|_| invalid(format!("{amount} is not a valid amount")))?,
+            ),
+        };
+        let respond_by = match respond_by {
+            "" => None,
+            date => Some(end_of(date)?),
+        };
+        let outcome = match status {
+            "OPEN" => None,
+            "WON" => Some(DisputeStatus::Won),
+            "LOST" => Some(DisputeStatus::Lost),
+            status => {
+                return Err(invalid(format!(
+                    "{status} is not a recognised dispute file status"
+                )))
+            }
+        };
+        Ok(Self {
+            acquirer: acquirer.into(),
+            case_id: case_id.into(),
+            transaction_reference: reference.into(),
+            reason_code: reason_code.into(),
+            amount,
+            respond_by,
+            outcome,
+        })
+    }
+}
+
+/// The last moment of a `YYYY-MM-DD` date, acquirers give deadlines as whole days
+pub fn end_of(date: &str) -> Result<DateTime<Utc>, Error> {
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| invalid(format!("{date} is not a valid date")))?;
+    Ok(date
+        .and_hms_opt(23, 59, 59)
+        .expect("23:59:59 is a valid time")
+        .and_utc())
+}
+
+fn invalid(message: String) -> Error {
+    Error {
+        kind: ErrorKind::Type,
+        message,
+    }
+}
+
+/// A cardholder's dispute of a transaction, raised through the issuer and passed on by the
+/// acquirer. The merchant has until `respond_by` to submit evidence, then the issuer decides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dispute {
+    pub dispute_id: String,
+    pub merchant_id: String,
+    pub transaction_reference: String,
+    pub acquirer: String,
+    pub case_id: String,
+    pub scheme: CardScheme,
+    /// One of the scheme's reason codes, see [`reason`]
+    pub reason_code: String,
+    /// In minor units of the currency, at most the transaction's amount
+    pub amount: u64,
+    pub currency: Currency,
+    pub status: DisputeStatus,
+    pub respond_by: DateTime<Utc>,
+    pub evidence: Vec<Evidence>,
+    pub adjustments: Vec<Adjustment>,
+    pub created_at: DateTime<Utc>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+impl Dispute {
+    /// Opens a dispute against the transaction the report is about, taking the disputed
+    /// amount back from the merchant
+    pub fn open(
+        report: &DisputeReport,
+        transaction: &TransactionSummary,
+        now: DateTime<Utc>,
+    ) -> Result<Self, Error> {
+        let now = now.trunc_subsecs(6);
+        if transaction.acquirer != report.acquirer {
+            return Err(invalid(format!(
+                "transaction {} was not sent to {}",
+                transaction.reference, report.acquirer
+            )));
+        }
+        let Some(scheme) = transaction.card_scheme else {
+            return Err(invalid(format!(
+                "transaction {} was not a card payment",
+                transaction.reference
+            )));
+        };
+        if reason(scheme, &report.reason_code).is_none() {
+            return Err(invalid(format!(
+                "{} is not a recognised {scheme} reason code",
+                report.reason_code
+            )));
+        }
+        let amount = report.amount.unwrap_or(transaction.amount);
+        if amount == 0 || amount > transaction.amount {
+            return Err(invalid(format!(
+                "disputed amount must be between 1 and {}",
+                transaction.amount
+            )));
+        }
+        let mut dispute = Self {
+            dispute_id: Uuid::new_v4().simple().to_string(),
+            merchant_id: transaction.merchant_id.clone(),
+            transaction_reference: transaction.reference.clone(),
+            acquirer: report.acquirer.clone(),
+            case_id: report.case_id.clone(),
+            scheme,
+            reason_code: report.reason_code.clone(),
+            amount,
+            currency: transaction.currency,
+            status: DisputeStatus::Received,
+            respond_by: report
+                .respond_by
+                .unwrap_or_else(|| now + response_window(scheme)),
+            evidence: vec![],
+            adjustments: vec![],
+            created_at: now,
+            submitted_at: None,
+            resolved_at: None,
+        };
+        dispute.adjust(AdjustmentReason::Chargeback, now);
+        Ok(dispute)
+    }
+
+    /// What the dispute's reason code means
+    pub fn reason(&self) -> &'static str {
+        reason(self.scheme, &self.reason_code).unwrap_or("Unknown")
+    }
+
+    /// Whether the merchant can still add evidence and submit it
+    pub fn check_respondable(&self, now: DateTime<Utc>) -> Result<(), Error> {
+        if self.status != DisputeStatus::Received {
+            return Err(invalid(format!(
+                "dispute {} is {}, evidence can only be added to received disputes",
+                self.dispute_id, self.status
+            )));
+        }
+        if now > self.respond_by {
+            return Err(invalid(format!(
+                "dispute {} had to be responded to by {}",
+                self.dispute_id, self.respond_by
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn add_evidence(&mut self, evidence: Evidence, now: DateTime<Utc>) -> Result<(), Error> {
+        self.check_respondable(now)?;
+        self.evidence.push(evidence);
+        Ok(())
+    }
+
+    /// Sends the merchant's case to the issuer, after which evidence can't be added
+    pub fn submit(&mut self, now: DateTime<Utc>) -> Result<(), Error> {
+        self.check_respondable(now)?;
+        if self.evidence.is_empty() {
+            return Err(invalid(format!(
+                "dispute {} has no evidence to submit",
+                self.dispute_id
+            )));
+        }
+        self.status = DisputeStatus::EvidenceSubmitted;
+        self.submitted_at = Some(now.trunc_subsecs(6));
+        Ok(())
+    }
+
+    /// Records the issuer's decision, returning the disputed amount to the merchant if they
+    /// won. Returns whether the dispute changed, it's only decided once.
+    pub fn resolve(&mut self, outcome: DisputeStatus, now: DateTime<Utc>) -> bool {
+        if self.status.is_resolved() || !outcome.is_resolved() {
+            return false;
+        }
+        let now = now.trunc_subsecs(6);
+        self.status = outcome;
+        self.resolved_at = Some(now);
+        if outcome == DisputeStatus::Won {
+            self.adjust(AdjustmentReason::ChargebackReversal, now);
+        }
+        true
+    }
+
+    fn adjust(&mut self, reason: AdjustmentReason, now: DateTime<Utc>) {
+        let amount = self.amount as i64;
+        self.adjustments.push(Adjustment {
+            adjustment_id: Uuid::new_v4().simple().to_string(),
+            transaction_reference: self.transaction_reference.clone(),
+            merchant_id: self.merchant_id.clone(),
+            dispute_id: Some(self.dispute_id.clone()),
+            reason,
+            amount: match reason {
+                AdjustmentReason::Chargeback => -amount,
+                AdjustmentReason::ChargebackReversal => amount,
+            },
+            currency: self.currency,
+            created_at: now,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::{TransactionStatus, TransactionType};
+    use chrono::TimeZone;
+    use rstest::*;
+    use std::collections::BTreeMap;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap()
+    }
+
+    fn transaction() -> TransactionSummary {
+        TransactionSummary {
+            reference: "ref123".into(),
+            merchant_id: "merchant123".into(),
+            acquirer: "bankone".into(),
+            r#type: TransactionType::Auth,
+            status: TransactionStatus::Settled,
+            amount: 1000,
+            currency: Currency::GBP,
+            card_scheme: Some(CardScheme::Visa),
+            masked_pan: None,
+            merchant_reference: None,
+            description: None,
+            metadata: BTreeMap::new(),
+            created_at: now(),
+            processed_at: None,
+        }
+    }
+
+    fn report() -> DisputeReport {
+        DisputeReport {
+            acquirer: "bankone".into(),
+            case_id: "case123".into(),
+            transaction_reference: "ref123".into(),
+            reason_code: "13.1".into(),
+            amount: None,
+            respond_by: None,
+            outcome: None,
+        }
+    }
+
+    fn evidence(dispute: &Dispute) -> Evidence {
+        Evidence::new(
+            dispute.dispute_id.clone(),
+            EvidenceType::ProofOfDelivery,
+            "tracking.pdf".into(),
+            "application/pdf".into(),
+            2048,
+            now(),
+        )
+    }
+
+    #[rstest]
+    #[case(CardScheme::Visa, "10.4", true)]
+    #[case(CardScheme::Visa, "12.6.1", true)]
+    #[case(CardScheme::Visa, "4837", false)]
+    #[case(CardScheme::Mastercard, "4837", true)]
+    #[case(CardScheme::Mastercard, "10.4", false)]
+    #[case(CardScheme::Mastercard, "", false)]
+    fn reason_codes_are_per_scheme(
+        #[case] scheme: CardScheme,
+        #[case] code: &str,
+        #[case] known: bool,
+    ) {
+        assert_eq!(reason(scheme, code).is_some(), known);
+    }
+
+    #[test]
+    fn opening_a_dispute_takes_the_funds_back() {
+        let dispute = Dispute::open(&report(), &transaction(), now()).unwrap();
+        assert_eq!(dispute.status, DisputeStatus::Received);
+        assert_eq!(dispute.amount, 1000);
+        assert_eq!(dispute.reason(), "Merchandise or services not received");
+        // Visa gives merchants 30 days unless the acquirer says otherwise
+        assert_eq!(dispute.respond_by, now() + TimeDelta::days(30));
+        assert_eq!(dispute.adjustments.len(), 1);
+        assert_eq!(dispute.adjustments[0].amount, -1000);
+        assert_eq!(dispute.adjustments[0].reason, AdjustmentReason::Chargeback);
+
+        let partial = DisputeReport {
+            amount: Some(250),
+            respond_by: Some(end_of("2026-03-10").unwrap()),
+            ..report()
+        };
+        let dispute = Dispute::open(&partial, &transaction(), now()).unwrap();
+        assert_eq!(dispute.adjustments[0].amount, -250);
+        assert_eq!(
+            dispute.respond_by,
+            Utc.with_ymd_and_hms(2026, 3, 10, 23, 59, 59).unwrap()
+        );
+    }
+
+    #[rstest]
+    #[case(DisputeReport { reason_code: "4837".into(), ..report() }, "4837 is not a recognised VISA reason code")]
+    #[case(DisputeReport { amount: Some(1001), ..report() }, "disputed amount must be between 1 and 1000")]
+    #[case(DisputeReport { amount: Some(0), ..report() }, "disputed amount must be between 1 and 1000")]
+    #[case(DisputeReport { acquirer: "banktwo".into(), ..report() }, "transaction ref123 was not sent to banktwo")]
+    fn disputes_must_fit_their_transaction(#[case] report: DisputeReport, #[case] message: &str) {
+        let err = Dispute::open(&report, &transaction(), now()).unwrap_err();
+        assert_eq!(err.message, message);
+    }
+
+    #[test]
+    fn evidence_is_submitted_before_the_deadline() {
+        let mut dispute = Dispute::open(&report(), &transaction(), now()).unwrap();
+        assert!(dispute.submit(now()).is_err());
+        let late = dispute.respond_by + TimeDelta::seconds(1);
+        assert!(dispute.add_evidence(evidence(&dispute), late).is_err());
+        dispute.add_evidence(evidence(&dispute), now()).unwrap();
+        assert!(dispute.submit(late).is_err());
+        dispute.submit(now()).unwrap();
+        assert_eq!(dispute.status, DisputeStatus::EvidenceSubmitted);
+        assert_eq!(dispute.submitted_at, Some(now()));
+        let err = dispute.add_evidence(evidence(&dispute), now()).unwrap_err();
+        assert_eq!(
+            err.message,
+            format!(
+                "dispute {} is EVIDENCE_SUBMITTED, evidence can only be added to received disputes",
+                dispute.dispute_id
+            )
+        );
+    }
+
+    #[test]
+    fn won_disputes_return_the_funds() {
+        let mut won = Dispute::open(&report(), &transaction(), now()).unwrap();
+        let mut lost = won.clone();
+        assert!(won.resolve(DisputeStatus::Won, now()));
+        assert_eq!(won.resolved_at, Some(now()));
+        let net: i64 = won.adjustments.iter().map(|a| a.amount).sum();
+        assert_eq!(net, 0);
+        assert_eq!(
+            won.adjustments[1].reason,
+            AdjustmentReason::ChargebackReversal
+        );
+        // the issuer only decides once
+        assert!(!won.resolve(DisputeStatus::Lost, now()));
+        assert_eq!(won.status, DisputeStatus::Won);
+
+        assert!(!lost.resolve(DisputeStatus::EvidenceSubmitted, now()));
+        assert!(lost.resolve(DisputeStatus::Lost, now()));
+        assert_eq!(lost.adjustments.len(), 1);
+    }
+
+    #[test]
+    fn dispute_files_are_read_line_by_line() {
+        let file = "case_id,transaction_reference,reason_code,amount,respond_by,status\n\
+                    case1,ref123,13.1,,2026-03-10,OPEN\n\
+                    case2, ref456 ,4837,500,,WON\n\
+                    \n\
+                    case3,ref789,10.4,lots,,OPEN\n\
+                    case4,ref789,10.4\n\
+                    case5,ref789,10.4,,,PENDING\n";
+        let reports = DisputeReport::parse_file("bankone", file).unwrap();
+        assert_eq!(reports.len(), 5);
+        assert_eq!(
+            reports.iter().map(|(line, _)| *line).collect::<Vec<_>>(),
+            vec![2, 3, 5, 6, 7]
+        );
+        let reports: Vec<_> = reports.into_iter().map(|(_, report)| report).collect();
+        assert_eq!(
+            reports[0].as_ref().unwrap(),
+            &DisputeReport {
+                case_id: "case1".into(),
+                respond_by: Some(end_of("2026-03-10").unwrap()),
+                ..report()
+            }
+        );
+        let second = reports[1].as_ref().unwrap();
+        assert_eq!(second.transaction_reference, "ref456");
+        assert_eq!(second.amount, Some(500));
+        assert_eq!(second.outcome, Some(DisputeStatus::Won));
+        let errors: Vec<String> = reports[2..]
+            .iter()
+            .map(|r| r.as_ref().unwrap_err().message.clone())
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "lots is not a valid amount",
+                "expected 6 fields but there are 3",
+                "PENDING is not a recognised dispute file status",
+            ]
+        );
+        assert!(DisputeReport::parse_file("bankone", "case1,ref123,13.1,,,OPEN").is_err());
+    }
+
+    #[test]
+    fn statuses_round_trip() {
+        for status in [
+            DisputeStatus::Received,
+            DisputeStatus::EvidenceSubmitted,
+            DisputeStatus::Won,
+            DisputeStatus::Lost,
+        ] {
+            assert_eq!(DisputeStatus::try_from(status.to_string()).unwrap(), status);
+            assert_eq!(serde_json::to_value(status).unwrap(), status.to_string());
+        }
+        assert_eq!(
+            EvidenceType::try_from("VIDEO".to_string())
+                .unwrap_err()
+                .message,
+            "VIDEO is not a recognised evidence type"
+        );
+    }
+}
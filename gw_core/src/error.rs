@@ -14,6 +14,7 @@ impl std::fmt::Display for Error {
                 write!(f, "DatabaseError [{db_err_kind}]: {}", self.message)
             }
             ErrorKind::Type => write!(f, "TypeError: {}", self.message),
+            ErrorKind::Limit => write!(f, "LimitError: {}", self.message),
         }
     }
 }
@@ -22,11 +23,14 @@ impl std::fmt::Display for Error {
 pub enum ErrorKind {
     Database(DbErrorKind),
     Type,
+    /// A merchant's limits would be exceeded
+    Limit,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum DbErrorKind {
     Query,
+    Duplicate,
     Connection,
     Other,
 }
@@ -35,6 +39,7 @@ impl std::fmt::Display for DbErrorKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             DbErrorKind::Query => write!(f, "Query"),
+            DbErrorKind::Duplicate => write!(f, "Duplicate"),
             DbErrorKind::Connection => write!(f, "Connection"),
             DbErrorKind::Other => write!(f, "Other"),
         }
@@ -48,6 +53,10 @@ impl From<sqlx::Error> for Error {
                 kind: ErrorKind::Database(DbErrorKind::Query),
                 message: "no records returned".into(),
             },
+            sqlx::Error::Database(ref e) if e.is_unique_violation() => Error {
+                kind: ErrorKind::Database(DbErrorKind::Duplicate),
+                message: "record already exists".into(),
+            },
             sqlx::Error::Configuration(..)
             | sqlx::Error::Database(..)
             | sqlx::Error::Io(..)
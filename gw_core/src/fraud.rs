@@ -0,0 +1,335 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    billing::Billing,
+    country::Country,
+    currency::Currency,
+    error::{Error, ErrorKind},
+    limits::CardUsage,
+    payment::Payment,
+};
+
+pub const DEFAULT_REVIEW_SCORE: u32 = 50;
+pub const DEFAULT_REJECT_SCORE: u32 = 100;
+
+/// A merchant's fraud rules, a transaction's score is the total of the rules it matches
+#[derive(Debug, Clone, PartialEq)]
+pub struct FraudSettings {
+    pub merchant_id: String,
+    /// Transactions scoring at least this are accepted but flagged for review
+    pub review_score: u32,
+    /// Transactions scoring at least this are declined
+    pub reject_score: u32,
+    pub rules: Vec<FraudRule>,
+}
+
+impl FraudSettings {
+    pub fn new(merchant_id: String) -> Self {
+        Self {
+            merchant_id,
+            review_score: DEFAULT_REVIEW_SCORE,
+            reject_score: DEFAULT_REJECT_SCORE,
+            rules: vec![],
+        }
+    }
+
+    pub fn screen(&self, check: &FraudCheck) -> FraudResult {
+        let matched = self
+            .rules
+            .iter()
+            .filter(|rule| rule.condition.matches(check))
+            .collect::<Vec<_>>();
+        let score = matched.iter().map(|rule| rule.score).sum::<u32>();
+        let decision = if score >= self.reject_score {
+            FraudDecision::Reject
+        } else if score >= self.review_score {
+            FraudDecision::Review
+        } else {
+            FraudDecision::Accept
+        };
+        FraudResult {
+            score,
+            decision,
+            matched_rules: matched
+                .iter()
+                .map(|rule| rule.condition.name().to_string())
+                .collect(),
+        }
+    }
+
+    /// Whether any of the rules need the card's recent usage
+    pub fn needs_card_usage(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| matches!(rule.condition, RuleCondition::CardVelocity { .. }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FraudRule {
+    pub score: u32,
+    #[serde(flatten)]
+    pub condition: RuleCondition,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleCondition {
+    /// Transactions in the currency for more than the amount, in minor units
+    AmountOver {
+        currency: Currency,
+        amount: u64,
+    },
+    /// The card was issued in a different country to the billing address
+    CountryMismatch,
+    /// Cards whose pan starts with one of the bins
+    BlockedBins {
+        bins: Vec<String>,
+    },
+    /// Cards with one of the fingerprints, see [`Payment::card_fingerprint`]
+    BlockedCards {
+        fingerprints: Vec<String>,
+    },
+    BlockedEmails {
+        emails: Vec<String>,
+    },
+    /// Cards with at least this many successful transactions in the last hour
+    CardVelocity {
+        max_transactions: u32,
+    },
+}
+
+impl RuleCondition {
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuleCondition::AmountOver { .. } => "amount_over",
+            RuleCondition::CountryMismatch => "country_mismatch",
+            RuleCondition::BlockedBins { .. } => "blocked_bins",
+            RuleCondition::BlockedCards { .. } => "blocked_cards",
+            RuleCondition::BlockedEmails { .. } => "blocked_emails",
+            RuleCondition::CardVelocity { .. } => "card_velocity",
+        }
+    }
+
+    fn matches(&self, check: &FraudCheck) -> bool {
+        match self {
+            RuleCondition::AmountOver { currency, amount } => {
+                check.currency == *currency && check.amount > *amount
+            }
+            RuleCondition::CountryMismatch => check
+                .bin_country
+                .is_some_and(|country| country != check.billing.country),
+            RuleCondition::BlockedBins { bins } => match check.payment {
+                Payment::Card { pan, .. } => bins.iter().any(|bin| pan.starts_with(bin.as_str())),
+                Payment::Account { .. } => false,
+            },
+            RuleCondition::BlockedCards { fingerprints } => check
+                .card_fingerprint
+                .is_some_and(|fingerprint| fingerprints.iter().any(|f| f == fingerprint)),
+            RuleCondition::BlockedEmails { emails } => {
+                !check.billing.email.is_empty()
+                    && emails
+                        .iter()
+                        .any(|email| email.eq_ignore_ascii_case(&check.billing.email))
+            }
+            RuleCondition::CardVelocity { max_transactions } => {
+                check.card_usage.transactions >= *max_transactions
+            }
+        }
+    }
+}
+
+/// Everything the rules are checked against
+#[derive(Debug)]
+pub struct FraudCheck<'a> {
+    pub amount: u64,
+    pub currency: Currency,
+    pub payment: &'a Payment,
+    pub billing: &'a Billing,
+    /// Where the card was issued, if its bin is known
+    pub bin_country: Option<Country>,
+    pub card_fingerprint: Option<&'a str>,
+    pub card_usage: CardUsage,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FraudDecision {
+    Accept,
+    Review,
+    Reject,
+}
+
+impl std::fmt::Display for FraudDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            FraudDecision::Accept => "ACCEPT",
+            FraudDecision::Review => "REVIEW",
+            FraudDecision::Reject => "REJECT",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl TryFrom<String> for FraudDecision {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<FraudDecision, Self::Error> {
+        match value.as_str() {
+            "ACCEPT" => Ok(Self::Accept),
+            "REVIEW" => Ok(Self::Review),
+            "REJECT" => Ok(Self::Reject),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised fraud decision"),
+            }),
+        }
+    }
+}
+
+/// The outcome of screening a transaction, stored alongside it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FraudResult {
+    pub score: u32,
+    pub decision: FraudDecision,
+    /// The names of the rules the transaction matched
+    pub matched_rules: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card_scheme::CardScheme;
+    use rstest::*;
+
+    fn settings(rules: Vec<(u32, RuleCondition)>) -> FraudSettings {
+        FraudSettings {
+            rules: rules
+                .into_iter()
+                .map(|(score, condition)| FraudRule { score, condition })
+                .collect(),
+            ..FraudSettings::new("merchant123".into())
+        }
+    }
+
+    fn screen(
+        settings: &FraudSettings,
+        amount: u64,
+        email: &str,
+        transactions: u32,
+    ) -> FraudResult {
+        let payment = Payment::from((CardScheme::Visa, (2030, 1), "123", "4000111122223333"));
+        let billing = Billing {
+            email: email.into(),
+            ..Default::default()
+        };
+        settings.screen(&FraudCheck {
+            amount,
+            currency: Currency::GBP,
+            payment: &payment,
+            billing: &billing,
+            bin_country: Some(Country::US),
+            card_fingerprint: Some("abc123"),
+            card_usage: CardUsage {
+                transactions,
+                amount: 0,
+            },
+        })
+    }
+
+    #[rstest]
+    #[case(RuleCondition::AmountOver { currency: Currency::GBP, amount: 10000 }, true)]
+    #[case(RuleCondition::AmountOver { currency: Currency::GBP, amount: 12345 }, false)]
+    #[case(RuleCondition::AmountOver { currency: Currency::EUR, amount: 10000 }, false)]
+    #[case(RuleCondition::CountryMismatch, true)]
+    #[case(RuleCondition::BlockedBins { bins: vec!["5555".into(), "400011".into()] }, true)]
+    #[case(RuleCondition::BlockedBins { bins: vec!["400012".into()] }, false)]
+    #[case(RuleCondition::BlockedCards { fingerprints: vec!["abc123".into()] }, true)]
+    #[case(RuleCondition::BlockedCards { fingerprints: vec!["def456".into()] }, false)]
+    #[case(RuleCondition::BlockedEmails { emails: vec!["Fraud@Example.com".into()] }, true)]
+    #[case(RuleCondition::BlockedEmails { emails: vec!["other@example.com".into()] }, false)]
+    #[case(RuleCondition::CardVelocity { max_transactions: 3 }, true)]
+    #[case(RuleCondition::CardVelocity { max_transactions: 4 }, false)]
+    fn rule_matches(#[case] condition: RuleCondition, #[case] matched: bool) {
+        let name = condition.name().to_string();
+        let result = screen(
+            &settings(vec![(10, condition)]),
+            12345,
+            "fraud@example.com",
+            3,
+        );
+        assert_eq!(
+            result.matched_rules,
+            if matched { vec![name] } else { vec![] }
+        );
+    }
+
+    #[rstest]
+    #[case(12345, 0, FraudDecision::Accept, vec![])]
+    #[case(20000, 0, FraudDecision::Review, vec!["amount_over"])]
+    #[case(20000, 5, FraudDecision::Reject, vec!["amount_over", "card_velocity"])]
+    fn score_and_decision(
+        #[case] amount: u64,
+        #[case] transactions: u32,
+        #[case] decision: FraudDecision,
+        #[case] matched_rules: Vec<&str>,
+    ) {
+        let settings = settings(vec![
+            (
+                60,
+                RuleCondition::AmountOver {
+                    currency: Currency::GBP,
+                    amount: 15000,
+                },
+            ),
+            (
+                40,
+                RuleCondition::CardVelocity {
+                    max_transactions: 5,
+                },
+            ),
+        ]);
+        let result = screen(&settings, amount, "", transactions);
+        assert_eq!(result.decision, decision);
+        assert_eq!(result.matched_rules, matched_rules);
+        assert!(settings.needs_card_usage());
+    }
+
+    #[rstest]
+    fn empty_email_is_never_blocked() {
+        let settings = settings(vec![(
+            100,
+            RuleCondition::BlockedEmails {
+                emails: vec!["".into()],
+            },
+        )]);
+        assert_eq!(screen(&settings, 1, "", 0).decision, FraudDecision::Accept);
+    }
+
+    #[rstest]
+    fn rules_round_trip_through_json() {
+        let rules = settings(vec![
+            (
+                50,
+                RuleCondition::AmountOver {
+                    currency: Currency::GBP,
+                    amount: 100,
+                },
+            ),
+            (20, RuleCondition::CountryMismatch),
+        ])
+        .rules;
+        let json = serde_json::to_value(&rules).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([
+                {"score": 50, "type": "amount_over", "currency": "GBP", "amount": 100},
+                {"score": 20, "type": "country_mismatch"}
+            ])
+        );
+        assert_eq!(
+            serde_json::from_value::<Vec<FraudRule>>(json).unwrap(),
+            rules
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::secret::Secret;
+
+/// A merchant's previous request made with an idempotency key, along with the response
+/// that was returned for it (None while the original request is still being processed).
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdempotencyRecord {
+    pub merchant_id: String,
+    pub key: String,
+    pub request_hash: String,
+    pub response: Option<StoredResponse>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredResponse {
+    pub status_code: u16,
+    pub body: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Reservation {
+    /// The key was free, the request should be processed and its response stored
+    Reserved,
+    /// The key has already been used within the retention window
+    Existing(IdempotencyRecord),
+}
+
+impl IdempotencyRecord {
+    pub fn matches(&self, request_hash: &str) -> bool {
+        self.request_hash == request_hash
+    }
+}
+
+/// Hashes a request body so repeats can be compared. serde_json sorts object keys, so
+/// the same JSON with different key order or whitespace hashes the same. Bodies can carry card
+/// numbers and security codes, so the hash is keyed like card fingerprints are, otherwise the
+/// few digits that aren't known could be guessed from it.
+pub fn hash_request(body: &serde_json::Value, key: &Secret<String>) -> String {
+    let body = Secret::new(body.to_string());
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.expose().as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body.expose().as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key() -> Secret<String> {
+        "idempotency-key".into()
+    }
+
+    #[test]
+    fn hash_ignores_key_order() {
+        let a = json!({"amount": 100, "currency": "GBP"});
+        let b: serde_json::Value =
+            serde_json::from_str(r#"{ "currency": "GBP",   "amount": 100 }"#).unwrap();
+        assert_eq!(hash_request(&a, &key()), hash_request(&b, &key()));
+        assert_eq!(hash_request(&a, &key()).len(), 64);
+    }
+
+    #[test]
+    fn hash_differs_for_different_bodies() {
+        let a = json!({"amount": 100, "currency": "GBP"});
+        let b = json!({"amount": 101, "currency": "GBP"});
+        assert_ne!(hash_request(&a, &key()), hash_request(&b, &key()));
+    }
+
+    #[test]
+    fn hash_is_keyed() {
+        let body = json!({"payment": {"pan": "4000111122223333", "security_code": "123"}});
+        assert_ne!(
+            hash_request(&body, &key()),
+            hash_request(&body, &"another-key".into())
+        );
+        let unkeyed = format!(
+            "{:x}",
+            <Sha256 as sha2::Digest>::digest(body.to_string().as_bytes())
+        );
+        assert_ne!(hash_request(&body, &key()), unkeyed);
+    }
+}
@@ -1,15 +1,36 @@
 pub mod account;
+pub mod acquirer;
 pub mod amount;
+pub mod api_key;
+pub mod avs;
 pub mod billing;
+pub mod block_list;
 pub mod card_scheme;
+pub mod clock;
 pub mod country;
 pub mod currency;
 pub mod customer;
+pub mod dispute;
+pub mod cvv;
 pub mod error;
+pub mod fraud;
+pub mod idempotency;
+pub mod limits;
 pub mod merchant;
+pub mod notification;
 pub mod payment;
+pub mod payment_method;
+pub mod payment_route;
+pub mod policy;
 pub mod repo;
+pub mod sca;
+pub mod secret;
+pub mod signing;
+pub mod stored_credential;
+pub mod subscription;
 #[cfg(test)]
 pub mod test_utils;
+pub mod three_ds;
 pub mod transaction;
 pub mod utils;
+pub mod webhook;
@@ -0,0 +1,141 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::{
+    currency::Currency,
+    error::{Error, ErrorKind},
+    payment::Payment,
+    secret::Secret,
+};
+
+/// A merchant's limits, anything left as `None` falls back to the gateway's defaults
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MerchantLimits {
+    pub merchant_id: String,
+    /// How many API requests the merchant can make each minute
+    pub requests_per_minute: Option<u32>,
+    pub velocity: VelocityLimits,
+}
+
+/// Business limits on how much a single card can be used with a merchant in an hour
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct VelocityLimits {
+    pub max_card_transactions_per_hour: Option<u32>,
+    /// In the minor units of the transaction's currency, only transactions in the same currency
+    /// count towards it
+    pub max_card_amount_per_hour: Option<u64>,
+}
+
+/// A card's successful transactions with a merchant over the last hour
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CardUsage {
+    pub transactions: u32,
+    pub amount: u64,
+}
+
+impl VelocityLimits {
+    /// Checks whether another transaction for `amount` would take the card over the limits
+    pub fn check(&self, usage: &CardUsage, amount: u64, currency: Currency) -> Result<(), Error> {
+        if let Some(max) = self.max_card_transactions_per_hour {
+            if usage.transactions >= max {
+                return Err(Error {
+                    kind: ErrorKind::Limit,
+                    message: format!("card has reached the limit of {max} transactions per hour"),
+                });
+            }
+        }
+        if let Some(max) = self.max_card_amount_per_hour {
+            if usage.amount.saturating_add(amount) > max {
+                return Err(Error {
+                    kind: ErrorKind::Limit,
+                    message: format!("card would go over the limit of {max} {currency} per hour"),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Payment {
+    /// A keyed hash of the card number, used to find a card's earlier transactions. Accounts
+    /// aren't fingerprinted.
+    pub fn card_fingerprint(&self, key: &Secret<String>) -> Option<String> {
+        let Payment::Card { pan, .. } = self else {
+            return None;
+        };
+        Some(fingerprint_pan(pan, key))
+    }
+}
+
+/// See [`Payment::card_fingerprint`], for when only the pan is known
+pub fn fingerprint_pan(pan: &str, key: &Secret<String>) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.expose().as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(pan.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::card_scheme::CardScheme;
+    use rstest::*;
+
+    #[rstest]
+    #[case(VelocityLimits::default(), CardUsage { transactions: 100, amount: 1_000_000 }, None)]
+    #[case(
+        VelocityLimits { max_card_transactions_per_hour: Some(2), max_card_amount_per_hour: None },
+        CardUsage { transactions: 1, amount: 100 },
+        None
+    )]
+    #[case(
+        VelocityLimits { max_card_transactions_per_hour: Some(2), max_card_amount_per_hour: None },
+        CardUsage { transactions: 2, amount: 100 },
+        Some("card has reached the limit of 2 transactions per hour")
+    )]
+    #[case(
+        VelocityLimits { max_card_transactions_per_hour: None, max_card_amount_per_hour: Some(1000) },
+        CardUsage { transactions: 1, amount: 900 },
+        None
+    )]
+    #[case(
+        VelocityLimits { max_card_transactions_per_hour: None, max_card_amount_per_hour: Some(1000) },
+        CardUsage { transactions: 1, amount: 901 },
+        Some("card would go over the limit of 1000 GBP per hour")
+    )]
+    fn check_velocity(
+        #[case] limits: VelocityLimits,
+        #[case] usage: CardUsage,
+        #[case] error: Option<&str>,
+    ) {
+        let res = limits.check(&usage, 100, Currency::GBP);
+        assert_eq!(res.err().map(|e| e.message), error.map(String::from));
+    }
+
+    #[rstest]
+    fn fingerprint_depends_on_pan_and_key() {
+        let key = Secret::new("key".to_string());
+        let card = |pan: &str| Payment::from((CardScheme::Visa, (2030, 1), "123", pan));
+        let fingerprint = card("4000111122223333").card_fingerprint(&key).unwrap();
+        assert_eq!(
+            card("4000111122223333").card_fingerprint(&key).unwrap(),
+            fingerprint
+        );
+        assert_ne!(
+            card("4000111122224444").card_fingerprint(&key).unwrap(),
+            fingerprint
+        );
+        assert_ne!(
+            card("4000111122223333")
+                .card_fingerprint(&Secret::new("other".to_string()))
+                .unwrap(),
+            fingerprint
+        );
+        assert!(!fingerprint.contains("4000111122223333"));
+        let account = Payment::Account {
+            account_number: "12345678".into(),
+            sort_code: "123456".into(),
+        };
+        assert_eq!(account.card_fingerprint(&key), None);
+    }
+}
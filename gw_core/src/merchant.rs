@@ -1,23 +1,68 @@
-use validify::Validify;
+use validify::{ValidationError, Validify};
 
 use crate::country::Country;
 
-#[derive(Clone, Debug, PartialEq, Validify, Default)]
+#[derive(Clone, Debug, PartialEq, Validify)]
 pub struct Merchant {
+    #[modify(trim)]
+    #[validate(length(min = 1, max = 255), custom(validate_merchant_id))]
     pub merchant_id: String,
     #[modify(trim)]
+    #[validate(length(min = 1, max = 255))]
     pub name: String,
     #[modify(trim)]
+    #[validate(length(max = 255))]
     pub premise: String,
     #[modify(trim)]
+    #[validate(length(max = 255))]
     pub street: String,
     #[modify(trim)]
+    #[validate(length(max = 255))]
     pub city: String,
     #[modify(trim)]
+    #[validate(length(max = 255))]
     pub postcode: String,
     #[modify(trim)]
+    #[validate(length(max = 255))]
     pub county: String,
     pub country: Country,
+    /// Deactivated merchants can no longer take transactions
+    pub active: bool,
+}
+
+impl Default for Merchant {
+    fn default() -> Self {
+        Self {
+            merchant_id: String::new(),
+            name: String::new(),
+            premise: String::new(),
+            street: String::new(),
+            city: String::new(),
+            postcode: String::new(),
+            county: String::new(),
+            country: Country::default(),
+            active: true,
+        }
+    }
+}
+
+/// Merchant ids end up in URLs, so they are kept to url safe characters
+fn validate_merchant_id(merchant_id: &str) -> Result<(), ValidationError> {
+    if merchant_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(ValidationError::new_field("characters").with_message("invalid characters".into()))
+    }
+}
+
+/// A page of merchants ordered by id, next_cursor is the id to continue after
+#[derive(Debug, PartialEq)]
+pub struct MerchantPage {
+    pub merchants: Vec<Merchant>,
+    pub next_cursor: Option<String>,
 }
 
 #[cfg(test)]
@@ -36,6 +81,7 @@ mod tests {
             postcode: "   postcode   ".into(),
             county: "   county   ".into(),
             country: Country::GB,
+            active: true,
         };
         let exp = Merchant {
             merchant_id: "merchant123".into(),
@@ -46,8 +92,23 @@ mod tests {
             postcode: "postcode".into(),
             county: "county".into(),
             country: Country::GB,
+            active: true,
         };
         assert!(m.validify().is_ok());
         assert_eq!(m, exp);
     }
+
+    #[rstest]
+    #[case("", "name")]
+    #[case("merchant 123", "name")]
+    #[case("merchant/123", "name")]
+    #[case("merchant123", "  ")]
+    fn test_invalid_merchant(#[case] merchant_id: &str, #[case] name: &str) {
+        let mut m = Merchant {
+            merchant_id: merchant_id.into(),
+            name: name.into(),
+            ..Default::default()
+        };
+        assert!(m.validify().is_err());
+    }
 }
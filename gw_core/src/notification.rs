@@ -0,0 +1,590 @@
+use chrono::{DateTime, SubsecRound, Utc};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+
+use crate::{
+    dispute::{end_of, DisputeReport},
+    error::{Error, ErrorKind},
+    secret::Secret,
+    transaction::{search::TransactionSummary, TransactionError, TransactionStatus},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// An acquirer that pushes late changes to transactions, each signs and shapes its
+/// notifications its own way
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NotificationSource {
+    /// Signs with the hex HMAC-SHA256 of the body in `x-bankone-signature`
+    BankOne,
+    /// Signs with `sha256=` and the hex HMAC-SHA256 of the body in `x-banktwo-signature`
+    BankTwo,
+}
+
+impl std::fmt::Display for NotificationSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // the names transactions are stored against, see `AcquirerAccount::acquirer`
+        let d = match self {
+            NotificationSource::BankOne => "bankone",
+            NotificationSource::BankTwo => "banktwo",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl TryFrom<String> for NotificationSource {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "bankone" => Ok(Self::BankOne),
+            "banktwo" => Ok(Self::BankTwo),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised acquirer"),
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BankOneNotification {
+    notification_id: String,
+    psp_reference: String,
+    event_code: String,
+    /// Chargebacks can carry the dispute they open, the rest are only set with it
+    dispute_id: Option<String>,
+    reason_code: Option<String>,
+    amount: Option<u64>,
+    respond_by: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BankTwoNotification {
+    id: String,
+    transaction_reference: String,
+    r#type: String,
+    /// Chargebacks can carry the dispute they open
+    dispute: Option<BankTwoDispute>,
+}
+
+#[derive(Deserialize)]
+struct BankTwoDispute {
+    case_id: String,
+    reason_code: String,
+    amount: Option<u64>,
+    respond_by: Option<String>,
+}
+
+impl NotificationSource {
+    pub const ALL: [NotificationSource; 2] =
+        [NotificationSource::BankOne, NotificationSource::BankTwo];
+
+    /// The header the acquirer puts its signature in
+    pub fn signature_header(&self) -> &'static str {
+        match self {
+            NotificationSource::BankOne => "x-bankone-signature",
+            NotificationSource::BankTwo => "x-banktwo-signature",
+        }
+    }
+
+    /// Checks the signature over the body with the secret shared with the acquirer, in
+    /// constant time
+    pub fn verify(&self, secret: &Secret<String>, body: &[u8], signature: &str) -> bool {
+        let signature = match self {
+            NotificationSource::BankOne => Some(signature),
+            NotificationSource::BankTwo => signature.strip_prefix("sha256="),
+        };
+        let Some(Ok(signature)) = signature.map(hex::decode) else {
+            return false;
+        };
+        mac(secret, body).verify_slice(&signature).is_ok()
+    }
+
+    /// Signs the body the way the acquirer does, for simulating its notifications
+    pub fn sign(&self, secret: &Secret<String>, body: &[u8]) -> String {
+        let signature = hex::encode(mac(secret, body).finalize().into_bytes());
+        match self {
+            NotificationSource::BankOne => signature,
+            NotificationSource::BankTwo => format!("sha256={signature}"),
+        }
+    }
+
+    /// Reads the acquirer's notification, keeping the body as it was sent
+    pub fn parse(&self, raw: String, now: DateTime<Utc>) -> Result<AcquirerNotification, Error> {
+        let (notification_id, transaction_reference, kind) = match self {
+            NotificationSource::BankOne => {
+                let n: BankOneNotification = serde_json::from_str(&raw).map_err(invalid)?;
+                let kind = match n.event_code.as_str() {
+                    "SETTLEMENT" => NotificationKind::Settlement,
+                    "CHARGEBACK" => NotificationKind::Chargeback,
+                    "DECLINE" => NotificationKind::Decline,
+                    code => return Err(unknown_kind(code)),
+                };
+                (n.notification_id, n.psp_reference, kind)
+            }
+            NotificationSource::BankTwo => {
+                let n: BankTwoNotification = serde_json::from_str(&raw).map_err(invalid)?;
+                let kind = match n.r#type.as_str() {
+                    "transaction.settled" => NotificationKind::Settlement,
+                    "transaction.charged_back" => NotificationKind::Chargeback,
+                    "transaction.declined" => NotificationKind::Decline,
+                    code => return Err(unknown_kind(code)),
+                };
+                (n.id, n.transaction_reference, kind)
+            }
+        };
+        if notification_id.is_empty() || notification_id.len() > 255 {
+            return Err(Error {
+                kind: ErrorKind::Type,
+                message: "notification id must be between 1 and 255 characters".into(),
+            });
+        }
+        Ok(AcquirerNotification {
+            acquirer: self.to_string(),
+            notification_id,
+            transaction_reference,
+            kind,
+            raw,
+            outcome: NotificationOutcome::Received,
+            detail: None,
+            received_at: now.trunc_subsecs(6),
+        })
+    }
+
+    /// The dispute a chargeback notification opens, if the acquirer sent the case with it.
+    /// Without one the chargeback only changes the transaction's status.
+    pub fn dispute_report(
+        &self,
+        notification: &AcquirerNotification,
+    ) -> Result<Option<DisputeReport>, Error> {
+        if notification.kind != NotificationKind::Chargeback {
+            return Ok(None);
+        }
+        let case = match self {
+            NotificationSource::BankOne => {
+                let n: BankOneNotification =
+                    serde_json::from_str(&notification.raw).map_err(invalid)?;
+                n.dispute_id
+                    .zip(n.reason_code)
+                    .map(|(case_id, reason_code)| (case_id, reason_code, n.amount, n.respond_by))
+            }
+            NotificationSource::BankTwo => {
+                let n: BankTwoNotification =
+                    serde_json::from_str(&notification.raw).map_err(invalid)?;
+                n.dispute
+                    .map(|d| (d.case_id, d.reason_code, d.amount, d.respond_by))
+            }
+        };
+        let Some((case_id, reason_code, amount, respond_by)) = case else {
+            return Ok(None);
+        };
+        Ok(Some(DisputeReport {
+            acquirer: self.to_string(),
+            case_id,
+            transaction_reference: notification.transaction_reference.clone(),
+            reason_code,
+            amount,
+            respond_by: respond_by.as_deref().map(end_of).transpose()?,
+            outcome: None,
+        }))
+    }
+}
+
+fn mac(secret: &Secret<String>, body: &[u8]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(secret.expose().as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac
+}
+
+fn invalid(e: serde_json::Error) -> Error {
+    Error {
+        kind: ErrorKind::Type,
+        message: format!("notification is not valid: {e}"),
+    }
+}
+
+fn unknown_kind(code: &str) -> Error {
+    Error {
+        kind: ErrorKind::Type,
+        message: format!("{code} is not a recognised notification type"),
+    }
+}
+
+/// The change to a transaction an acquirer is telling the gateway about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationKind {
+    /// The funds have been settled
+    Settlement,
+    /// The issuer has taken the funds back
+    Chargeback,
+    /// An approval has been reversed into a decline
+    Decline,
+}
+
+impl std::fmt::Display for NotificationKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            NotificationKind::Settlement => "SETTLEMENT",
+            NotificationKind::Chargeback => "CHARGEBACK",
+            NotificationKind::Decline => "DECLINE",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl TryFrom<String> for NotificationKind {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "SETTLEMENT" => Ok(Self::Settlement),
+            "CHARGEBACK" => Ok(Self::Chargeback),
+            "DECLINE" => Ok(Self::Decline),
+            invalid => Err(unknown_kind(invalid)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationOutcome {
+    /// Not processed yet
+    Received,
+    /// The transaction was moved to a new status
+    Applied,
+    /// The transaction doesn't exist, or can't make the change from its status
+    Ignored,
+}
+
+impl std::fmt::Display for NotificationOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            NotificationOutcome::Received => "RECEIVED",
+            NotificationOutcome::Applied => "APPLIED",
+            NotificationOutcome::Ignored => "IGNORED",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl TryFrom<String> for NotificationOutcome {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "RECEIVED" => Ok(Self::Received),
+            "APPLIED" => Ok(Self::Applied),
+            "IGNORED" => Ok(Self::Ignored),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised notification outcome"),
+            }),
+        }
+    }
+}
+
+/// A notification an acquirer pushed to the gateway, kept with what was done about it. The
+/// acquirer's notification ID makes it unique, so redelivered notifications are only applied
+/// once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcquirerNotification {
+    pub acquirer: String,
+    pub notification_id: String,
+    pub transaction_reference: String,
+    pub kind: NotificationKind,
+    /// The body exactly as the acquirer sent it, for audit
+    pub raw: String,
+    pub outcome: NotificationOutcome,
+    /// The change made to the transaction, or why nothing was
+    pub detail: Option<String>,
+    pub received_at: DateTime<Utc>,
+}
+
+impl AcquirerNotification {
+    /// Works out what the notification does to the transaction it's about, returning the
+    /// transaction's new status if it changes
+    pub fn apply(&mut self, transaction: Option<&TransactionSummary>) -> Option<TransactionStatus> {
+        let transaction = match transaction {
+            Some(t) if t.acquirer == self.acquirer => t,
+            _ => {
+                let detail = format!(
+                    "transaction {} was not sent to {}",
+                    self.transaction_reference, self.acquirer
+                );
+                return self.ignore(detail);
+            }
+        };
+        match transaction.status.after(self.kind) {
+            Some(status) => {
+                self.outcome = NotificationOutcome::Applied;
+                self.detail = Some(format!("{} to {status}", transaction.status));
+                Some(status)
+            }
+            None => {
+                let detail = format!(
+                    "a {} transaction can't have a {}",
+                    transaction.status, self.kind
+                );
+                self.ignore(detail)
+            }
+        }
+    }
+
+    fn ignore(&mut self, detail: String) -> Option<TransactionStatus> {
+        self.outcome = NotificationOutcome::Ignored;
+        self.detail = Some(detail);
+        None
+    }
+}
+
+impl TransactionStatus {
+    /// The transaction state machine's moves after authorisation. Only a successful
+    /// transaction can be settled or have its approval reversed, and only one whose funds were
+    /// taken can be charged back.
+    pub fn after(&self, kind: NotificationKind) -> Option<TransactionStatus> {
+        match (self, kind) {
+            (TransactionStatus::Success, NotificationKind::Settlement) => {
+                Some(TransactionStatus::Settled)
+            }
+            (TransactionStatus::Success, NotificationKind::Decline) => {
+                Some(TransactionStatus::Failed(Some(TransactionError::Declined)))
+            }
+            (
+                TransactionStatus::Success | TransactionStatus::Settled,
+                NotificationKind::Chargeback,
+            ) => Some(TransactionStatus::ChargedBack),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{currency::Currency, transaction::TransactionType};
+    use rstest::*;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    fn sign(secret: &str, body: &str) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body.as_bytes());
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    fn summary(status: TransactionStatus) -> TransactionSummary {
+        TransactionSummary {
+            reference: "ref123".into(),
+            merchant_id: "merchant123".into(),
+            acquirer: "bankone".into(),
+            r#type: TransactionType::Auth,
+            status,
+            amount: 12345,
+            currency: Currency::GBP,
+            card_scheme: None,
+            masked_pan: None,
+            merchant_reference: None,
+            description: None,
+            metadata: BTreeMap::new(),
+            created_at: Utc::now(),
+            processed_at: None,
+        }
+    }
+
+    #[rstest]
+    fn signatures_are_checked_the_acquirers_way() {
+        let secret = Secret::from("secret");
+        let body = r#"{"a":1}"#;
+        let signature = sign("secret", body);
+        let bank_one = NotificationSource::BankOne;
+        assert!(bank_one.verify(&secret, body.as_bytes(), &signature));
+        assert!(!bank_one.verify(&secret, br#"{"a":2}"#, &signature));
+        assert!(!bank_one.verify(&"other".into(), body.as_bytes(), &signature));
+        assert!(!bank_one.verify(&secret, body.as_bytes(), "not hex"));
+        let bank_two = NotificationSource::BankTwo;
+        assert!(bank_two.verify(&secret, body.as_bytes(), &format!("sha256={signature}")));
+        assert!(!bank_two.verify(&secret, body.as_bytes(), &signature));
+        assert_eq!(bank_one.sign(&secret, body.as_bytes()), signature);
+        assert_eq!(
+            bank_two.sign(&secret, body.as_bytes()),
+            format!("sha256={signature}")
+        );
+    }
+
+    #[rstest]
+    #[case(
+        NotificationSource::BankOne,
+        json!({"notificationId": "n1", "pspReference": "ref123", "eventCode": "CHARGEBACK"}),
+        NotificationKind::Chargeback
+    )]
+    #[case(
+        NotificationSource::BankTwo,
+        json!({"id": "n1", "transaction_reference": "ref123", "type": "transaction.settled"}),
+        NotificationKind::Settlement
+    )]
+    fn notifications_are_parsed_from_each_acquirers_format(
+        #[case] source: NotificationSource,
+        #[case] body: serde_json::Value,
+        #[case] kind: NotificationKind,
+    ) {
+        let raw = body.to_string();
+        let notification = source.parse(raw.clone(), Utc::now()).unwrap();
+        assert_eq!(notification.acquirer, source.to_string());
+        assert_eq!(notification.notification_id, "n1");
+        assert_eq!(notification.transaction_reference, "ref123");
+        assert_eq!(notification.kind, kind);
+        assert_eq!(notification.raw, raw);
+        assert_eq!(notification.outcome, NotificationOutcome::Received);
+    }
+
+    #[rstest]
+    #[case(json!({"notificationId": "n1", "pspReference": "ref123", "eventCode": "REFUND"}))]
+    #[case(json!({"notificationId": "", "pspReference": "ref123", "eventCode": "DECLINE"}))]
+    #[case(json!({"pspReference": "ref123", "eventCode": "DECLINE"}))]
+    #[case(json!([]))]
+    fn bad_notifications_are_rejected(#[case] body: serde_json::Value) {
+        assert!(NotificationSource::BankOne
+            .parse(body.to_string(), Utc::now())
+            .is_err());
+    }
+
+    #[rstest]
+    #[case(
+        TransactionStatus::Success,
+        NotificationKind::Settlement,
+        Some(TransactionStatus::Settled)
+    )]
+    #[case(
+        TransactionStatus::Success,
+        NotificationKind::Chargeback,
+        Some(TransactionStatus::ChargedBack)
+    )]
+    #[case(
+        TransactionStatus::Settled,
+        NotificationKind::Chargeback,
+        Some(TransactionStatus::ChargedBack)
+    )]
+    #[case(
+        TransactionStatus::Success,
+        NotificationKind::Decline,
+        Some(TransactionStatus::Failed(Some(TransactionError::Declined)))
+    )]
+    #[case(TransactionStatus::Settled, NotificationKind::Settlement, None)]
+    #[case(TransactionStatus::Settled, NotificationKind::Decline, None)]
+    #[case(TransactionStatus::ChargedBack, NotificationKind::Chargeback, None)]
+    #[case(TransactionStatus::Failed(None), NotificationKind::Settlement, None)]
+    #[case(TransactionStatus::Pending3DS, NotificationKind::Chargeback, None)]
+    fn notifications_follow_the_state_machine(
+        #[case] status: TransactionStatus,
+        #[case] kind: NotificationKind,
+        #[case] after: Option<TransactionStatus>,
+    ) {
+        assert_eq!(status.after(kind), after);
+    }
+
+    #[rstest]
+    fn notifications_record_what_they_did() {
+        let body =
+            json!({"notificationId": "n1", "pspReference": "ref123", "eventCode": "SETTLEMENT"});
+        let parse = || {
+            NotificationSource::BankOne
+                .parse(body.to_string(), Utc::now())
+                .unwrap()
+        };
+        let mut notification = parse();
+        let status = notification.apply(Some(&summary(TransactionStatus::Success)));
+        assert_eq!(status, Some(TransactionStatus::Settled));
+        assert_eq!(notification.outcome, NotificationOutcome::Applied);
+        assert_eq!(notification.detail.as_deref(), Some("SUCCESS to SETTLED"));
+
+        let mut notification = parse();
+        assert_eq!(
+            notification.apply(Some(&summary(TransactionStatus::Failed(None)))),
+            None
+        );
+        assert_eq!(notification.outcome, NotificationOutcome::Ignored);
+        assert_eq!(
+            notification.detail.as_deref(),
+            Some("a FAILED transaction can't have a SETTLEMENT")
+        );
+
+        let mut notification = parse();
+        assert_eq!(notification.apply(None), None);
+        assert_eq!(
+            notification.detail.as_deref(),
+            Some("transaction ref123 was not sent to bankone")
+        );
+        let mut other_acquirer = summary(TransactionStatus::Success);
+        other_acquirer.acquirer = "banktwo".into();
+        let mut notification = parse();
+        assert_eq!(notification.apply(Some(&other_acquirer)), None);
+        assert_eq!(notification.outcome, NotificationOutcome::Ignored);
+    }
+
+    #[rstest]
+    fn chargebacks_can_open_disputes() {
+        let now = Utc::now();
+        let body = json!({
+            "notificationId": "n1",
+            "pspReference": "ref123",
+            "eventCode": "CHARGEBACK",
+            "disputeId": "case123",
+            "reasonCode": "10.4",
+            "respondBy": "2026-03-10"
+        });
+        let notification = NotificationSource::BankOne
+            .parse(body.to_string(), now)
+            .unwrap();
+        let report = NotificationSource::BankOne
+            .dispute_report(&notification)
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.case_id, "case123");
+        assert_eq!(report.transaction_reference, "ref123");
+        assert_eq!(report.reason_code, "10.4");
+        assert_eq!(report.amount, None);
+        assert_eq!(report.respond_by, Some(end_of("2026-03-10").unwrap()));
+
+        let body = json!({
+            "id": "n2",
+            "transaction_reference": "ref123",
+            "type": "transaction.charged_back",
+            "dispute": {"case_id": "case456", "reason_code": "4837", "amount": 500}
+        });
+        let notification = NotificationSource::BankTwo
+            .parse(body.to_string(), now)
+            .unwrap();
+        let report = NotificationSource::BankTwo
+            .dispute_report(&notification)
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.acquirer, "banktwo");
+        assert_eq!(report.amount, Some(500));
+
+        // only chargebacks open disputes, and only with a case
+        for body in [
+            json!({"notificationId": "n3", "pspReference": "ref123", "eventCode": "CHARGEBACK"}),
+            json!({"notificationId": "n4", "pspReference": "ref123", "eventCode": "SETTLEMENT", "disputeId": "case123", "reasonCode": "10.4"}),
+        ] {
+            let notification = NotificationSource::BankOne
+                .parse(body.to_string(), now)
+                .unwrap();
+            assert_eq!(
+                NotificationSource::BankOne
+                    .dispute_report(&notification)
+                    .unwrap(),
+                None
+            );
+        }
+        let body = json!({"notificationId": "n5", "pspReference": "ref123", "eventCode": "CHARGEBACK", "disputeId": "case123", "reasonCode": "10.4", "respondBy": "soon"});
+        let notification = NotificationSource::BankOne
+            .parse(body.to_string(), now)
+            .unwrap();
+        assert!(NotificationSource::BankOne
+            .dispute_report(&notification)
+            .is_err());
+    }
+}
@@ -1,16 +1,16 @@
-use crate::card_scheme::CardScheme;
-use validify::{schema_err, schema_validation, Validate, ValidationErrors};
+use crate::{card_scheme::CardScheme, secret::Secret, utils};
+use validify::Validate;
 
 pub type ExpiryDate = (u32, u8);
 
-#[derive(Clone, Debug, PartialEq, Validate)]
+#[derive(Clone, PartialEq, Validate)]
 // #[validate(validate_payment)]
 pub enum Payment {
     Card {
         scheme: CardScheme,
         expiry_date: ExpiryDate,
         #[validate(length(min = 3, max = 4, message = "invalid length"))]
-        security_code: String,
+        security_code: Secret<String>,
         #[validate(length(min = 16, max = 20, message = "invalid length"))]
         pan: String,
     },
@@ -25,12 +25,42 @@ impl From<(CardScheme, ExpiryDate, &str, &str)> for Payment {
         Payment::Card {
             scheme: value.0,
             expiry_date: value.1.to_owned(),
-            security_code: value.2.to_owned(),
+            security_code: value.2.into(),
             pan: value.3.to_owned(),
         }
     }
 }
 
+impl std::fmt::Debug for Payment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Payment::Card {
+                scheme,
+                expiry_date,
+                security_code,
+                pan,
+            } => f
+                .debug_struct("Card")
+                .field("scheme", scheme)
+                .field("expiry_date", expiry_date)
+                .field("security_code", security_code)
+                .field("pan", &utils::mask_pan(pan))
+                .finish(),
+            Payment::Account {
+                account_number,
+                sort_code,
+            } => f
+                .debug_struct("Account")
+                .field(
+                    "account_number",
+                    &utils::mask_account_number(account_number),
+                )
+                .field("sort_code", sort_code)
+                .finish(),
+        }
+    }
+}
+
 // fn validate_payment(p: &Payment) -> Result<(), ValidationErrors> {
 //     match p {
 //         Payment::Card {
@@ -78,4 +108,11 @@ mod tests {
     ) {
         check_validation(Payment::from(inputs), errors);
     }
+
+    #[rstest]
+    #[case(Payment::from((CardScheme::Visa, (2021, 3), "987", "4000111122223333")), r#"Card { scheme: Visa, expiry_date: (2021, 3), security_code: Secret([REDACTED]), pan: "400011######3333" }"#)]
+    #[case(Payment::Account { account_number: "12345678".into(), sort_code: "010203".into() }, "Account { account_number: \"####5678\", sort_code: \"010203\" }")]
+    fn test_debug_is_masked(#[case] payment: Payment, #[case] exp: &str) {
+        assert_eq!(format!("{payment:?}"), exp);
+    }
 }
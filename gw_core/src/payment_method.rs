@@ -0,0 +1,281 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use chrono::{DateTime, SubsecRound, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    card_scheme::CardScheme,
+    error::{Error, ErrorKind},
+    payment::{ExpiryDate, Payment},
+    secret::Secret,
+    utils,
+};
+
+const NONCE_LEN: usize = 12;
+
+/// A card or account saved against a customer, referred to by its token. The card or account
+/// details are only stored encrypted, and a card's security code is never stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentMethod {
+    pub token: String,
+    pub customer_id: String,
+    pub merchant_id: String,
+    /// Only set for cards
+    pub scheme: Option<CardScheme>,
+    /// The masked pan or account number
+    pub masked_number: String,
+    /// Only set for cards
+    pub expiry_date: Option<ExpiryDate>,
+    /// See [`Payment::card_fingerprint`]
+    pub card_fingerprint: Option<String>,
+    /// The encrypted card or account details, see [`PaymentMethod::payment`]
+    pub sealed: String,
+    pub created_at: DateTime<Utc>,
+    /// The scheme transaction ID of the initial stored credential transaction made with it,
+    /// later transactions are chained to it
+    pub scheme_transaction_id: Option<String>,
+}
+
+/// What's encrypted, everything needed to charge the payment method again
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum SealedPayment {
+    Card {
+        scheme: CardScheme,
+        expiry_date: ExpiryDate,
+        pan: String,
+    },
+    Account {
+        account_number: String,
+        sort_code: String,
+    },
+}
+
+impl PaymentMethod {
+    pub fn new(
+        customer_id: String,
+        merchant_id: String,
+        payment: &Payment,
+        key: &Secret<String>,
+        card_fingerprint_key: &Secret<String>,
+    ) -> Result<Self, Error> {
+        let token = Uuid::new_v4().simple().to_string();
+        let (scheme, masked_number, expiry_date) = match payment {
+            Payment::Card {
+                scheme,
+                expiry_date,
+                pan,
+                ..
+            } => (Some(*scheme), utils::mask_pan(pan), Some(*expiry_date)),
+            Payment::Account { account_number, .. } => {
+                (None, utils::mask_account_number(account_number), None)
+            }
+        };
+        let sealed = seal_payment(payment, &token, key)?;
+        Ok(Self {
+            customer_id,
+            merchant_id,
+            scheme,
+            masked_number,
+            expiry_date,
+            card_fingerprint: payment.card_fingerprint(card_fingerprint_key),
+            sealed,
+            created_at: Utc::now().trunc_subsecs(6),
+            scheme_transaction_id: None,
+            token,
+        })
+    }
+
+    /// Decrypts the card or account so it can be charged. Cards come back without a security
+    /// code.
+    pub fn payment(&self, key: &Secret<String>) -> Result<Payment, Error> {
+        open_payment(&self.sealed, &self.token, key)
+    }
+}
+
+/// Encrypts the card or account, bound to the ID it's stored under. A card's security code is
+/// never sealed.
+pub(crate) fn seal_payment(
+    payment: &Payment,
+    id: &str,
+    key: &Secret<String>,
+) -> Result<String, Error> {
+    let sealed = match payment {
+        Payment::Card {
+            scheme,
+            expiry_date,
+            pan,
+            ..
+        } => SealedPayment::Card {
+            scheme: *scheme,
+            expiry_date: *expiry_date,
+            pan: pan.clone(),
+        },
+        Payment::Account {
+            account_number,
+            sort_code,
+        } => SealedPayment::Account {
+            account_number: account_number.clone(),
+            sort_code: sort_code.clone(),
+        },
+    };
+    let plaintext =
+        Secret::new(serde_json::to_string(&sealed).map_err(|_| crypto_error("encrypted"))?);
+    seal(plaintext.expose().as_bytes(), id, key)
+}
+
+/// Decrypts a card or account sealed under the ID, see [`seal_payment`]. Cards come back
+/// without a security code.
+pub(crate) fn open_payment(sealed: &str, id: &str, key: &Secret<String>) -> Result<Payment, Error> {
+    let plaintext = Secret::new(open(sealed, id, key)?);
+    let sealed =
+        serde_json::from_slice(plaintext.expose()).map_err(|_| crypto_error("decrypted"))?;
+    let payment = match sealed {
+        SealedPayment::Card {
+            scheme,
+            expiry_date,
+            pan,
+        } => Payment::Card {
+            scheme,
+            expiry_date,
+            security_code: Secret::default(),
+            pan,
+        },
+        SealedPayment::Account {
+            account_number,
+            sort_code,
+        } => Payment::Account {
+            account_number,
+            sort_code,
+        },
+    };
+    Ok(payment)
+}
+
+/// The AES-256 key is the hash of the configured key, so any length of key can be used
+fn cipher(key: &Secret<String>) -> Aes256Gcm {
+    let key = Sha256::digest(key.expose().as_bytes());
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+}
+
+/// Encrypts with a random nonce, which is kept in front of the ciphertext. The token is bound
+/// to it, so sealed details can't be moved to another payment method.
+fn seal(plaintext: &[u8], token: &str, key: &Secret<String>) -> Result<String, Error> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(key)
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad: token.as_bytes(),
+            },
+        )
+        .map_err(|_| crypto_error("encrypted"))?;
+    Ok(hex::encode([nonce.as_slice(), &ciphertext].concat()))
+}
+
+fn open(sealed: &str, token: &str, key: &Secret<String>) -> Result<Vec<u8>, Error> {
+    let sealed = hex::decode(sealed).map_err(|_| crypto_error("decrypted"))?;
+    if sealed.len() < NONCE_LEN {
+        return Err(crypto_error("decrypted"));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    cipher(key)
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: token.as_bytes(),
+            },
+        )
+        .map_err(|_| crypto_error("decrypted"))
+}
+
+fn crypto_error(action: &str) -> Error {
+    Error {
+        kind: ErrorKind::Type,
+        message: format!("stored payment details could not be {action}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key() -> Secret<String> {
+        "payment-method-key".into()
+    }
+
+    fn card() -> Payment {
+        Payment::from((CardScheme::Visa, (2030, 1), "123", "4000111122223333"))
+    }
+
+    #[test]
+    fn test_cards_are_sealed_without_security_code() {
+        let method = PaymentMethod::new(
+            "cus123".into(),
+            "merchant123".into(),
+            &card(),
+            &key(),
+            &"fingerprint-key".into(),
+        )
+        .unwrap();
+        assert_eq!(method.scheme, Some(CardScheme::Visa));
+        assert_eq!(method.masked_number, "400011######3333");
+        assert_eq!(method.expiry_date, Some((2030, 1)));
+        assert_eq!(
+            method.card_fingerprint,
+            card().card_fingerprint(&"fingerprint-key".into())
+        );
+        assert!(!method.sealed.contains("4000111122223333"));
+        let Payment::Card {
+            security_code, pan, ..
+        } = method.payment(&key()).unwrap()
+        else {
+            panic!("expected a card");
+        };
+        assert_eq!(pan, "4000111122223333");
+        assert_eq!(security_code.expose(), "");
+    }
+
+    #[test]
+    fn test_accounts_round_trip() {
+        let account = Payment::Account {
+            account_number: "12345678".into(),
+            sort_code: "010203".into(),
+        };
+        let method = PaymentMethod::new(
+            "cus123".into(),
+            "merchant123".into(),
+            &account,
+            &key(),
+            &"fingerprint-key".into(),
+        )
+        .unwrap();
+        assert_eq!(method.masked_number, "####5678");
+        assert_eq!(method.scheme, None);
+        assert_eq!(method.card_fingerprint, None);
+        assert_eq!(method.payment(&key()).unwrap(), account);
+    }
+
+    #[test]
+    fn test_sealed_details_need_the_key_and_token() {
+        let mut method = PaymentMethod::new(
+            "cus123".into(),
+            "merchant123".into(),
+            &card(),
+            &key(),
+            &"fingerprint-key".into(),
+        )
+        .unwrap();
+        assert!(method.payment(&"another-key".into()).is_err());
+        method.token = "another-token".into();
+        assert!(method.payment(&key()).is_err());
+        method.sealed = "00".into();
+        assert!(method.payment(&key()).is_err());
+    }
+}
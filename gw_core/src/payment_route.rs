@@ -0,0 +1,11 @@
+use crate::{card_scheme::CardScheme, currency::Currency};
+
+/// Sends a merchant's payments in a scheme and currency to one of its acquirer accounts
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaymentRoute {
+    pub merchant_id: String,
+    pub scheme: CardScheme,
+    pub currency: Currency,
+    pub acquirer: String,
+    pub account_id: i32,
+}
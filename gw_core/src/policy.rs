@@ -0,0 +1,18 @@
+use crate::{avs::AvsPolicy, cvv::CvvPolicy};
+
+/// How a merchant wants the acquirer's checks acted on, everything is off by default
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MerchantPolicies {
+    pub merchant_id: String,
+    pub avs: AvsPolicy,
+    pub cvv: CvvPolicy,
+}
+
+impl MerchantPolicies {
+    pub fn new(merchant_id: String) -> Self {
+        Self {
+            merchant_id,
+            ..Default::default()
+        }
+    }
+}
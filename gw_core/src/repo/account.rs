@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use crate::{
-    account::{AcquirerAccount, BankOneAccount, BankTwoAccount},
+    account::{AcquirerAccount, BankOneAccount, BankTwoAccount, MerchantAccount},
     currency::Currency,
     error::{DbErrorKind, Error, ErrorKind},
     payment::Payment,
@@ -20,16 +20,36 @@ impl Repo for AccountRepo {
     type Id = i32;
 
     fn pool(&self) -> &PgPool {
-        &**self.pool
+        &self.pool
     }
 }
 
+/// The table holding the given acquirer's accounts
+fn account_table(acquirer: &str) -> Result<&'static str, Error> {
+    match acquirer {
+        "bankone" => Ok("account.bankone"),
+        "banktwo" => Ok("account.banktwo"),
+        invalid => Err(Error {
+            kind: ErrorKind::Type,
+            message: format!("{invalid} is not a recognised acquirer"),
+        }),
+    }
+}
+
+/// The column holding the acquirer's own id for the merchant
+fn account_column(account: &AcquirerAccount) -> &'static str {
+    match account {
+        AcquirerAccount::BankOne(..) => "merchant_identification_value",
+        AcquirerAccount::BankTwo(..) => "banktwo_merchant_id",
+    }
+}
+
+/// Every acquirer which has an account table
+pub const ACQUIRERS: [&str; 2] = ["bankone", "banktwo"];
+
 impl Entity for AcquirerAccount {
     fn values_str_for_insert(&self) -> String {
-        match self {
-            AcquirerAccount::BankOne(bank_one_account) => todo!(),
-            AcquirerAccount::BankTwo(bank_two_account) => todo!(),
-        }
+        "DEFAULT, $1".into()
     }
 
     fn bind_to_insert<'a>(
@@ -37,45 +57,105 @@ impl Entity for AcquirerAccount {
         stmt: Query<'a, Postgres, PgArguments>,
     ) -> Query<'a, Postgres, PgArguments> {
         match self {
-            AcquirerAccount::BankOne(bank_one_account) => todo!(),
-            AcquirerAccount::BankTwo(bank_two_account) => todo!(),
+            AcquirerAccount::BankOne(acct) => stmt.bind(acct.merchant_identification_value.clone()),
+            AcquirerAccount::BankTwo(acct) => stmt.bind(acct.merchant_reference.clone()),
         }
     }
 
     fn table_name(&self) -> &'static str {
         match self {
             AcquirerAccount::BankOne(..) => "account.bankone",
-            AcquirerAccount::BankTwo(..) => "account.bankone",
+            AcquirerAccount::BankTwo(..) => "account.banktwo",
         }
     }
 
     fn values_str_for_update(&self) -> String {
-        match self {
-            AcquirerAccount::BankOne(bank_one_account) => todo!(),
-            AcquirerAccount::BankTwo(bank_two_account) => todo!(),
-        }
+        format!("{} = $2", account_column(self))
     }
 
     fn bind_to_update<'a>(
         &'a self,
         stmt: Query<'a, Postgres, PgArguments>,
     ) -> Query<'a, Postgres, PgArguments> {
-        todo!()
+        self.bind_to_insert(stmt)
     }
 }
 
 impl<'r> FromRow<'r, PgRow> for AcquirerAccount {
     fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
-        let table_name: &str = row.get("table_name"); // using 'tableoid::regclass::text as table_name' in the select, this allows us to know which acquirer we are turnting the row into
+        let table_name: &str = row.try_get("table_name")?; // using 'tableoid::regclass::text as table_name' in the select, this allows us to know which acquirer we are turnting the row into
         match table_name {
             "account.bankone" => Ok(AcquirerAccount::BankOne(BankOneAccount::from_row(row)?)),
             "account.banktwo" => Ok(AcquirerAccount::BankTwo(BankTwoAccount::from_row(row)?)),
-            invalid => panic!("{invalid} is invalid acquirer"),
+            invalid => Err(sqlx::Error::ColumnDecode {
+                index: "table_name".into(),
+                source: format!("{invalid} is not an acquirer account table").into(),
+            }),
         }
     }
 }
 
+impl<'r> FromRow<'r, PgRow> for MerchantAccount {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(MerchantAccount {
+            id: row.try_get("id")?,
+            merchant_id: row.try_get("merchant_id")?,
+            account: AcquirerAccount::from_row(row)?,
+        })
+    }
+}
+
 impl AccountRepo {
+    /// Creates an account owned by the merchant, returning its id
+    pub async fn create_for(
+        &self,
+        merchant_id: &str,
+        account: &AcquirerAccount,
+    ) -> Result<i32, Error> {
+        let stmt = format!(
+            "INSERT INTO {} (merchant_id, {}) VALUES ($1, $2) RETURNING id",
+            account.table_name(),
+            account_column(account)
+        );
+        let query = account.bind_to_insert(sqlx::query(&stmt).bind(merchant_id));
+        let res = query.fetch_one(&**self.pool).await?;
+        Ok(res.try_get("id")?)
+    }
+
+    /// Lists every acquirer's accounts owned by the merchant
+    pub async fn list_for(&self, merchant_id: &str) -> Result<Vec<MerchantAccount>, Error> {
+        let mut accounts = vec![];
+        for acquirer in ACQUIRERS {
+            let table = account_table(acquirer)?;
+            let mut found = sqlx::query_as::<_, MerchantAccount>(&format!(
+                "SELECT *, tableoid::regclass::text as table_name FROM {table} WHERE merchant_id = $1 ORDER BY id"
+            ))
+            .bind(merchant_id)
+            .fetch_all(&**self.pool)
+            .await?;
+            accounts.append(&mut found);
+        }
+        Ok(accounts)
+    }
+
+    /// Finds one of the acquirer's accounts, as long as the merchant owns it
+    pub async fn find_for(
+        &self,
+        merchant_id: &str,
+        acquirer: &str,
+        id: i32,
+    ) -> Result<MerchantAccount, Error> {
+        let table = account_table(acquirer)?;
+        let account = sqlx::query_as::<_, MerchantAccount>(&format!(
+            "SELECT *, tableoid::regclass::text as table_name FROM {table} WHERE id = $1 AND merchant_id = $2"
+        ))
+        .bind(id)
+        .bind(merchant_id)
+        .fetch_one(&**self.pool)
+        .await?;
+        Ok(account)
+    }
+
     pub async fn select_for(
         &self,
         merchant_id: &str,
@@ -106,8 +186,17 @@ impl AccountRepo {
         let res = res.unwrap();
         let acquirer: &str = res.get_unchecked("acquirer");
         let account_id: i32 = res.get_unchecked("account_id");
-        self.select_one(&account_id, &format!("account.{acquirer}"))
-            .await
+        self.find(acquirer, account_id).await
+    }
+
+    /// Finds one of the acquirer's accounts by its id
+    pub async fn find(&self, acquirer: &str, id: i32) -> Result<AcquirerAccount, Error> {
+        self.select_one(&id, account_table(acquirer)?).await
+    }
+
+    /// Deletes one of the acquirer's accounts by its id
+    pub async fn delete(&self, acquirer: &str, id: i32) -> Result<(), Error> {
+        self.delete_one(&id, account_table(acquirer)?).await
     }
 }
 
@@ -122,24 +211,11 @@ impl<'r> FromRow<'r, PgRow> for BankOneAccount {
 impl<'r> FromRow<'r, PgRow> for BankTwoAccount {
     fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
         Ok(BankTwoAccount {
-            merchant_reference: row.try_get("merchant_reference")?,
+            merchant_reference: row.try_get("banktwo_merchant_id")?,
         })
     }
 }
 
-// impl Entity for BankOneAccount {
-//     fn values_str(&self) -> String {
-//         "merchant_identification_value".into()
-//     }
-
-//     fn bind_to<'a>(
-//         &'a self,
-//         stmt: Query<'a, Postgres, PgArguments>,
-//     ) -> Query<'a, Postgres, PgArguments> {
-//         stmt.bind(self.merchant_identification_value.clone())
-//     }
-// }
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,4 +263,109 @@ mod tests {
         assert_eq!(err.message, expected_msg);
         assert_eq!(err.to_string(), "DatabaseError [Query]: no account found");
     }
+
+    #[sqlx::test]
+    async fn test_bankone_crud(pool: PgPool) {
+        let repo = AccountRepo {
+            pool: Arc::new(Pool { _pool: pool }),
+        };
+        let account = AcquirerAccount::BankOne(BankOneAccount {
+            merchant_identification_value: "mid-1".into(),
+        });
+        let id = repo.insert_one(&account).await.unwrap();
+        // the seeded account already has id 0
+        assert_eq!(id, 1);
+        assert_eq!(repo.find("bankone", id).await.unwrap(), account);
+        let updated = AcquirerAccount::BankOne(BankOneAccount {
+            merchant_identification_value: "mid-2".into(),
+        });
+        repo.update_one(&id, &updated).await.unwrap();
+        assert_eq!(repo.find("bankone", id).await.unwrap(), updated);
+        repo.delete("bankone", id).await.unwrap();
+        let err = repo.find("bankone", id).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Database(DbErrorKind::Query));
+        assert!(repo.delete("bankone", id).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_banktwo_crud(pool: PgPool) {
+        let repo = AccountRepo {
+            pool: Arc::new(Pool { _pool: pool }),
+        };
+        let account = AcquirerAccount::BankTwo(BankTwoAccount {
+            merchant_reference: "ref-1".into(),
+        });
+        let id = repo.insert_one(&account).await.unwrap();
+        assert_eq!(repo.find("banktwo", id).await.unwrap(), account);
+        // the same id in the other acquirer's table is a different account
+        assert!(repo.find("bankone", id).await.is_err());
+        let updated = AcquirerAccount::BankTwo(BankTwoAccount {
+            merchant_reference: "ref-2".into(),
+        });
+        repo.update_one(&id, &updated).await.unwrap();
+        assert_eq!(repo.find("banktwo", id).await.unwrap(), updated);
+        repo.delete("banktwo", id).await.unwrap();
+        assert!(repo.find("banktwo", id).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_merchant_accounts(pool: PgPool) {
+        let repo = AccountRepo {
+            pool: Arc::new(Pool { _pool: pool }),
+        };
+        let account = AcquirerAccount::BankTwo(BankTwoAccount {
+            merchant_reference: "ref-1".into(),
+        });
+        let id = repo.create_for("merchant123", &account).await.unwrap();
+        let accounts = repo.list_for("merchant123").await.unwrap();
+        assert_eq!(
+            accounts,
+            vec![
+                MerchantAccount {
+                    id: 0,
+                    merchant_id: "merchant123".into(),
+                    account: AcquirerAccount::BankOne(BankOneAccount {
+                        merchant_identification_value: "merchant123".into(),
+                    }),
+                },
+                MerchantAccount {
+                    id,
+                    merchant_id: "merchant123".into(),
+                    account,
+                },
+            ]
+        );
+        assert_eq!(
+            repo.find_for("merchant123", "banktwo", id).await.unwrap(),
+            accounts[1]
+        );
+        assert!(repo.find_for("merchant456", "banktwo", id).await.is_err());
+        assert!(repo.find_for("merchant123", "bankone", id).await.is_err());
+        assert!(repo.list_for("merchant456").await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_unknown_acquirer(pool: PgPool) {
+        sqlx::query("UPDATE account.paymentroute SET acquirer = 'bankthree'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let repo = AccountRepo {
+            pool: Arc::new(Pool { _pool: pool }),
+        };
+        let err = repo.find("bankthree", 0).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Type);
+        assert_eq!(err.message, "bankthree is not a recognised acquirer");
+        let payment_data = Payment::Card {
+            scheme: CardScheme::Visa,
+            security_code: "123".into(),
+            expiry_date: (2025, 12),
+            pan: "4111111111111111".into(),
+        };
+        let err = repo
+            .select_for("merchant123", &payment_data, Currency::GBP)
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Type);
+    }
 }
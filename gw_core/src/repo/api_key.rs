@@ -0,0 +1,256 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::{
+    postgres::{PgArguments, PgRow},
+    query::Query,
+    FromRow, PgPool, Postgres, Row,
+};
+
+use crate::{
+    api_key::{ApiKey, Role},
+    error::Error,
+};
+
+use super::{Entity, Pool, Repo};
+
+#[derive(Debug)]
+pub struct ApiKeyRepo {
+    pub pool: Arc<Pool>,
+}
+
+impl Repo for ApiKeyRepo {
+    type Entity = ApiKey;
+
+    type Id = String;
+
+    fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for ApiKey {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(ApiKey {
+            key_id: row.try_get("key_id")?,
+            merchant_id: row.try_get("merchant_id")?,
+            secret_hash: row.try_get("secret_hash")?,
+            created_at: row.try_get("created_at")?,
+            expires_at: row.try_get("expires_at")?,
+            revoked_at: row.try_get("revoked_at")?,
+            role: Role::try_from(row.try_get::<String, _>("role")?).map_err(|e| {
+                sqlx::Error::ColumnDecode {
+                    index: "role".into(),
+                    source: Box::new(e),
+                }
+            })?,
+        })
+    }
+}
+
+impl Entity for ApiKey {
+    const ID_COLUMN: &'static str = "key_id";
+
+    fn values_str_for_insert(&self) -> String {
+        "$1, $2, $3, $4, $5, $6, $7".into()
+    }
+
+    fn bind_to_insert<'a>(
+        &'a self,
+        stmt: Query<'a, Postgres, PgArguments>,
+    ) -> Query<'a, Postgres, PgArguments> {
+        stmt.bind(self.key_id.clone())
+            .bind(self.merchant_id.clone())
+            .bind(self.secret_hash.clone())
+            .bind(self.created_at)
+            .bind(self.expires_at)
+            .bind(self.revoked_at)
+            .bind(self.role.to_string())
+    }
+
+    /// The key id, merchant, role and secret never change, only when the key stops working
+    fn values_str_for_update(&self) -> String {
+        "expires_at = $2, revoked_at = $3".into()
+    }
+
+    fn bind_to_update<'a>(
+        &'a self,
+        stmt: Query<'a, Postgres, PgArguments>,
+    ) -> Query<'a, Postgres, PgArguments> {
+        stmt.bind(self.expires_at).bind(self.revoked_at)
+    }
+
+    fn table_name(&self) -> &'static str {
+        "account.api_key"
+    }
+}
+
+impl ApiKeyRepo {
+    pub async fn find(&self, key_id: &str) -> Result<ApiKey, Error> {
+        self.select_one(&key_id.into(), "account.api_key").await
+    }
+
+    /// Lists the merchant's keys, newest first
+    pub async fn list_for(&self, merchant_id: &str) -> Result<Vec<ApiKey>, Error> {
+        let keys = sqlx::query_as(
+            "SELECT * FROM account.api_key WHERE merchant_id = $1 ORDER BY created_at DESC, key_id",
+        )
+        .bind(merchant_id)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(keys)
+    }
+
+    /// Adds the new key and has the merchant's other active keys expire at `old_keys_expire_at`,
+    /// unless they already expire before then
+    pub async fn rotate(
+        &self,
+        new_key: &ApiKey,
+        old_keys_expire_at: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "UPDATE account.api_key SET expires_at = $2 \
+             WHERE merchant_id = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > $2)",
+        )
+        .bind(&new_key.merchant_id)
+        .bind(old_keys_expire_at)
+        .execute(&mut *tx)
+        .await?;
+        let stmt = format!(
+            "INSERT INTO account.api_key VALUES ({})",
+            new_key.values_str_for_insert()
+        );
+        new_key
+            .bind_to_insert(sqlx::query(&stmt))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Lists the staff keys, newest first
+    pub async fn list_staff(&self) -> Result<Vec<ApiKey>, Error> {
+        let keys = sqlx::query_as(
+            "SELECT * FROM account.api_key WHERE merchant_id IS NULL ORDER BY created_at DESC, key_id",
+        )
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(keys)
+    }
+
+    /// Stops one of the merchant's keys, or a staff key when there's no merchant, from working
+    /// straight away
+    pub async fn revoke(&self, merchant_id: Option<&str>, key_id: &str) -> Result<(), Error> {
+        let res = sqlx::query(
+            "UPDATE account.api_key SET revoked_at = now() \
+             WHERE merchant_id IS NOT DISTINCT FROM $1 AND key_id = $2 AND revoked_at IS NULL",
+        )
+        .bind(merchant_id)
+        .bind(key_id)
+        .execute(&**self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[sqlx::test]
+    async fn test_insert_and_find(pool: PgPool) {
+        let repo = ApiKeyRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let (key, _) = ApiKey::generate("merchant123");
+        let id = repo.insert_one(&key).await.unwrap();
+        let found = repo.find(&id).await.unwrap();
+        assert_eq!(found.secret_hash, key.secret_hash);
+        assert_eq!(found.merchant_id.as_deref(), Some("merchant123"));
+        assert_eq!(found.role, Role::Merchant);
+        assert!(found.is_active_at(Utc::now()));
+        assert!(repo.find("missing").await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_rotate_and_revoke(pool: PgPool) {
+        let repo = ApiKeyRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let (first, _) = ApiKey::generate("merchant123");
+        repo.insert_one(&first).await.unwrap();
+        let (second, _) = ApiKey::generate("merchant123");
+        let grace = Utc::now() + TimeDelta::hours(1);
+        repo.rotate(&second, grace).await.unwrap();
+        let first = repo.find(&first.key_id).await.unwrap();
+        let expires_at = first.expires_at.unwrap();
+        assert!((expires_at - grace).num_milliseconds().abs() < 1);
+        assert!(first.is_active_at(Utc::now()));
+        assert!(!first.is_active_at(grace));
+        assert!(repo
+            .find(&second.key_id)
+            .await
+            .unwrap()
+            .expires_at
+            .is_none());
+        // rotating again soon after doesn't extend the first key
+        let (third, _) = ApiKey::generate("merchant123");
+        repo.rotate(&third, grace + TimeDelta::hours(1))
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.find(&first.key_id).await.unwrap().expires_at,
+            first.expires_at
+        );
+        assert_eq!(repo.list_for("merchant123").await.unwrap().len(), 3);
+        repo.revoke(Some("merchant123"), &third.key_id)
+            .await
+            .unwrap();
+        assert!(!repo
+            .find(&third.key_id)
+            .await
+            .unwrap()
+            .is_active_at(Utc::now()));
+        assert!(repo
+            .revoke(Some("merchant123"), &third.key_id)
+            .await
+            .is_err());
+        assert!(repo
+            .revoke(Some("merchant456"), &second.key_id)
+            .await
+            .is_err());
+        assert!(repo.revoke(None, &second.key_id).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_staff_keys(pool: PgPool) {
+        let repo = ApiKeyRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let (merchant_key, _) = ApiKey::generate("merchant123");
+        repo.insert_one(&merchant_key).await.unwrap();
+        let (staff_key, _) = ApiKey::generate_staff(Role::Admin).unwrap();
+        repo.insert_one(&staff_key).await.unwrap();
+        let found = repo.find(&staff_key.key_id).await.unwrap();
+        assert_eq!(found.role, Role::Admin);
+        assert_eq!(found.merchant_id, None);
+        let staff = repo.list_staff().await.unwrap();
+        assert_eq!(staff.len(), 1);
+        assert_eq!(staff[0].key_id, staff_key.key_id);
+        assert!(repo
+            .revoke(Some("merchant123"), &staff_key.key_id)
+            .await
+            .is_err());
+        repo.revoke(None, &staff_key.key_id).await.unwrap();
+        assert!(!repo
+            .find(&staff_key.key_id)
+            .await
+            .unwrap()
+            .is_active_at(Utc::now()));
+    }
+}
@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::{
+    postgres::{PgArguments, PgRow},
+    query::Query,
+    FromRow, PgPool, Postgres, QueryBuilder, Row,
+};
+
+use crate::{
+    block_list::{EntryKind, ListCheck, ListEntry, ListMatches, ListType},
+    error::Error,
+};
+
+use super::{Entity, Pool, Repo};
+
+#[derive(Debug)]
+pub struct BlockListRepo {
+    pub pool: Arc<Pool>,
+}
+
+impl Repo for BlockListRepo {
+    type Entity = ListEntry;
+
+    type Id = String;
+
+    fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for ListEntry {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let decode_error = |index: &str, e| sqlx::Error::ColumnDecode {
+            index: index.into(),
+            source: Box::new(e),
+        };
+        Ok(ListEntry {
+            id: row.try_get("id")?,
+            list: ListType::try_from(row.try_get::<String, _>("list")?)
+                .map_err(|e| decode_error("list", e))?,
+            merchant_id: row.try_get("merchant_id")?,
+            kind: EntryKind::try_from(row.try_get::<String, _>("kind")?)
+                .map_err(|e| decode_error("kind", e))?,
+            value: row.try_get("value")?,
+            reason: row.try_get("reason")?,
+            expires_at: row.try_get("expires_at")?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl Entity for ListEntry {
+    fn values_str_for_insert(&self) -> String {
+        "$1, $2, $3, $4, $5, $6, $7, $8".into()
+    }
+
+    fn bind_to_insert<'a>(
+        &'a self,
+        stmt: Query<'a, Postgres, PgArguments>,
+    ) -> Query<'a, Postgres, PgArguments> {
+        stmt.bind(self.id.clone())
+            .bind(self.list.to_string())
+            .bind(self.merchant_id.clone())
+            .bind(self.kind.to_string())
+            .bind(self.value.clone())
+            .bind(self.reason.clone())
+            .bind(self.expires_at)
+            .bind(self.created_at)
+    }
+
+    /// Only the reason and expiry of an entry can change
+    fn values_str_for_update(&self) -> String {
+        "reason = $2, expires_at = $3".into()
+    }
+
+    fn bind_to_update<'a>(
+        &'a self,
+        stmt: Query<'a, Postgres, PgArguments>,
+    ) -> Query<'a, Postgres, PgArguments> {
+        stmt.bind(self.reason.clone()).bind(self.expires_at)
+    }
+
+    fn table_name(&self) -> &'static str {
+        "fraud.list_entries"
+    }
+}
+
+impl BlockListRepo {
+    /// Lists entries newest first, including expired ones. Without a merchant, every entry is
+    /// listed.
+    pub async fn list(
+        &self,
+        merchant_id: Option<&str>,
+        list: Option<ListType>,
+    ) -> Result<Vec<ListEntry>, Error> {
+        let mut query =
+            QueryBuilder::<Postgres>::new("SELECT * FROM fraud.list_entries WHERE TRUE");
+        if let Some(merchant_id) = merchant_id {
+            query
+                .push(" AND merchant_id = ")
+                .push_bind(merchant_id.to_string());
+        }
+        if let Some(list) = list {
+            query.push(" AND list = ").push_bind(list.to_string());
+        }
+        query.push(" ORDER BY created_at DESC, id");
+        let entries = query
+            .build_query_as::<ListEntry>()
+            .fetch_all(&**self.pool)
+            .await?;
+        Ok(entries)
+    }
+
+    /// The unexpired entries, global or the merchant's own, matching any of the checked values
+    pub async fn find_matches(
+        &self,
+        merchant_id: &str,
+        check: &ListCheck<'_>,
+        at: DateTime<Utc>,
+    ) -> Result<ListMatches, Error> {
+        let entries = sqlx::query_as(
+            "SELECT * FROM fraud.list_entries \
+             WHERE (merchant_id IS NULL OR merchant_id = $1) \
+             AND (expires_at IS NULL OR expires_at > $2) \
+             AND ((kind = 'card' AND value = $3) \
+             OR (kind = 'bin' AND $4 LIKE value || '%') \
+             OR (kind = 'email' AND value = lower($5)) \
+             OR (kind = 'ip' AND value = $6)) \
+             ORDER BY created_at, id",
+        )
+        .bind(merchant_id)
+        .bind(at)
+        .bind(check.card_fingerprint)
+        .bind(check.pan)
+        .bind(check.email.filter(|email| !email.is_empty()))
+        .bind(check.ip.map(|ip| ip.to_string()))
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(ListMatches { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use sqlx::PgPool;
+
+    fn entry(list: ListType, merchant_id: Option<&str>, kind: EntryKind, value: &str) -> ListEntry {
+        ListEntry::new(
+            list,
+            merchant_id.map(String::from),
+            kind,
+            value,
+            "test".into(),
+            None,
+        )
+        .unwrap()
+    }
+
+    /// The values of the matching entries
+    async fn find(repo: &BlockListRepo, merchant_id: &str, check: ListCheck<'_>) -> Vec<String> {
+        repo.find_matches(merchant_id, &check, Utc::now())
+            .await
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|e| e.value)
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn test_find_matches(pool: PgPool) {
+        sqlx::query("INSERT INTO account.merchant (id, name) VALUES ('merchant456', 'Other')")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let repo = BlockListRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let fingerprint = "a".repeat(64);
+        let mut expired = entry(ListType::Block, None, EntryKind::Email, "old@example.com");
+        expired.expires_at = Some(Utc::now() - TimeDelta::minutes(1));
+        let entries = [
+            entry(ListType::Block, None, EntryKind::Card, &fingerprint),
+            entry(
+                ListType::Block,
+                Some("merchant123"),
+                EntryKind::Bin,
+                "400011",
+            ),
+            entry(
+                ListType::Allow,
+                Some("merchant123"),
+                EntryKind::Email,
+                "vip@example.com",
+            ),
+            entry(
+                ListType::Block,
+                Some("merchant456"),
+                EntryKind::Ip,
+                "10.0.0.1",
+            ),
+            expired,
+        ];
+        for entry in entries.iter() {
+            repo.insert_one(entry).await.unwrap();
+        }
+        assert_eq!(
+            find(
+                &repo,
+                "merchant123",
+                ListCheck {
+                    card_fingerprint: Some(&fingerprint),
+                    pan: Some("4000111122223333"),
+                    email: Some("VIP@example.com"),
+                    ip: Some("10.0.0.1".parse().unwrap()),
+                },
+            )
+            .await,
+            vec![fingerprint.as_str(), "400011", "vip@example.com"]
+        );
+        assert_eq!(
+            find(
+                &repo,
+                "merchant456",
+                ListCheck {
+                    pan: Some("4000111122223333"),
+                    ip: Some("10.0.0.1".parse().unwrap()),
+                    email: Some("old@example.com"),
+                    ..Default::default()
+                },
+            )
+            .await,
+            vec!["10.0.0.1"]
+        );
+        assert!(find(&repo, "merchant123", ListCheck::default())
+            .await
+            .is_empty());
+        assert_eq!(repo.list(None, None).await.unwrap().len(), 5);
+        assert_eq!(repo.list(Some("merchant123"), None).await.unwrap().len(), 2);
+        let allowed = repo.list(None, Some(ListType::Allow)).await.unwrap();
+        assert_eq!(allowed.len(), 1);
+        assert_eq!(allowed[0].id, entries[2].id);
+        repo.delete_one(&entries[0].id, "fraud.list_entries")
+            .await
+            .unwrap();
+        assert_eq!(repo.list(None, None).await.unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,351 @@
+use std::sync::Arc;
+
+use sqlx::{postgres::PgRow, types::Json, FromRow, Row};
+
+use crate::{
+    card_scheme::CardScheme,
+    customer::{Address, Customer},
+    error::Error,
+    payment_method::PaymentMethod,
+};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct CustomerRepo {
+    pub pool: Arc<Pool>,
+}
+
+impl<'r> FromRow<'r, PgRow> for Customer {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Customer {
+            customer_id: row.try_get("id")?,
+            merchant_id: row.try_get("merchant_id")?,
+            first_name: row.try_get("first_name")?,
+            last_name: row.try_get("last_name")?,
+            email: row.try_get("email")?,
+            phone: row.try_get("phone")?,
+            addresses: row.try_get::<Json<Vec<Address>>, _>("addresses")?.0,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for PaymentMethod {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let scheme = row
+            .try_get::<Option<String>, _>("card_scheme")?
+            .map(CardScheme::try_from)
+            .transpose()
+            .map_err(|e| sqlx::Error::ColumnDecode {
+                index: "card_scheme".into(),
+                source: Box::new(e),
+            })?;
+        let expiry_year = row.try_get::<Option<i32>, _>("expiry_year")?;
+        let expiry_month = row.try_get::<Option<i16>, _>("expiry_month")?;
+        Ok(PaymentMethod {
+            token: row.try_get("token")?,
+            customer_id: row.try_get("customer_id")?,
+            merchant_id: row.try_get("merchant_id")?,
+            scheme,
+            masked_number: row.try_get("masked_number")?,
+            expiry_date: expiry_year
+                .zip(expiry_month)
+                .map(|(year, month)| (year as u32, month as u8)),
+            card_fingerprint: row.try_get("card_fingerprint")?,
+            sealed: row.try_get("sealed")?,
+            created_at: row.try_get("created_at")?,
+            scheme_transaction_id: row.try_get("scheme_transaction_id")?,
+        })
+    }
+}
+
+impl CustomerRepo {
+    pub async fn insert(&self, customer: &Customer) -> Result<(), Error> {
+        sqlx::query("INSERT INTO account.customers VALUES ($1, $2, $3, $4, $5, $6, $7, $8)")
+            .bind(&customer.customer_id)
+            .bind(&customer.merchant_id)
+            .bind(&customer.first_name)
+            .bind(&customer.last_name)
+            .bind(&customer.email)
+            .bind(&customer.phone)
+            .bind(Json(&customer.addresses))
+            .bind(customer.created_at)
+            .execute(&**self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Finds one of the merchant's customers, other merchants' customers are never found
+    pub async fn find(&self, merchant_id: &str, customer_id: &str) -> Result<Customer, Error> {
+        let customer =
+            sqlx::query_as("SELECT * FROM account.customers WHERE merchant_id = $1 AND id = $2")
+                .bind(merchant_id)
+                .bind(customer_id)
+                .fetch_one(&**self.pool)
+                .await?;
+        Ok(customer)
+    }
+
+    /// Lists the merchant's customers oldest first
+    pub async fn list_for(&self, merchant_id: &str) -> Result<Vec<Customer>, Error> {
+        let customers = sqlx::query_as(
+            "SELECT * FROM account.customers WHERE merchant_id = $1 ORDER BY created_at, id",
+        )
+        .bind(merchant_id)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(customers)
+    }
+
+    /// Replaces the customer's details, their payment methods are left as they are
+    pub async fn update(&self, customer: &Customer) -> Result<(), Error> {
+        let res = sqlx::query(
+            "UPDATE account.customers SET first_name = $3, last_name = $4, email = $5, \
+             phone = $6, addresses = $7 WHERE merchant_id = $1 AND id = $2",
+        )
+        .bind(&customer.merchant_id)
+        .bind(&customer.customer_id)
+        .bind(&customer.first_name)
+        .bind(&customer.last_name)
+        .bind(&customer.email)
+        .bind(&customer.phone)
+        .bind(Json(&customer.addresses))
+        .execute(&**self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    /// Deletes the customer along with their payment methods, their transactions are kept
+    pub async fn delete(&self, merchant_id: &str, customer_id: &str) -> Result<(), Error> {
+        let res = sqlx::query("DELETE FROM account.customers WHERE merchant_id = $1 AND id = $2")
+            .bind(merchant_id)
+            .bind(customer_id)
+            .execute(&**self.pool)
+            .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    pub async fn insert_payment_method(&self, method: &PaymentMethod) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO account.payment_methods \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+        )
+        .bind(&method.token)
+        .bind(&method.customer_id)
+        .bind(&method.merchant_id)
+        .bind(method.scheme.map(|s| s.to_string()))
+        .bind(&method.masked_number)
+        .bind(method.expiry_date.map(|(year, _)| year as i32))
+        .bind(method.expiry_date.map(|(_, month)| month as i16))
+        .bind(&method.card_fingerprint)
+        .bind(&method.sealed)
+        .bind(method.created_at)
+        .bind(&method.scheme_transaction_id)
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Lists the customer's payment methods oldest first
+    pub async fn payment_methods(
+        &self,
+        merchant_id: &str,
+        customer_id: &str,
+    ) -> Result<Vec<PaymentMethod>, Error> {
+        let methods = sqlx::query_as(
+            "SELECT * FROM account.payment_methods WHERE merchant_id = $1 AND customer_id = $2 \
+             ORDER BY created_at, token",
+        )
+        .bind(merchant_id)
+        .bind(customer_id)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(methods)
+    }
+
+    pub async fn find_payment_method(
+        &self,
+        merchant_id: &str,
+        customer_id: &str,
+        token: &str,
+    ) -> Result<PaymentMethod, Error> {
+        let method = sqlx::query_as(
+            "SELECT * FROM account.payment_methods \
+             WHERE merchant_id = $1 AND customer_id = $2 AND token = $3",
+        )
+        .bind(merchant_id)
+        .bind(customer_id)
+        .bind(token)
+        .fetch_one(&**self.pool)
+        .await?;
+        Ok(method)
+    }
+
+    /// Saves the scheme transaction ID later transactions with the payment method are chained to
+    pub async fn chain_payment_method(
+        &self,
+        merchant_id: &str,
+        token: &str,
+        scheme_transaction_id: &str,
+    ) -> Result<(), Error> {
+        let res = sqlx::query(
+            "UPDATE account.payment_methods SET scheme_transaction_id = $3 \
+             WHERE merchant_id = $1 AND token = $2",
+        )
+        .bind(merchant_id)
+        .bind(token)
+        .bind(scheme_transaction_id)
+        .execute(&**self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    pub async fn delete_payment_method(
+        &self,
+        merchant_id: &str,
+        customer_id: &str,
+        token: &str,
+    ) -> Result<(), Error> {
+        let res = sqlx::query(
+            "DELETE FROM account.payment_methods \
+             WHERE merchant_id = $1 AND customer_id = $2 AND token = $3",
+        )
+        .bind(merchant_id)
+        .bind(customer_id)
+        .bind(token)
+        .execute(&**self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{payment::Payment, secret::Secret};
+    use sqlx::PgPool;
+
+    fn repo(pool: PgPool) -> CustomerRepo {
+        CustomerRepo {
+            pool: Arc::new(Pool::from(pool)),
+        }
+    }
+
+    fn customer() -> Customer {
+        Customer {
+            first_name: "Jo".into(),
+            email: "jo@example.com".into(),
+            addresses: vec![Address {
+                premise: "10".into(),
+                street: "Downing Street".into(),
+                ..Default::default()
+            }],
+            ..Customer::new("merchant123".into())
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_customers(pool: PgPool) {
+        let repo = repo(pool);
+        let mut customer = customer();
+        repo.insert(&customer).await.unwrap();
+        assert_eq!(
+            repo.find("merchant123", &customer.customer_id)
+                .await
+                .unwrap(),
+            customer
+        );
+        assert!(repo
+            .find("merchant456", &customer.customer_id)
+            .await
+            .is_err());
+        customer.phone = "01134960000".into();
+        customer.addresses.clear();
+        repo.update(&customer).await.unwrap();
+        assert_eq!(
+            repo.list_for("merchant123").await.unwrap(),
+            vec![customer.clone()]
+        );
+        assert_eq!(repo.list_for("merchant456").await.unwrap(), vec![]);
+        repo.delete("merchant123", &customer.customer_id)
+            .await
+            .unwrap();
+        assert!(repo.update(&customer).await.is_err());
+        assert!(repo
+            .delete("merchant123", &customer.customer_id)
+            .await
+            .is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_payment_methods(pool: PgPool) {
+        let repo = repo(pool);
+        let customer = customer();
+        repo.insert(&customer).await.unwrap();
+        let key: Secret<String> = "payment-method-key".into();
+        let card = Payment::from((CardScheme::Visa, (2030, 1), "123", "4000111122223333"));
+        let method = PaymentMethod::new(
+            customer.customer_id.clone(),
+            customer.merchant_id.clone(),
+            &card,
+            &key,
+            &"fingerprint-key".into(),
+        )
+        .unwrap();
+        repo.insert_payment_method(&method).await.unwrap();
+        let found = repo
+            .find_payment_method("merchant123", &customer.customer_id, &method.token)
+            .await
+            .unwrap();
+        assert_eq!(found, method);
+        assert!(
+            matches!(found.payment(&key).unwrap(), Payment::Card { pan, .. } if pan == "4000111122223333")
+        );
+        assert!(repo
+            .find_payment_method("merchant456", &customer.customer_id, &method.token)
+            .await
+            .is_err());
+        assert_eq!(
+            repo.payment_methods("merchant123", &customer.customer_id)
+                .await
+                .unwrap(),
+            vec![method.clone()]
+        );
+        repo.chain_payment_method("merchant123", &method.token, "123456789012345")
+            .await
+            .unwrap();
+        let found = repo
+            .find_payment_method("merchant123", &customer.customer_id, &method.token)
+            .await
+            .unwrap();
+        assert_eq!(
+            found.scheme_transaction_id.as_deref(),
+            Some("123456789012345")
+        );
+        assert!(repo
+            .chain_payment_method("merchant456", &method.token, "123456789012345")
+            .await
+            .is_err());
+        // deleting the customer deletes their payment methods
+        repo.delete("merchant123", &customer.customer_id)
+            .await
+            .unwrap();
+        assert!(repo
+            .delete_payment_method("merchant123", &customer.customer_id, &method.token)
+            .await
+            .is_err());
+    }
+}
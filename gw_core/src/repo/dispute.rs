@@ -0,0 +1,391 @@
+use std::sync::Arc;
+
+use sqlx::{postgres::PgRow, FromRow, PgConnection, Row};
+
+use crate::{
+    card_scheme::CardScheme,
+    currency::Currency,
+    dispute::{Adjustment, AdjustmentReason, Dispute, DisputeStatus, Evidence, EvidenceType},
+    error::Error,
+};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct DisputeRepo {
+    pub pool: Arc<Pool>,
+}
+
+fn decode<T, E>(column: &str, value: Result<T, E>) -> Result<T, sqlx::Error>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    value.map_err(|e| sqlx::Error::ColumnDecode {
+        index: column.into(),
+        source: Box::new(e),
+    })
+}
+
+// evidence and adjustments are in their own tables, see `DisputeRepo::with_details`
+impl<'r> FromRow<'r, PgRow> for Dispute {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Dispute {
+            dispute_id: row.try_get("id")?,
+            merchant_id: row.try_get("merchant_id")?,
+            transaction_reference: row.try_get("transaction_reference")?,
+            acquirer: row.try_get("acquirer")?,
+            case_id: row.try_get("case_id")?,
+            scheme: decode(
+                "scheme",
+                CardScheme::try_from(row.try_get::<String, _>("scheme")?),
+            )?,
+            reason_code: row.try_get("reason_code")?,
+            amount: row.try_get::<i64, _>("amount")? as u64,
+            currency: decode(
+                "currency",
+                Currency::try_from(row.try_get::<String, _>("currency")?),
+            )?,
+            status: decode(
+                "status",
+                DisputeStatus::try_from(row.try_get::<String, _>("status")?),
+            )?,
+            respond_by: row.try_get("respond_by")?,
+            evidence: vec![],
+            adjustments: vec![],
+            created_at: row.try_get("created_at")?,
+            submitted_at: row.try_get("submitted_at")?,
+            resolved_at: row.try_get("resolved_at")?,
+        })
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for Evidence {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Evidence {
+            evidence_id: row.try_get("id")?,
+            dispute_id: row.try_get("dispute_id")?,
+            evidence_type: decode(
+                "evidence_type",
+                EvidenceType::try_from(row.try_get::<String, _>("evidence_type")?),
+            )?,
+            file_name: row.try_get("file_name")?,
+            content_type: row.try_get("content_type")?,
+            size_bytes: row.try_get::<i64, _>("size_bytes")? as u64,
+            description: row.try_get("description")?,
+            uploaded_at: row.try_get("uploaded_at")?,
+        })
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for Adjustment {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Adjustment {
+            adjustment_id: row.try_get("id")?,
+            transaction_reference: row.try_get("transaction_reference")?,
+            merchant_id: row.try_get("merchant_id")?,
+            dispute_id: row.try_get("dispute_id")?,
+            reason: decode(
+                "reason",
+                AdjustmentReason::try_from(row.try_get::<String, _>("reason")?),
+            )?,
+            amount: row.try_get("amount")?,
+            currency: decode(
+                "currency",
+                Currency::try_from(row.try_get::<String, _>("currency")?),
+            )?,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl DisputeRepo {
+    /// Stores the dispute with its adjustments unless the acquirer has reported the case
+    /// before, returning whether it was stored
+    pub async fn insert(&self, dispute: &Dispute) -> Result<bool, Error> {
+        let mut tx = self.pool.begin().await?;
+        let res = sqlx::query(
+            "INSERT INTO transaction.disputes \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14) \
+             ON CONFLICT (acquirer, case_id) DO NOTHING",
+        )
+        .bind(&dispute.dispute_id)
+        .bind(&dispute.merchant_id)
+        .bind(&dispute.transaction_reference)
+        .bind(&dispute.acquirer)
+        .bind(&dispute.case_id)
+        .bind(dispute.scheme.to_string())
+        .bind(&dispute.reason_code)
+        .bind(dispute.amount as i64)
+        .bind(dispute.currency.to_string())
+        .bind(dispute.status.to_string())
+        .bind(dispute.respond_by)
+        .bind(dispute.created_at)
+        .bind(dispute.submitted_at)
+        .bind(dispute.resolved_at)
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Ok(false);
+        }
+        insert_adjustments(&mut tx, &dispute.adjustments).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    pub async fn find(&self, merchant_id: &str, dispute_id: &str) -> Result<Dispute, Error> {
+        let dispute =
+            sqlx::query_as("SELECT * FROM transaction.disputes WHERE merchant_id = $1 AND id = $2")
+                .bind(merchant_id)
+                .bind(dispute_id)
+                .fetch_one(&**self.pool)
+                .await?;
+        let mut disputes = self.with_details(vec![dispute]).await?;
+        Ok(disputes.remove(0))
+    }
+
+    /// The dispute the acquirer knows by the case id, if it has reported it before
+    pub async fn find_by_case(
+        &self,
+        acquirer: &str,
+        case_id: &str,
+    ) -> Result<Option<Dispute>, Error> {
+        let dispute: Option<Dispute> = sqlx::query_as(
+            "SELECT * FROM transaction.disputes WHERE acquirer = $1 AND case_id = $2",
+        )
+        .bind(acquirer)
+        .bind(case_id)
+        .fetch_optional(&**self.pool)
+        .await?;
+        let Some(dispute) = dispute else {
+            return Ok(None);
+        };
+        let mut disputes = self.with_details(vec![dispute]).await?;
+        Ok(disputes.pop())
+    }
+
+    /// Lists the merchant's disputes newest first, only those with the status if one is given
+    pub async fn list(
+        &self,
+        merchant_id: &str,
+        status: Option<DisputeStatus>,
+        limit: i64,
+    ) -> Result<Vec<Dispute>, Error> {
+        let disputes = sqlx::query_as(
+            "SELECT * FROM transaction.disputes \
+             WHERE merchant_id = $1 AND ($2::text IS NULL OR status = $2) \
+             ORDER BY created_at DESC, id DESC LIMIT $3",
+        )
+        .bind(merchant_id)
+        .bind(status.map(|s| s.to_string()))
+        .bind(limit)
+        .fetch_all(&**self.pool)
+        .await?;
+        self.with_details(disputes).await
+    }
+
+    pub async fn insert_evidence(&self, evidence: &Evidence) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO transaction.dispute_evidence VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(&evidence.evidence_id)
+        .bind(&evidence.dispute_id)
+        .bind(evidence.evidence_type.to_string())
+        .bind(&evidence.file_name)
+        .bind(&evidence.content_type)
+        .bind(evidence.size_bytes as i64)
+        .bind(&evidence.description)
+        .bind(evidence.uploaded_at)
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Saves where the dispute is up to along with any adjustments made since it was stored.
+    /// Evidence is stored as it's added, see [`DisputeRepo::insert_evidence`].
+    pub async fn update(&self, dispute: &Dispute) -> Result<(), Error> {
+        let mut tx = self.pool.begin().await?;
+        let res = sqlx::query(
+            "UPDATE transaction.disputes SET status = $3, submitted_at = $4, resolved_at = $5 \
+             WHERE merchant_id = $1 AND id = $2",
+        )
+        .bind(&dispute.merchant_id)
+        .bind(&dispute.dispute_id)
+        .bind(dispute.status.to_string())
+        .bind(dispute.submitted_at)
+        .bind(dispute.resolved_at)
+        .execute(&mut *tx)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        insert_adjustments(&mut tx, &dispute.adjustments).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The changes made to the transaction's net amount, oldest first. A reversal made at the
+    /// same moment as its chargeback still comes after it.
+    pub async fn list_adjustments(&self, reference: &str) -> Result<Vec<Adjustment>, Error> {
+        let adjustments = sqlx::query_as(
+            "SELECT * FROM transaction.adjustments WHERE transaction_reference = $1 \
+             ORDER BY created_at, reason, id",
+        )
+        .bind(reference)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(adjustments)
+    }
+
+    /// Fills in the disputes' evidence and adjustments, oldest first
+    async fn with_details(&self, mut disputes: Vec<Dispute>) -> Result<Vec<Dispute>, Error> {
+        let ids: Vec<&str> = disputes.iter().map(|d| d.dispute_id.as_str()).collect();
+        let evidence: Vec<Evidence> = sqlx::query_as(
+            "SELECT * FROM transaction.dispute_evidence WHERE dispute_id = ANY($1) \
+             ORDER BY uploaded_at, id",
+        )
+        .bind(&ids)
+        .fetch_all(&**self.pool)
+        .await?;
+        let adjustments: Vec<Adjustment> = sqlx::query_as(
+            "SELECT * FROM transaction.adjustments WHERE dispute_id = ANY($1) \
+             ORDER BY created_at, reason, id",
+        )
+        .bind(&ids)
+        .fetch_all(&**self.pool)
+        .await?;
+        for dispute in disputes.iter_mut() {
+            dispute.evidence = evidence
+                .iter()
+                .filter(|e| e.dispute_id == dispute.dispute_id)
+                .cloned()
+                .collect();
+            dispute.adjustments = adjustments
+                .iter()
+                .filter(|a| a.dispute_id.as_ref() == Some(&dispute.dispute_id))
+                .cloned()
+                .collect();
+        }
+        Ok(disputes)
+    }
+}
+
+/// Adjustments are never changed once made, so ones already stored are left alone
+async fn insert_adjustments(
+    conn: &mut PgConnection,
+    adjustments: &[Adjustment],
+) -> Result<(), Error> {
+    for adjustment in adjustments {
+        sqlx::query(
+            "INSERT INTO transaction.adjustments VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (id) DO NOTHING",
+        )
+        .bind(&adjustment.adjustment_id)
+        .bind(&adjustment.transaction_reference)
+        .bind(&adjustment.merchant_id)
+        .bind(&adjustment.dispute_id)
+        .bind(adjustment.reason.to_string())
+        .bind(adjustment.amount)
+        .bind(adjustment.currency.to_string())
+        .bind(adjustment.created_at)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dispute::DisputeReport,
+        transaction::{search::TransactionSummary, TransactionStatus, TransactionType},
+    };
+    use chrono::{SubsecRound, TimeDelta, Utc};
+    use sqlx::PgPool;
+    use std::collections::BTreeMap;
+
+    fn transaction() -> TransactionSummary {
+        TransactionSummary {
+            reference: "ref123".into(),
+            merchant_id: "merchant123".into(),
+            acquirer: "bankone".into(),
+            r#type: TransactionType::Auth,
+            status: TransactionStatus::Settled,
+            amount: 1000,
+            currency: Currency::GBP,
+            card_scheme: Some(CardScheme::Mastercard),
+            masked_pan: None,
+            merchant_reference: None,
+            description: None,
+            metadata: BTreeMap::new(),
+            created_at: Utc::now().trunc_subsecs(6),
+            processed_at: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_disputes(pool: PgPool) {
+        let repo = DisputeRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let report = DisputeReport {
+            acquirer: "bankone".into(),
+            case_id: "case123".into(),
+            transaction_reference: "ref123".into(),
+            reason_code: "4853".into(),
+            amount: Some(400),
+            respond_by: None,
+            outcome: None,
+        };
+        let now = Utc::now();
+        let mut dispute = Dispute::open(&report, &transaction(), now).unwrap();
+        assert_eq!(repo.find_by_case("bankone", "case123").await.unwrap(), None);
+        assert!(repo.insert(&dispute).await.unwrap());
+        // the same case reported again isn't a new dispute
+        let again = Dispute::open(&report, &transaction(), now).unwrap();
+        assert!(!repo.insert(&again).await.unwrap());
+        assert_eq!(
+            repo.find_by_case("bankone", "case123").await.unwrap(),
+            Some(dispute.clone())
+        );
+        assert!(repo.find("merchant456", &dispute.dispute_id).await.is_err());
+
+        let evidence = Evidence::new(
+            dispute.dispute_id.clone(),
+            EvidenceType::Receipt,
+            "receipt.png".into(),
+            "image/png".into(),
+            1024,
+            now,
+        );
+        dispute.add_evidence(evidence.clone(), now).unwrap();
+        repo.insert_evidence(&evidence).await.unwrap();
+        dispute.submit(now).unwrap();
+        repo.update(&dispute).await.unwrap();
+        assert_eq!(
+            repo.list("merchant123", Some(DisputeStatus::EvidenceSubmitted), 10)
+                .await
+                .unwrap(),
+            vec![dispute.clone()]
+        );
+        assert_eq!(
+            repo.list("merchant123", Some(DisputeStatus::Received), 10)
+                .await
+                .unwrap(),
+            vec![]
+        );
+
+        dispute.resolve(DisputeStatus::Won, now + TimeDelta::days(1));
+        repo.update(&dispute).await.unwrap();
+        assert_eq!(
+            repo.find("merchant123", &dispute.dispute_id).await.unwrap(),
+            dispute
+        );
+        let adjustments = repo.list_adjustments("ref123").await.unwrap();
+        assert_eq!(
+            adjustments.iter().map(|a| a.amount).collect::<Vec<_>>(),
+            vec![-400, 400]
+        );
+        assert_eq!(repo.list("merchant123", None, 10).await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use sqlx::{postgres::PgRow, types::Json, FromRow, Row};
+
+use crate::{
+    country::Country,
+    error::Error,
+    fraud::{FraudRule, FraudSettings},
+};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct FraudRepo {
+    pub pool: Arc<Pool>,
+}
+
+impl<'r> FromRow<'r, PgRow> for FraudSettings {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(FraudSettings {
+            merchant_id: row.try_get("merchant_id")?,
+            review_score: row.try_get::<i32, _>("review_score")? as u32,
+            reject_score: row.try_get::<i32, _>("reject_score")? as u32,
+            rules: row.try_get::<Json<Vec<FraudRule>>, _>("rules")?.0,
+        })
+    }
+}
+
+impl FraudRepo {
+    /// The merchant's fraud settings, `None` if they haven't set any up
+    pub async fn find(&self, merchant_id: &str) -> Result<Option<FraudSettings>, Error> {
+        let settings = sqlx::query_as("SELECT * FROM fraud.merchant_rules WHERE merchant_id = $1")
+            .bind(merchant_id)
+            .fetch_optional(&**self.pool)
+            .await?;
+        Ok(settings)
+    }
+
+    /// Replaces all of the merchant's fraud settings
+    pub async fn upsert(&self, settings: &FraudSettings) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO fraud.merchant_rules VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (merchant_id) DO UPDATE SET \
+             review_score = EXCLUDED.review_score, \
+             reject_score = EXCLUDED.reject_score, \
+             rules = EXCLUDED.rules",
+        )
+        .bind(&settings.merchant_id)
+        .bind(settings.review_score as i32)
+        .bind(settings.reject_score as i32)
+        .bind(Json(&settings.rules))
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Where the card was issued, from the longest bin matching the pan. Countries the gateway
+    /// doesn't support are treated as unknown.
+    pub async fn bin_country(&self, pan: &str) -> Result<Option<Country>, Error> {
+        let country: Option<String> = sqlx::query_scalar(
+            "SELECT country FROM fraud.card_bins WHERE $1 LIKE bin || '%' \
+             ORDER BY length(bin) DESC LIMIT 1",
+        )
+        .bind(pan)
+        .fetch_optional(&**self.pool)
+        .await?;
+        Ok(country.and_then(|c| Country::try_from(c).ok()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{currency::Currency, fraud::RuleCondition};
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_find_and_upsert(pool: PgPool) {
+        let repo = FraudRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        assert_eq!(repo.find("merchant123").await.unwrap(), None);
+        let mut settings = FraudSettings {
+            rules: vec![FraudRule {
+                score: 60,
+                condition: RuleCondition::AmountOver {
+                    currency: Currency::GBP,
+                    amount: 10000,
+                },
+            }],
+            ..FraudSettings::new("merchant123".into())
+        };
+        repo.upsert(&settings).await.unwrap();
+        assert_eq!(
+            repo.find("merchant123").await.unwrap(),
+            Some(settings.clone())
+        );
+        settings.reject_score = 80;
+        settings.rules.push(FraudRule {
+            score: 30,
+            condition: RuleCondition::CountryMismatch,
+        });
+        repo.upsert(&settings).await.unwrap();
+        assert_eq!(
+            repo.find("merchant123").await.unwrap(),
+            Some(settings.clone())
+        );
+        settings.merchant_id = "nobody".into();
+        assert!(repo.upsert(&settings).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_bin_country(pool: PgPool) {
+        sqlx::query(
+            "INSERT INTO fraud.card_bins VALUES ('4000', 'US'), ('400011', 'GB'), ('5100', 'FR')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let repo = FraudRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        assert_eq!(
+            repo.bin_country("4000111122223333").await.unwrap(),
+            Some(Country::GB)
+        );
+        assert_eq!(
+            repo.bin_country("4000221122223333").await.unwrap(),
+            Some(Country::US)
+        );
+        assert_eq!(repo.bin_country("5100111122223333").await.unwrap(), None);
+        assert_eq!(repo.bin_country("6011111122223333").await.unwrap(), None);
+    }
+}
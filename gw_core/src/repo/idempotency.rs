@@ -0,0 +1,309 @@
+use std::sync::Arc;
+
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{
+    error::Error,
+    idempotency::{IdempotencyRecord, Reservation, StoredResponse},
+};
+
+use super::Pool;
+
+#[derive(Debug, Clone)]
+pub struct IdempotencyRepo {
+    pub pool: Arc<Pool>,
+    /// How long a key is remembered for, after which it can be reused
+    pub retention_secs: i64,
+    /// How long a key is held for a request still being processed, unless it's renewed. After
+    /// that the request is taken to have been abandoned, like when the gateway stopped part way
+    /// through, and the key can be reserved again.
+    pub lease_secs: i64,
+}
+
+impl<'r> FromRow<'r, PgRow> for IdempotencyRecord {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let status_code: Option<i32> = row.try_get("status_code")?;
+        let body: Option<String> = row.try_get("response_body")?;
+        let response = match (status_code, body) {
+            (Some(status_code), Some(body)) => Some(StoredResponse {
+                status_code: status_code as u16,
+                body,
+            }),
+            _ => None,
+        };
+        Ok(IdempotencyRecord {
+            merchant_id: row.try_get("merchant_id")?,
+            key: row.try_get("idempotency_key")?,
+            request_hash: row.try_get("request_hash")?,
+            response,
+        })
+    }
+}
+
+impl IdempotencyRepo {
+    /// Claims the key for this merchant on behalf of the owner, or returns what has already
+    /// been stored against it. Keys older than the retention window, and reservations which
+    /// haven't been renewed within the lease and never got a response, are discarded first.
+    pub async fn reserve(
+        &self,
+        merchant_id: &str,
+        key: &str,
+        owner: &str,
+        request_hash: &str,
+    ) -> Result<Reservation, Error> {
+        sqlx::query(
+            "DELETE FROM transaction.idempotency WHERE merchant_id = $1 AND idempotency_key = $2 \
+             AND (created_at < now() - make_interval(secs => $3) \
+             OR (status_code IS NULL AND renewed_at < now() - make_interval(secs => $4)));",
+        )
+        .bind(merchant_id)
+        .bind(key)
+        .bind(self.retention_secs as f64)
+        .bind(self.lease_secs as f64)
+        .execute(&**self.pool)
+        .await?;
+        let inserted = sqlx::query("INSERT INTO transaction.idempotency (merchant_id, idempotency_key, request_hash, owner) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING;")
+            .bind(merchant_id)
+            .bind(key)
+            .bind(request_hash)
+            .bind(owner)
+            .execute(&**self.pool)
+            .await?;
+        if inserted.rows_affected() == 1 {
+            return Ok(Reservation::Reserved);
+        }
+        let existing = sqlx::query_as::<_, IdempotencyRecord>(
+            "SELECT * FROM transaction.idempotency WHERE merchant_id = $1 AND idempotency_key = $2;",
+        )
+        .bind(merchant_id)
+        .bind(key)
+        .fetch_one(&**self.pool)
+        .await?;
+        Ok(Reservation::Existing(existing))
+    }
+
+    /// Extends the owner's lease on a key it's still processing a request for. Returns false
+    /// when the owner no longer holds the key.
+    pub async fn renew(&self, merchant_id: &str, key: &str, owner: &str) -> Result<bool, Error> {
+        let renewed = sqlx::query(
+            "UPDATE transaction.idempotency SET renewed_at = now() WHERE merchant_id = $1 \
+             AND idempotency_key = $2 AND owner = $3 AND status_code IS NULL;",
+        )
+        .bind(merchant_id)
+        .bind(key)
+        .bind(owner)
+        .execute(&**self.pool)
+        .await?;
+        Ok(renewed.rows_affected() == 1)
+    }
+
+    /// Stores the response for a key the owner reserved so that repeats can be answered with
+    /// it. Returns false when the owner no longer holds the key, so nothing was stored.
+    pub async fn complete(
+        &self,
+        merchant_id: &str,
+        key: &str,
+        owner: &str,
+        response: &StoredResponse,
+    ) -> Result<bool, Error> {
+        let completed = sqlx::query("UPDATE transaction.idempotency SET status_code = $4, response_body = $5 WHERE merchant_id = $1 AND idempotency_key = $2 AND owner = $3 AND status_code IS NULL;")
+            .bind(merchant_id)
+            .bind(key)
+            .bind(owner)
+            .bind(response.status_code as i32)
+            .bind(&response.body)
+            .execute(&**self.pool)
+            .await?;
+        Ok(completed.rows_affected() == 1)
+    }
+
+    /// Frees a key the owner reserved, used when the request failed in a way that should be
+    /// retryable
+    pub async fn release(&self, merchant_id: &str, key: &str, owner: &str) -> Result<(), Error> {
+        sqlx::query(
+            "DELETE FROM transaction.idempotency WHERE merchant_id = $1 AND idempotency_key = $2 \
+             AND owner = $3 AND status_code IS NULL;",
+        )
+        .bind(merchant_id)
+        .bind(key)
+        .bind(owner)
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    fn repo(pool: PgPool, retention_secs: i64) -> IdempotencyRepo {
+        IdempotencyRepo {
+            pool: Arc::new(Pool::from(pool)),
+            retention_secs,
+            lease_secs: 60,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_reserve_then_replay(pool: PgPool) {
+        let repo = repo(pool, 3600);
+        let res = repo
+            .reserve("merchant123", "key1", "owner1", "hash")
+            .await
+            .unwrap();
+        assert_eq!(res, Reservation::Reserved);
+        let pending = repo
+            .reserve("merchant123", "key1", "owner1", "hash")
+            .await
+            .unwrap();
+        assert_eq!(
+            pending,
+            Reservation::Existing(IdempotencyRecord {
+                merchant_id: "merchant123".into(),
+                key: "key1".into(),
+                request_hash: "hash".into(),
+                response: None,
+            })
+        );
+        let stored = StoredResponse {
+            status_code: 201,
+            body: "{}".into(),
+        };
+        assert!(repo
+            .complete("merchant123", "key1", "owner1", &stored)
+            .await
+            .unwrap());
+        let Reservation::Existing(record) = repo
+            .reserve("merchant123", "key1", "owner1", "other")
+            .await
+            .unwrap()
+        else {
+            panic!("expected existing record");
+        };
+        assert!(!record.matches("other"));
+        assert_eq!(record.response, Some(stored));
+    }
+
+    #[sqlx::test]
+    async fn test_keys_are_per_merchant(pool: PgPool) {
+        let repo = repo(pool, 3600);
+        let res = repo
+            .reserve("merchant123", "key1", "owner1", "hash")
+            .await
+            .unwrap();
+        assert_eq!(res, Reservation::Reserved);
+        let res = repo
+            .reserve("merchant456", "key1", "owner1", "hash")
+            .await
+            .unwrap();
+        assert_eq!(res, Reservation::Reserved);
+    }
+
+    #[sqlx::test]
+    async fn test_expired_keys_can_be_reused(pool: PgPool) {
+        let repo = repo(pool.clone(), 60);
+        repo.reserve("merchant123", "key1", "owner1", "hash")
+            .await
+            .unwrap();
+        sqlx::query("UPDATE transaction.idempotency SET created_at = now() - interval '2 minutes'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let res = repo
+            .reserve("merchant123", "key1", "owner1", "other")
+            .await
+            .unwrap();
+        assert_eq!(res, Reservation::Reserved);
+    }
+
+    #[sqlx::test]
+    async fn test_abandoned_reservations_can_be_taken_over(pool: PgPool) {
+        let repo = repo(pool.clone(), 3600);
+        repo.reserve("merchant123", "key1", "owner1", "hash")
+            .await
+            .unwrap();
+        repo.reserve("merchant123", "key2", "owner1", "hash")
+            .await
+            .unwrap();
+        let stored = StoredResponse {
+            status_code: 201,
+            body: "{}".into(),
+        };
+        assert!(repo
+            .complete("merchant123", "key2", "owner1", &stored)
+            .await
+            .unwrap());
+        sqlx::query("UPDATE transaction.idempotency SET renewed_at = now() - interval '2 minutes'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let res = repo
+            .reserve("merchant123", "key1", "owner2", "hash")
+            .await
+            .unwrap();
+        assert_eq!(res, Reservation::Reserved);
+        // the abandoned request can't store its response over the new one's, or free its key
+        let late = StoredResponse {
+            status_code: 500,
+            body: "{}".into(),
+        };
+        assert!(!repo.renew("merchant123", "key1", "owner1").await.unwrap());
+        assert!(!repo
+            .complete("merchant123", "key1", "owner1", &late)
+            .await
+            .unwrap());
+        repo.release("merchant123", "key1", "owner1").await.unwrap();
+        assert!(repo
+            .complete("merchant123", "key1", "owner2", &stored)
+            .await
+            .unwrap());
+        // completed keys are kept for the whole retention window
+        let Reservation::Existing(record) = repo
+            .reserve("merchant123", "key2", "owner1", "hash")
+            .await
+            .unwrap()
+        else {
+            panic!("expected existing record");
+        };
+        assert_eq!(record.response, Some(stored));
+    }
+
+    #[sqlx::test]
+    async fn test_renewed_reservations_are_not_taken_over(pool: PgPool) {
+        let repo = repo(pool.clone(), 3600);
+        repo.reserve("merchant123", "key1", "owner1", "hash")
+            .await
+            .unwrap();
+        sqlx::query(
+            "UPDATE transaction.idempotency SET created_at = now() - interval '2 minutes', \
+             renewed_at = now() - interval '2 minutes'",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        // still being processed, just slowly
+        assert!(repo.renew("merchant123", "key1", "owner1").await.unwrap());
+        let res = repo
+            .reserve("merchant123", "key1", "owner2", "hash")
+            .await
+            .unwrap();
+        assert!(matches!(res, Reservation::Existing(record) if record.response.is_none()));
+        assert!(!repo.renew("merchant123", "key1", "owner2").await.unwrap());
+    }
+
+    #[sqlx::test]
+    async fn test_release(pool: PgPool) {
+        let repo = repo(pool, 3600);
+        repo.reserve("merchant123", "key1", "owner1", "hash")
+            .await
+            .unwrap();
+        repo.release("merchant123", "key1", "owner1").await.unwrap();
+        let res = repo
+            .reserve("merchant123", "key1", "owner1", "hash")
+            .await
+            .unwrap();
+        assert_eq!(res, Reservation::Reserved);
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{
+    error::Error,
+    limits::{MerchantLimits, VelocityLimits},
+};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct LimitsRepo {
+    pub pool: Arc<Pool>,
+}
+
+impl<'r> FromRow<'r, PgRow> for MerchantLimits {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(MerchantLimits {
+            merchant_id: row.try_get("merchant_id")?,
+            requests_per_minute: row
+                .try_get::<Option<i32>, _>("requests_per_minute")?
+                .map(|n| n as u32),
+            velocity: VelocityLimits {
+                max_card_transactions_per_hour: row
+                    .try_get::<Option<i32>, _>("max_card_transactions_per_hour")?
+                    .map(|n| n as u32),
+                max_card_amount_per_hour: row
+                    .try_get::<Option<i64>, _>("max_card_amount_per_hour")?
+                    .map(|n| n as u64),
+            },
+        })
+    }
+}
+
+impl LimitsRepo {
+    /// The merchant's limits, with every limit unset if none have been configured
+    pub async fn find(&self, merchant_id: &str) -> Result<MerchantLimits, Error> {
+        let limits = sqlx::query_as("SELECT * FROM account.merchant_limits WHERE merchant_id = $1")
+            .bind(merchant_id)
+            .fetch_optional(&**self.pool)
+            .await?;
+        Ok(limits.unwrap_or_else(|| MerchantLimits {
+            merchant_id: merchant_id.into(),
+            ..Default::default()
+        }))
+    }
+
+    /// Replaces all of the merchant's limits
+    pub async fn upsert(&self, limits: &MerchantLimits) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO account.merchant_limits VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (merchant_id) DO UPDATE SET \
+             requests_per_minute = EXCLUDED.requests_per_minute, \
+             max_card_transactions_per_hour = EXCLUDED.max_card_transactions_per_hour, \
+             max_card_amount_per_hour = EXCLUDED.max_card_amount_per_hour",
+        )
+        .bind(&limits.merchant_id)
+        .bind(limits.requests_per_minute.map(|n| n as i32))
+        .bind(
+            limits
+                .velocity
+                .max_card_transactions_per_hour
+                .map(|n| n as i32),
+        )
+        .bind(limits.velocity.max_card_amount_per_hour.map(|n| n as i64))
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_find_and_upsert(pool: PgPool) {
+        let repo = LimitsRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let limits = repo.find("merchant123").await.unwrap();
+        assert_eq!(limits.merchant_id, "merchant123");
+        assert_eq!(limits.requests_per_minute, None);
+        assert_eq!(limits.velocity, VelocityLimits::default());
+        let mut limits = MerchantLimits {
+            merchant_id: "merchant123".into(),
+            requests_per_minute: Some(60),
+            velocity: VelocityLimits {
+                max_card_transactions_per_hour: Some(5),
+                max_card_amount_per_hour: None,
+            },
+        };
+        repo.upsert(&limits).await.unwrap();
+        assert_eq!(repo.find("merchant123").await.unwrap(), limits);
+        limits.requests_per_minute = None;
+        limits.velocity.max_card_amount_per_hour = Some(100_000);
+        repo.upsert(&limits).await.unwrap();
+        assert_eq!(repo.find("merchant123").await.unwrap(), limits);
+        limits.merchant_id = "nobody".into();
+        assert!(repo.upsert(&limits).await.is_err());
+    }
+}
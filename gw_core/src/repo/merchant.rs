@@ -1,11 +1,14 @@
 use std::sync::Arc;
 
-use crate::{error::Error, merchant::Merchant};
+use crate::{
+    error::Error,
+    merchant::{Merchant, MerchantPage},
+};
 use sqlx::{
     postgres::{PgArguments, PgRow},
     prelude::FromRow,
     query::Query,
-    PgPool, Postgres, Row,
+    PgPool, Postgres, QueryBuilder, Row,
 };
 
 use super::{Entity, Pool, Repo};
@@ -19,6 +22,52 @@ impl MerchantRepo {
     pub async fn find(&self, id: &str) -> Result<Merchant, Error> {
         self.select_one(&id.into(), "account.merchant").await
     }
+
+    /// Lists merchants ordered by id, starting after the cursor
+    pub async fn list(
+        &self,
+        active: Option<bool>,
+        cursor: Option<&str>,
+        limit: u32,
+    ) -> Result<MerchantPage, Error> {
+        let mut query = QueryBuilder::<Postgres>::new("SELECT * FROM account.merchant WHERE TRUE");
+        if let Some(active) = active {
+            query.push(" AND active = ").push_bind(active);
+        }
+        if let Some(cursor) = cursor {
+            query.push(" AND id > ").push_bind(cursor.to_owned());
+        }
+        // fetch one extra to find out whether there is another page
+        query
+            .push(" ORDER BY id LIMIT ")
+            .push_bind(limit as i64 + 1);
+        let mut merchants = query
+            .build_query_as::<Merchant>()
+            .fetch_all(&**self.pool)
+            .await?;
+        let next_cursor = if merchants.len() > limit as usize {
+            merchants.truncate(limit as usize);
+            merchants.last().map(|m| m.merchant_id.clone())
+        } else {
+            None
+        };
+        Ok(MerchantPage {
+            merchants,
+            next_cursor,
+        })
+    }
+
+    /// Stops the merchant from taking any more transactions, it is kept so its history remains
+    pub async fn deactivate(&self, id: &str) -> Result<(), Error> {
+        let res = sqlx::query("UPDATE account.merchant SET active = FALSE WHERE id = $1")
+            .bind(id)
+            .execute(&**self.pool)
+            .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
 }
 
 impl<'r> FromRow<'r, PgRow> for Merchant {
@@ -39,27 +88,38 @@ impl<'r> FromRow<'r, PgRow> for Merchant {
             postcode: row.try_get("postcode")?,
             county: row.try_get("county")?,
             country,
+            active: row.try_get("active")?,
         })
     }
 }
 
-impl Entity for Merchant {
-    fn values_str_for_insert(&self) -> String {
-        "id, name, premise, street, city, postcode, country, country".into()
-    }
-
-    fn bind_to_insert<'a>(
+impl Merchant {
+    /// Binds every column apart from the id, in table order
+    fn bind_values<'a>(
         &'a self,
         stmt: Query<'a, Postgres, PgArguments>,
     ) -> Query<'a, Postgres, PgArguments> {
-        stmt.bind(self.merchant_id.clone())
-            .bind(self.name.clone())
+        stmt.bind(self.name.clone())
             .bind(self.premise.clone())
             .bind(self.street.clone())
             .bind(self.city.clone())
             .bind(self.postcode.clone())
             .bind(self.county.clone())
             .bind(self.country.to_string())
+            .bind(self.active)
+    }
+}
+
+impl Entity for Merchant {
+    fn values_str_for_insert(&self) -> String {
+        "$1, $2, $3, $4, $5, $6, $7, $8, $9".into()
+    }
+
+    fn bind_to_insert<'a>(
+        &'a self,
+        stmt: Query<'a, Postgres, PgArguments>,
+    ) -> Query<'a, Postgres, PgArguments> {
+        self.bind_values(stmt.bind(self.merchant_id.clone()))
     }
 
     fn table_name(&self) -> &'static str {
@@ -67,14 +127,16 @@ impl Entity for Merchant {
     }
 
     fn values_str_for_update(&self) -> String {
-        todo!()
+        "name = $2, premise = $3, street = $4, city = $5, postcode = $6, county = $7, \
+         country = $8, active = $9"
+            .into()
     }
 
     fn bind_to_update<'a>(
         &'a self,
         stmt: Query<'a, Postgres, PgArguments>,
     ) -> Query<'a, Postgres, PgArguments> {
-        todo!()
+        self.bind_values(stmt)
     }
 }
 
@@ -86,3 +148,72 @@ impl Repo for MerchantRepo {
         &self.pool
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        country::Country,
+        error::{DbErrorKind, ErrorKind},
+    };
+
+    fn merchant(id: &str) -> Merchant {
+        Merchant {
+            merchant_id: id.into(),
+            name: "Name".into(),
+            premise: "1".into(),
+            street: "Street".into(),
+            city: "City".into(),
+            postcode: "AB1 2CD".into(),
+            county: "County".into(),
+            country: Country::US,
+            active: true,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_insert_update_deactivate(pool: PgPool) {
+        let repo = MerchantRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let mut m = merchant("merchant456");
+        let id = repo.insert_one(&m).await.unwrap();
+        assert_eq!(repo.find(&id).await.unwrap(), m);
+        let err = repo.insert_one(&m).await.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Database(DbErrorKind::Duplicate));
+        m.name = "New Name".into();
+        m.county = "New County".into();
+        repo.update_one(&id, &m).await.unwrap();
+        assert_eq!(repo.find(&id).await.unwrap(), m);
+        repo.deactivate(&id).await.unwrap();
+        assert!(!repo.find(&id).await.unwrap().active);
+        assert!(repo.deactivate("nobody").await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_list(pool: PgPool) {
+        let repo = MerchantRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        for id in ["a", "b", "c"] {
+            repo.insert_one(&merchant(id)).await.unwrap();
+        }
+        repo.deactivate("b").await.unwrap();
+        let ids = |page: &MerchantPage| {
+            page.merchants
+                .iter()
+                .map(|m| m.merchant_id.clone())
+                .collect::<Vec<_>>()
+        };
+        let first = repo.list(None, None, 2).await.unwrap();
+        assert_eq!(ids(&first), vec!["a", "b"]);
+        let second = repo
+            .list(None, first.next_cursor.as_deref(), 2)
+            .await
+            .unwrap();
+        assert_eq!(ids(&second), vec!["c", "merchant123"]);
+        assert_eq!(second.next_cursor, None);
+        let active = repo.list(Some(true), None, 10).await.unwrap();
+        assert_eq!(ids(&active), vec!["a", "c", "merchant123"]);
+    }
+}
@@ -1,6 +1,21 @@
 pub mod account;
+pub mod api_key;
+pub mod block_list;
+pub mod customer;
+pub mod dispute;
+pub mod fraud;
+pub mod idempotency;
+pub mod limits;
 pub mod merchant;
+pub mod notification;
+pub mod payment_route;
+pub mod policy;
+pub mod sca;
+pub mod signing;
+pub mod subscription;
+pub mod three_ds;
 pub mod transaction;
+pub mod webhook;
 
 use std::ops::Deref;
 
@@ -52,50 +67,70 @@ pub trait Repo {
     where
         for<'i> <Self as Repo>::Id: std::fmt::Display + Encode<'i, Postgres> + Type<Postgres>,
     {
+        let id_column = Self::Entity::ID_COLUMN;
         let res = query_as::<_, Self::Entity>(&format!(
-            "SELECT *, tableoid::regclass::text as table_name FROM {table} WHERE id = $1",
+            "SELECT *, tableoid::regclass::text as table_name FROM {table} WHERE {id_column} = $1",
         ))
         .bind(id)
         .fetch_one(self.pool())
         .await
-        .map_err(|e| Error::from(e))?;
+        .map_err(Error::from)?;
         Ok(res)
     }
 
     #[allow(async_fn_in_trait)] // only using in own code
-    async fn insert_one<'e>(&self, entity: &'e Self::Entity) -> Result<Self::Id, Error>
+    async fn insert_one(&self, entity: &Self::Entity) -> Result<Self::Id, Error>
     where
         for<'a> <Self as Repo>::Id: Decode<'a, Postgres> + Type<Postgres>,
     {
         let table_name = entity.table_name();
         let values = entity.values_str_for_insert();
-        let stmt = format!("INSERT INTO {table_name} VALUES ({values}) RETURNING id",);
+        let columns = match Self::Entity::INSERT_COLUMNS {
+            [] => String::new(),
+            columns => format!(" ({})", columns.join(", ")),
+        };
+        let id_column = Self::Entity::ID_COLUMN;
+        let stmt =
+            format!("INSERT INTO {table_name}{columns} VALUES ({values}) RETURNING {id_column}",);
         let query = sqlx::query(&stmt);
         let query = entity.bind_to_insert(query);
-        let res = query
-            .fetch_one(self.pool())
-            .await
-            .map_err(|e| Error::from(e))?;
-        let id: Self::Id = res.get::<Self::Id, &str>("id");
+        let res = query.fetch_one(self.pool()).await.map_err(Error::from)?;
+        let id: Self::Id = res.get::<Self::Id, &str>(id_column);
         Ok(id)
     }
 
     /// Updates a whole entity in the db with the one passed in
     #[allow(async_fn_in_trait)]
-    async fn update_one<'e>(&self, id: &Self::Id, entity: &'e Self::Entity) -> Result<(), Error>
+    async fn update_one(&self, id: &Self::Id, entity: &Self::Entity) -> Result<(), Error>
     where
         for<'a> <Self as Repo>::Id: Decode<'a, Postgres> + Type<Postgres>,
         for<'i> <Self as Repo>::Id: std::fmt::Display + Encode<'i, Postgres> + Type<Postgres>,
     {
         let table_name = entity.table_name();
         let values = entity.values_str_for_update();
-        let stmt = format!("UPDATE {table_name} SET {values} WHERE id = $1",);
+        let id_column = Self::Entity::ID_COLUMN;
+        let stmt = format!("UPDATE {table_name} SET {values} WHERE {id_column} = $1",);
         let query = sqlx::query(&stmt).bind(id);
         let query = entity.bind_to_update(query);
-        query
+        query.execute(self.pool()).await.map_err(Error::from)?;
+        Ok(())
+    }
+
+    /// Deletes the entity with the given id from the table, erroring if there was nothing to delete
+    #[allow(async_fn_in_trait)]
+    async fn delete_one(&self, id: &Self::Id, table: &str) -> Result<(), Error>
+    where
+        for<'i> <Self as Repo>::Id: std::fmt::Display + Encode<'i, Postgres> + Type<Postgres>,
+    {
+        let id_column = Self::Entity::ID_COLUMN;
+        let res = sqlx::query(&format!("DELETE FROM {table} WHERE {id_column} = $1"))
+            .bind(id)
             .execute(self.pool())
             .await
-            .map_err(|e| Error::from(e))?;
+            .map_err(Error::from)?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
         Ok(())
     }
 
@@ -103,6 +138,13 @@ pub trait Repo {
 }
 
 pub trait Entity: for<'r> FromRow<'r, PgRow> + Send + Unpin {
+    /// The primary key column used to select, update and return the Repo's Id
+    const ID_COLUMN: &'static str = "id";
+
+    /// The columns the INSERT values go into, in order. Every column of the table, in the
+    /// table's order, when empty
+    const INSERT_COLUMNS: &'static [&'static str] = &[];
+
     /// The string to be passed into the SQL INSERT query after VALUES
     fn values_str_for_insert(&self) -> String;
 
@@ -223,6 +265,28 @@ mod tests {
         assert_eq!(name, "new_name");
     }
 
+    #[test]
+    fn test_delete_one(pool: PgPool) {
+        sqlx::query("create table test (id serial, name text)")
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("insert into test (name) values ($1)")
+            .bind("test")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let repo = TestRepo::new(pool.clone());
+        assert!(repo.delete_one(&1, "test").await.is_ok());
+        let count: i64 = sqlx::query_scalar("SELECT count(*) FROM test")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+        let err = repo.delete_one(&1, "test").await.unwrap_err();
+        assert_eq!(err.message, "no records returned");
+    }
+
     struct TestRepo {
         pub pool: PgPool,
     }
@@ -281,14 +345,14 @@ mod tests {
         }
 
         fn values_str_for_update(&self) -> String {
-            "name = $1".into()
+            "name = $2".into()
         }
 
         fn bind_to_update<'a>(
             &'a self,
             stmt: Query<'a, Postgres, PgArguments>,
         ) -> Query<'a, Postgres, PgArguments> {
-            todo!()
+            stmt.bind(self.name.clone())
         }
     }
 
@@ -323,7 +387,7 @@ mod tests {
 
         fn bind_to_update<'a>(
             &'a self,
-            stmt: Query<'a, Postgres, PgArguments>,
+            _stmt: Query<'a, Postgres, PgArguments>,
         ) -> Query<'a, Postgres, PgArguments> {
             todo!()
         }
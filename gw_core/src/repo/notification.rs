@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{
+    error::Error,
+    notification::{AcquirerNotification, NotificationKind, NotificationOutcome},
+};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct NotificationRepo {
+    pub pool: Arc<Pool>,
+}
+
+fn decode<T, E>(column: &str, value: Result<T, E>) -> Result<T, sqlx::Error>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    value.map_err(|e| sqlx::Error::ColumnDecode {
+        index: column.into(),
+        source: Box::new(e),
+    })
+}
+
+impl<'r> FromRow<'r, PgRow> for AcquirerNotification {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(AcquirerNotification {
+            acquirer: row.try_get("acquirer")?,
+            notification_id: row.try_get("notification_id")?,
+            transaction_reference: row.try_get("transaction_reference")?,
+            kind: decode(
+                "kind",
+                NotificationKind::try_from(row.try_get::<String, _>("kind")?),
+            )?,
+            raw: row.try_get("raw")?,
+            outcome: decode(
+                "outcome",
+                NotificationOutcome::try_from(row.try_get::<String, _>("outcome")?),
+            )?,
+            detail: row.try_get("detail")?,
+            received_at: row.try_get("received_at")?,
+        })
+    }
+}
+
+impl NotificationRepo {
+    pub async fn find(
+        &self,
+        acquirer: &str,
+        notification_id: &str,
+    ) -> Result<Option<AcquirerNotification>, Error> {
+        let notification = sqlx::query_as(
+            "SELECT * FROM transaction.acquirer_notifications \
+             WHERE acquirer = $1 AND notification_id = $2",
+        )
+        .bind(acquirer)
+        .bind(notification_id)
+        .fetch_optional(&**self.pool)
+        .await?;
+        Ok(notification)
+    }
+
+    /// Stores the notification unless the acquirer has sent it before, returning whether it
+    /// was stored
+    pub async fn insert(&self, notification: &AcquirerNotification) -> Result<bool, Error> {
+        let res = sqlx::query(
+            "INSERT INTO transaction.acquirer_notifications \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+             ON CONFLICT (acquirer, notification_id) DO NOTHING",
+        )
+        .bind(&notification.acquirer)
+        .bind(&notification.notification_id)
+        .bind(&notification.transaction_reference)
+        .bind(notification.kind.to_string())
+        .bind(&notification.raw)
+        .bind(notification.outcome.to_string())
+        .bind(&notification.detail)
+        .bind(notification.received_at)
+        .execute(&**self.pool)
+        .await?;
+        Ok(res.rows_affected() == 1)
+    }
+
+    /// The notifications about a transaction, oldest first
+    pub async fn list_for_transaction(
+        &self,
+        reference: &str,
+    ) -> Result<Vec<AcquirerNotification>, Error> {
+        let notifications = sqlx::query_as(
+            "SELECT * FROM transaction.acquirer_notifications WHERE transaction_reference = $1 \
+             ORDER BY received_at, notification_id",
+        )
+        .bind(reference)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(notifications)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notification::NotificationSource;
+    use chrono::Utc;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_notifications_are_only_stored_once(pool: PgPool) {
+        let repo = NotificationRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let raw = r#"{"notificationId":"n1","pspReference":"ref123","eventCode":"SETTLEMENT"}"#;
+        let mut notification = NotificationSource::BankOne
+            .parse(raw.into(), Utc::now())
+            .unwrap();
+        notification.apply(None);
+        assert_eq!(repo.find("bankone", "n1").await.unwrap(), None);
+        assert!(repo.insert(&notification).await.unwrap());
+        assert!(!repo.insert(&notification).await.unwrap());
+        assert_eq!(
+            repo.find("bankone", "n1").await.unwrap(),
+            Some(notification.clone())
+        );
+        // ids are only unique to the acquirer
+        assert_eq!(repo.find("banktwo", "n1").await.unwrap(), None);
+        assert_eq!(
+            repo.list_for_transaction("ref123").await.unwrap(),
+            vec![notification]
+        );
+        assert_eq!(repo.list_for_transaction("ref456").await.unwrap(), vec![]);
+    }
+}
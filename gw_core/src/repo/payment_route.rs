@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{
+    card_scheme::CardScheme, currency::Currency, error::Error, payment_route::PaymentRoute,
+};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct PaymentRouteRepo {
+    pub pool: Arc<Pool>,
+}
+
+impl<'r> FromRow<'r, PgRow> for PaymentRoute {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let decode_err = |index: &str| {
+            let index = index.to_owned();
+            move |e: Error| sqlx::Error::ColumnDecode {
+                index,
+                source: Box::new(e),
+            }
+        };
+        Ok(PaymentRoute {
+            merchant_id: row.try_get("merchant_id")?,
+            scheme: CardScheme::try_from(row.try_get::<String, _>("scheme")?)
+                .map_err(decode_err("scheme"))?,
+            currency: Currency::try_from(row.try_get::<String, _>("currency")?)
+                .map_err(decode_err("currency"))?,
+            acquirer: row.try_get("acquirer")?,
+            account_id: row.try_get("account_id")?,
+        })
+    }
+}
+
+impl PaymentRouteRepo {
+    /// Lists the merchant's routes ordered by scheme then currency
+    pub async fn list_for(&self, merchant_id: &str) -> Result<Vec<PaymentRoute>, Error> {
+        let routes = sqlx::query_as(
+            "SELECT * FROM account.paymentroute WHERE merchant_id = $1 ORDER BY scheme, currency",
+        )
+        .bind(merchant_id)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(routes)
+    }
+
+    /// Creates the route, or points the existing route for the scheme and currency at the new account
+    pub async fn upsert(&self, route: &PaymentRoute) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO account.paymentroute (scheme, currency, merchant_id, account_id, acquirer) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (scheme, currency, merchant_id) \
+             DO UPDATE SET account_id = EXCLUDED.account_id, acquirer = EXCLUDED.acquirer",
+        )
+        .bind(route.scheme.to_string())
+        .bind(route.currency.to_string())
+        .bind(&route.merchant_id)
+        .bind(route.account_id)
+        .bind(&route.acquirer)
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(
+        &self,
+        merchant_id: &str,
+        scheme: CardScheme,
+        currency: Currency,
+    ) -> Result<(), Error> {
+        let res = sqlx::query(
+            "DELETE FROM account.paymentroute WHERE merchant_id = $1 AND scheme = $2 AND currency = $3",
+        )
+        .bind(merchant_id)
+        .bind(scheme.to_string())
+        .bind(currency.to_string())
+        .execute(&**self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    /// Counts the routes which send payments to the account
+    pub async fn count_for_account(&self, acquirer: &str, account_id: i32) -> Result<i64, Error> {
+        let count = sqlx::query_scalar(
+            "SELECT count(*) FROM account.paymentroute WHERE acquirer = $1 AND account_id = $2",
+        )
+        .bind(acquirer)
+        .bind(account_id)
+        .fetch_one(&**self.pool)
+        .await?;
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_routes(pool: PgPool) {
+        let repo = PaymentRouteRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let seeded = repo.list_for("merchant123").await.unwrap();
+        assert_eq!(seeded.len(), 2);
+        assert_eq!(seeded[0].currency, Currency::GBP);
+        let mut route = PaymentRoute {
+            merchant_id: "merchant123".into(),
+            scheme: CardScheme::Mastercard,
+            currency: Currency::GBP,
+            acquirer: "bankone".into(),
+            account_id: 0,
+        };
+        repo.upsert(&route).await.unwrap();
+        route.account_id = 3;
+        repo.upsert(&route).await.unwrap();
+        let routes = repo.list_for("merchant123").await.unwrap();
+        assert_eq!(routes.len(), 3);
+        assert_eq!(routes[0], route);
+        assert_eq!(repo.count_for_account("bankone", 0).await.unwrap(), 2);
+        repo.delete("merchant123", CardScheme::Mastercard, Currency::GBP)
+            .await
+            .unwrap();
+        assert!(repo
+            .delete("merchant123", CardScheme::Mastercard, Currency::GBP)
+            .await
+            .is_err());
+        assert_eq!(repo.list_for("merchant123").await.unwrap(), seeded);
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{avs::AvsPolicy, cvv::CvvPolicy, error::Error, policy::MerchantPolicies};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct PolicyRepo {
+    pub pool: Arc<Pool>,
+}
+
+impl<'r> FromRow<'r, PgRow> for MerchantPolicies {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(MerchantPolicies {
+            merchant_id: row.try_get("merchant_id")?,
+            avs: AvsPolicy {
+                decline_address_mismatch: row.try_get("decline_avs_address_mismatch")?,
+                decline_postcode_mismatch: row.try_get("decline_avs_postcode_mismatch")?,
+            },
+            cvv: CvvPolicy {
+                void_on_mismatch: row.try_get("void_on_cvv_mismatch")?,
+            },
+        })
+    }
+}
+
+impl PolicyRepo {
+    /// The merchant's policies, with everything off if none have been configured
+    pub async fn find(&self, merchant_id: &str) -> Result<MerchantPolicies, Error> {
+        let policies =
+            sqlx::query_as("SELECT * FROM account.merchant_policies WHERE merchant_id = $1")
+                .bind(merchant_id)
+                .fetch_optional(&**self.pool)
+                .await?;
+        Ok(policies.unwrap_or_else(|| MerchantPolicies::new(merchant_id.into())))
+    }
+
+    /// Replaces all of the merchant's policies
+    pub async fn upsert(&self, policies: &MerchantPolicies) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO account.merchant_policies VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (merchant_id) DO UPDATE SET \
+             decline_avs_address_mismatch = EXCLUDED.decline_avs_address_mismatch, \
+             decline_avs_postcode_mismatch = EXCLUDED.decline_avs_postcode_mismatch, \
+             void_on_cvv_mismatch = EXCLUDED.void_on_cvv_mismatch",
+        )
+        .bind(&policies.merchant_id)
+        .bind(policies.avs.decline_address_mismatch)
+        .bind(policies.avs.decline_postcode_mismatch)
+        .bind(policies.cvv.void_on_mismatch)
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_find_and_upsert(pool: PgPool) {
+        let repo = PolicyRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let mut policies = repo.find("merchant123").await.unwrap();
+        assert_eq!(policies, MerchantPolicies::new("merchant123".into()));
+        policies.avs.decline_postcode_mismatch = true;
+        repo.upsert(&policies).await.unwrap();
+        assert_eq!(repo.find("merchant123").await.unwrap(), policies);
+        policies.avs = AvsPolicy {
+            decline_address_mismatch: true,
+            decline_postcode_mismatch: false,
+        };
+        policies.cvv.void_on_mismatch = true;
+        repo.upsert(&policies).await.unwrap();
+        assert_eq!(repo.find("merchant123").await.unwrap(), policies);
+        policies.merchant_id = "nobody".into();
+        assert!(repo.upsert(&policies).await.is_err());
+    }
+}
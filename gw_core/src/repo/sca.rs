@@ -0,0 +1,143 @@
+use std::sync::Arc;
+
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{currency::Currency, error::Error, sca::ScaThresholds};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct ScaRepo {
+    pub pool: Arc<Pool>,
+}
+
+impl<'r> FromRow<'r, PgRow> for ScaThresholds {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let amount = |column: &str| -> Result<Option<u64>, sqlx::Error> {
+            Ok(row.try_get::<Option<i64>, _>(column)?.map(|n| n as u64))
+        };
+        Ok(ScaThresholds {
+            merchant_id: row.try_get("merchant_id")?,
+            currency: Currency::try_from(row.try_get::<String, _>("currency")?).map_err(|e| {
+                sqlx::Error::ColumnDecode {
+                    index: "currency".into(),
+                    source: Box::new(e),
+                }
+            })?,
+            low_value: amount("low_value")?,
+            transaction_risk_analysis: amount("transaction_risk_analysis")?,
+            merchant_initiated: amount("merchant_initiated")?,
+            recurring: amount("recurring")?,
+            corporate: amount("corporate")?,
+        })
+    }
+}
+
+impl ScaRepo {
+    /// Lists the merchant's thresholds ordered by currency
+    pub async fn list_for(&self, merchant_id: &str) -> Result<Vec<ScaThresholds>, Error> {
+        let thresholds = sqlx::query_as(
+            "SELECT * FROM account.sca_thresholds WHERE merchant_id = $1 ORDER BY currency",
+        )
+        .bind(merchant_id)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(thresholds)
+    }
+
+    /// The merchant's thresholds for the currency, `None` means no exemptions are asked for
+    pub async fn find(
+        &self,
+        merchant_id: &str,
+        currency: Currency,
+    ) -> Result<Option<ScaThresholds>, Error> {
+        let thresholds = sqlx::query_as(
+            "SELECT * FROM account.sca_thresholds WHERE merchant_id = $1 AND currency = $2",
+        )
+        .bind(merchant_id)
+        .bind(currency.to_string())
+        .fetch_optional(&**self.pool)
+        .await?;
+        Ok(thresholds)
+    }
+
+    /// Replaces all of the merchant's thresholds for the currency
+    pub async fn upsert(&self, thresholds: &ScaThresholds) -> Result<(), Error> {
+        let amount = |max: Option<u64>| max.map(|n| n as i64);
+        sqlx::query(
+            "INSERT INTO account.sca_thresholds VALUES ($1, $2, $3, $4, $5, $6, $7) \
+             ON CONFLICT (merchant_id, currency) DO UPDATE SET \
+             low_value = EXCLUDED.low_value, \
+             transaction_risk_analysis = EXCLUDED.transaction_risk_analysis, \
+             merchant_initiated = EXCLUDED.merchant_initiated, \
+             recurring = EXCLUDED.recurring, \
+             corporate = EXCLUDED.corporate",
+        )
+        .bind(&thresholds.merchant_id)
+        .bind(thresholds.currency.to_string())
+        .bind(amount(thresholds.low_value))
+        .bind(amount(thresholds.transaction_risk_analysis))
+        .bind(amount(thresholds.merchant_initiated))
+        .bind(amount(thresholds.recurring))
+        .bind(amount(thresholds.corporate))
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, merchant_id: &str, currency: Currency) -> Result<(), Error> {
+        let res = sqlx::query(
+            "DELETE FROM account.sca_thresholds WHERE merchant_id = $1 AND currency = $2",
+        )
+        .bind(merchant_id)
+        .bind(currency.to_string())
+        .execute(&**self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_thresholds(pool: PgPool) {
+        let repo = ScaRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        assert_eq!(repo.list_for("merchant123").await.unwrap(), vec![]);
+        assert_eq!(repo.find("merchant123", Currency::EUR).await.unwrap(), None);
+        let mut eur = ScaThresholds {
+            low_value: Some(3000),
+            ..ScaThresholds::new("merchant123".into(), Currency::EUR)
+        };
+        let gbp = ScaThresholds {
+            transaction_risk_analysis: Some(10000),
+            corporate: Some(500000),
+            ..ScaThresholds::new("merchant123".into(), Currency::GBP)
+        };
+        repo.upsert(&gbp).await.unwrap();
+        repo.upsert(&eur).await.unwrap();
+        assert_eq!(
+            repo.list_for("merchant123").await.unwrap(),
+            vec![eur.clone(), gbp.clone()]
+        );
+        eur.low_value = None;
+        eur.recurring = Some(20000);
+        repo.upsert(&eur).await.unwrap();
+        assert_eq!(
+            repo.find("merchant123", Currency::EUR).await.unwrap(),
+            Some(eur.clone())
+        );
+        repo.delete("merchant123", Currency::EUR).await.unwrap();
+        assert!(repo.delete("merchant123", Currency::EUR).await.is_err());
+        assert_eq!(repo.list_for("merchant123").await.unwrap(), vec![gbp]);
+        eur.merchant_id = "nobody".into();
+        assert!(repo.upsert(&eur).await.is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+
+use crate::{error::Error, secret::Secret};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct SigningRepo {
+    pub pool: Arc<Pool>,
+}
+
+impl SigningRepo {
+    /// The merchant's signing secret, None if they don't sign their requests
+    pub async fn find_secret(&self, merchant_id: &str) -> Result<Option<Secret<String>>, Error> {
+        let secret: Option<String> =
+            sqlx::query_scalar("SELECT secret FROM account.signing_secret WHERE merchant_id = $1")
+                .bind(merchant_id)
+                .fetch_optional(&**self.pool)
+                .await?;
+        Ok(secret.map(Secret::new))
+    }
+
+    /// Sets or replaces the merchant's signing secret, requiring them to sign their requests
+    pub async fn set_secret(
+        &self,
+        merchant_id: &str,
+        secret: &Secret<String>,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO account.signing_secret (merchant_id, secret) VALUES ($1, $2) \
+             ON CONFLICT (merchant_id) DO UPDATE SET secret = EXCLUDED.secret, created_at = now()",
+        )
+        .bind(merchant_id)
+        .bind(secret.expose())
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn remove_secret(&self, merchant_id: &str) -> Result<(), Error> {
+        let res = sqlx::query("DELETE FROM account.signing_secret WHERE merchant_id = $1")
+            .bind(merchant_id)
+            .execute(&**self.pool)
+            .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    /// Records the nonce as used until it expires, returning false if it has already been used
+    pub async fn use_nonce(
+        &self,
+        merchant_id: &str,
+        nonce: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        sqlx::query(
+            "DELETE FROM account.signature_nonce WHERE merchant_id = $1 AND expires_at <= now()",
+        )
+        .bind(merchant_id)
+        .execute(&**self.pool)
+        .await?;
+        let res = sqlx::query(
+            "INSERT INTO account.signature_nonce VALUES ($1, $2, $3) ON CONFLICT DO NOTHING",
+        )
+        .bind(merchant_id)
+        .bind(nonce)
+        .bind(expires_at)
+        .execute(&**self.pool)
+        .await?;
+        Ok(res.rows_affected() == 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_secrets(pool: PgPool) {
+        let repo = SigningRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        assert!(repo.find_secret("merchant123").await.unwrap().is_none());
+        repo.set_secret("merchant123", &Secret::from("first"))
+            .await
+            .unwrap();
+        repo.set_secret("merchant123", &Secret::from("second"))
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.find_secret("merchant123").await.unwrap(),
+            Some(Secret::from("second"))
+        );
+        repo.remove_secret("merchant123").await.unwrap();
+        assert!(repo.find_secret("merchant123").await.unwrap().is_none());
+        assert!(repo.remove_secret("merchant123").await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_nonces(pool: PgPool) {
+        let repo = SigningRepo {
+            pool: Arc::new(Pool::from(pool)),
+        };
+        let later = Utc::now() + TimeDelta::minutes(5);
+        assert!(repo.use_nonce("merchant123", "n1", later).await.unwrap());
+        assert!(!repo.use_nonce("merchant123", "n1", later).await.unwrap());
+        assert!(repo.use_nonce("merchant456", "n1", later).await.unwrap());
+        // once expired the nonce is forgotten, its timestamp would be rejected anyway
+        let earlier = Utc::now() - TimeDelta::minutes(5);
+        assert!(repo.use_nonce("merchant123", "n2", earlier).await.unwrap());
+        assert!(repo.use_nonce("merchant123", "n2", later).await.unwrap());
+    }
+}
@@ -0,0 +1,311 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{
+    currency::Currency,
+    error::Error,
+    subscription::{Interval, Plan, Subscription, SubscriptionStatus},
+};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct SubscriptionRepo {
+    pub pool: Arc<Pool>,
+}
+
+fn decode<T, E>(column: &str, value: Result<T, E>) -> Result<T, sqlx::Error>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    value.map_err(|e| sqlx::Error::ColumnDecode {
+        index: column.into(),
+        source: Box::new(e),
+    })
+}
+
+impl<'r> FromRow<'r, PgRow> for Plan {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Plan {
+            plan_id: row.try_get("id")?,
+            merchant_id: row.try_get("merchant_id")?,
+            name: row.try_get("name")?,
+            amount: row.try_get::<i64, _>("amount")? as u64,
+            currency: decode(
+                "currency",
+                Currency::try_from(row.try_get::<String, _>("currency")?),
+            )?,
+            interval: decode(
+                "billing_interval",
+                Interval::try_from(row.try_get::<String, _>("billing_interval")?),
+            )?,
+            interval_count: row.try_get::<i32, _>("interval_count")? as u32,
+            trial_days: row.try_get::<i32, _>("trial_days")? as u32,
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for Subscription {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(Subscription {
+            subscription_id: row.try_get("id")?,
+            merchant_id: row.try_get("merchant_id")?,
+            plan_id: row.try_get("plan_id")?,
+            customer_id: row.try_get("customer_id")?,
+            payment_token: row.try_get("payment_token")?,
+            status: decode(
+                "status",
+                SubscriptionStatus::try_from(row.try_get::<String, _>("status")?),
+            )?,
+            next_charge_at: row.try_get("next_charge_at")?,
+            retry_at: row.try_get("retry_at")?,
+            failed_attempts: row.try_get::<i32, _>("failed_attempts")? as u32,
+            last_transaction: row.try_get("last_transaction")?,
+            created_at: row.try_get("created_at")?,
+            cancelled_at: row.try_get("cancelled_at")?,
+        })
+    }
+}
+
+impl SubscriptionRepo {
+    pub async fn insert_plan(&self, plan: &Plan) -> Result<(), Error> {
+        sqlx::query("INSERT INTO account.plans VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)")
+            .bind(&plan.plan_id)
+            .bind(&plan.merchant_id)
+            .bind(&plan.name)
+            .bind(plan.amount as i64)
+            .bind(plan.currency.to_string())
+            .bind(plan.interval.to_string())
+            .bind(plan.interval_count as i32)
+            .bind(plan.trial_days as i32)
+            .bind(plan.created_at)
+            .execute(&**self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn find_plan(&self, merchant_id: &str, plan_id: &str) -> Result<Plan, Error> {
+        let plan = sqlx::query_as("SELECT * FROM account.plans WHERE merchant_id = $1 AND id = $2")
+            .bind(merchant_id)
+            .bind(plan_id)
+            .fetch_one(&**self.pool)
+            .await?;
+        Ok(plan)
+    }
+
+    /// Lists the merchant's plans oldest first
+    pub async fn list_plans(&self, merchant_id: &str) -> Result<Vec<Plan>, Error> {
+        let plans = sqlx::query_as(
+            "SELECT * FROM account.plans WHERE merchant_id = $1 ORDER BY created_at, id",
+        )
+        .bind(merchant_id)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(plans)
+    }
+
+    pub async fn insert(&self, subscription: &Subscription) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO account.subscriptions \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+        )
+        .bind(&subscription.subscription_id)
+        .bind(&subscription.merchant_id)
+        .bind(&subscription.plan_id)
+        .bind(&subscription.customer_id)
+        .bind(&subscription.payment_token)
+        .bind(subscription.status.to_string())
+        .bind(subscription.next_charge_at)
+        .bind(subscription.retry_at)
+        .bind(subscription.failed_attempts as i32)
+        .bind(&subscription.last_transaction)
+        .bind(subscription.created_at)
+        .bind(subscription.cancelled_at)
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find(
+        &self,
+        merchant_id: &str,
+        subscription_id: &str,
+    ) -> Result<Subscription, Error> {
+        let subscription = sqlx::query_as(
+            "SELECT * FROM account.subscriptions WHERE merchant_id = $1 AND id = $2",
+        )
+        .bind(merchant_id)
+        .bind(subscription_id)
+        .fetch_one(&**self.pool)
+        .await?;
+        Ok(subscription)
+    }
+
+    /// Lists the merchant's subscriptions oldest first
+    pub async fn list_for(&self, merchant_id: &str) -> Result<Vec<Subscription>, Error> {
+        let subscriptions = sqlx::query_as(
+            "SELECT * FROM account.subscriptions WHERE merchant_id = $1 ORDER BY created_at, id",
+        )
+        .bind(merchant_id)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(subscriptions)
+    }
+
+    /// Claims the subscriptions due to be charged or retried by `now`, the longest waiting
+    /// first, until `lease_until`. Claimed subscriptions aren't claimed again until the lease
+    /// runs out or they're updated, so schedulers running side by side don't charge them twice.
+    pub async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<Subscription>, Error> {
+        let mut subscriptions: Vec<Subscription> = sqlx::query_as(
+            "UPDATE account.subscriptions SET claimed_until = $2 WHERE id IN ( \
+             SELECT id FROM account.subscriptions \
+             WHERE status IN ('TRIALING', 'ACTIVE', 'PAST_DUE') \
+             AND COALESCE(retry_at, next_charge_at) <= $1 \
+             AND (claimed_until IS NULL OR claimed_until <= $1) \
+             ORDER BY COALESCE(retry_at, next_charge_at), id LIMIT $3 FOR UPDATE SKIP LOCKED) \
+             RETURNING *",
+        )
+        .bind(now)
+        .bind(lease_until)
+        .bind(limit)
+        .fetch_all(&**self.pool)
+        .await?;
+        subscriptions.sort_by(|a, b| {
+            (a.retry_at.unwrap_or(a.next_charge_at), &a.subscription_id)
+                .cmp(&(b.retry_at.unwrap_or(b.next_charge_at), &b.subscription_id))
+        });
+        Ok(subscriptions)
+    }
+
+    /// Saves where the subscription is up to, releasing any claim on it. What it's for and who
+    /// it's charged to don't change.
+    pub async fn update(&self, subscription: &Subscription) -> Result<(), Error> {
+        let res = sqlx::query(
+            "UPDATE account.subscriptions SET status = $3, next_charge_at = $4, retry_at = $5, \
+             failed_attempts = $6, last_transaction = $7, cancelled_at = $8, \
+             claimed_until = NULL WHERE merchant_id = $1 AND id = $2",
+        )
+        .bind(&subscription.merchant_id)
+        .bind(&subscription.subscription_id)
+        .bind(subscription.status.to_string())
+        .bind(subscription.next_charge_at)
+        .bind(subscription.retry_at)
+        .bind(subscription.failed_attempts as i32)
+        .bind(&subscription.last_transaction)
+        .bind(subscription.cancelled_at)
+        .execute(&**self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{customer::Customer, repo::customer::CustomerRepo};
+    use chrono::TimeDelta;
+    use sqlx::PgPool;
+
+    #[sqlx::test]
+    async fn test_plans_and_subscriptions(pool: PgPool) {
+        let pool = Arc::new(Pool::from(pool));
+        let repo = SubscriptionRepo {
+            pool: Arc::clone(&pool),
+        };
+        let customer = Customer::new("merchant123".into());
+        CustomerRepo { pool }.insert(&customer).await.unwrap();
+        let plan = Plan {
+            trial_days: 7,
+            ..Plan::new(
+                "merchant123".into(),
+                "Weekly".into(),
+                500,
+                Currency::EUR,
+                Interval::Week,
+            )
+        };
+        repo.insert_plan(&plan).await.unwrap();
+        assert_eq!(
+            repo.find_plan("merchant123", &plan.plan_id).await.unwrap(),
+            plan
+        );
+        assert!(repo.find_plan("merchant456", &plan.plan_id).await.is_err());
+        assert_eq!(
+            repo.list_plans("merchant123").await.unwrap(),
+            vec![plan.clone()]
+        );
+
+        let now = Utc::now();
+        let mut subscription =
+            Subscription::new(&plan, customer.customer_id.clone(), "token123".into(), now);
+        repo.insert(&subscription).await.unwrap();
+        assert_eq!(
+            repo.find("merchant123", &subscription.subscription_id)
+                .await
+                .unwrap(),
+            subscription
+        );
+        let lease = |at: DateTime<Utc>| at + TimeDelta::minutes(5);
+        assert_eq!(repo.claim_due(now, lease(now), 10).await.unwrap(), vec![]);
+        let trial_over = now + TimeDelta::days(7);
+        assert_eq!(
+            repo.claim_due(trial_over, lease(trial_over), 10)
+                .await
+                .unwrap(),
+            vec![subscription.clone()]
+        );
+        // claimed until the lease runs out, in case whoever claimed it stopped
+        assert_eq!(
+            repo.claim_due(trial_over, lease(trial_over), 10)
+                .await
+                .unwrap(),
+            vec![]
+        );
+        let lease_over = lease(trial_over);
+        assert_eq!(
+            repo.claim_due(lease_over, lease(lease_over), 10)
+                .await
+                .unwrap(),
+            vec![subscription.clone()]
+        );
+
+        subscription.record_failure(trial_over);
+        subscription.last_transaction = Some("reference123".into());
+        repo.update(&subscription).await.unwrap();
+        assert_eq!(
+            repo.list_for("merchant123").await.unwrap(),
+            vec![subscription.clone()]
+        );
+        assert_eq!(
+            repo.claim_due(lease_over, lease(lease_over), 10)
+                .await
+                .unwrap(),
+            vec![]
+        );
+        // the retry is due a day later, it isn't held up by the earlier claim
+        let retry = trial_over + TimeDelta::days(1);
+        assert_eq!(
+            repo.claim_due(retry, lease(retry), 10).await.unwrap().len(),
+            1
+        );
+        subscription.cancel(trial_over);
+        repo.update(&subscription).await.unwrap();
+        let later = trial_over + TimeDelta::days(30);
+        assert_eq!(
+            repo.claim_due(later, lease(later), 10).await.unwrap(),
+            vec![]
+        );
+        assert_eq!(repo.list_for("merchant456").await.unwrap(), vec![]);
+    }
+}
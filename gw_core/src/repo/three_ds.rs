@@ -0,0 +1,313 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use sqlx::{postgres::PgRow, FromRow, Row};
+
+use crate::{
+    error::Error,
+    payment_method::{open_payment, seal_payment},
+    secret::Secret,
+    three_ds::{Challenge, PendingAuthentication, CHALLENGE_TIMEOUT_SECS},
+    transaction::{Transaction, TransactionError, TransactionStatus},
+};
+
+use super::Pool;
+
+/// The pending columns selected alongside the transaction's, renamed where they'd clash
+const PENDING_COLUMNS: &str = "sealed_card, ds_transaction_id AS challenge_ds_transaction_id, \
+     acs_transaction_id, acs_url, creq, expires_at";
+
+/// Keeps transactions waiting on 3DS challenges until the cardholder completes them or they
+/// expire. The card is sealed with the payment method key, without its security code, and the
+/// rest of the transaction is read back from the transactions table.
+#[derive(Debug)]
+pub struct PendingAuthenticationRepo {
+    pub pool: Arc<Pool>,
+}
+
+/// The transaction with its card opened again, and the challenge it's waiting on. The card has
+/// no security code, it was never sealed.
+fn decode(row: &PgRow, key: &Secret<String>) -> Result<PendingAuthentication, Error> {
+    let mut transaction = Transaction::from_row(row)?;
+    let sealed: String = row.try_get("sealed_card")?;
+    transaction.payment = open_payment(&sealed, &transaction.reference, key)?;
+    Ok(PendingAuthentication {
+        transaction,
+        challenge: Challenge {
+            ds_transaction_id: row.try_get("challenge_ds_transaction_id")?,
+            acs_transaction_id: row.try_get("acs_transaction_id")?,
+            acs_url: row.try_get("acs_url")?,
+            creq: row.try_get("creq")?,
+        },
+        expires_at: row.try_get("expires_at")?,
+    })
+}
+
+impl PendingAuthenticationRepo {
+    /// Stores the challenge, the transaction itself must already be stored
+    pub async fn insert(
+        &self,
+        pending: &PendingAuthentication,
+        key: &Secret<String>,
+    ) -> Result<(), Error> {
+        let transaction = &pending.transaction;
+        let sealed = seal_payment(&transaction.payment, &transaction.reference, key)?;
+        sqlx::query(
+            "INSERT INTO transaction.pending_authentications (reference, merchant_id, sealed_card, \
+             ds_transaction_id, acs_transaction_id, acs_url, creq, expires_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8);",
+        )
+        .bind(&transaction.reference)
+        .bind(&transaction.merchant.merchant_id)
+        .bind(sealed)
+        .bind(&pending.challenge.ds_transaction_id)
+        .bind(&pending.challenge.acs_transaction_id)
+        .bind(&pending.challenge.acs_url)
+        .bind(&pending.challenge.creq)
+        .bind(pending.expires_at)
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// The merchant's unexpired challenge for the transaction
+    pub async fn find(
+        &self,
+        reference: &str,
+        merchant_id: &str,
+        now: DateTime<Utc>,
+        key: &Secret<String>,
+    ) -> Result<Option<PendingAuthentication>, Error> {
+        let row = sqlx::query(&format!(
+            "SELECT t.*, t.tableoid::regclass::text AS table_name, {PENDING_COLUMNS} \
+             FROM transaction.pending_authentications p \
+             JOIN transaction.transactions t ON t.reference = p.reference \
+             WHERE p.reference = $1 AND p.merchant_id = $2 AND p.expires_at > $3;"
+        ))
+        .bind(reference)
+        .bind(merchant_id)
+        .bind(now)
+        .fetch_optional(&**self.pool)
+        .await?;
+        row.map(|row| decode(&row, key)).transpose()
+    }
+
+    /// Removes the merchant's unexpired challenge for the transaction, so it's only completed
+    /// once, even when several gateways are asked to complete it
+    pub async fn take(
+        &self,
+        reference: &str,
+        merchant_id: &str,
+        now: DateTime<Utc>,
+        key: &Secret<String>,
+    ) -> Result<Option<PendingAuthentication>, Error> {
+        let row = sqlx::query(&format!(
+            "WITH taken AS (DELETE FROM transaction.pending_authentications \
+             WHERE reference = $1 AND merchant_id = $2 AND expires_at > $3 \
+             RETURNING reference AS pending_reference, {PENDING_COLUMNS}) \
+             SELECT t.*, t.tableoid::regclass::text AS table_name, taken.* FROM taken \
+             JOIN transaction.transactions t ON t.reference = taken.pending_reference;"
+        ))
+        .bind(reference)
+        .bind(merchant_id)
+        .bind(now)
+        .fetch_optional(&**self.pool)
+        .await?;
+        row.map(|row| decode(&row, key)).transpose()
+    }
+
+    /// Deletes the expired challenges and fails their transactions, returning their references.
+    /// Transactions left waiting without a challenge, like when the gateway stopped before it
+    /// could be stored, are failed once they're twice as old as a challenge can be.
+    pub async fn expire(&self, now: DateTime<Utc>) -> Result<Vec<String>, Error> {
+        let references = sqlx::query_scalar(
+            "WITH expired AS (DELETE FROM transaction.pending_authentications \
+             WHERE expires_at <= $1 RETURNING reference) \
+             UPDATE transaction.transactions t SET status = $2, failure_reason = $5 \
+             WHERE t.status = $3 AND (t.reference IN (SELECT reference FROM expired) \
+             OR (t.created_at <= $4 AND NOT EXISTS (SELECT 1 FROM \
+             transaction.pending_authentications p WHERE p.reference = t.reference))) \
+             RETURNING t.reference;",
+        )
+        .bind(now)
+        .bind(TransactionStatus::Failed(None).to_string())
+        .bind(TransactionStatus::Pending3DS.to_string())
+        .bind(now - TimeDelta::seconds(2 * CHALLENGE_TIMEOUT_SECS))
+        .bind(TransactionError::AuthenticationFailed.to_string())
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(references)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::{AcquirerAccount, BankOneAccount},
+        billing::Billing,
+        card_scheme::CardScheme,
+        currency::Currency,
+        merchant::Merchant,
+        payment::Payment,
+        repo::{transaction::TransactionRepo, Repo},
+        three_ds::SIMULATED_ACS_URL,
+        transaction::{transaction_builder::TransactionBuilder, TransactionType},
+    };
+    use sqlx::PgPool;
+
+    fn key() -> Secret<String> {
+        "payment-method-key".into()
+    }
+
+    /// A stored transaction waiting on a challenge
+    async fn pending(pool: &PgPool, now: DateTime<Utc>) -> PendingAuthentication {
+        let mut trx = TransactionBuilder::new()
+            .transaction_type(TransactionType::Auth)
+            .amount(12345)
+            .currency(Currency::GBP)
+            .payment(Payment::from((
+                CardScheme::Visa,
+                (2030, 1),
+                "123",
+                "4000111100000002",
+            )))
+            .billing(Billing::default())
+            .merchant(Merchant {
+                merchant_id: "merchant123".into(),
+                ..Default::default()
+            })
+            .account(AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: "12345678".into(),
+            }))
+            .build();
+        let challenge = Challenge {
+            ds_transaction_id: "ds".into(),
+            acs_transaction_id: "acs".into(),
+            acs_url: SIMULATED_ACS_URL.into(),
+            creq: "creq".into(),
+        };
+        trx.challenge(&challenge);
+        let transactions = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        transactions.insert_one(&trx).await.unwrap();
+        PendingAuthentication::new(trx, challenge, now)
+    }
+
+    async fn status(pool: &PgPool, reference: &str) -> TransactionStatus {
+        let transactions = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        transactions
+            .find_summary(reference)
+            .await
+            .unwrap()
+            .unwrap()
+            .status
+    }
+
+    #[sqlx::test]
+    async fn test_challenges_are_taken_once(pool: PgPool) {
+        let repo = PendingAuthenticationRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let now = Utc::now();
+        let pending = pending(&pool, now).await;
+        let reference = pending.transaction.reference.clone();
+        repo.insert(&pending, &key()).await.unwrap();
+        let sealed: String = sqlx::query_scalar(
+            "SELECT sealed_card FROM transaction.pending_authentications WHERE reference = $1",
+        )
+        .bind(&reference)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(!sealed.contains("4000111100000002"));
+
+        assert!(repo
+            .find(&reference, "merchant456", now, &key())
+            .await
+            .unwrap()
+            .is_none());
+        assert!(repo
+            .find(&reference, "merchant123", now, &"another-key".into())
+            .await
+            .is_err());
+        let found = repo
+            .find(&reference, "merchant123", now, &key())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.challenge, pending.challenge);
+        assert_eq!(found.transaction.status, TransactionStatus::Pending3DS);
+        // the security code is never stored, the rest of the card comes back
+        assert_eq!(
+            found.transaction.payment,
+            Payment::from((CardScheme::Visa, (2030, 1), "", "4000111100000002"))
+        );
+
+        let expired = pending.expires_at;
+        assert!(repo
+            .take(&reference, "merchant123", expired, &key())
+            .await
+            .unwrap()
+            .is_none());
+        let taken = repo
+            .take(&reference, "merchant123", now, &key())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(taken.transaction.reference, reference);
+        assert!(repo
+            .take(&reference, "merchant123", now, &key())
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[sqlx::test]
+    async fn test_expired_challenges_fail_their_transactions(pool: PgPool) {
+        let repo = PendingAuthenticationRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let now = Utc::now();
+        let waiting = pending(&pool, now).await;
+        repo.insert(&waiting, &key()).await.unwrap();
+        let later = pending(&pool, now + TimeDelta::seconds(60)).await;
+        repo.insert(&later, &key()).await.unwrap();
+        // left waiting without a challenge
+        let orphan = pending(&pool, now).await;
+        assert!(repo.expire(now).await.unwrap().is_empty());
+
+        let expired = repo.expire(waiting.expires_at).await.unwrap();
+        assert_eq!(expired, vec![waiting.transaction.reference.clone()]);
+        assert_eq!(
+            status(&pool, &waiting.transaction.reference).await,
+            TransactionStatus::Failed(Some(TransactionError::AuthenticationFailed))
+        );
+        assert_eq!(
+            status(&pool, &later.transaction.reference).await,
+            TransactionStatus::Pending3DS
+        );
+        assert_eq!(
+            status(&pool, &orphan.transaction.reference).await,
+            TransactionStatus::Pending3DS
+        );
+        assert!(repo
+            .find(&waiting.transaction.reference, "merchant123", now, &key())
+            .await
+            .unwrap()
+            .is_none());
+
+        let much_later = now + TimeDelta::seconds(3 * CHALLENGE_TIMEOUT_SECS);
+        let mut expired = repo.expire(much_later).await.unwrap();
+        expired.sort();
+        let mut expected = vec![
+            later.transaction.reference.clone(),
+            orphan.transaction.reference.clone(),
+        ];
+        expected.sort();
+        assert_eq!(expired, expected);
+    }
+}
@@ -1,12 +1,35 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
+use chrono::{DateTime, Utc};
 use sqlx::{
     postgres::{PgArguments, PgRow},
     query::Query,
-    FromRow, Postgres, Row,
+    types::Json,
+    FromRow, Postgres, QueryBuilder, Row,
 };
 
-use crate::{account::AcquirerAccount, payment::Payment, transaction::Transaction};
+use crate::{
+    account::{AcquirerAccount, BankOneAccount, BankTwoAccount},
+    amount::Amount,
+    billing::Billing,
+    card_scheme::CardScheme,
+    country::Country,
+    currency::Currency,
+    customer::Customer,
+    error::{Error, ErrorKind},
+    fraud::FraudResult,
+    limits::CardUsage,
+    merchant::Merchant,
+    payment::{ExpiryDate, Payment},
+    secret::Secret,
+    stored_credential::StoredCredential,
+    three_ds::ThreeDsResult,
+    transaction::{
+        search::{Cursor, TransactionFilter, TransactionPage, TransactionSummary},
+        Transaction, TransactionStatus,
+    },
+    utils,
+};
 
 use super::{Entity, Pool, Repo};
 
@@ -21,85 +44,1313 @@ impl Repo for TransactionRepo {
     type Id = String;
 
     fn pool(&self) -> &sqlx::PgPool {
-        &**self.pool
+        &self.pool
+    }
+}
+
+impl TransactionRepo {
+    /// Finds the references of a merchant's transactions made with the given merchant reference
+    pub async fn find_by_merchant_reference(
+        &self,
+        merchant_id: &str,
+        merchant_reference: &str,
+    ) -> Result<Vec<String>, Error> {
+        let references = sqlx::query_scalar(
+            "SELECT reference FROM transaction.transactions WHERE merchant_id = $1 AND merchant_reference = $2;",
+        )
+        .bind(merchant_id)
+        .bind(merchant_reference)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(references)
+    }
+
+    /// The transaction made for a subscription charge, None if it hasn't been made
+    pub async fn find_subscription_charge(
+        &self,
+        merchant_id: &str,
+        subscription_charge: &str,
+    ) -> Result<Option<TransactionSummary>, Error> {
+        let summary = sqlx::query_as(
+            "SELECT * FROM transaction.transactions \
+             WHERE merchant_id = $1 AND subscription_charge = $2",
+        )
+        .bind(merchant_id)
+        .bind(subscription_charge)
+        .fetch_optional(&**self.pool)
+        .await?;
+        Ok(summary)
+    }
+
+    /// The transaction as it's listed, None if there isn't one with the reference
+    pub async fn find_summary(&self, reference: &str) -> Result<Option<TransactionSummary>, Error> {
+        let summary = sqlx::query_as("SELECT * FROM transaction.transactions WHERE reference = $1")
+            .bind(reference)
+            .fetch_optional(&**self.pool)
+            .await?;
+        Ok(summary)
+    }
+
+    /// Moves a stored transaction on to a new status, like when its acquirer says it has
+    /// settled
+    pub async fn update_status(
+        &self,
+        reference: &str,
+        status: &TransactionStatus,
+    ) -> Result<(), Error> {
+        let res = sqlx::query(
+            "UPDATE transaction.transactions SET status = $2, failure_reason = $3 \
+             WHERE reference = $1",
+        )
+        .bind(reference)
+        .bind(status.to_string())
+        .bind(status.reason().map(|reason| reason.to_string()))
+        .execute(&**self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    /// Counts the card's successful transactions with the merchant since the given time, only
+    /// amounts in the given currency are added up. Settled and charged back transactions
+    /// succeeded too, so they're counted.
+    pub async fn card_usage(
+        &self,
+        merchant_id: &str,
+        card_fingerprint: &str,
+        currency: Currency,
+        since: DateTime<Utc>,
+    ) -> Result<CardUsage, Error> {
+        let row = sqlx::query(
+            "SELECT count(*) AS transactions, \
+             COALESCE(sum(amount) FILTER (WHERE currency = $3), 0) AS amount \
+             FROM transaction.transactions \
+             WHERE merchant_id = $1 AND card_fingerprint = $2 AND created_at >= $4 \
+             AND status IN ('SUCCESS', 'SETTLED', 'CHARGED_BACK')",
+        )
+        .bind(merchant_id)
+        .bind(card_fingerprint)
+        .bind(currency.to_string())
+        .bind(since)
+        .fetch_one(&**self.pool)
+        .await?;
+        Ok(CardUsage {
+            transactions: row.try_get::<i64, _>("transactions")? as u32,
+            amount: row.try_get::<i64, _>("amount")? as u64,
+        })
+    }
+}
+
+impl TransactionRepo {
+    /// Lists the transactions matching the filter, newest first, starting after the cursor
+    pub async fn search(
+        &self,
+        filter: &TransactionFilter,
+        cursor: Option<&Cursor>,
+        limit: u32,
+    ) -> Result<TransactionPage, Error> {
+        let mut query =
+            QueryBuilder::<Postgres>::new("SELECT * FROM transaction.transactions WHERE TRUE");
+        if let Some(merchant_id) = &filter.merchant_id {
+            query
+                .push(" AND merchant_id = ")
+                .push_bind(merchant_id.clone());
+        }
+        if let Some(from) = filter.from {
+            query.push(" AND created_at >= ").push_bind(from);
+        }
+        if let Some(to) = filter.to {
+            query.push(" AND created_at < ").push_bind(to);
+        }
+        if let Some(status) = &filter.status {
+            query.push(" AND status = ").push_bind(status.to_string());
+        }
+        if let Some(r#type) = &filter.r#type {
+            query
+                .push(" AND transaction_type = ")
+                .push_bind(r#type.to_string());
+        }
+        if let Some(currency) = filter.currency {
+            query
+                .push(" AND currency = ")
+                .push_bind(currency.to_string());
+        }
+        if let Some(min_amount) = filter.min_amount {
+            query.push(" AND amount >= ").push_bind(min_amount as i64);
+        }
+        if let Some(max_amount) = filter.max_amount {
+            query.push(" AND amount <= ").push_bind(max_amount as i64);
+        }
+        if let Some(last_four) = &filter.last_four {
+            query
+                .push(" AND right(masked_pan, 4) = ")
+                .push_bind(last_four.clone());
+        }
+        if let Some(merchant_reference) = &filter.merchant_reference {
+            query
+                .push(" AND merchant_reference = ")
+                .push_bind(merchant_reference.clone());
+        }
+        if let Some(cursor) = cursor {
+            query
+                .push(" AND (created_at, reference) < (")
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.reference.clone())
+                .push(")");
+        }
+        // fetch one extra to find out whether there is another page
+        query
+            .push(" ORDER BY created_at DESC, reference DESC LIMIT ")
+            .push_bind(limit as i64 + 1);
+        let mut transactions = query
+            .build_query_as::<TransactionSummary>()
+            .fetch_all(&**self.pool)
+            .await?;
+        let next_cursor = if transactions.len() > limit as usize {
+            transactions.truncate(limit as usize);
+            transactions.last().map(Cursor::from)
+        } else {
+            None
+        };
+        Ok(TransactionPage {
+            transactions,
+            next_cursor,
+        })
     }
 }
 
+fn decode_err(index: &str, e: Error) -> sqlx::Error {
+    sqlx::Error::ColumnDecode {
+        index: index.into(),
+        source: Box::new(e),
+    }
+}
+
+/// Decodes a column with the type's `TryFrom<String>`, None when it's null
+fn decode_optional<T>(row: &PgRow, column: &str) -> Result<Option<T>, sqlx::Error>
+where
+    T: TryFrom<String, Error = Error>,
+{
+    row.try_get::<Option<String>, _>(column)?
+        .map(|value| T::try_from(value).map_err(|e| decode_err(column, e)))
+        .transpose()
+}
+
+fn decode_required<T>(row: &PgRow, column: &str) -> Result<T, sqlx::Error>
+where
+    T: TryFrom<String, Error = Error>,
+{
+    T::try_from(row.try_get::<String, _>(column)?).map_err(|e| decode_err(column, e))
+}
+
+/// The transaction's status, with the reason it failed if it did
+pub(crate) fn decode_status(row: &PgRow) -> Result<TransactionStatus, sqlx::Error> {
+    let status: TransactionStatus = decode_required(row, "status")?;
+    Ok(status.with_reason(decode_optional(row, "failure_reason")?))
+}
+
+/// The account the transaction was sent to the acquirer with, from its acquirer data
+fn decode_account(
+    acquirer: &str,
+    data: &serde_json::Value,
+) -> Result<AcquirerAccount, sqlx::Error> {
+    let field = |name: &str| {
+        data[name].as_str().map(String::from).ok_or_else(|| {
+            decode_err(
+                "acquirer_data",
+                Error {
+                    kind: ErrorKind::Type,
+                    message: format!("{acquirer} acquirer data has no {name}"),
+                },
+            )
+        })
+    };
+    match acquirer {
+        "bankone" => Ok(AcquirerAccount::BankOne(BankOneAccount {
+            merchant_identification_value: field("merchant_identification_value")?,
+        })),
+        "banktwo" => Ok(AcquirerAccount::BankTwo(BankTwoAccount {
+            merchant_reference: field("merchant_reference")?,
+        })),
+        _ => Err(decode_err(
+            "acquirer",
+            Error {
+                kind: ErrorKind::Type,
+                message: format!("{acquirer} is not a recognised acquirer"),
+            },
+        )),
+    }
+}
+
+/// Card expiry dates are stored as MM/YYYY
+fn decode_expiry_date(expiry_date: &str) -> Result<ExpiryDate, sqlx::Error> {
+    expiry_date
+        .split_once('/')
+        .and_then(|(month, year)| Some((year.parse().ok()?, month.parse().ok()?)))
+        .ok_or_else(|| {
+            decode_err(
+                "expiry_date",
+                Error {
+                    kind: ErrorKind::Type,
+                    message: format!("{expiry_date} is not a MM/YYYY expiry date"),
+                },
+            )
+        })
+}
+
+/// Only what's stored with the transaction comes back. The PAN is masked and the security code
+/// is never stored, so a card has the masked PAN and no security code, and an account's details
+/// aren't stored at all. The merchant and customer only have their ids, and the billing name is
+/// split back into first and last at its first space.
 impl<'r> FromRow<'r, PgRow> for Transaction {
     fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
-        let table_name: &str = row.get("table_name");
-        todo!()
+        let currency: Currency = decode_required(row, "currency")?;
+        let amount: i32 = row.try_get("amount")?;
+        let payment = match row.try_get::<Option<String>, _>("card_scheme")? {
+            Some(scheme) if !scheme.is_empty() => Payment::Card {
+                scheme: CardScheme::try_from(scheme).map_err(|e| decode_err("card_scheme", e))?,
+                expiry_date: decode_expiry_date(&row.try_get::<String, _>("expiry_date")?)?,
+                security_code: Secret::default(),
+                pan: row
+                    .try_get::<Option<String>, _>("masked_pan")?
+                    .unwrap_or_default(),
+            },
+            _ => Payment::Account {
+                account_number: String::new(),
+                sort_code: String::new(),
+            },
+        };
+        let billing_name: Option<String> = row.try_get("billing_name")?;
+        let billing_name = billing_name.unwrap_or_default();
+        let (first_name, last_name) = billing_name
+            .split_once(' ')
+            .unwrap_or((billing_name.as_str(), ""));
+        let text = |column: &str| -> Result<String, sqlx::Error> {
+            Ok(row
+                .try_get::<Option<String>, _>(column)?
+                .unwrap_or_default())
+        };
+        let billing = Billing {
+            first_name: first_name.into(),
+            last_name: last_name.into(),
+            premise: text("billing_premise")?,
+            street: text("billing_street")?,
+            city: text("billing_city")?,
+            county: text("billing_county")?,
+            postcode: text("billing_postcode")?,
+            country: match row.try_get::<Option<String>, _>("billing_country")? {
+                Some(country) if !country.is_empty() => {
+                    Country::try_from(country).map_err(|e| decode_err("billing_country", e))?
+                }
+                _ => Country::default(),
+            },
+            email: String::new(),
+        };
+        let merchant_id: String = row.try_get("merchant_id")?;
+        let acquirer: String = row.try_get("acquirer")?;
+        let acquirer_data: Json<serde_json::Value> = row.try_get("acquirer_data")?;
+        let customer = row
+            .try_get::<Option<String>, _>("customer_id")?
+            .map(|customer_id| -> Result<Customer, sqlx::Error> {
+                Ok(Customer {
+                    customer_id,
+                    merchant_id: merchant_id.clone(),
+                    first_name: String::new(),
+                    last_name: String::new(),
+                    email: String::new(),
+                    phone: String::new(),
+                    addresses: vec![],
+                    created_at: row.try_get("created_at")?,
+                })
+            })
+            .transpose()?;
+        let fraud = match row.try_get::<Option<i32>, _>("fraud_score")? {
+            Some(score) => {
+                let rules: Option<Json<Vec<String>>> = row.try_get("fraud_rules")?;
+                Some(FraudResult {
+                    score: score as u32,
+                    decision: decode_required(row, "fraud_decision")?,
+                    matched_rules: rules.map(|rules| rules.0).unwrap_or_default(),
+                })
+            }
+            None => None,
+        };
+        let three_ds = match decode_optional(row, "three_ds_status")? {
+            Some(status) => Some(ThreeDsResult {
+                status,
+                eci: row
+                    .try_get::<Option<String>, _>("three_ds_eci")?
+                    .map(|eci| eci.trim().to_string()),
+                cavv: None,
+                ds_transaction_id: text("three_ds_ds_transaction_id")?,
+            }),
+            None => None,
+        };
+        let stored_credential = match decode_optional(row, "stored_credential_usage")? {
+            Some(usage) => Some(StoredCredential {
+                usage,
+                initiator: decode_required(row, "stored_credential_initiator")?,
+                original_scheme_transaction_id: row.try_get("original_scheme_transaction_id")?,
+            }),
+            None => None,
+        };
+        let metadata: Json<BTreeMap<String, String>> = row.try_get("metadata")?;
+        Ok(Transaction {
+            reference: row.try_get("reference")?,
+            r#type: decode_required(row, "transaction_type")?,
+            amount: Amount::from((amount as u64, currency)),
+            payment,
+            billing,
+            merchant: Merchant {
+                merchant_id,
+                ..Default::default()
+            },
+            account: decode_account(&acquirer, &acquirer_data.0)?,
+            customer,
+            status: decode_status(row)?,
+            currency,
+            merchant_reference: row.try_get("merchant_reference")?,
+            description: row.try_get("description")?,
+            metadata: metadata.0,
+            card_fingerprint: row.try_get("card_fingerprint")?,
+            fraud,
+            avs: decode_optional(row, "avs_result")?,
+            cvv: decode_optional(row, "cvv_result")?,
+            three_ds,
+            sca_exemption: decode_optional(row, "sca_exemption")?,
+            payment_token: row.try_get("payment_token")?,
+            stored_credential,
+            scheme_transaction_id: row.try_get("scheme_transaction_id")?,
+            subscription_charge: row.try_get("subscription_charge")?,
+        })
+    }
+}
+
+/// The columns a transaction is stored in, in the order [`Transaction::bind_values`] binds
+/// them after the reference
+const COLUMNS: &[&str] = &[
+    "reference",
+    "transaction_type",
+    "merchant_id",
+    "amount",
+    "currency",
+    "card_scheme",
+    "masked_pan",
+    "expiry_date",
+    "billing_name",
+    "billing_premise",
+    "billing_street",
+    "billing_city",
+    "billing_country",
+    "billing_county",
+    "acquirer",
+    "acquirer_data",
+    "merchant_reference",
+    "description",
+    "metadata",
+    "status",
+    "card_fingerprint",
+    "fraud_score",
+    "fraud_decision",
+    "fraud_rules",
+    "billing_postcode",
+    "avs_result",
+    "cvv_result",
+    "three_ds_status",
+    "three_ds_eci",
+    "three_ds_ds_transaction_id",
+    "sca_exemption",
+    "customer_id",
+    "payment_token",
+    "stored_credential_usage",
+    "stored_credential_initiator",
+    "original_scheme_transaction_id",
+    "scheme_transaction_id",
+    "failure_reason",
+    "subscription_charge",
+];
+
+impl Transaction {
+    /// Binds every persisted value apart from the reference. The security code is never bound,
+    /// it is a `Secret` so can't be encoded into a query anyway.
+    fn bind_values<'a>(
+        &'a self,
+        stmt: Query<'a, Postgres, PgArguments>,
+    ) -> Query<'a, Postgres, PgArguments> {
+        let (card_scheme, masked_pan, expiry_date) = match &self.payment {
+            Payment::Card {
+                scheme,
+                expiry_date,
+                pan,
+                ..
+            } => (
+                scheme.to_string(),
+                Some(utils::mask_pan(pan)),
+                format!("{:02}/{}", expiry_date.1, expiry_date.0),
+            ),
+            Payment::Account { .. } => (String::new(), None, String::new()),
+        };
+        let billing_name = format!("{} {}", self.billing.first_name, self.billing.last_name)
+            .trim()
+            .to_string();
+        stmt.bind(self.r#type.to_string())
+            .bind(self.merchant.merchant_id.clone())
+            .bind(self.amount.value() as i64)
+            .bind(self.currency.to_string())
+            .bind(card_scheme)
+            .bind(masked_pan)
+            .bind(expiry_date)
+            .bind(billing_name)
+            .bind(self.billing.premise.clone())
+            .bind(self.billing.street.clone())
+            .bind(self.billing.city.clone())
+            .bind(self.billing.country.to_string())
+            .bind(self.billing.county.clone())
+            .bind(self.account.acquirer())
+            .bind(Json(self.account.acquirer_data()))
+            .bind(self.merchant_reference.clone())
+            .bind(self.description.clone())
+            .bind(Json(self.metadata.clone()))
+            .bind(self.status.to_string())
+            .bind(self.card_fingerprint.clone())
+            .bind(self.fraud.as_ref().map(|f| f.score as i32))
+            .bind(self.fraud.as_ref().map(|f| f.decision.to_string()))
+            .bind(self.fraud.as_ref().map(|f| Json(f.matched_rules.clone())))
+            .bind(self.billing.postcode.clone())
+            .bind(self.avs.as_ref().map(|avs| avs.code().to_string()))
+            .bind(self.cvv.map(|cvv| cvv.to_string()))
+            .bind(self.three_ds.as_ref().map(|t| t.status.to_string()))
+            .bind(self.three_ds.as_ref().and_then(|t| t.eci.clone()))
+            .bind(self.three_ds.as_ref().map(|t| t.ds_transaction_id.clone()))
+            .bind(self.sca_exemption.map(|e| e.to_string()))
+            .bind(self.customer.as_ref().map(|c| c.customer_id.clone()))
+            .bind(self.payment_token.clone())
+            .bind(self.stored_credential.as_ref().map(|c| c.usage.to_string()))
+            .bind(
+                self.stored_credential
+                    .as_ref()
+                    .map(|c| c.initiator.to_string()),
+            )
+            .bind(
+                self.stored_credential
+                    .as_ref()
+                    .and_then(|c| c.original_scheme_transaction_id.clone()),
+            )
+            .bind(self.scheme_transaction_id.clone())
+            .bind(self.status.reason().map(|reason| reason.to_string()))
+            .bind(self.subscription_charge.clone())
     }
 }
 
 impl Entity for Transaction {
+    const ID_COLUMN: &'static str = "reference";
+
+    const INSERT_COLUMNS: &'static [&'static str] = COLUMNS;
+
+    /// encrypted_pan and the customer detail columns are left as their defaults, the customer
+    /// is stored by their id
     fn values_str_for_insert(&self) -> String {
-        let num_base_args = 5; // reference, amount, currency, transaction_type, result
-        let num_payment_args = match self.payment {
-            Payment::Card { .. } => 4,
-            Payment::Account { .. } => 2,
-        };
-        let num_billing_args = 7; // name, premise, street, city, postcode, county, country
-        let num_customer_args = 7; // name, premise, street, city, postcode, county, country
-        let num_account_args = match self.account {
-            // TODO get these from the actual accounts?
-            AcquirerAccount::BankOne(..) => 1,
-            AcquirerAccount::BankTwo(..) => 1,
-        };
-        let total = num_base_args
-            + num_payment_args
-            + num_billing_args
-            + num_customer_args
-            + num_account_args;
-        (1..=total)
-            .into_iter()
+        (1..=COLUMNS.len())
             .map(|n| format!("${n}"))
             .collect::<Vec<_>>()
-            .join(",")
+            .join(", ")
     }
 
     fn bind_to_insert<'a>(
         &'a self,
         stmt: Query<'a, Postgres, PgArguments>,
     ) -> Query<'a, Postgres, PgArguments> {
-        todo!()
+        self.bind_values(stmt.bind(self.reference.clone()))
     }
 
+    /// Sets every column apart from the reference, which is bound first
+    /// A transaction is only updated once it has been processed, so it's marked as processed too
     fn values_str_for_update(&self) -> String {
-        let num_base_args = 4; // amount, currency, transaction_type, result
-        let num_payment_args = match self.payment {
-            Payment::Card { .. } => 4,
-            Payment::Account { .. } => 2,
-        };
-        let num_billing_args = 7;
-        let num_customer_args = 7;
-        let num_account_args = match self.account {
-            AcquirerAccount::BankOne(..) => 1,
-            AcquirerAccount::BankTwo(..) => 1,
-        };
-        let total = num_base_args
-            + num_payment_args
-            + num_billing_args
-            + num_customer_args
-            + num_account_args;
-        (1..=total)
-            .into_iter()
-            .map(|n| format!("${n}"))
+        COLUMNS
+            .iter()
+            .enumerate()
+            .skip(1)
+            .map(|(n, column)| format!("{column} = ${}", n + 1))
+            .chain(["processed_at = now()".to_string()])
             .collect::<Vec<_>>()
-            .join(",")
+            .join(", ")
     }
 
     fn bind_to_update<'a>(
         &'a self,
         stmt: Query<'a, Postgres, PgArguments>,
     ) -> Query<'a, Postgres, PgArguments> {
-        todo!()
+        self.bind_values(stmt)
     }
 
     fn table_name(&self) -> &'static str {
-        match self.account {
-            AcquirerAccount::BankOne(..) => "transaction.bankone",
-            AcquirerAccount::BankTwo(..) => "transaction.banktwo",
+        "transaction.transactions"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::{AcquirerAccount, BankOneAccount, BankTwoAccount},
+        avs::AvsResult,
+        billing::Billing,
+        card_scheme::CardScheme,
+        currency::Currency,
+        customer::Customer,
+        cvv::CvvResult,
+        fraud::{FraudDecision, FraudResult},
+        merchant::Merchant,
+        sca::ScaExemption,
+        stored_credential::{CredentialUsage, Initiator, StoredCredential},
+        three_ds::{AuthenticationStatus, ThreeDsResult},
+        transaction::{
+            transaction_builder::TransactionBuilder, TransactionError, TransactionStatus,
+            TransactionType,
+        },
+    };
+    use chrono::{DateTime, Utc};
+    use sqlx::{PgPool, Row};
+    use std::collections::BTreeMap;
+
+    #[sqlx::test]
+    async fn test_insert_never_persists_security_code(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let trx = TransactionBuilder::new()
+            .transaction_type(TransactionType::Auth)
+            .amount(12345)
+            .currency(Currency::GBP)
+            .payment(Payment::Card {
+                scheme: CardScheme::Visa,
+                expiry_date: (2026, 3),
+                security_code: "987".into(),
+                pan: "4000111122223333".into(),
+            })
+            .billing(Billing {
+                first_name: "Ben".into(),
+                last_name: "Jones".into(),
+                ..Default::default()
+            })
+            .merchant(Merchant {
+                merchant_id: "merchant123".into(),
+                ..Default::default()
+            })
+            .account(AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: "merchant123".into(),
+            }))
+            .merchant_reference(Some("order-1".into()))
+            .metadata(BTreeMap::from([("basket".into(), "3".into())]))
+            .build();
+        let id = repo.insert_one(&trx).await.unwrap();
+        assert_eq!(id, trx.reference);
+        let row = sqlx::query("SELECT * FROM transaction.transactions WHERE reference = $1")
+            .bind(&id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, _>("masked_pan"), "400011######3333");
+        assert_eq!(row.get::<String, _>("expiry_date"), "03/2026");
+        assert_eq!(row.get::<String, _>("billing_name"), "Ben Jones");
+        assert_eq!(row.get::<String, _>("merchant_reference"), "order-1");
+        assert_eq!(row.get::<String, _>("acquirer"), "bankone");
+        assert_eq!(
+            row.get::<Json<serde_json::Value>, _>("acquirer_data").0,
+            serde_json::json!({"merchant_identification_value": "merchant123"})
+        );
+        assert_eq!(
+            row.get::<Json<BTreeMap<String, String>>, _>("metadata").0,
+            trx.metadata
+        );
+        let found = repo
+            .find_by_merchant_reference("merchant123", "order-1")
+            .await
+            .unwrap();
+        assert_eq!(found, vec![trx.reference.clone()]);
+        let found = repo
+            .find_by_merchant_reference("merchant123", "order-2")
+            .await
+            .unwrap();
+        assert!(found.is_empty());
+        // the generated reference and timestamp could contain the digits by chance
+        let row_text = sqlx::query(
+            "SELECT (to_jsonb(t) - 'reference' - 'created_at')::text AS row_text FROM transaction.transactions t",
+        )
+            .fetch_one(&pool)
+            .await
+            .unwrap()
+            .get::<String, _>("row_text");
+        assert!(!row_text.contains("987"));
+        assert!(!row_text.contains("4000111122223333"));
+    }
+
+    fn search_trx(
+        account: AcquirerAccount,
+        amount: u64,
+        currency: Currency,
+        pan: &str,
+        merchant_reference: Option<&str>,
+    ) -> Transaction {
+        TransactionBuilder::new()
+            .transaction_type(TransactionType::Auth)
+            .amount(amount)
+            .currency(currency)
+            .payment(Payment::from((CardScheme::Visa, (2026, 3), "123", pan)))
+            .billing(Billing::default())
+            .merchant(Merchant {
+                merchant_id: "merchant123".into(),
+                ..Default::default()
+            })
+            .account(account)
+            .merchant_reference(merchant_reference.map(String::from))
+            .build()
+    }
+
+    fn bank_one() -> AcquirerAccount {
+        AcquirerAccount::BankOne(BankOneAccount {
+            merchant_identification_value: "merchant123".into(),
+        })
+    }
+
+    fn bank_two() -> AcquirerAccount {
+        AcquirerAccount::BankTwo(BankTwoAccount {
+            merchant_reference: "merchant123".into(),
+        })
+    }
+
+    /// Inserts the transactions an hour apart, oldest first, returning their references
+    async fn insert_all(
+        pool: &PgPool,
+        repo: &TransactionRepo,
+        trxs: Vec<Transaction>,
+    ) -> Vec<String> {
+        let mut references = vec![];
+        for (n, trx) in trxs.iter().enumerate() {
+            repo.insert_one(trx).await.unwrap();
+            let table = trx.table_name();
+            sqlx::query(&format!(
+                "UPDATE {table} SET created_at = '2025-01-01T00:00:00Z'::timestamptz + make_interval(hours => $2) WHERE reference = $1"
+            ))
+            .bind(&trx.reference)
+            .bind(n as i32)
+            .execute(pool)
+            .await
+            .unwrap();
+            references.push(trx.reference.clone());
         }
+        references
+    }
+
+    fn refs(page: &TransactionPage) -> Vec<String> {
+        page.transactions
+            .iter()
+            .map(|t| t.reference.clone())
+            .collect()
+    }
+
+    #[sqlx::test]
+    async fn test_card_usage(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let fingerprinted = |amount, currency, pan: &str| {
+            let mut trx = search_trx(bank_one(), amount, currency, pan, None);
+            trx.card_fingerprint = Some(format!("fp-{pan}"));
+            trx
+        };
+        let start = Utc::now();
+        for trx in [
+            fingerprinted(1000, Currency::GBP, "4000111122223333"),
+            fingerprinted(2000, Currency::GBP, "4000111122223333"),
+            fingerprinted(4000, Currency::USD, "4000111122223333"),
+            fingerprinted(8000, Currency::GBP, "4000111122224444"),
+        ] {
+            repo.insert_one(&trx).await.unwrap();
+        }
+        let usage = repo
+            .card_usage("merchant123", "fp-4000111122223333", Currency::GBP, start)
+            .await
+            .unwrap();
+        assert_eq!(
+            usage,
+            CardUsage {
+                transactions: 3,
+                amount: 3000
+            }
+        );
+        let usage = repo
+            .card_usage("merchant123", "fp-unknown", Currency::GBP, start)
+            .await
+            .unwrap();
+        assert_eq!(usage, CardUsage::default());
+        let usage = repo
+            .card_usage(
+                "merchant123",
+                "fp-4000111122223333",
+                Currency::GBP,
+                Utc::now(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(usage.transactions, 0);
+    }
+
+    #[sqlx::test]
+    async fn test_status_updates(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let mut trx = search_trx(bank_one(), 1000, Currency::GBP, "4000111122223333", None);
+        trx.card_fingerprint = Some("fp".into());
+        let start = Utc::now();
+        repo.insert_one(&trx).await.unwrap();
+        assert_eq!(repo.find_summary("missing").await.unwrap(), None);
+        repo.update_status(&trx.reference, &TransactionStatus::Settled)
+            .await
+            .unwrap();
+        let summary = repo.find_summary(&trx.reference).await.unwrap().unwrap();
+        assert_eq!(summary.status, TransactionStatus::Settled);
+        assert_eq!(summary.acquirer, "bankone");
+        // settled transactions still count towards the card's usage
+        let usage = repo
+            .card_usage("merchant123", "fp", Currency::GBP, start)
+            .await
+            .unwrap();
+        assert_eq!(usage.transactions, 1);
+        let declined = TransactionStatus::Failed(Some(TransactionError::Declined));
+        repo.update_status(&trx.reference, &declined).await.unwrap();
+        let summary = repo.find_summary(&trx.reference).await.unwrap().unwrap();
+        assert_eq!(summary.status, declined);
+        assert!(repo
+            .update_status("missing", &TransactionStatus::Settled)
+            .await
+            .is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_subscription_charges(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let mut trx = search_trx(bank_one(), 1000, Currency::GBP, "4000111122223333", None);
+        trx.subscription_charge = Some("sub-1-1767258000".into());
+        repo.insert_one(&trx).await.unwrap();
+        let found = repo
+            .find_subscription_charge("merchant123", "sub-1-1767258000")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.reference, trx.reference);
+        // stored but not yet answered by the acquirer
+        assert_eq!(found.processed_at, None);
+        repo.update_one(&trx.reference, &trx).await.unwrap();
+        let found = repo.find_summary(&trx.reference).await.unwrap().unwrap();
+        assert!(found.processed_at.is_some());
+        let read = repo
+            .select_one(&trx.reference, "transaction.transactions")
+            .await
+            .unwrap();
+        assert_eq!(read.subscription_charge, trx.subscription_charge);
+        assert_eq!(
+            repo.find_subscription_charge("merchant123", "sub-1-1767258001")
+                .await
+                .unwrap(),
+            None
+        );
+        // a charge can only be made once
+        let mut again = search_trx(bank_one(), 1000, Currency::GBP, "4000111122223333", None);
+        again.subscription_charge = trx.subscription_charge.clone();
+        assert!(repo.insert_one(&again).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_fraud_result_is_stored(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let mut trx = search_trx(bank_one(), 1000, Currency::GBP, "4000111122223333", None);
+        repo.insert_one(&trx).await.unwrap();
+        let fraud_columns =
+            "SELECT fraud_score, fraud_decision, fraud_rules FROM transaction.transactions";
+        let row = sqlx::query(fraud_columns).fetch_one(&pool).await.unwrap();
+        assert_eq!(row.get::<Option<i32>, _>("fraud_score"), None);
+        assert_eq!(row.get::<Option<String>, _>("fraud_decision"), None);
+        trx.fraud = Some(FraudResult {
+            score: 120,
+            decision: FraudDecision::Reject,
+            matched_rules: vec!["amount_over".into(), "country_mismatch".into()],
+        });
+        repo.update_one(&trx.reference, &trx).await.unwrap();
+        let row = sqlx::query(fraud_columns).fetch_one(&pool).await.unwrap();
+        assert_eq!(row.get::<Option<i32>, _>("fraud_score"), Some(120));
+        assert_eq!(
+            row.get::<Option<String>, _>("fraud_decision").as_deref(),
+            Some("REJECT")
+        );
+        assert_eq!(
+            row.get::<Json<Vec<String>>, _>("fraud_rules").0,
+            vec!["amount_over", "country_mismatch"]
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_fraud_rejections_are_read_back(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let trx = TransactionBuilder::new()
+            .transaction_type(TransactionType::Auth)
+            .amount(1000)
+            .currency(Currency::GBP)
+            .payment(Payment::from((
+                CardScheme::Visa,
+                (2026, 3),
+                "123",
+                "4000111122223333",
+            )))
+            .billing(Billing::default())
+            .merchant(Merchant {
+                merchant_id: "merchant123".into(),
+                ..Default::default()
+            })
+            .account(bank_one())
+            .fraud(Some(FraudResult {
+                score: 120,
+                decision: FraudDecision::Reject,
+                matched_rules: vec!["amount_over".into()],
+            }))
+            .build();
+        let rejected = TransactionStatus::Failed(Some(TransactionError::FraudRejected));
+        assert_eq!(trx.status, rejected);
+        repo.insert_one(&trx).await.unwrap();
+        let mut declined = search_trx(bank_one(), 1000, Currency::GBP, "4000111122223333", None);
+        declined.status = TransactionStatus::Failed(Some(TransactionError::Declined));
+        repo.insert_one(&declined).await.unwrap();
+
+        let read = repo
+            .select_one(&trx.reference, "transaction.transactions")
+            .await
+            .unwrap();
+        assert_eq!(read.status, rejected);
+        assert_eq!(read.fraud, trx.fraud);
+        let failed = repo
+            .search(
+                &TransactionFilter {
+                    status: Some(TransactionStatus::Failed(None)),
+                    ..Default::default()
+                },
+                None,
+                10,
+            )
+            .await
+            .unwrap();
+        let statuses = failed
+            .transactions
+            .iter()
+            .map(|t| (t.reference.as_str(), &t.status))
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            statuses,
+            BTreeMap::from([
+                (trx.reference.as_str(), &rejected),
+                (declined.reference.as_str(), &declined.status),
+            ])
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_postcode_and_check_results_are_stored(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let mut trx = search_trx(bank_one(), 1000, Currency::GBP, "4000111122223333", None);
+        trx.billing.postcode = "LS1 4AB".into();
+        repo.insert_one(&trx).await.unwrap();
+        let avs_columns = "SELECT billing_postcode, avs_result, cvv_result, three_ds_status, \
+             three_ds_eci, three_ds_ds_transaction_id, sca_exemption FROM transaction.transactions";
+        let row = sqlx::query(avs_columns).fetch_one(&pool).await.unwrap();
+        assert_eq!(row.get::<String, _>("billing_postcode"), "LS1 4AB");
+        assert_eq!(row.get::<Option<String>, _>("avs_result"), None);
+        trx.avs = Some(AvsResult::try_from("A".to_string()).unwrap());
+        trx.cvv = Some(CvvResult::NoMatch);
+        trx.three_ds = Some(ThreeDsResult {
+            status: AuthenticationStatus::Authenticated,
+            eci: Some("05".into()),
+            cavv: Some("AAABBBCCC".into()),
+            ds_transaction_id: "ds-123".into(),
+        });
+        trx.sca_exemption = Some(ScaExemption::TransactionRiskAnalysis);
+        repo.update_one(&trx.reference, &trx).await.unwrap();
+        let row = sqlx::query(avs_columns).fetch_one(&pool).await.unwrap();
+        assert_eq!(
+            row.get::<Option<String>, _>("avs_result").as_deref(),
+            Some("A")
+        );
+        assert_eq!(
+            row.get::<Option<String>, _>("cvv_result").as_deref(),
+            Some("N")
+        );
+        assert_eq!(row.get::<String, _>("three_ds_status"), "Y");
+        assert_eq!(row.get::<String, _>("three_ds_eci"), "05");
+        assert_eq!(row.get::<String, _>("three_ds_ds_transaction_id"), "ds-123");
+        assert_eq!(row.get::<String, _>("sca_exemption"), "TRA");
+    }
+
+    #[sqlx::test]
+    async fn test_customer_is_stored_by_id(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let mut trx = search_trx(bank_one(), 1000, Currency::GBP, "4000111122223333", None);
+        repo.insert_one(&trx).await.unwrap();
+        let customer_columns = "SELECT customer_id, payment_token FROM transaction.transactions";
+        let row = sqlx::query(customer_columns)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<Option<String>, _>("customer_id"), None);
+        let customer = Customer::new("merchant123".into());
+        trx.customer = Some(customer.clone());
+        trx.payment_token = Some("token123".into());
+        repo.update_one(&trx.reference, &trx).await.unwrap();
+        let row = sqlx::query(customer_columns)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(row.get::<String, _>("customer_id"), customer.customer_id);
+        assert_eq!(row.get::<String, _>("payment_token"), "token123");
+    }
+
+    #[sqlx::test]
+    async fn test_stored_credential_is_stored(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let mut trx = search_trx(bank_one(), 1000, Currency::GBP, "4000111122223333", None);
+        trx.stored_credential = Some(StoredCredential {
+            usage: CredentialUsage::Subsequent,
+            initiator: Initiator::Merchant,
+            original_scheme_transaction_id: Some("123456789012345".into()),
+        });
+        repo.insert_one(&trx).await.unwrap();
+        trx.scheme_transaction_id = Some("543210987654321".into());
+        repo.update_one(&trx.reference, &trx).await.unwrap();
+        let row = sqlx::query(
+            "SELECT stored_credential_usage, stored_credential_initiator, \
+             original_scheme_transaction_id, scheme_transaction_id FROM transaction.transactions",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            row.get::<String, _>("stored_credential_usage"),
+            "SUBSEQUENT"
+        );
+        assert_eq!(row.get::<String, _>("stored_credential_initiator"), "MIT");
+        assert_eq!(
+            row.get::<String, _>("original_scheme_transaction_id"),
+            "123456789012345"
+        );
+        assert_eq!(
+            row.get::<String, _>("scheme_transaction_id"),
+            "543210987654321"
+        );
+    }
+
+    #[sqlx::test]
+    async fn test_search_filters(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let refs_in = insert_all(
+            &pool,
+            &repo,
+            vec![
+                search_trx(
+                    bank_one(),
+                    1000,
+                    Currency::GBP,
+                    "4000111122223333",
+                    Some("order-1"),
+                ),
+                search_trx(bank_two(), 2000, Currency::USD, "4000111122224444", None),
+                search_trx(
+                    bank_one(),
+                    3000,
+                    Currency::GBP,
+                    "4000111122225555",
+                    Some("order-3"),
+                ),
+            ],
+        )
+        .await;
+        let search = |filter: TransactionFilter| {
+            let repo = &repo;
+            async move { refs(&repo.search(&filter, None, 50).await.unwrap()) }
+        };
+        let all = search(TransactionFilter::default()).await;
+        assert_eq!(
+            all,
+            vec![refs_in[2].clone(), refs_in[1].clone(), refs_in[0].clone()]
+        );
+        let by_currency = search(TransactionFilter {
+            currency: Some(Currency::USD),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(by_currency, vec![refs_in[1].clone()]);
+        let by_amount = search(TransactionFilter {
+            min_amount: Some(1500),
+            max_amount: Some(3000),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(by_amount, vec![refs_in[2].clone(), refs_in[1].clone()]);
+        let by_last_four = search(TransactionFilter {
+            last_four: Some("3333".into()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(by_last_four, vec![refs_in[0].clone()]);
+        let by_reference = search(TransactionFilter {
+            merchant_reference: Some("order-3".into()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(by_reference, vec![refs_in[2].clone()]);
+        let by_date = search(TransactionFilter {
+            from: Some("2025-01-01T01:00:00Z".parse::<DateTime<Utc>>().unwrap()),
+            to: Some("2025-01-01T02:00:00Z".parse::<DateTime<Utc>>().unwrap()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(by_date, vec![refs_in[1].clone()]);
+        let by_status = search(TransactionFilter {
+            status: Some(TransactionStatus::Failed(None)),
+            ..Default::default()
+        })
+        .await;
+        assert!(by_status.is_empty());
+        let by_merchant = search(TransactionFilter {
+            merchant_id: Some("merchant456".into()),
+            r#type: Some(TransactionType::Auth),
+            ..Default::default()
+        })
+        .await;
+        assert!(by_merchant.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn test_search_pages_across_acquirers(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let mut refs_in = insert_all(
+            &pool,
+            &repo,
+            vec![
+                search_trx(bank_one(), 1000, Currency::GBP, "4000111122223333", None),
+                search_trx(bank_two(), 2000, Currency::GBP, "4000111122223333", None),
+                search_trx(bank_one(), 3000, Currency::GBP, "4000111122223333", None),
+                search_trx(bank_two(), 4000, Currency::GBP, "4000111122223333", None),
+                search_trx(bank_one(), 5000, Currency::GBP, "4000111122223333", None),
+            ],
+        )
+        .await;
+        refs_in.reverse();
+        let filter = TransactionFilter::default();
+        let first = repo.search(&filter, None, 2).await.unwrap();
+        assert_eq!(refs(&first), refs_in[0..2]);
+        let second = repo
+            .search(&filter, first.next_cursor.as_ref(), 2)
+            .await
+            .unwrap();
+        assert_eq!(refs(&second), refs_in[2..4]);
+        assert_eq!(second.transactions[1].acquirer, "banktwo");
+        let third = repo
+            .search(&filter, second.next_cursor.as_ref(), 2)
+            .await
+            .unwrap();
+        assert_eq!(refs(&third), refs_in[4..]);
+        assert_eq!(third.next_cursor, None);
+        let summary = &third.transactions[0];
+        assert_eq!(summary.amount, 1000);
+        assert_eq!(summary.masked_pan.as_deref(), Some("400011######3333"));
+        assert_eq!(summary.card_scheme, Some(CardScheme::Visa));
+        assert_eq!(summary.status, TransactionStatus::Success);
+    }
+
+    #[sqlx::test]
+    async fn test_transactions_are_read_back(pool: PgPool) {
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let mut trx = search_trx(bank_two(), 1000, Currency::GBP, "4000111122223333", None);
+        trx.billing = Billing {
+            first_name: "Mary Ann".into(),
+            last_name: "Jones".into(),
+            postcode: "LS1 4AB".into(),
+            ..Default::default()
+        };
+        trx.merchant_reference = Some("order-1".into());
+        trx.metadata = BTreeMap::from([("basket".into(), "3".into())]);
+        trx.fraud = Some(FraudResult {
+            score: 20,
+            decision: FraudDecision::Accept,
+            matched_rules: vec!["amount_over".into()],
+        });
+        trx.avs = Some(AvsResult::try_from("Y".to_string()).unwrap());
+        trx.cvv = Some(CvvResult::Match);
+        trx.three_ds = Some(ThreeDsResult {
+            status: AuthenticationStatus::Authenticated,
+            eci: Some("05".into()),
+            cavv: Some("AAABBBCCC".into()),
+            ds_transaction_id: "ds-123".into(),
+        });
+        trx.sca_exemption = Some(ScaExemption::TransactionRiskAnalysis);
+        let customer = Customer::new("merchant123".into());
+        trx.customer = Some(customer.clone());
+        trx.payment_token = Some("token123".into());
+        trx.stored_credential = Some(StoredCredential {
+            usage: CredentialUsage::Initial,
+            initiator: Initiator::Cardholder,
+            original_scheme_transaction_id: None,
+        });
+        trx.scheme_transaction_id = Some("543210987654321".into());
+        trx.status = TransactionStatus::Failed(Some(TransactionError::CvvMismatch));
+        repo.insert_one(&trx).await.unwrap();
+
+        let read = repo
+            .select_one(&trx.reference, "transaction.transactions")
+            .await
+            .unwrap();
+        assert_eq!(read.reference, trx.reference);
+        assert_eq!(read.r#type, trx.r#type);
+        assert_eq!(read.amount, trx.amount);
+        assert_eq!(read.currency, trx.currency);
+        assert_eq!(
+            read.payment,
+            Payment::from((CardScheme::Visa, (2026, 3), "", "400011######3333"))
+        );
+        // the first name's second word is taken to be part of the last name
+        assert_eq!(read.billing.first_name, "Mary");
+        assert_eq!(read.billing.last_name, "Ann Jones");
+        assert_eq!(read.billing.postcode, "LS1 4AB");
+        assert_eq!(read.merchant.merchant_id, "merchant123");
+        assert_eq!(read.account, bank_two());
+        assert_eq!(
+            read.customer.map(|c| c.customer_id),
+            Some(customer.customer_id)
+        );
+        assert_eq!(read.status, trx.status);
+        assert_eq!(read.merchant_reference, trx.merchant_reference);
+        assert_eq!(read.metadata, trx.metadata);
+        assert_eq!(read.card_fingerprint, trx.card_fingerprint);
+        assert_eq!(read.fraud, trx.fraud);
+        assert_eq!(read.avs, trx.avs);
+        assert_eq!(read.cvv, trx.cvv);
+        // the cryptogram is only ever sent to the acquirer
+        assert_eq!(
+            read.three_ds,
+            Some(ThreeDsResult {
+                cavv: None,
+                ..trx.three_ds.clone().unwrap()
+            })
+        );
+        assert_eq!(read.sca_exemption, trx.sca_exemption);
+        assert_eq!(read.payment_token, trx.payment_token);
+        assert_eq!(read.stored_credential, trx.stored_credential);
+        assert_eq!(read.scheme_transaction_id, trx.scheme_transaction_id);
+    }
+
+    #[sqlx::test]
+    async fn test_unify_migration_keeps_each_acquirers_transactions(pool: PgPool) {
+        // back to the separate bankone and banktwo tables
+        let migrator = sqlx::migrate!("./migrations");
+        migrator.undo(&pool, 20261019000003).await.unwrap();
+        sqlx::query(
+            "INSERT INTO transaction.bankone (reference, transaction_type, merchant_id, amount, \
+             currency, card_scheme, masked_pan, expiry_date, billing_name, billing_country, \
+             merchant_identification_value, merchant_reference, metadata, status, created_at) \
+             VALUES ('ref-1', 'AUTH', 'merchant123', 1000, 'GBP', 'VISA', '400011######3333', \
+             '03/2026', 'Ben Jones', 'GB', 'mid-1', 'order-1', '{\"basket\": \"3\"}', 'SUCCESS', \
+             '2025-01-01T00:00:00Z')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO transaction.banktwo (reference, transaction_type, merchant_id, amount, \
+             currency, card_scheme, masked_pan, expiry_date, banktwo_merchant_id, status) \
+             VALUES ('ref-2', 'AUTH', 'merchant123', 2000, 'USD', 'VISA', '400011######4444', \
+             '12/2027', 'mref-2', 'FAILED')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        migrator.run(&pool).await.unwrap();
+
+        let repo = TransactionRepo {
+            pool: Arc::new(Pool::from(pool.clone())),
+        };
+        let bankone = repo
+            .select_one(&"ref-1".to_string(), "transaction.transactions")
+            .await
+            .unwrap();
+        assert_eq!(
+            bankone.account,
+            AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: "mid-1".into(),
+            })
+        );
+        assert_eq!(bankone.amount.value(), 1000);
+        assert_eq!(bankone.currency, Currency::GBP);
+        assert_eq!(
+            bankone.payment,
+            Payment::from((CardScheme::Visa, (2026, 3), "", "400011######3333"))
+        );
+        assert_eq!(bankone.billing.first_name, "Ben");
+        assert_eq!(bankone.billing.last_name, "Jones");
+        assert_eq!(bankone.merchant_reference.as_deref(), Some("order-1"));
+        assert_eq!(
+            bankone.metadata,
+            BTreeMap::from([("basket".into(), "3".into())])
+        );
+        assert_eq!(bankone.status, TransactionStatus::Success);
+        let banktwo = repo
+            .select_one(&"ref-2".to_string(), "transaction.transactions")
+            .await
+            .unwrap();
+        assert_eq!(
+            banktwo.account,
+            AcquirerAccount::BankTwo(BankTwoAccount {
+                merchant_reference: "mref-2".into(),
+            })
+        );
+        assert_eq!(banktwo.currency, Currency::USD);
+        // failures stored before their reasons were have none
+        assert_eq!(banktwo.status, TransactionStatus::Failed(None));
+        let created_at: DateTime<Utc> = sqlx::query_scalar(
+            "SELECT created_at FROM transaction.transactions WHERE reference = 'ref-1'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            created_at,
+            "2025-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT table_name::text FROM information_schema.tables \
+             WHERE table_schema = 'transaction' AND table_name IN ('bankone', 'banktwo')",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert!(tables.is_empty());
     }
 }
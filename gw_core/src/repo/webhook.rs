@@ -0,0 +1,304 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, types::Json, FromRow, Row};
+
+use crate::{
+    error::Error,
+    secret::Secret,
+    webhook::{DeliveryStatus, EventType, WebhookEndpoint, WebhookEvent},
+};
+
+use super::Pool;
+
+#[derive(Debug)]
+pub struct WebhookRepo {
+    pub pool: Arc<Pool>,
+}
+
+fn decode<T, E>(column: &str, value: Result<T, E>) -> Result<T, sqlx::Error>
+where
+    E: std::error::Error + Send + Sync + 'static,
+{
+    value.map_err(|e| sqlx::Error::ColumnDecode {
+        index: column.into(),
+        source: Box::new(e),
+    })
+}
+
+impl<'r> FromRow<'r, PgRow> for WebhookEndpoint {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(WebhookEndpoint {
+            merchant_id: row.try_get("merchant_id")?,
+            url: row.try_get("url")?,
+            secret: Secret::new(row.try_get("secret")?),
+            created_at: row.try_get("created_at")?,
+        })
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for WebhookEvent {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        Ok(WebhookEvent {
+            event_id: row.try_get("id")?,
+            merchant_id: row.try_get("merchant_id")?,
+            event_type: decode(
+                "event_type",
+                EventType::try_from(row.try_get::<String, _>("event_type")?),
+            )?,
+            data: row.try_get::<Json<serde_json::Value>, _>("data")?.0,
+            status: decode(
+                "status",
+                DeliveryStatus::try_from(row.try_get::<String, _>("status")?),
+            )?,
+            attempts: row.try_get::<i32, _>("attempts")? as u32,
+            next_attempt_at: row.try_get("next_attempt_at")?,
+            last_error: row.try_get("last_error")?,
+            created_at: row.try_get("created_at")?,
+            delivered_at: row.try_get("delivered_at")?,
+        })
+    }
+}
+
+impl WebhookRepo {
+    /// The merchant's endpoint, None if they don't want events
+    pub async fn find_endpoint(&self, merchant_id: &str) -> Result<Option<WebhookEndpoint>, Error> {
+        let endpoint =
+            sqlx::query_as("SELECT * FROM account.webhook_endpoints WHERE merchant_id = $1")
+                .bind(merchant_id)
+                .fetch_optional(&**self.pool)
+                .await?;
+        Ok(endpoint)
+    }
+
+    pub async fn insert_endpoint(&self, endpoint: &WebhookEndpoint) -> Result<(), Error> {
+        sqlx::query("INSERT INTO account.webhook_endpoints VALUES ($1, $2, $3, $4)")
+            .bind(&endpoint.merchant_id)
+            .bind(&endpoint.url)
+            .bind(endpoint.secret.expose())
+            .bind(endpoint.created_at)
+            .execute(&**self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Moves the merchant's endpoint, keeping its secret
+    pub async fn update_url(&self, merchant_id: &str, url: &str) -> Result<(), Error> {
+        let res =
+            sqlx::query("UPDATE account.webhook_endpoints SET url = $2 WHERE merchant_id = $1")
+                .bind(merchant_id)
+                .bind(url)
+                .execute(&**self.pool)
+                .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    pub async fn delete_endpoint(&self, merchant_id: &str) -> Result<(), Error> {
+        let res = sqlx::query("DELETE FROM account.webhook_endpoints WHERE merchant_id = $1")
+            .bind(merchant_id)
+            .execute(&**self.pool)
+            .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+
+    pub async fn insert_event(&self, event: &WebhookEvent) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO transaction.webhook_events \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(&event.event_id)
+        .bind(&event.merchant_id)
+        .bind(event.event_type.to_string())
+        .bind(Json(&event.data))
+        .bind(event.status.to_string())
+        .bind(event.attempts as i32)
+        .bind(event.next_attempt_at)
+        .bind(&event.last_error)
+        .bind(event.created_at)
+        .bind(event.delivered_at)
+        .execute(&**self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn find_event(
+        &self,
+        merchant_id: &str,
+        event_id: &str,
+    ) -> Result<WebhookEvent, Error> {
+        let event = sqlx::query_as(
+            "SELECT * FROM transaction.webhook_events WHERE merchant_id = $1 AND id = $2",
+        )
+        .bind(merchant_id)
+        .bind(event_id)
+        .fetch_one(&**self.pool)
+        .await?;
+        Ok(event)
+    }
+
+    /// Lists the merchant's events with the status, newest first
+    pub async fn list_events(
+        &self,
+        merchant_id: &str,
+        status: DeliveryStatus,
+        limit: i64,
+    ) -> Result<Vec<WebhookEvent>, Error> {
+        let events = sqlx::query_as(
+            "SELECT * FROM transaction.webhook_events WHERE merchant_id = $1 AND status = $2 \
+             ORDER BY created_at DESC, id DESC LIMIT $3",
+        )
+        .bind(merchant_id)
+        .bind(status.to_string())
+        .bind(limit)
+        .fetch_all(&**self.pool)
+        .await?;
+        Ok(events)
+    }
+
+    /// Claims the pending events due by `now`, oldest first. They aren't claimed again until
+    /// `lease_until`, so a delivery that's cut short is retried, and concurrent senders skip
+    /// each other's events.
+    pub async fn claim_due(
+        &self,
+        now: DateTime<Utc>,
+        lease_until: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<WebhookEvent>, Error> {
+        let mut events: Vec<WebhookEvent> = sqlx::query_as(
+            "UPDATE transaction.webhook_events SET next_attempt_at = $2 WHERE id IN ( \
+             SELECT id FROM transaction.webhook_events \
+             WHERE status = 'PENDING' AND next_attempt_at <= $1 \
+             ORDER BY next_attempt_at, id LIMIT $3 FOR UPDATE SKIP LOCKED) \
+             RETURNING *",
+        )
+        .bind(now)
+        .bind(lease_until)
+        .bind(limit)
+        .fetch_all(&**self.pool)
+        .await?;
+        events.sort_by(|a, b| (a.created_at, &a.event_id).cmp(&(b.created_at, &b.event_id)));
+        Ok(events)
+    }
+
+    /// Saves how delivery went, what the event is and who it's for don't change
+    pub async fn update_event(&self, event: &WebhookEvent) -> Result<(), Error> {
+        let res = sqlx::query(
+            "UPDATE transaction.webhook_events SET status = $3, attempts = $4, \
+             next_attempt_at = $5, last_error = $6, delivered_at = $7 \
+             WHERE merchant_id = $1 AND id = $2",
+        )
+        .bind(&event.merchant_id)
+        .bind(&event.event_id)
+        .bind(event.status.to_string())
+        .bind(event.attempts as i32)
+        .bind(event.next_attempt_at)
+        .bind(&event.last_error)
+        .bind(event.delivered_at)
+        .execute(&**self.pool)
+        .await?;
+        if res.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{SubsecRound, TimeDelta};
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    fn repo(pool: PgPool) -> WebhookRepo {
+        WebhookRepo {
+            pool: Arc::new(Pool::from(pool)),
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_endpoints(pool: PgPool) {
+        let repo = repo(pool);
+        assert_eq!(repo.find_endpoint("merchant123").await.unwrap(), None);
+        let endpoint = WebhookEndpoint::new("merchant123".into(), "https://example.com/a".into());
+        repo.insert_endpoint(&endpoint).await.unwrap();
+        assert!(repo.insert_endpoint(&endpoint).await.is_err());
+        repo.update_url("merchant123", "https://example.com/b")
+            .await
+            .unwrap();
+        let found = repo.find_endpoint("merchant123").await.unwrap().unwrap();
+        assert_eq!(found.url, "https://example.com/b");
+        assert_eq!(found.secret, endpoint.secret);
+        repo.delete_endpoint("merchant123").await.unwrap();
+        assert!(repo.delete_endpoint("merchant123").await.is_err());
+        assert!(repo
+            .update_url("merchant123", "https://example.com/c")
+            .await
+            .is_err());
+    }
+
+    #[sqlx::test]
+    async fn test_events_are_claimed_once_due(pool: PgPool) {
+        let repo = repo(pool);
+        let now = Utc::now();
+        let event = WebhookEvent::new(
+            "merchant123".into(),
+            EventType::TransactionSucceeded,
+            json!({"reference": "abc"}),
+            now,
+        );
+        repo.insert_event(&event).await.unwrap();
+        assert_eq!(
+            repo.find_event("merchant123", &event.event_id)
+                .await
+                .unwrap(),
+            event
+        );
+        assert!(repo
+            .find_event("merchant456", &event.event_id)
+            .await
+            .is_err());
+        assert_eq!(
+            repo.claim_due(now - TimeDelta::seconds(1), now, 10)
+                .await
+                .unwrap(),
+            vec![]
+        );
+        let lease_until = now + TimeDelta::minutes(5);
+        let mut claimed = repo.claim_due(now, lease_until, 10).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].next_attempt_at, lease_until.trunc_subsecs(6));
+        // claimed events wait for their lease to run out
+        assert_eq!(repo.claim_due(now, lease_until, 10).await.unwrap(), vec![]);
+
+        let mut event = claimed.remove(0);
+        for _ in 0..crate::webhook::MAX_ATTEMPTS {
+            event.record_failure("HTTP 500".into(), now);
+        }
+        repo.update_event(&event).await.unwrap();
+        assert_eq!(
+            repo.list_events("merchant123", DeliveryStatus::Dead, 10)
+                .await
+                .unwrap(),
+            vec![event.clone()]
+        );
+        assert_eq!(
+            repo.claim_due(now + TimeDelta::days(1), now + TimeDelta::days(1), 10)
+                .await
+                .unwrap(),
+            vec![]
+        );
+        assert_eq!(
+            repo.list_events("merchant123", DeliveryStatus::Pending, 10)
+                .await
+                .unwrap(),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use serde::Serialize;
+
+use crate::{
+    currency::Currency,
+    error::{Error, ErrorKind},
+    fraud::{FraudDecision, FraudResult},
+    transaction::{Transaction, TransactionError, TransactionStatus},
+};
+
+/// The PSD2 exemptions from strong customer authentication the gateway can ask the issuer for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ScaExemption {
+    LowValue,
+    /// Transaction risk analysis, the gateway's own screening found the transaction low risk
+    #[serde(rename = "TRA")]
+    TransactionRiskAnalysis,
+    /// Started by the merchant without the cardholder there, so it can't be authenticated
+    #[serde(rename = "MIT")]
+    MerchantInitiated,
+    Recurring,
+    /// A payment made with a corporate card through a secure corporate process
+    Corporate,
+}
+
+impl std::fmt::Display for ScaExemption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            ScaExemption::LowValue => "LOW_VALUE",
+            ScaExemption::TransactionRiskAnalysis => "TRA",
+            ScaExemption::MerchantInitiated => "MIT",
+            ScaExemption::Recurring => "RECURRING",
+            ScaExemption::Corporate => "CORPORATE",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl TryFrom<String> for ScaExemption {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "LOW_VALUE" => Ok(Self::LowValue),
+            "TRA" => Ok(Self::TransactionRiskAnalysis),
+            "MIT" => Ok(Self::MerchantInitiated),
+            "RECURRING" => Ok(Self::Recurring),
+            "CORPORATE" => Ok(Self::Corporate),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised SCA exemption"),
+            }),
+        }
+    }
+}
+
+/// The highest amount, in minor units of the currency, each exemption is asked for with the
+/// merchant's transactions in a currency. Exemptions left as `None` are never asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScaThresholds {
+    pub merchant_id: String,
+    pub currency: Currency,
+    pub low_value: Option<u64>,
+    pub transaction_risk_analysis: Option<u64>,
+    pub merchant_initiated: Option<u64>,
+    pub recurring: Option<u64>,
+    pub corporate: Option<u64>,
+}
+
+/// What the merchant told us about how the transaction was made
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScaIndicators {
+    pub merchant_initiated: bool,
+    pub recurring: bool,
+    pub corporate: bool,
+}
+
+impl ScaThresholds {
+    pub fn new(merchant_id: String, currency: Currency) -> Self {
+        Self {
+            merchant_id,
+            currency,
+            low_value: None,
+            transaction_risk_analysis: None,
+            merchant_initiated: None,
+            recurring: None,
+            corporate: None,
+        }
+    }
+
+    /// The exemption to ask for, if any apply. The ones the merchant told us about are preferred
+    /// over low value, and risk analysis is only used for transactions the fraud rules accepted.
+    pub fn choose(
+        &self,
+        amount: u64,
+        indicators: ScaIndicators,
+        fraud: Option<&FraudResult>,
+    ) -> Option<ScaExemption> {
+        let within = |max: Option<u64>| max.is_some_and(|max| amount <= max);
+        let low_risk = fraud.is_some_and(|f| f.decision == FraudDecision::Accept);
+        [
+            (
+                ScaExemption::MerchantInitiated,
+                indicators.merchant_initiated && within(self.merchant_initiated),
+            ),
+            (
+                ScaExemption::Recurring,
+                indicators.recurring && within(self.recurring),
+            ),
+            (
+                ScaExemption::Corporate,
+                indicators.corporate && within(self.corporate),
+            ),
+            (ScaExemption::LowValue, within(self.low_value)),
+            (
+                ScaExemption::TransactionRiskAnalysis,
+                low_risk && within(self.transaction_risk_analysis),
+            ),
+        ]
+        .into_iter()
+        .find_map(|(exemption, applies)| applies.then_some(exemption))
+    }
+}
+
+impl Transaction {
+    /// Whether the issuer soft declined the exemption and the cardholder can be authenticated
+    /// instead. Merchant initiated transactions can't be, as the cardholder isn't there.
+    pub fn needs_authentication_retry(&self) -> bool {
+        self.status == TransactionStatus::Failed(Some(TransactionError::ScaRequired))
+            && self
+                .sca_exemption
+                .is_some_and(|e| e != ScaExemption::MerchantInitiated)
+    }
+
+    /// Drops the exemption the issuer turned down, ready for the transaction to be authenticated
+    /// and authorised again
+    pub fn retry_with_authentication(&mut self) {
+        self.sca_exemption = None;
+        self.avs = None;
+        self.cvv = None;
+        self.status = TransactionStatus::Success;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::*;
+
+    fn thresholds() -> ScaThresholds {
+        ScaThresholds {
+            low_value: Some(3000),
+            transaction_risk_analysis: Some(25000),
+            recurring: Some(10000),
+            ..ScaThresholds::new("merchant123".into(), Currency::EUR)
+        }
+    }
+
+    fn fraud(decision: FraudDecision) -> FraudResult {
+        FraudResult {
+            score: 0,
+            decision,
+            matched_rules: vec![],
+        }
+    }
+
+    #[rstest]
+    #[case(3000, ScaIndicators::default(), None, Some(ScaExemption::LowValue))]
+    #[case(3001, ScaIndicators::default(), None, None)]
+    #[case(
+        20000,
+        ScaIndicators::default(),
+        Some(FraudDecision::Accept),
+        Some(ScaExemption::TransactionRiskAnalysis)
+    )]
+    #[case(20000, ScaIndicators::default(), Some(FraudDecision::Review), None)]
+    #[case(30000, ScaIndicators::default(), Some(FraudDecision::Accept), None)]
+    #[case(
+        1000,
+        ScaIndicators { recurring: true, ..Default::default() },
+        None,
+        Some(ScaExemption::Recurring)
+    )]
+    #[case(
+        20000,
+        ScaIndicators { recurring: true, ..Default::default() },
+        None,
+        None
+    )]
+    #[case(
+        1000,
+        ScaIndicators { merchant_initiated: true, corporate: true, ..Default::default() },
+        None,
+        Some(ScaExemption::LowValue)
+    )]
+    fn choose(
+        #[case] amount: u64,
+        #[case] indicators: ScaIndicators,
+        #[case] decision: Option<FraudDecision>,
+        #[case] exp: Option<ScaExemption>,
+    ) {
+        let fraud = decision.map(fraud);
+        assert_eq!(thresholds().choose(amount, indicators, fraud.as_ref()), exp);
+    }
+
+    #[test]
+    fn nothing_is_chosen_without_thresholds() {
+        let thresholds = ScaThresholds::new("merchant123".into(), Currency::EUR);
+        let indicators = ScaIndicators {
+            merchant_initiated: true,
+            recurring: true,
+            corporate: true,
+        };
+        assert_eq!(
+            thresholds.choose(1, indicators, Some(&fraud(FraudDecision::Accept))),
+            None
+        );
+    }
+
+    #[test]
+    fn exemptions_round_trip() {
+        for exemption in [
+            ScaExemption::LowValue,
+            ScaExemption::TransactionRiskAnalysis,
+            ScaExemption::MerchantInitiated,
+            ScaExemption::Recurring,
+            ScaExemption::Corporate,
+        ] {
+            assert_eq!(
+                ScaExemption::try_from(exemption.to_string()).unwrap(),
+                exemption
+            );
+            assert_eq!(
+                serde_json::to_value(exemption).unwrap(),
+                exemption.to_string()
+            );
+        }
+        assert_eq!(
+            ScaExemption::try_from("NONE".to_string())
+                .unwrap_err()
+                .message,
+            "NONE is not a recognised SCA exemption"
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Deserializer};
+use validify::Length;
+use zeroize::Zeroize;
+
+/// Holds sensitive data (e.g. card security codes) that may be passed on to an acquirer
+/// but must never be logged, serialised or persisted.
+///
+/// `Secret` deliberately implements neither `Serialize`, `Display` nor any sqlx encoding
+/// traits, its `Debug` output is redacted and the inner value is zeroised on drop. The only
+/// way to get at the value is through [`Secret::expose`].
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(value: T) -> Secret<T> {
+        Secret(value)
+    }
+
+    /// Gives access to the inner value, this should only be used when building acquirer messages.
+    pub fn expose(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<T: Zeroize> std::fmt::Debug for Secret<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Secret([REDACTED])")
+    }
+}
+
+impl<T: Zeroize + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Secret(self.0.clone())
+    }
+}
+
+impl<T: Zeroize + PartialEq> PartialEq for Secret<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T: Zeroize + Default> Default for Secret<T> {
+    fn default() -> Self {
+        Secret(T::default())
+    }
+}
+
+impl From<String> for Secret<String> {
+    fn from(value: String) -> Self {
+        Secret(value)
+    }
+}
+
+impl From<&str> for Secret<String> {
+    fn from(value: &str) -> Self {
+        Secret(value.to_owned())
+    }
+}
+
+impl<T: Zeroize + Length> Length for Secret<T> {
+    fn length(&self) -> usize {
+        self.0.length()
+    }
+}
+
+impl<'de, T: Zeroize + Deserialize<'de>> Deserialize<'de> for Secret<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer).map(Secret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_is_redacted() {
+        let secret = Secret::from("123");
+        assert_eq!(format!("{secret:?}"), "Secret([REDACTED])");
+        assert_eq!(format!("{:?}", Some(secret)), "Some(Secret([REDACTED]))");
+    }
+
+    #[test]
+    fn expose_inner_value() {
+        let secret = Secret::from("1234");
+        assert_eq!(secret.expose(), "1234");
+        assert_eq!(secret.length(), 4);
+    }
+
+    #[test]
+    fn deserialize_from_plain_value() {
+        let secret: Secret<String> = serde_json::from_str(r#""123""#).unwrap();
+        assert_eq!(secret, Secret::from("123"));
+    }
+}
@@ -0,0 +1,89 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::secret::Secret;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The parts of a request covered by its signature
+#[derive(Debug, Clone, Copy)]
+pub struct SignedRequest<'a> {
+    pub method: &'a str,
+    /// The path including any query string
+    pub path: &'a str,
+    /// Seconds since the unix epoch
+    pub timestamp: i64,
+    pub nonce: &'a str,
+    pub body: &'a [u8],
+}
+
+impl SignedRequest<'_> {
+    /// The signature is over `{METHOD}\n{path}\n{timestamp}\n{nonce}\n{body}`
+    fn mac(&self, secret: &Secret<String>) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(secret.expose().as_bytes())
+            .expect("HMAC accepts keys of any length");
+        mac.update(self.method.to_ascii_uppercase().as_bytes());
+        mac.update(b"\n");
+        mac.update(self.path.as_bytes());
+        mac.update(b"\n");
+        mac.update(self.timestamp.to_string().as_bytes());
+        mac.update(b"\n");
+        mac.update(self.nonce.as_bytes());
+        mac.update(b"\n");
+        mac.update(self.body);
+        mac
+    }
+
+    /// The lowercase hex HMAC-SHA256 signature of the request
+    pub fn sign(&self, secret: &Secret<String>) -> String {
+        hex::encode(self.mac(secret).finalize().into_bytes())
+    }
+
+    /// Checks a hex signature in constant time
+    pub fn verify(&self, secret: &Secret<String>, signature: &str) -> bool {
+        match hex::decode(signature) {
+            Ok(signature) => self.mac(secret).verify_slice(&signature).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+pub fn generate_secret() -> Secret<String> {
+    Secret::new(format!(
+        "{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: &[u8]) -> SignedRequest<'_> {
+        SignedRequest {
+            method: "post",
+            path: "/transaction",
+            timestamp: 1760832000,
+            nonce: "abc",
+            body,
+        }
+    }
+
+    #[test]
+    fn sign_and_verify() {
+        let secret = Secret::from("secret");
+        let signature = request(b"{}").sign(&secret);
+        // HMAC-SHA256("secret", "POST\n/transaction\n1760832000\nabc\n{}")
+        assert_eq!(
+            signature,
+            "a25b69856ea93f031013be8ef270cbbf72f6d31fba48cdc976265b2a9f19a045"
+        );
+        assert!(request(b"{}").verify(&secret, &signature));
+        assert!(request(b"{}").verify(&secret, &signature.to_uppercase()));
+        assert!(!request(b"{ }").verify(&secret, &signature));
+        assert!(!request(b"{}").verify(&Secret::from("other"), &signature));
+        assert!(!request(b"{}").verify(&secret, "not hex"));
+    }
+}
@@ -0,0 +1,197 @@
+use serde::Serialize;
+
+use crate::{
+    error::{Error, ErrorKind},
+    transaction::{Transaction, TransactionStatus},
+};
+
+/// Whether the card is being stored for later transactions, or is a stored card being used
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum CredentialUsage {
+    Initial,
+    Subsequent,
+}
+
+impl std::fmt::Display for CredentialUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            CredentialUsage::Initial => "INITIAL",
+            CredentialUsage::Subsequent => "SUBSEQUENT",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl TryFrom<String> for CredentialUsage {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "INITIAL" => Ok(Self::Initial),
+            "SUBSEQUENT" => Ok(Self::Subsequent),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised stored credential usage"),
+            }),
+        }
+    }
+}
+
+/// Who started the transaction, the cardholder (CIT) or the merchant without them there (MIT)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Initiator {
+    #[serde(rename = "CIT")]
+    Cardholder,
+    #[serde(rename = "MIT")]
+    Merchant,
+}
+
+impl std::fmt::Display for Initiator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            Initiator::Cardholder => "CIT",
+            Initiator::Merchant => "MIT",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl TryFrom<String> for Initiator {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "CIT" => Ok(Self::Cardholder),
+            "MIT" => Ok(Self::Merchant),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised transaction initiator"),
+            }),
+        }
+    }
+}
+
+/// The credential-on-file indicators sent to the acquirer with a card transaction
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StoredCredential {
+    pub usage: CredentialUsage,
+    pub initiator: Initiator,
+    /// The scheme transaction ID of the initial transaction a subsequent one is chained to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_scheme_transaction_id: Option<String>,
+}
+
+impl Transaction {
+    /// The scheme transaction ID to save with the payment method the transaction was paid
+    /// with, so later transactions can be chained to it. Only successful initial transactions
+    /// with a saved payment method have one.
+    pub fn scheme_transaction_id_to_chain(&self) -> Option<&str> {
+        let initial = self
+            .stored_credential
+            .as_ref()
+            .is_some_and(|c| c.usage == CredentialUsage::Initial);
+        if initial && self.status == TransactionStatus::Success && self.payment_token.is_some() {
+            self.scheme_transaction_id.as_deref()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::{AcquirerAccount, BankOneAccount},
+        billing::Billing,
+        card_scheme::CardScheme,
+        currency::Currency,
+        merchant::Merchant,
+        payment::Payment,
+        transaction::{transaction_builder::TransactionBuilder, TransactionError, TransactionType},
+    };
+    use rstest::*;
+
+    #[rstest]
+    #[case(
+        CredentialUsage::Initial,
+        TransactionStatus::Success,
+        Some("token123"),
+        true
+    )]
+    #[case(
+        CredentialUsage::Subsequent,
+        TransactionStatus::Success,
+        Some("token123"),
+        false
+    )]
+    #[case(
+        CredentialUsage::Initial,
+        TransactionStatus::Failed(Some(TransactionError::Declined)),
+        Some("token123"),
+        false
+    )]
+    #[case(CredentialUsage::Initial, TransactionStatus::Success, None, false)]
+    fn only_successful_initial_transactions_are_chained(
+        #[case] usage: CredentialUsage,
+        #[case] status: TransactionStatus,
+        #[case] payment_token: Option<&str>,
+        #[case] chained: bool,
+    ) {
+        let mut trx = TransactionBuilder::new()
+            .transaction_type(TransactionType::Auth)
+            .amount(12345)
+            .currency(Currency::GBP)
+            .payment(Payment::from((
+                CardScheme::Visa,
+                (2030, 1),
+                "",
+                "4000111122223333",
+            )))
+            .billing(Billing::default())
+            .merchant(Merchant::default())
+            .account(AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: "12345678".into(),
+            }))
+            .payment_token(payment_token.map(String::from))
+            .stored_credential(Some(StoredCredential {
+                usage,
+                initiator: Initiator::Cardholder,
+                original_scheme_transaction_id: None,
+            }))
+            .build();
+        trx.status = status;
+        trx.scheme_transaction_id = Some("123456789012345".into());
+        assert_eq!(
+            trx.scheme_transaction_id_to_chain(),
+            chained.then_some("123456789012345")
+        );
+    }
+
+    #[rstest]
+    fn serialises_with_scheme_names() {
+        let credential = StoredCredential {
+            usage: CredentialUsage::Subsequent,
+            initiator: Initiator::Merchant,
+            original_scheme_transaction_id: Some("123456789012345".into()),
+        };
+        assert_eq!(
+            serde_json::to_value(&credential).unwrap(),
+            serde_json::json!({
+                "usage": "SUBSEQUENT",
+                "initiator": "MIT",
+                "original_scheme_transaction_id": "123456789012345"
+            })
+        );
+        assert_eq!(
+            CredentialUsage::try_from(CredentialUsage::Initial.to_string()).unwrap(),
+            CredentialUsage::Initial
+        );
+        assert_eq!(
+            Initiator::try_from(Initiator::Merchant.to_string()).unwrap(),
+            Initiator::Merchant
+        );
+        assert!(CredentialUsage::try_from("FIRST".to_string()).is_err());
+    }
+}
@@ -0,0 +1,388 @@
+use chrono::{DateTime, Days, Months, SubsecRound, TimeDelta, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+use validify::Validify;
+
+use crate::{
+    currency::Currency,
+    error::{Error, ErrorKind},
+};
+
+/// How long after each declined charge it's retried, once these run out the subscription is
+/// left unpaid
+pub const RETRY_DELAYS_DAYS: [u64; 3] = [1, 3, 7];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Interval {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let i = match self {
+            Interval::Day => "DAY",
+            Interval::Week => "WEEK",
+            Interval::Month => "MONTH",
+            Interval::Year => "YEAR",
+        };
+        write!(f, "{i}")
+    }
+}
+
+impl TryFrom<String> for Interval {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "DAY" => Ok(Self::Day),
+            "WEEK" => Ok(Self::Week),
+            "MONTH" => Ok(Self::Month),
+            "YEAR" => Ok(Self::Year),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised interval"),
+            }),
+        }
+    }
+}
+
+impl Interval {
+    /// Moves the date on by `count` intervals. Months keep to the day of the month where they
+    /// can, charges on the 31st are taken on the last day of shorter months.
+    pub fn advance(self, from: DateTime<Utc>, count: u32) -> DateTime<Utc> {
+        let advanced = match self {
+            Interval::Day => from.checked_add_days(Days::new(count.into())),
+            Interval::Week => from.checked_add_days(Days::new(u64::from(count) * 7)),
+            Interval::Month => from.checked_add_months(Months::new(count)),
+            Interval::Year => from.checked_add_months(Months::new(count.saturating_mul(12))),
+        };
+        advanced.unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+}
+
+/// What a merchant charges their subscribers, and how often
+#[derive(Debug, Clone, PartialEq, Validify)]
+pub struct Plan {
+    pub plan_id: String,
+    pub merchant_id: String,
+    #[modify(trim)]
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    /// In minor units of the currency
+    #[validate(range(min = 1.0))]
+    pub amount: u64,
+    pub currency: Currency,
+    pub interval: Interval,
+    #[validate(range(min = 1.0, max = 365.0))]
+    pub interval_count: u32,
+    /// How long new subscribers have before they're first charged
+    #[validate(range(max = 730.0))]
+    pub trial_days: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Plan {
+    pub fn new(
+        merchant_id: String,
+        name: String,
+        amount: u64,
+        currency: Currency,
+        interval: Interval,
+    ) -> Self {
+        Self {
+            plan_id: Uuid::new_v4().simple().to_string(),
+            merchant_id,
+            name,
+            amount,
+            currency,
+            interval,
+            interval_count: 1,
+            trial_days: 0,
+            created_at: Utc::now().trunc_subsecs(6),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum SubscriptionStatus {
+    /// Not charged until the plan's trial is over
+    Trialing,
+    Active,
+    /// The last charge was declined and is waiting to be retried
+    PastDue,
+    /// Every retry was declined, so the subscription is no longer charged
+    Unpaid,
+    Cancelled,
+}
+
+impl std::fmt::Display for SubscriptionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SubscriptionStatus::Trialing => "TRIALING",
+            SubscriptionStatus::Active => "ACTIVE",
+            SubscriptionStatus::PastDue => "PAST_DUE",
+            SubscriptionStatus::Unpaid => "UNPAID",
+            SubscriptionStatus::Cancelled => "CANCELLED",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl TryFrom<String> for SubscriptionStatus {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "TRIALING" => Ok(Self::Trialing),
+            "ACTIVE" => Ok(Self::Active),
+            "PAST_DUE" => Ok(Self::PastDue),
+            "UNPAID" => Ok(Self::Unpaid),
+            "CANCELLED" => Ok(Self::Cancelled),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised subscription status"),
+            }),
+        }
+    }
+}
+
+/// A customer's subscription to a plan, charged to one of their saved payment methods
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub subscription_id: String,
+    pub merchant_id: String,
+    pub plan_id: String,
+    pub customer_id: String,
+    pub payment_token: String,
+    pub status: SubscriptionStatus,
+    /// When the current period is charged for, this doesn't move while a charge is retried
+    pub next_charge_at: DateTime<Utc>,
+    /// When a declined charge is next retried
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Declined charges since the last successful one
+    pub failed_attempts: u32,
+    /// The reference of the last transaction made for the subscription
+    pub last_transaction: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub cancelled_at: Option<DateTime<Utc>>,
+}
+
+impl Subscription {
+    /// Starts a subscription, which is first charged once the plan's trial is over, or straight
+    /// away if it has none
+    pub fn new(
+        plan: &Plan,
+        customer_id: String,
+        payment_token: String,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let now = now.trunc_subsecs(6);
+        Self {
+            subscription_id: Uuid::new_v4().simple().to_string(),
+            merchant_id: plan.merchant_id.clone(),
+            plan_id: plan.plan_id.clone(),
+            customer_id,
+            payment_token,
+            status: if plan.trial_days > 0 {
+                SubscriptionStatus::Trialing
+            } else {
+                SubscriptionStatus::Active
+            },
+            next_charge_at: Interval::Day.advance(now, plan.trial_days),
+            retry_at: None,
+            failed_attempts: 0,
+            last_transaction: None,
+            created_at: now,
+            cancelled_at: None,
+        }
+    }
+
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        match self.status {
+            SubscriptionStatus::Trialing | SubscriptionStatus::Active => self.next_charge_at <= now,
+            SubscriptionStatus::PastDue => self.retry_at.is_some_and(|at| at <= now),
+            SubscriptionStatus::Unpaid | SubscriptionStatus::Cancelled => false,
+        }
+    }
+
+    /// Moves the subscription on to its next period once a charge succeeds. Periods missed while
+    /// the subscription wasn't being charged aren't charged for.
+    pub fn record_success(&mut self, plan: &Plan, now: DateTime<Utc>) {
+        self.status = SubscriptionStatus::Active;
+        self.failed_attempts = 0;
+        self.retry_at = None;
+        self.next_charge_at = plan
+            .interval
+            .advance(self.next_charge_at, plan.interval_count);
+        while self.next_charge_at <= now {
+            self.next_charge_at = plan
+                .interval
+                .advance(self.next_charge_at, plan.interval_count);
+        }
+    }
+
+    /// Schedules a retry of a declined charge, or leaves the subscription unpaid when there are
+    /// no retries left
+    pub fn record_failure(&mut self, now: DateTime<Utc>) {
+        let delay = RETRY_DELAYS_DAYS.get(self.failed_attempts as usize);
+        self.failed_attempts += 1;
+        match delay {
+            Some(days) => {
+                self.status = SubscriptionStatus::PastDue;
+                self.retry_at = Some(now.trunc_subsecs(6) + TimeDelta::days(*days as i64));
+            }
+            None => {
+                self.status = SubscriptionStatus::Unpaid;
+                self.retry_at = None;
+            }
+        }
+    }
+
+    pub fn cancel(&mut self, now: DateTime<Utc>) {
+        self.status = SubscriptionStatus::Cancelled;
+        self.retry_at = None;
+        self.cancelled_at = Some(now.trunc_subsecs(6));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::*;
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 9, 0, 0).unwrap()
+    }
+
+    fn plan() -> Plan {
+        Plan::new(
+            "merchant123".into(),
+            "Monthly".into(),
+            999,
+            Currency::GBP,
+            Interval::Month,
+        )
+    }
+
+    #[rstest]
+    #[case(Interval::Day, 3, at(2026, 1, 31), at(2026, 2, 3))]
+    #[case(Interval::Week, 2, at(2026, 1, 31), at(2026, 2, 14))]
+    #[case(Interval::Month, 1, at(2026, 1, 31), at(2026, 2, 28))]
+    #[case(Interval::Month, 3, at(2026, 1, 15), at(2026, 4, 15))]
+    #[case(Interval::Year, 1, at(2028, 2, 29), at(2029, 2, 28))]
+    fn advance(
+        #[case] interval: Interval,
+        #[case] count: u32,
+        #[case] from: DateTime<Utc>,
+        #[case] exp: DateTime<Utc>,
+    ) {
+        assert_eq!(interval.advance(from, count), exp);
+    }
+
+    #[test]
+    fn trials_delay_the_first_charge() {
+        let now = at(2026, 1, 1);
+        let subscription = Subscription::new(&plan(), "cus123".into(), "token123".into(), now);
+        assert_eq!(subscription.status, SubscriptionStatus::Active);
+        assert!(subscription.is_due(now));
+        let trial = Plan {
+            trial_days: 14,
+            ..plan()
+        };
+        let subscription = Subscription::new(&trial, "cus123".into(), "token123".into(), now);
+        assert_eq!(subscription.status, SubscriptionStatus::Trialing);
+        assert_eq!(subscription.next_charge_at, at(2026, 1, 15));
+        assert!(!subscription.is_due(now));
+        assert!(subscription.is_due(at(2026, 1, 15)));
+    }
+
+    #[test]
+    fn successful_charges_move_to_the_next_period() {
+        let mut subscription =
+            Subscription::new(&plan(), "cus123".into(), "token123".into(), at(2026, 1, 1));
+        subscription.record_success(&plan(), at(2026, 1, 1));
+        assert_eq!(subscription.next_charge_at, at(2026, 2, 1));
+        // periods missed while nothing was charged are skipped
+        subscription.record_success(&plan(), at(2026, 5, 10));
+        assert_eq!(subscription.next_charge_at, at(2026, 6, 1));
+    }
+
+    #[test]
+    fn declined_charges_are_retried_until_unpaid() {
+        let plan = plan();
+        let now = at(2026, 1, 1);
+        let mut subscription = Subscription::new(&plan, "cus123".into(), "token123".into(), now);
+        for (attempt, days) in RETRY_DELAYS_DAYS.iter().enumerate() {
+            subscription.record_failure(now);
+            assert_eq!(subscription.status, SubscriptionStatus::PastDue);
+            assert_eq!(subscription.failed_attempts, attempt as u32 + 1);
+            let retry_at = now + TimeDelta::days(*days as i64);
+            assert_eq!(subscription.retry_at, Some(retry_at));
+            assert!(!subscription.is_due(retry_at - TimeDelta::seconds(1)));
+            assert!(subscription.is_due(retry_at));
+        }
+        let mut recovered = subscription.clone();
+        subscription.record_failure(now);
+        assert_eq!(subscription.status, SubscriptionStatus::Unpaid);
+        assert!(!subscription.is_due(at(2027, 1, 1)));
+
+        // the period paid for is the one that was declined
+        recovered.record_success(&plan, at(2026, 1, 9));
+        assert_eq!(recovered.status, SubscriptionStatus::Active);
+        assert_eq!(recovered.failed_attempts, 0);
+        assert_eq!(recovered.retry_at, None);
+        assert_eq!(recovered.next_charge_at, at(2026, 2, 1));
+    }
+
+    #[test]
+    fn cancelled_subscriptions_are_never_due() {
+        let now = at(2026, 1, 1);
+        let mut subscription = Subscription::new(&plan(), "cus123".into(), "token123".into(), now);
+        subscription.cancel(now);
+        assert_eq!(subscription.cancelled_at, Some(now));
+        assert!(!subscription.is_due(at(2027, 1, 1)));
+    }
+
+    #[rstest]
+    #[case(0, 1, false)]
+    #[case(999, 0, false)]
+    #[case(999, 1, true)]
+    #[case(999, 366, false)]
+    fn validate_plans(#[case] amount: u64, #[case] interval_count: u32, #[case] valid: bool) {
+        let mut plan = Plan {
+            amount,
+            interval_count,
+            ..plan()
+        };
+        assert_eq!(plan.validify().is_ok(), valid);
+    }
+
+    #[test]
+    fn statuses_round_trip() {
+        for status in [
+            SubscriptionStatus::Trialing,
+            SubscriptionStatus::Active,
+            SubscriptionStatus::PastDue,
+            SubscriptionStatus::Unpaid,
+            SubscriptionStatus::Cancelled,
+        ] {
+            assert_eq!(
+                SubscriptionStatus::try_from(status.to_string()).unwrap(),
+                status
+            );
+            assert_eq!(serde_json::to_value(status).unwrap(), status.to_string());
+        }
+        assert_eq!(
+            Interval::try_from("FORTNIGHT".to_string())
+                .unwrap_err()
+                .message,
+            "FORTNIGHT is not a recognised interval"
+        );
+    }
+}
@@ -11,7 +11,10 @@ pub type ExpectedValidationErrors = Vec<(
 )>;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub enum ValidationErrorKind { Field, Schema }
+pub enum ValidationErrorKind {
+    Field,
+    Schema,
+}
 
 pub fn create_validation_errors(errors: ExpectedValidationErrors) -> ValidationErrors {
     errors
@@ -34,7 +37,7 @@ pub fn create_validation_errors(errors: ExpectedValidationErrors) -> ValidationE
 }
 
 pub fn check_validation<T: Validate>(t: T, errors: ExpectedValidationErrors) {
-    if errors.len() == 0 {
+    if errors.is_empty() {
         assert_eq!(t.validate(), Ok(()));
     } else {
         let exp = create_validation_errors(errors);
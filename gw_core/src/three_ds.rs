@@ -0,0 +1,368 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    card_scheme::CardScheme,
+    error::{Error, ErrorKind},
+    payment::Payment,
+    secret::Secret,
+    transaction::{Transaction, TransactionError, TransactionStatus},
+};
+
+/// How long the cardholder has to complete a challenge
+pub const CHALLENGE_TIMEOUT_SECS: i64 = 600;
+
+/// The EMV 3DS transaction status, from the directory server or the ACS
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AuthenticationStatus {
+    Authenticated,
+    /// The issuer doesn't support 3DS but the attempt was recorded, so counts as authenticated
+    Attempted,
+    ChallengeRequired,
+    NotAuthenticated,
+    Rejected,
+    Unavailable,
+}
+
+impl AuthenticationStatus {
+    pub fn is_authenticated(&self) -> bool {
+        matches!(
+            self,
+            AuthenticationStatus::Authenticated | AuthenticationStatus::Attempted
+        )
+    }
+}
+
+impl std::fmt::Display for AuthenticationStatus {
+    /// The transStatus code it's stored as
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            AuthenticationStatus::Authenticated => "Y",
+            AuthenticationStatus::Attempted => "A",
+            AuthenticationStatus::ChallengeRequired => "C",
+            AuthenticationStatus::NotAuthenticated => "N",
+            AuthenticationStatus::Rejected => "R",
+            AuthenticationStatus::Unavailable => "U",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl TryFrom<String> for AuthenticationStatus {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<AuthenticationStatus, Self::Error> {
+        match value.as_str() {
+            "Y" => Ok(Self::Authenticated),
+            "A" => Ok(Self::Attempted),
+            "C" => Ok(Self::ChallengeRequired),
+            "N" => Ok(Self::NotAuthenticated),
+            "R" => Ok(Self::Rejected),
+            "U" => Ok(Self::Unavailable),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised 3DS transaction status"),
+            }),
+        }
+    }
+}
+
+/// The outcome of authenticating the cardholder, carried into the authorisation message
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ThreeDsResult {
+    pub status: AuthenticationStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub eci: Option<String>,
+    /// The cryptogram proving the authentication, only sent to the acquirer
+    #[serde(skip)]
+    pub cavv: Option<Secret<String>>,
+    pub ds_transaction_id: String,
+}
+
+/// What the ACS needs to show the cardholder a challenge
+#[derive(Debug, Clone, PartialEq)]
+pub struct Challenge {
+    pub ds_transaction_id: String,
+    pub acs_transaction_id: String,
+    pub acs_url: String,
+    /// The challenge request message, posted to the ACS by the cardholder's browser
+    pub creq: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Authentication {
+    Frictionless(ThreeDsResult),
+    Challenge(Challenge),
+}
+
+/// Sends authentication requests to the card scheme's directory server, and gets the results of
+/// challenges from the issuer's ACS
+pub trait DirectoryServer {
+    #[allow(async_fn_in_trait)] // only using in own code
+    async fn authenticate(&self, transaction: &Transaction) -> Result<Authentication, Error>;
+
+    /// Gets the result of a challenge from the challenge response the ACS gave the cardholder
+    #[allow(async_fn_in_trait)]
+    async fn challenge_result(
+        &self,
+        pending: &PendingAuthentication,
+        cres: &str,
+    ) -> Result<ThreeDsResult, Error>;
+}
+
+/// A directory server and ACS for testing. Card numbers ending 0002 are challenged, 0003 fail
+/// and 0004 are only attempted, everything else is authenticated without friction. A challenge
+/// response of Y passes the challenge.
+#[derive(Debug, Default)]
+pub struct SimulatedDirectoryServer;
+
+pub const SIMULATED_ACS_URL: &str = "https://acs.simulator.invalid/challenge";
+
+impl SimulatedDirectoryServer {
+    fn result(
+        scheme: CardScheme,
+        status: AuthenticationStatus,
+        ds_transaction_id: String,
+    ) -> ThreeDsResult {
+        ThreeDsResult {
+            status,
+            eci: Some(eci(scheme, status).into()),
+            cavv: status
+                .is_authenticated()
+                .then(|| Uuid::new_v4().simple().to_string().into()),
+            ds_transaction_id,
+        }
+    }
+}
+
+/// The scheme's electronic commerce indicator for the authentication
+fn eci(scheme: CardScheme, status: AuthenticationStatus) -> &'static str {
+    use AuthenticationStatus::*;
+    match (scheme, status) {
+        (CardScheme::Mastercard, Authenticated) => "02",
+        (CardScheme::Mastercard, Attempted) => "01",
+        (CardScheme::Mastercard, _) => "00",
+        (_, Authenticated) => "05",
+        (_, Attempted) => "06",
+        (_, _) => "07",
+    }
+}
+
+impl DirectoryServer for SimulatedDirectoryServer {
+    async fn authenticate(&self, transaction: &Transaction) -> Result<Authentication, Error> {
+        let Payment::Card { scheme, pan, .. } = &transaction.payment else {
+            return Err(Error {
+                kind: ErrorKind::Type,
+                message: "only cards can be authenticated".into(),
+            });
+        };
+        let ds_transaction_id = Uuid::new_v4().to_string();
+        let status = match &pan[pan.len().saturating_sub(4)..] {
+            "0002" => {
+                let acs_transaction_id = Uuid::new_v4().to_string();
+                let creq = serde_json::json!({
+                    "threeDSServerTransID": transaction.reference,
+                    "acsTransID": acs_transaction_id,
+                    "messageType": "CReq",
+                    "messageVersion": "2.2.0",
+                });
+                return Ok(Authentication::Challenge(Challenge {
+                    ds_transaction_id,
+                    acs_transaction_id,
+                    acs_url: SIMULATED_ACS_URL.into(),
+                    creq: hex::encode(creq.to_string()),
+                }));
+            }
+            "0003" => AuthenticationStatus::NotAuthenticated,
+            "0004" => AuthenticationStatus::Attempted,
+            _ => AuthenticationStatus::Authenticated,
+        };
+        Ok(Authentication::Frictionless(Self::result(
+            *scheme,
+            status,
+            ds_transaction_id,
+        )))
+    }
+
+    async fn challenge_result(
+        &self,
+        pending: &PendingAuthentication,
+        cres: &str,
+    ) -> Result<ThreeDsResult, Error> {
+        let Payment::Card { scheme, .. } = &pending.transaction.payment else {
+            return Err(Error {
+                kind: ErrorKind::Type,
+                message: "only cards can be authenticated".into(),
+            });
+        };
+        let status = if cres == "Y" {
+            AuthenticationStatus::Authenticated
+        } else {
+            AuthenticationStatus::NotAuthenticated
+        };
+        Ok(Self::result(
+            *scheme,
+            status,
+            pending.challenge.ds_transaction_id.clone(),
+        ))
+    }
+}
+
+impl Transaction {
+    /// Records the authentication on the transaction, failing it if the cardholder wasn't
+    /// authenticated
+    pub fn apply_authentication(&mut self, result: ThreeDsResult) {
+        if !result.status.is_authenticated() {
+            self.status = TransactionStatus::Failed(Some(TransactionError::AuthenticationFailed));
+        }
+        self.three_ds = Some(result);
+    }
+
+    /// Leaves the transaction waiting for the cardholder to complete the challenge
+    pub fn challenge(&mut self, challenge: &Challenge) {
+        self.status = TransactionStatus::Pending3DS;
+        self.three_ds = Some(ThreeDsResult {
+            status: AuthenticationStatus::ChallengeRequired,
+            eci: None,
+            cavv: None,
+            ds_transaction_id: challenge.ds_transaction_id.clone(),
+        });
+    }
+}
+
+/// A transaction waiting on a challenge. The card details are needed to authorise it once the
+/// challenge is done, so they're kept sealed until then, see
+/// [`crate::repo::three_ds::PendingAuthenticationRepo`]. The security code isn't, so the card
+/// is authorised like a saved one, without it.
+#[derive(Debug)]
+pub struct PendingAuthentication {
+    pub transaction: Transaction,
+    pub challenge: Challenge,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PendingAuthentication {
+    /// The cardholder has [`CHALLENGE_TIMEOUT_SECS`] from now to complete the challenge
+    pub fn new(transaction: Transaction, challenge: Challenge, now: DateTime<Utc>) -> Self {
+        Self {
+            transaction,
+            challenge,
+            expires_at: now + TimeDelta::seconds(CHALLENGE_TIMEOUT_SECS),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::{AcquirerAccount, BankOneAccount},
+        billing::Billing,
+        currency::Currency,
+        merchant::Merchant,
+        transaction::{transaction_builder::TransactionBuilder, TransactionType},
+    };
+    use rstest::*;
+
+    fn transaction(scheme: CardScheme, pan: &str) -> Transaction {
+        TransactionBuilder::new()
+            .transaction_type(TransactionType::Auth)
+            .amount(12345)
+            .currency(Currency::GBP)
+            .payment(Payment::from((scheme, (2030, 1), "123", pan)))
+            .billing(Billing::default())
+            .merchant(Merchant {
+                merchant_id: "merchant123".into(),
+                ..Default::default()
+            })
+            .account(AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: "12345678".into(),
+            }))
+            .build()
+    }
+
+    #[rstest]
+    #[case(
+        CardScheme::Visa,
+        "4000111122223333",
+        AuthenticationStatus::Authenticated,
+        "05",
+        TransactionStatus::Success
+    )]
+    #[case(
+        CardScheme::Visa,
+        "4000111100000004",
+        AuthenticationStatus::Attempted,
+        "06",
+        TransactionStatus::Success
+    )]
+    #[case(
+        CardScheme::Visa,
+        "4000111100000003",
+        AuthenticationStatus::NotAuthenticated,
+        "07",
+        TransactionStatus::Failed(Some(TransactionError::AuthenticationFailed))
+    )]
+    #[case(
+        CardScheme::Mastercard,
+        "5555111122223333",
+        AuthenticationStatus::Authenticated,
+        "02",
+        TransactionStatus::Success
+    )]
+    #[tokio::test]
+    async fn frictionless(
+        #[case] scheme: CardScheme,
+        #[case] pan: &str,
+        #[case] status: AuthenticationStatus,
+        #[case] eci: &str,
+        #[case] transaction_status: TransactionStatus,
+    ) {
+        let mut trx = transaction(scheme, pan);
+        let Authentication::Frictionless(result) =
+            SimulatedDirectoryServer.authenticate(&trx).await.unwrap()
+        else {
+            panic!("expected a frictionless authentication");
+        };
+        assert_eq!(result.status, status);
+        assert_eq!(result.eci.as_deref(), Some(eci));
+        assert_eq!(result.cavv.is_some(), status.is_authenticated());
+        trx.apply_authentication(result);
+        assert_eq!(trx.status, transaction_status);
+        assert_eq!(trx.three_ds.unwrap().status, status);
+    }
+
+    #[rstest]
+    #[case("Y", AuthenticationStatus::Authenticated)]
+    #[case("N", AuthenticationStatus::NotAuthenticated)]
+    #[tokio::test]
+    async fn challenge(#[case] cres: &str, #[case] status: AuthenticationStatus) {
+        let mut trx = transaction(CardScheme::Visa, "4000111100000002");
+        let Authentication::Challenge(challenge) =
+            SimulatedDirectoryServer.authenticate(&trx).await.unwrap()
+        else {
+            panic!("expected a challenge");
+        };
+        assert_eq!(challenge.acs_url, SIMULATED_ACS_URL);
+        trx.challenge(&challenge);
+        assert_eq!(trx.status, TransactionStatus::Pending3DS);
+        let pending = PendingAuthentication::new(trx, challenge.clone(), Utc::now());
+        let result = SimulatedDirectoryServer
+            .challenge_result(&pending, cres)
+            .await
+            .unwrap();
+        assert_eq!(result.status, status);
+        assert_eq!(result.ds_transaction_id, challenge.ds_transaction_id);
+    }
+
+    #[rstest]
+    fn statuses_round_trip() {
+        for code in ["Y", "A", "C", "N", "R", "U"] {
+            let status = AuthenticationStatus::try_from(code.to_string()).unwrap();
+            assert_eq!(status.to_string(), code);
+        }
+        assert!(AuthenticationStatus::try_from("X".to_string()).is_err());
+    }
+}
@@ -1,11 +1,27 @@
+pub mod search;
 pub mod transaction_builder;
 
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use validify::{schema_validation, ValidationErrors, Validify};
+use validify::{schema_validation, ValidationError, ValidationErrors, Validify};
 
 use crate::{
-    account::AcquirerAccount, amount::Amount, billing::Billing, currency::Currency,
-    customer::Customer, merchant::Merchant, payment::Payment,
+    account::AcquirerAccount,
+    amount::Amount,
+    avs::AvsResult,
+    billing::Billing,
+    currency::Currency,
+    customer::Customer,
+    cvv::CvvResult,
+    error::{Error, ErrorKind},
+    fraud::FraudResult,
+    merchant::Merchant,
+    payment::Payment,
+    sca::ScaExemption,
+    stored_credential::StoredCredential,
+    three_ds::ThreeDsResult,
 };
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -14,11 +30,42 @@ pub enum TransactionType {
     Refund,
 }
 
+impl std::fmt::Display for TransactionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let t = match self {
+            TransactionType::Auth => "AUTH",
+            TransactionType::Refund => "REFUND",
+        };
+        write!(f, "{t}")
+    }
+}
+
+impl TryFrom<String> for TransactionType {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<TransactionType, Self::Error> {
+        match value.as_str() {
+            "AUTH" => Ok(Self::Auth),
+            "REFUND" => Ok(Self::Refund),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised transaction type"),
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Default, Serialize)]
 pub enum TransactionStatus {
     #[default]
     Success,
     Failed(Option<TransactionError>),
+    /// Waiting for the cardholder to complete a 3DS challenge
+    Pending3DS,
+    /// The acquirer has confirmed the funds were settled
+    Settled,
+    /// The cardholder's issuer took the funds back after the transaction succeeded
+    ChargedBack,
 }
 
 impl std::fmt::Display for TransactionStatus {
@@ -26,13 +73,105 @@ impl std::fmt::Display for TransactionStatus {
         let d = match self {
             TransactionStatus::Success => "SUCCESS",
             TransactionStatus::Failed(_) => "FAILED",
+            TransactionStatus::Pending3DS => "PENDING_3DS",
+            TransactionStatus::Settled => "SETTLED",
+            TransactionStatus::ChargedBack => "CHARGED_BACK",
         };
         write!(f, "{d}")
     }
 }
 
+impl TryFrom<String> for TransactionStatus {
+    type Error = Error;
+
+    /// A failure's reason is stored in its own column, see [`TransactionStatus::with_reason`]
+    fn try_from(value: String) -> Result<TransactionStatus, Self::Error> {
+        match value.as_str() {
+            "SUCCESS" => Ok(Self::Success),
+            "FAILED" => Ok(Self::Failed(None)),
+            "PENDING_3DS" => Ok(Self::Pending3DS),
+            "SETTLED" => Ok(Self::Settled),
+            "CHARGED_BACK" => Ok(Self::ChargedBack),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised transaction status"),
+            }),
+        }
+    }
+}
+
+impl TransactionStatus {
+    /// Why the transaction failed, if it did and the reason is known
+    pub fn reason(&self) -> Option<&TransactionError> {
+        match self {
+            TransactionStatus::Failed(reason) => reason.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Puts a stored failure's reason back, other statuses don't have one
+    pub fn with_reason(self, reason: Option<TransactionError>) -> Self {
+        match self {
+            TransactionStatus::Failed(None) => TransactionStatus::Failed(reason),
+            status => status,
+        }
+    }
+}
+
+/// Why a transaction failed, shown to the merchant as the transaction's error
 #[derive(Debug, Clone, PartialEq, Serialize)]
-pub enum TransactionError {}
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransactionError {
+    /// Declined by the merchant's fraud rules
+    FraudRejected,
+    /// The card, email or ip is on a block list
+    Blocked,
+    /// Declined by the acquirer
+    Declined,
+    /// Approved by the acquirer, but the AVS result is one the merchant declines
+    AvsMismatch,
+    /// Approved by the acquirer, then voided because the security code didn't match
+    CvvMismatch,
+    /// The cardholder failed 3DS authentication
+    AuthenticationFailed,
+    /// Soft declined by the issuer, the cardholder has to be authenticated
+    ScaRequired,
+}
+
+impl std::fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let e = match self {
+            TransactionError::FraudRejected => "FRAUD_REJECTED",
+            TransactionError::Blocked => "BLOCKED",
+            TransactionError::Declined => "DECLINED",
+            TransactionError::AvsMismatch => "AVS_MISMATCH",
+            TransactionError::CvvMismatch => "CVV_MISMATCH",
+            TransactionError::AuthenticationFailed => "AUTHENTICATION_FAILED",
+            TransactionError::ScaRequired => "SCA_REQUIRED",
+        };
+        write!(f, "{e}")
+    }
+}
+
+impl TryFrom<String> for TransactionError {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<TransactionError, Self::Error> {
+        match value.as_str() {
+            "FRAUD_REJECTED" => Ok(Self::FraudRejected),
+            "BLOCKED" => Ok(Self::Blocked),
+            "DECLINED" => Ok(Self::Declined),
+            "AVS_MISMATCH" => Ok(Self::AvsMismatch),
+            "CVV_MISMATCH" => Ok(Self::CvvMismatch),
+            "AUTHENTICATION_FAILED" => Ok(Self::AuthenticationFailed),
+            "SCA_REQUIRED" => Ok(Self::ScaRequired),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised transaction error"),
+            }),
+        }
+    }
+}
 
 #[derive(Debug, Validify)]
 #[validate(validate_transaction)]
@@ -47,6 +186,45 @@ pub struct Transaction {
     pub customer: Option<Customer>,
     pub status: TransactionStatus,
     pub currency: Currency,
+    /// The merchant's own reference for the order, e.g. their order ID
+    #[modify(trim)]
+    #[validate(
+        length(min = 1, max = 64, message = "invalid length"),
+        custom(validate_merchant_reference)
+    )]
+    pub merchant_reference: Option<String>,
+    #[modify(trim)]
+    #[validate(
+        length(max = 255, message = "invalid length"),
+        non_control_char(message = "contains control characters")
+    )]
+    pub description: Option<String>,
+    #[validate(
+        length(max = 20, message = "too many entries"),
+        custom(validate_metadata)
+    )]
+    pub metadata: BTreeMap<String, String>,
+    /// See [`Payment::card_fingerprint`]
+    pub card_fingerprint: Option<String>,
+    /// Only set when the merchant has fraud rules
+    pub fraud: Option<FraudResult>,
+    /// Set once the acquirer has checked the billing address
+    pub avs: Option<AvsResult>,
+    /// Set once the acquirer has checked the security code
+    pub cvv: Option<CvvResult>,
+    /// Only set when the cardholder was authenticated with 3DS
+    pub three_ds: Option<ThreeDsResult>,
+    /// The exemption from strong customer authentication sent to the acquirer
+    pub sca_exemption: Option<ScaExemption>,
+    /// The customer's saved payment method the transaction was paid with
+    pub payment_token: Option<String>,
+    /// Only set for card transactions made with a stored card, or storing one
+    pub stored_credential: Option<StoredCredential>,
+    /// The scheme's ID for the transaction, set once the acquirer has authorised it
+    pub scheme_transaction_id: Option<String>,
+    /// The scheduler's key for the subscription charge the transaction was made for, a charge
+    /// is only ever made once
+    pub subscription_charge: Option<String>,
 }
 
 impl PartialEq<Transaction> for Transaction {
@@ -60,28 +238,165 @@ impl PartialEq<Transaction> for Transaction {
             && self.status == other.status
             && self.account == other.account
             && self.currency == other.currency
+            && self.merchant_reference == other.merchant_reference
+            && self.description == other.description
+            && self.metadata == other.metadata
+            && self.fraud == other.fraud
+            && self.avs == other.avs
+            && self.cvv == other.cvv
+            && self.three_ds == other.three_ds
+            && self.sca_exemption == other.sca_exemption
+            && self.payment_token == other.payment_token
+            && self.stored_credential == other.stored_credential
+            && self.scheme_transaction_id == other.scheme_transaction_id
+            && self.subscription_charge == other.subscription_charge
+    }
+}
+
+pub const METADATA_KEY_MAX_LENGTH: usize = 40;
+pub const METADATA_VALUE_MAX_LENGTH: usize = 500;
+
+static REFERENCE_CHARS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9 _\-./#:]+$").expect("valid regex"));
+static METADATA_KEY_CHARS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[A-Za-z0-9_\-.]+$").expect("valid regex"));
+
+fn validate_merchant_reference(reference: &str) -> Result<(), ValidationError> {
+    if REFERENCE_CHARS.is_match(reference) {
+        Ok(())
+    } else {
+        Err(ValidationError::new_field("characters").with_message("invalid characters".into()))
     }
 }
 
+fn validate_metadata(metadata: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+    for (key, value) in metadata.iter() {
+        if key.is_empty()
+            || key.chars().count() > METADATA_KEY_MAX_LENGTH
+            || !METADATA_KEY_CHARS.is_match(key)
+        {
+            return Err(
+                ValidationError::new_field("key").with_message(format!("invalid key {key}"))
+            );
+        }
+        if value.chars().count() > METADATA_VALUE_MAX_LENGTH
+            || !validify::validate_non_control_character(value)
+        {
+            return Err(ValidationError::new_field("value")
+                .with_message(format!("invalid value for {key}")));
+        }
+    }
+    Ok(())
+}
+
 #[schema_validation]
-fn validate_transaction(t: &Transaction) -> Result<(), ValidationErrors> {}
+fn validate_transaction(_t: &Transaction) -> Result<(), ValidationErrors> {}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::check_validation;
+    use crate::{account::BankOneAccount, card_scheme::CardScheme};
     use rstest::*;
     use transaction_builder::TransactionBuilder;
+    use validify::Validate;
+
+    fn transaction(
+        merchant_reference: Option<String>,
+        description: Option<String>,
+        metadata: Vec<(String, String)>,
+    ) -> Transaction {
+        TransactionBuilder::new()
+            .transaction_type(TransactionType::Auth)
+            .amount(12345)
+            .currency(Currency::GBP)
+            .payment(Payment::from((
+                CardScheme::Visa,
+                (2021, 3),
+                "123",
+                "4000111122223333",
+            )))
+            .billing(Billing::default())
+            .merchant(Merchant::default())
+            .account(AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: "12345678".into(),
+            }))
+            .merchant_reference(merchant_reference)
+            .description(description)
+            .metadata(metadata.into_iter().collect())
+            .build()
+    }
+
+    fn error_fields(t: Transaction) -> Vec<(String, String)> {
+        match t.validate() {
+            Ok(()) => vec![],
+            Err(errs) => errs
+                .errors()
+                .iter()
+                .map(|e| {
+                    (
+                        e.field_name().unwrap_or("schema").to_string(),
+                        e.message().unwrap_or_default(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[rstest]
+    #[case(None, None, vec![], vec![])]
+    #[case(Some("order-123/A#1".into()), Some("2 x widgets".into()), vec![("basket_id".into(), "987".into()), ("channel".into(), "web".into())], vec![])]
+    #[case(Some("".into()), None, vec![], vec![("merchant_reference", "invalid length".into()), ("merchant_reference", "invalid characters".into())])]
+    #[case(Some("a".repeat(65)), None, vec![], vec![("merchant_reference", "invalid length".into())])]
+    #[case(Some("order<script>".into()), None, vec![], vec![("merchant_reference", "invalid characters".into())])]
+    #[case(None, Some("a".repeat(256)), vec![], vec![("description", "invalid length".into())])]
+    #[case(None, Some("bad\u{0007}".into()), vec![], vec![("description", "contains control characters".into())])]
+    #[case(None, None, vec![("bad key".into(), "1".into())], vec![("metadata", "invalid key bad key".into())])]
+    #[case(None, None, vec![("k".repeat(41), "1".into())], vec![("metadata", format!("invalid key {}", "k".repeat(41)))])]
+    #[case(None, None, vec![("key".into(), "v".repeat(501))], vec![("metadata", "invalid value for key".into())])]
+    fn test_validate_order_details(
+        #[case] merchant_reference: Option<String>,
+        #[case] description: Option<String>,
+        #[case] metadata: Vec<(String, String)>,
+        #[case] errors: Vec<(&str, String)>,
+    ) {
+        let t = transaction(merchant_reference, description, metadata);
+        let exp = errors
+            .into_iter()
+            .map(|(f, m)| (f.to_string(), m))
+            .collect::<Vec<_>>();
+        assert_eq!(error_fields(t), exp);
+    }
+
+    #[rstest]
+    fn test_validate_too_much_metadata() {
+        let keys = (0..21).map(|n| format!("key{n}")).collect::<Vec<_>>();
+        let t = transaction(
+            None,
+            None,
+            keys.into_iter().map(|k| (k, "v".into())).collect(),
+        );
+        assert_eq!(
+            error_fields(t),
+            vec![("metadata".to_string(), "too many entries".to_string())]
+        );
+    }
 
     #[rstest]
-    fn test_schema_validate_transaction() {
-        // let t = {
-        //     let t = TransactionBuilder::new()
-        //         .amount(12345)
-        //         .payment(payment)
-        //         .billing(billing)
-
-        //     t.build()
-        // };
+    #[case(TransactionError::FraudRejected)]
+    #[case(TransactionError::Blocked)]
+    #[case(TransactionError::Declined)]
+    #[case(TransactionError::AvsMismatch)]
+    #[case(TransactionError::CvvMismatch)]
+    #[case(TransactionError::AuthenticationFailed)]
+    #[case(TransactionError::ScaRequired)]
+    fn test_transaction_errors_are_stored_as_shown(#[case] error: TransactionError) {
+        let stored = error.to_string();
+        assert_eq!(serde_json::to_value(&error).unwrap(), stored.as_str());
+        assert_eq!(TransactionError::try_from(stored).unwrap(), error);
+        let status = TransactionStatus::try_from("FAILED".to_string()).unwrap();
+        assert_eq!(
+            status.with_reason(Some(error.clone())),
+            TransactionStatus::Failed(Some(error))
+        );
     }
 }
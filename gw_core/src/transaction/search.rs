@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::{postgres::PgRow, types::Json, FromRow, Row};
+
+use crate::{
+    card_scheme::CardScheme,
+    currency::Currency,
+    error::{Error, ErrorKind},
+    repo::transaction::decode_status,
+};
+
+use super::{TransactionStatus, TransactionType};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 100;
+
+/// Criteria for finding transactions, every filter that is set must match
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TransactionFilter {
+    pub merchant_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub status: Option<TransactionStatus>,
+    pub r#type: Option<TransactionType>,
+    pub currency: Option<Currency>,
+    pub min_amount: Option<u64>,
+    pub max_amount: Option<u64>,
+    /// The last four digits of the card number
+    pub last_four: Option<String>,
+    pub merchant_reference: Option<String>,
+}
+
+/// What is stored about a transaction, enough to list and reconcile it without any
+/// sensitive payment data
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionSummary {
+    pub reference: String,
+    pub merchant_id: String,
+    pub acquirer: String,
+    pub r#type: TransactionType,
+    pub status: TransactionStatus,
+    pub amount: u64,
+    pub currency: Currency,
+    pub card_scheme: Option<CardScheme>,
+    pub masked_pan: Option<String>,
+    pub merchant_reference: Option<String>,
+    pub description: Option<String>,
+    pub metadata: BTreeMap<String, String>,
+    pub created_at: DateTime<Utc>,
+    /// When the acquirer answered, None when the transaction failed before it was sent or was
+    /// interrupted before it was answered
+    pub processed_at: Option<DateTime<Utc>>,
+}
+
+/// Position in a listing, results are ordered newest first with the reference breaking ties
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub created_at: DateTime<Utc>,
+    pub reference: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct TransactionPage {
+    pub transactions: Vec<TransactionSummary>,
+    /// Set when there are more results, pass it back in to get the next page
+    pub next_cursor: Option<Cursor>,
+}
+
+impl std::fmt::Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}",
+            self.created_at.timestamp_micros(),
+            self.reference
+        )
+    }
+}
+
+impl TryFrom<&str> for Cursor {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Cursor, Self::Error> {
+        let invalid = || Error {
+            kind: ErrorKind::Type,
+            message: format!("{value} is not a valid cursor"),
+        };
+        let (micros, reference) = value.split_once('.').ok_or_else(invalid)?;
+        let micros = micros.parse::<i64>().map_err(|_| invalid())?;
+        let created_at = DateTime::from_timestamp_micros(micros).ok_or_else(invalid)?;
+        if reference.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Cursor {
+            created_at,
+            reference: reference.to_string(),
+        })
+    }
+}
+
+impl From<&TransactionSummary> for Cursor {
+    fn from(value: &TransactionSummary) -> Self {
+        Cursor {
+            created_at: value.created_at,
+            reference: value.reference.clone(),
+        }
+    }
+}
+
+fn decode_err(index: &str, e: Error) -> sqlx::Error {
+    sqlx::Error::ColumnDecode {
+        index: index.into(),
+        source: Box::new(e),
+    }
+}
+
+impl<'r> FromRow<'r, PgRow> for TransactionSummary {
+    fn from_row(row: &'r PgRow) -> Result<Self, sqlx::Error> {
+        let r#type = row
+            .try_get::<String, &str>("transaction_type")?
+            .try_into()
+            .map_err(|e| decode_err("transaction_type", e))?;
+        let status = decode_status(row)?;
+        let currency = row
+            .try_get::<String, &str>("currency")?
+            .try_into()
+            .map_err(|e| decode_err("currency", e))?;
+        let card_scheme = match row.try_get::<Option<String>, &str>("card_scheme")? {
+            Some(scheme) if !scheme.is_empty() => Some(
+                scheme
+                    .try_into()
+                    .map_err(|e| decode_err("card_scheme", e))?,
+            ),
+            _ => None,
+        };
+        let amount: i32 = row.try_get("amount")?;
+        let metadata: Json<BTreeMap<String, String>> = row.try_get("metadata")?;
+        Ok(TransactionSummary {
+            reference: row.try_get("reference")?,
+            merchant_id: row.try_get("merchant_id")?,
+            acquirer: row.try_get("acquirer")?,
+            r#type,
+            status,
+            amount: amount as u64,
+            currency,
+            card_scheme,
+            masked_pan: row.try_get("masked_pan")?,
+            merchant_reference: row.try_get("merchant_reference")?,
+            description: row.try_get("description")?,
+            metadata: metadata.0,
+            created_at: row.try_get("created_at")?,
+            processed_at: row.try_get("processed_at")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = Cursor {
+            created_at: DateTime::from_timestamp_micros(1_700_000_000_123_456).unwrap(),
+            reference: "8a4b1c2d-0000-4000-8000-000000000000".into(),
+        };
+        let encoded = cursor.to_string();
+        assert_eq!(
+            encoded,
+            "1700000000123456.8a4b1c2d-0000-4000-8000-000000000000"
+        );
+        assert_eq!(Cursor::try_from(encoded.as_str()).unwrap(), cursor);
+    }
+
+    #[test]
+    fn invalid_cursor() {
+        for invalid in ["", "abc", "123.", "abc.ref"] {
+            let err = Cursor::try_from(invalid).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::Type);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use super::*;
-use std::marker::PhantomData;
+use crate::fraud::FraudDecision;
+use std::{collections::BTreeMap, marker::PhantomData};
 use uuid::Uuid;
 
 #[derive(Default)]
@@ -12,6 +13,14 @@ pub struct TransactionBuilder<T, A, P, Acc, M, B, C> {
     account: Option<AcquirerAccount>,
     customer: Option<Customer>,
     currency: Option<Currency>,
+    merchant_reference: Option<String>,
+    description: Option<String>,
+    metadata: BTreeMap<String, String>,
+    card_fingerprint: Option<String>,
+    fraud: Option<FraudResult>,
+    payment_token: Option<String>,
+    stored_credential: Option<StoredCredential>,
+    subscription_charge: Option<String>,
     _t: PhantomData<T>,
     _a: PhantomData<A>,
     _p: PhantomData<P>,
@@ -69,7 +78,14 @@ impl
         HasCurrency,
     >
 {
+    /// Transactions rejected by fraud screening are built as failed
     pub fn build(self) -> Transaction {
+        let status = match &self.fraud {
+            Some(fraud) if fraud.decision == FraudDecision::Reject => {
+                TransactionStatus::Failed(Some(TransactionError::FraudRejected))
+            }
+            _ => TransactionStatus::Success,
+        };
         Transaction {
             r#type: self.transaction_type.unwrap(),
             amount: self.amount.unwrap(),
@@ -78,9 +94,22 @@ impl
             merchant: self.merchant.unwrap(),
             account: self.account.unwrap(),
             customer: self.customer,
-            status: TransactionStatus::Success,
+            status,
             reference: Uuid::new_v4().to_string(),
             currency: self.currency.unwrap(),
+            merchant_reference: self.merchant_reference,
+            description: self.description,
+            metadata: self.metadata,
+            card_fingerprint: self.card_fingerprint,
+            fraud: self.fraud,
+            avs: None,
+            cvv: None,
+            three_ds: None,
+            sca_exemption: None,
+            payment_token: self.payment_token,
+            stored_credential: self.stored_credential,
+            scheme_transaction_id: None,
+            subscription_charge: self.subscription_charge,
         }
     }
 }
@@ -88,6 +117,51 @@ impl
 impl<T: Default, A: Default, P: Default, Acc: Default, M: Default, B: Default, C: Default>
     TransactionBuilder<T, A, P, Acc, M, B, C>
 {
+    pub fn merchant_reference(mut self, merchant_reference: Option<String>) -> Self {
+        self.merchant_reference = merchant_reference;
+        self
+    }
+
+    pub fn description(mut self, description: Option<String>) -> Self {
+        self.description = description;
+        self
+    }
+
+    pub fn metadata(mut self, metadata: BTreeMap<String, String>) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn card_fingerprint(mut self, card_fingerprint: Option<String>) -> Self {
+        self.card_fingerprint = card_fingerprint;
+        self
+    }
+
+    pub fn fraud(mut self, fraud: Option<FraudResult>) -> Self {
+        self.fraud = fraud;
+        self
+    }
+
+    pub fn customer(mut self, customer: Option<Customer>) -> Self {
+        self.customer = customer;
+        self
+    }
+
+    pub fn payment_token(mut self, payment_token: Option<String>) -> Self {
+        self.payment_token = payment_token;
+        self
+    }
+
+    pub fn stored_credential(mut self, stored_credential: Option<StoredCredential>) -> Self {
+        self.stored_credential = stored_credential;
+        self
+    }
+
+    pub fn subscription_charge(mut self, subscription_charge: Option<String>) -> Self {
+        self.subscription_charge = subscription_charge;
+        self
+    }
+
     pub fn transaction_type(
         self,
         t_type: TransactionType,
@@ -101,6 +175,13 @@ impl<T: Default, A: Default, P: Default, Acc: Default, M: Default, B: Default, C
             customer: self.customer,
             payment: self.payment,
             currency: self.currency,
+            merchant_reference: self.merchant_reference,
+            description: self.description,
+            metadata: self.metadata,
+            card_fingerprint: self.card_fingerprint,
+            fraud: self.fraud,
+            payment_token: self.payment_token,
+            stored_credential: self.stored_credential,
             ..Default::default()
         }
     }
@@ -118,6 +199,13 @@ impl<T: Default, A: Default, P: Default, Acc: Default, M: Default, B: Default, C
             customer: self.customer,
             payment: self.payment,
             currency: self.currency,
+            merchant_reference: self.merchant_reference,
+            description: self.description,
+            metadata: self.metadata,
+            card_fingerprint: self.card_fingerprint,
+            fraud: self.fraud,
+            payment_token: self.payment_token,
+            stored_credential: self.stored_credential,
             ..Default::default()
         }
     }
@@ -132,6 +220,13 @@ impl<T: Default, A: Default, P: Default, Acc: Default, M: Default, B: Default, C
             customer: self.customer,
             payment: Some(payment),
             currency: self.currency,
+            merchant_reference: self.merchant_reference,
+            description: self.description,
+            metadata: self.metadata,
+            card_fingerprint: self.card_fingerprint,
+            fraud: self.fraud,
+            payment_token: self.payment_token,
+            stored_credential: self.stored_credential,
             ..Default::default()
         }
     }
@@ -149,6 +244,13 @@ impl<T: Default, A: Default, P: Default, Acc: Default, M: Default, B: Default, C
             customer: self.customer,
             payment: self.payment,
             currency: self.currency,
+            merchant_reference: self.merchant_reference,
+            description: self.description,
+            metadata: self.metadata,
+            card_fingerprint: self.card_fingerprint,
+            fraud: self.fraud,
+            payment_token: self.payment_token,
+            stored_credential: self.stored_credential,
             ..Default::default()
         }
     }
@@ -166,6 +268,13 @@ impl<T: Default, A: Default, P: Default, Acc: Default, M: Default, B: Default, C
             customer: self.customer,
             payment: self.payment,
             currency: self.currency,
+            merchant_reference: self.merchant_reference,
+            description: self.description,
+            metadata: self.metadata,
+            card_fingerprint: self.card_fingerprint,
+            fraud: self.fraud,
+            payment_token: self.payment_token,
+            stored_credential: self.stored_credential,
             ..Default::default()
         }
     }
@@ -180,6 +289,13 @@ impl<T: Default, A: Default, P: Default, Acc: Default, M: Default, B: Default, C
             customer: self.customer,
             payment: self.payment,
             currency: self.currency,
+            merchant_reference: self.merchant_reference,
+            description: self.description,
+            metadata: self.metadata,
+            card_fingerprint: self.card_fingerprint,
+            fraud: self.fraud,
+            payment_token: self.payment_token,
+            stored_credential: self.stored_credential,
             ..Default::default()
         }
     }
@@ -197,6 +313,13 @@ impl<T: Default, A: Default, P: Default, Acc: Default, M: Default, B: Default, C
             customer: self.customer,
             payment: self.payment,
             currency: Some(currency),
+            merchant_reference: self.merchant_reference,
+            description: self.description,
+            metadata: self.metadata,
+            card_fingerprint: self.card_fingerprint,
+            fraud: self.fraud,
+            payment_token: self.payment_token,
+            stored_credential: self.stored_credential,
             ..Default::default()
         }
     }
@@ -255,8 +378,57 @@ mod tests {
                 customer: None,
                 status: TransactionStatus::Success,
                 reference: trx.reference.clone(),
-                currency: Currency::GBP
+                currency: Currency::GBP,
+                merchant_reference: None,
+                description: None,
+                metadata: BTreeMap::new(),
+                card_fingerprint: None,
+                fraud: None,
+                avs: None,
+                cvv: None,
+                three_ds: None,
+                sca_exemption: None,
+                payment_token: None,
+                stored_credential: None,
+                scheme_transaction_id: None,
+                subscription_charge: None,
             }
         )
     }
+
+    #[rstest]
+    #[case(FraudDecision::Accept, TransactionStatus::Success)]
+    #[case(FraudDecision::Review, TransactionStatus::Success)]
+    #[case(
+        FraudDecision::Reject,
+        TransactionStatus::Failed(Some(TransactionError::FraudRejected))
+    )]
+    fn fraud_rejections_fail(#[case] decision: FraudDecision, #[case] status: TransactionStatus) {
+        let fraud = FraudResult {
+            score: 100,
+            decision,
+            matched_rules: vec!["amount_over".into()],
+        };
+        let trx = TransactionBuilder::new()
+            .fraud(Some(fraud.clone()))
+            .card_fingerprint(Some("abc123".into()))
+            .transaction_type(TransactionType::Auth)
+            .amount(12345)
+            .payment(Payment::from((
+                CardScheme::Visa,
+                (2030, 1),
+                "123",
+                "4000111122223333",
+            )))
+            .account(AcquirerAccount::BankOne(BankOneAccount {
+                merchant_identification_value: "12345678".into(),
+            }))
+            .billing(Billing::default())
+            .currency(Currency::GBP)
+            .merchant(Merchant::default())
+            .build();
+        assert_eq!(trx.status, status);
+        assert_eq!(trx.fraud, Some(fraud));
+        assert_eq!(trx.card_fingerprint.as_deref(), Some("abc123"));
+    }
 }
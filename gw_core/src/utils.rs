@@ -6,6 +6,10 @@ pub fn mask_account_number(num: &str) -> String {
     mask_number(num, '#', |i| i >= num.len().saturating_sub(4))
 }
 
+pub fn mask_name(name: &str) -> String {
+    mask_number(name, '#', |i| i < 1)
+}
+
 fn mask_number(num: &str, ch: char, predicate: impl Fn(usize) -> bool) -> String {
     num.chars()
         .enumerate()
@@ -35,4 +39,12 @@ mod tests {
     fn test_mask_account_number(#[case] num: &str, #[case] exp: &str) {
         assert_eq!(mask_account_number(num), exp);
     }
+
+    #[rstest]
+    #[case("Benjamin", "B#######")]
+    #[case("B", "B")]
+    #[case("", "")]
+    fn test_mask_name(#[case] name: &str, #[case] exp: &str) {
+        assert_eq!(mask_name(name), exp);
+    }
 }
@@ -0,0 +1,363 @@
+use chrono::{DateTime, SubsecRound, TimeDelta, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::{
+    dispute::DisputeStatus,
+    error::{Error, ErrorKind},
+    secret::Secret,
+    signing::{generate_secret, SignedRequest},
+    transaction::TransactionStatus,
+};
+
+/// Deliveries are given up on after this many attempts, leaving the event dead-lettered
+pub const MAX_ATTEMPTS: u32 = 8;
+/// The wait before the first retry, doubled for every one after it
+const FIRST_RETRY_SECS: i64 = 30;
+const MAX_RETRY_SECS: i64 = 6 * 60 * 60;
+
+/// Where a merchant's events are sent. The secret signs every event, so the merchant can check
+/// it came from the gateway.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookEndpoint {
+    pub merchant_id: String,
+    pub url: String,
+    pub secret: Secret<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookEndpoint {
+    pub fn new(merchant_id: String, url: String) -> Self {
+        Self {
+            merchant_id,
+            url,
+            secret: generate_secret(),
+            created_at: Utc::now().trunc_subsecs(6),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum EventType {
+    TransactionSucceeded,
+    TransactionFailed,
+    #[serde(rename = "TRANSACTION_PENDING_3DS")]
+    TransactionPending3DS,
+    TransactionSettled,
+    TransactionChargedBack,
+    DisputeReceived,
+    DisputeEvidenceSubmitted,
+    DisputeWon,
+    DisputeLost,
+}
+
+impl From<&TransactionStatus> for EventType {
+    fn from(value: &TransactionStatus) -> Self {
+        match value {
+            TransactionStatus::Success => EventType::TransactionSucceeded,
+            TransactionStatus::Failed(_) => EventType::TransactionFailed,
+            TransactionStatus::Pending3DS => EventType::TransactionPending3DS,
+            TransactionStatus::Settled => EventType::TransactionSettled,
+            TransactionStatus::ChargedBack => EventType::TransactionChargedBack,
+        }
+    }
+}
+
+impl From<DisputeStatus> for EventType {
+    fn from(value: DisputeStatus) -> Self {
+        match value {
+            DisputeStatus::Received => EventType::DisputeReceived,
+            DisputeStatus::EvidenceSubmitted => EventType::DisputeEvidenceSubmitted,
+            DisputeStatus::Won => EventType::DisputeWon,
+            DisputeStatus::Lost => EventType::DisputeLost,
+        }
+    }
+}
+
+impl std::fmt::Display for EventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            EventType::TransactionSucceeded => "TRANSACTION_SUCCEEDED",
+            EventType::TransactionFailed => "TRANSACTION_FAILED",
+            EventType::TransactionPending3DS => "TRANSACTION_PENDING_3DS",
+            EventType::TransactionSettled => "TRANSACTION_SETTLED",
+            EventType::TransactionChargedBack => "TRANSACTION_CHARGED_BACK",
+            EventType::DisputeReceived => "DISPUTE_RECEIVED",
+            EventType::DisputeEvidenceSubmitted => "DISPUTE_EVIDENCE_SUBMITTED",
+            EventType::DisputeWon => "DISPUTE_WON",
+            EventType::DisputeLost => "DISPUTE_LOST",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl TryFrom<String> for EventType {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "TRANSACTION_SUCCEEDED" => Ok(Self::TransactionSucceeded),
+            "TRANSACTION_FAILED" => Ok(Self::TransactionFailed),
+            "TRANSACTION_PENDING_3DS" => Ok(Self::TransactionPending3DS),
+            "TRANSACTION_SETTLED" => Ok(Self::TransactionSettled),
+            "TRANSACTION_CHARGED_BACK" => Ok(Self::TransactionChargedBack),
+            "DISPUTE_RECEIVED" => Ok(Self::DisputeReceived),
+            "DISPUTE_EVIDENCE_SUBMITTED" => Ok(Self::DisputeEvidenceSubmitted),
+            "DISPUTE_WON" => Ok(Self::DisputeWon),
+            "DISPUTE_LOST" => Ok(Self::DisputeLost),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised event type"),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum DeliveryStatus {
+    /// Waiting to be sent, or to be retried
+    Pending,
+    Delivered,
+    /// Every attempt failed, it's only sent again if it's requeued
+    Dead,
+}
+
+impl std::fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let d = match self {
+            DeliveryStatus::Pending => "PENDING",
+            DeliveryStatus::Delivered => "DELIVERED",
+            DeliveryStatus::Dead => "DEAD",
+        };
+        write!(f, "{d}")
+    }
+}
+
+impl TryFrom<String> for DeliveryStatus {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.as_str() {
+            "PENDING" => Ok(Self::Pending),
+            "DELIVERED" => Ok(Self::Delivered),
+            "DEAD" => Ok(Self::Dead),
+            invalid => Err(Error {
+                kind: ErrorKind::Type,
+                message: format!("{invalid} is not a recognised delivery status"),
+            }),
+        }
+    }
+}
+
+/// An event in the outbox, kept until it's delivered to the merchant's endpoint or dead-lettered
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebhookEvent {
+    pub event_id: String,
+    pub merchant_id: String,
+    pub event_type: EventType,
+    /// What the event is about, e.g. the transaction as the API returns it
+    pub data: Value,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookEvent {
+    /// A pending event, due to be sent straight away
+    pub fn new(
+        merchant_id: String,
+        event_type: EventType,
+        data: Value,
+        now: DateTime<Utc>,
+    ) -> Self {
+        let now = now.trunc_subsecs(6);
+        Self {
+            event_id: Uuid::new_v4().to_string(),
+            merchant_id,
+            event_type,
+            data,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+            created_at: now,
+            delivered_at: None,
+        }
+    }
+
+    /// The JSON body posted to the merchant, the same on every attempt
+    pub fn body(&self) -> Vec<u8> {
+        json!({
+            "event_id": self.event_id,
+            "event_type": self.event_type,
+            "created_at": self.created_at,
+            "data": self.data,
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    /// Signs the body the same way merchants sign their requests to the gateway, with the event
+    /// ID as the nonce, so retries of an event can be recognised
+    pub fn sign(&self, secret: &Secret<String>, path: &str, timestamp: i64, body: &[u8]) -> String {
+        SignedRequest {
+            method: "POST",
+            path,
+            timestamp,
+            nonce: &self.event_id,
+            body,
+        }
+        .sign(secret)
+    }
+
+    pub fn record_delivery(&mut self, now: DateTime<Utc>) {
+        self.attempts += 1;
+        self.status = DeliveryStatus::Delivered;
+        self.last_error = None;
+        self.delivered_at = Some(now.trunc_subsecs(6));
+    }
+
+    /// Schedules the next attempt with exponential backoff, or dead-letters the event once the
+    /// attempts run out
+    pub fn record_failure(&mut self, error: String, now: DateTime<Utc>) {
+        self.attempts += 1;
+        self.last_error = Some(error);
+        if self.attempts >= MAX_ATTEMPTS {
+            self.status = DeliveryStatus::Dead;
+        } else {
+            self.next_attempt_at = (now + retry_delay(self.attempts)).trunc_subsecs(6);
+        }
+    }
+
+    /// Sends a dead-lettered event again, with a fresh set of attempts
+    pub fn requeue(&mut self, now: DateTime<Utc>) {
+        self.status = DeliveryStatus::Pending;
+        self.attempts = 0;
+        self.next_attempt_at = now.trunc_subsecs(6);
+    }
+}
+
+/// How long to wait after the given number of failed attempts
+fn retry_delay(attempts: u32) -> TimeDelta {
+    let secs = FIRST_RETRY_SECS.saturating_mul(1 << (attempts - 1).min(20));
+    TimeDelta::seconds(secs.min(MAX_RETRY_SECS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rstest::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 1, 1, 9, 0, 0).unwrap()
+    }
+
+    fn event() -> WebhookEvent {
+        WebhookEvent::new(
+            "merchant123".into(),
+            EventType::TransactionSucceeded,
+            json!({"reference": "abc"}),
+            now(),
+        )
+    }
+
+    #[rstest]
+    #[case(1, 30)]
+    #[case(2, 60)]
+    #[case(3, 120)]
+    #[case(7, 1920)]
+    #[case(12, 6 * 60 * 60)]
+    #[case(40, 6 * 60 * 60)]
+    fn retries_back_off_exponentially(#[case] attempts: u32, #[case] secs: i64) {
+        assert_eq!(retry_delay(attempts), TimeDelta::seconds(secs));
+    }
+
+    #[rstest]
+    fn failed_events_are_retried_then_dead_lettered() {
+        let mut event = event();
+        assert_eq!(event.next_attempt_at, now());
+        event.record_failure("HTTP 500".into(), now());
+        assert_eq!(event.status, DeliveryStatus::Pending);
+        assert_eq!(event.next_attempt_at, now() + TimeDelta::seconds(30));
+        assert_eq!(event.last_error.as_deref(), Some("HTTP 500"));
+        for _ in 1..MAX_ATTEMPTS {
+            event.record_failure("timed out".into(), now());
+        }
+        assert_eq!(event.status, DeliveryStatus::Dead);
+        assert_eq!(event.attempts, MAX_ATTEMPTS);
+
+        event.requeue(now() + TimeDelta::days(1));
+        assert_eq!(event.status, DeliveryStatus::Pending);
+        assert_eq!(event.attempts, 0);
+        assert_eq!(event.next_attempt_at, now() + TimeDelta::days(1));
+        event.record_delivery(now() + TimeDelta::days(1));
+        assert_eq!(event.status, DeliveryStatus::Delivered);
+        assert_eq!(event.last_error, None);
+        assert_eq!(event.delivered_at, Some(now() + TimeDelta::days(1)));
+    }
+
+    #[rstest]
+    fn bodies_are_signed_like_merchant_requests() {
+        let event = event();
+        let body = event.body();
+        let value: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(value["event_id"], event.event_id);
+        assert_eq!(value["event_type"], "TRANSACTION_SUCCEEDED");
+        assert_eq!(value["created_at"], "2026-01-01T09:00:00Z");
+        assert_eq!(value["data"], json!({"reference": "abc"}));
+
+        let secret = Secret::from("secret");
+        let signature = event.sign(&secret, "/hooks", 1760832000, &body);
+        let request = SignedRequest {
+            method: "POST",
+            path: "/hooks",
+            timestamp: 1760832000,
+            nonce: &event.event_id,
+            body: &body,
+        };
+        assert!(request.verify(&secret, &signature));
+        assert!(!request.verify(&"another-secret".into(), &signature));
+    }
+
+    #[rstest]
+    #[case(TransactionStatus::Success, "TRANSACTION_SUCCEEDED")]
+    #[case(TransactionStatus::Failed(None), "TRANSACTION_FAILED")]
+    #[case(TransactionStatus::Pending3DS, "TRANSACTION_PENDING_3DS")]
+    #[case(TransactionStatus::Settled, "TRANSACTION_SETTLED")]
+    #[case(TransactionStatus::ChargedBack, "TRANSACTION_CHARGED_BACK")]
+    fn event_types_follow_transaction_status(
+        #[case] status: TransactionStatus,
+        #[case] event_type: &str,
+    ) {
+        let from_status = EventType::from(&status);
+        assert_eq!(from_status.to_string(), event_type);
+        assert_eq!(serde_json::to_value(from_status).unwrap(), event_type);
+        assert_eq!(
+            EventType::try_from(event_type.to_string()).unwrap(),
+            from_status
+        );
+    }
+
+    #[rstest]
+    #[case(DisputeStatus::Received, "DISPUTE_RECEIVED")]
+    #[case(DisputeStatus::EvidenceSubmitted, "DISPUTE_EVIDENCE_SUBMITTED")]
+    #[case(DisputeStatus::Won, "DISPUTE_WON")]
+    #[case(DisputeStatus::Lost, "DISPUTE_LOST")]
+    fn event_types_follow_dispute_status(#[case] status: DisputeStatus, #[case] event_type: &str) {
+        let from_status = EventType::from(status);
+        assert_eq!(from_status.to_string(), event_type);
+        assert_eq!(serde_json::to_value(from_status).unwrap(), event_type);
+        assert_eq!(
+            EventType::try_from(event_type.to_string()).unwrap(),
+            from_status
+        );
+    }
+}